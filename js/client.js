@@ -0,0 +1,78 @@
+// Runs the webmessage module in a web worker (see `worker.js`) and calls it from the main thread.
+//
+// ```js
+// import { WebMessageWorker } from './webmessage/js/client.js';
+//
+// const worker = new WebMessageWorker(
+//   new URL('./webmessage/js/worker.js', import.meta.url),
+//   new URL('./webmessage/pkg/webmessage.js', import.meta.url).href,
+// );
+// const valid = await worker.validate('chat 1', {
+//   onProgress: (checked, total) => console.log(`${checked} / ${total}`),
+// });
+// const msgs = await worker.call('messages', 'chat 1');
+// ```
+//
+// The worker keeps a copy of the items of `localStorage` taken when it starts, and its writes are
+// applied to `localStorage`. Writes by the main thread after the start are not seen by the worker.
+// If the main thread also loads the module, pass `onStorageChange` so that it invalidates the values
+// it cached from `localStorage`:
+//
+// ```js
+// const worker = new WebMessageWorker(workerUrl, moduleUrl, {
+//   onStorageChange: (key) => webmessage.invalidateCache(key),
+// });
+// ```
+
+export class WebMessageWorker {
+  constructor(workerUrl, moduleUrl, { onStorageChange } = {}) {
+    this.onStorageChange = onStorageChange;
+    this.worker = new Worker(workerUrl, { type: 'module' });
+    this.nextId = 0;
+    this.pending = new Map();
+    this.worker.onmessage = ({ data }) => this.handle(data);
+    this.worker.postMessage({ type: 'init', moduleUrl, items: { ...localStorage } });
+  }
+
+  // Calls the export with the name and the arguments in the worker. It resolves to the result.
+  call(method, ...args) {
+    return this.request({ type: 'call', method, args });
+  }
+
+  // Validates the chain of the group in chunks of messages. It resolves to the result, and reports
+  // the number of checks done and the total with `onProgress`.
+  validate(groupId, { chunkSize = 100, onProgress } = {}) {
+    return this.request({ type: 'validate', groupId, chunkSize }, onProgress);
+  }
+
+  terminate() {
+    this.worker.terminate();
+  }
+
+  request(message, onProgress) {
+    const id = this.nextId++;
+    return new Promise((resolve, reject) => {
+      this.pending.set(id, { resolve, reject, onProgress });
+      this.worker.postMessage({ ...message, id });
+    });
+  }
+
+  handle(data) {
+    if (data.type === 'storage') {
+      if (data.op === 'set') localStorage.setItem(data.key, data.value);
+      if (data.op === 'remove') localStorage.removeItem(data.key);
+      if (data.op === 'clear') localStorage.clear();
+      this.onStorageChange?.(data.op === 'clear' ? null : data.key);
+      return;
+    }
+    const request = this.pending.get(data.id);
+    if (!request) return;
+    if (data.type === 'progress') {
+      request.onProgress?.(data.checked, data.total);
+      return;
+    }
+    this.pending.delete(data.id);
+    if (data.type === 'result') request.resolve(data.result);
+    else request.reject(new Error(data.error));
+  }
+}
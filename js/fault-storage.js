@@ -0,0 +1,85 @@
+// A storage object with the methods of the Web Storage API which injects faults, to test how an app
+// behaves when `localStorage` fails: writes beyond the quota, writes which fail intermittently, and items
+// changed by other parties, e.g. other tabs, while the library runs.
+//
+// ```js
+// import { FaultStorage } from './webmessage/js/fault-storage.js';
+//
+// const storage = new FaultStorage({ quota: 64 * 1024, failureRate: 0.1 });
+// webmessage.setStorage(storage);
+// storage.onWrite = (key) => storage.externalSetItem('groups', '[]');
+// ```
+//
+// A failed write throws `QuotaExceededError` or `FaultError`, as `localStorage` throws. The items written
+// with `externalSetItem` and `externalRemoveItem` bypass the library, so call
+// `webmessage.invalidateCache(key)` after them as after the writes of other tabs.
+
+export class FaultStorage {
+  // `quota` is the maximum total length of the keys and the values. `failureRate` is the probability,
+  // from 0 to 1, that a write or a removal fails. `random` returns numbers in [0, 1), e.g. a seeded
+  // generator so that the failures are reproducible.
+  constructor({ quota = Infinity, failureRate = 0, random = Math.random, items = {} } = {}) {
+    this.quota = quota;
+    this.failureRate = failureRate;
+    this.random = random;
+    this.items = new Map(Object.entries(items));
+    // called with the key after each write or removal of the library
+    this.onWrite = null;
+  }
+
+  get length() {
+    return this.items.size;
+  }
+
+  key(index) {
+    return [...this.items.keys()][index] ?? null;
+  }
+
+  getItem(key) {
+    return this.items.has(key) ? this.items.get(key) : null;
+  }
+
+  setItem(key, value) {
+    key = String(key);
+    value = String(value);
+    this.injectFailure();
+    const previous = this.items.has(key) ? key.length + this.items.get(key).length : 0;
+    if (this.usedLength() - previous + key.length + value.length > this.quota) {
+      throw new DOMException(`fail to set item ${key}`, 'QuotaExceededError');
+    }
+    this.items.set(key, value);
+    this.onWrite?.(key);
+  }
+
+  removeItem(key) {
+    this.injectFailure();
+    this.items.delete(String(key));
+    this.onWrite?.(String(key));
+  }
+
+  clear() {
+    this.items.clear();
+  }
+
+  // Sets the item as other parties do. The faults are not injected.
+  externalSetItem(key, value) {
+    this.items.set(String(key), String(value));
+  }
+
+  // Removes the item as other parties do. The faults are not injected.
+  externalRemoveItem(key) {
+    this.items.delete(String(key));
+  }
+
+  usedLength() {
+    let length = 0;
+    for (const [key, value] of this.items) length += key.length + value.length;
+    return length;
+  }
+
+  injectFailure() {
+    if (this.random() < this.failureRate) {
+      throw new DOMException('intermittent failure', 'FaultError');
+    }
+  }
+}
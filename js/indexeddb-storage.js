@@ -0,0 +1,86 @@
+// A storage object with the methods of the Web Storage API which keeps the items in IndexedDB, e.g. where
+// the quota of `localStorage` is too small for the chains, or in web workers which have no `localStorage`.
+//
+// ```js
+// import { IndexedDbStorage } from './webmessage/js/indexeddb-storage.js';
+//
+// const storage = await IndexedDbStorage.open('webmessage');
+// webmessage.migrateStorage(localStorage, storage, (stage, done, total) => console.log(stage, done, total));
+// await storage.flush();
+// webmessage.setStorage(storage);
+// ```
+//
+// The library reads the storage synchronously, so the items are loaded into memory when the storage is
+// opened, and the writes are applied to memory at once and to IndexedDB in the background, in order.
+// `flush` resolves when the writes so far are stored, and rejects if any of them failed.
+
+export class IndexedDbStorage {
+  constructor(db, items) {
+    this.db = db;
+    this.items = items;
+    this.pending = Promise.resolve();
+  }
+
+  // Opens the database with the name, creating it if it does not exist, and loads its items.
+  static async open(name = 'webmessage') {
+    const open = indexedDB.open(name, 1);
+    open.onupgradeneeded = () => open.result.createObjectStore('items');
+    const db = await request(open);
+    const store = db.transaction('items', 'readonly').objectStore('items');
+    const [keys, values] = await Promise.all([request(store.getAllKeys()), request(store.getAll())]);
+    return new IndexedDbStorage(db, new Map(keys.map((key, idx) => [key, values[idx]])));
+  }
+
+  get length() {
+    return this.items.size;
+  }
+
+  key(index) {
+    return [...this.items.keys()][index] ?? null;
+  }
+
+  getItem(key) {
+    return this.items.has(key) ? this.items.get(key) : null;
+  }
+
+  setItem(key, value) {
+    key = String(key);
+    value = String(value);
+    this.items.set(key, value);
+    this.write((store) => store.put(value, key));
+  }
+
+  removeItem(key) {
+    key = String(key);
+    this.items.delete(key);
+    this.write((store) => store.delete(key));
+  }
+
+  clear() {
+    this.items.clear();
+    this.write((store) => store.clear());
+  }
+
+  // Resolves when the writes so far are stored in IndexedDB.
+  flush() {
+    return this.pending;
+  }
+
+  write(operation) {
+    // the transactions on the same object store complete in the order they are created
+    const transaction = this.db.transaction('items', 'readwrite');
+    operation(transaction.objectStore('items'));
+    const done = new Promise((resolve, reject) => {
+      transaction.oncomplete = () => resolve();
+      transaction.onerror = transaction.onabort = () => reject(transaction.error);
+    });
+    this.pending = Promise.all([this.pending, done]).then(() => undefined);
+  }
+}
+
+function request(req) {
+  return new Promise((resolve, reject) => {
+    req.onsuccess = () => resolve(req.result);
+    req.onerror = () => reject(req.error);
+  });
+}
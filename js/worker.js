@@ -0,0 +1,87 @@
+// Runs the webmessage module in a web worker, so that long operations such as the validation of long
+// chains do not block the main thread. It is started by `WebMessageWorker` in `client.js`.
+//
+// Web workers have no `localStorage`, so the worker keeps a copy of the items of the main thread, and
+// posts its writes back to the main thread, which applies them to its `localStorage`.
+//
+// Messages from the main thread:
+//   { type: 'init', moduleUrl, items }            loads the module with a copy of the items
+//   { type: 'call', id, method, args }            calls the export `method` with `args`
+//   { type: 'validate', id, groupId, chunkSize }  validates a chain in chunks, reporting progress
+// Messages to the main thread:
+//   { type: 'result', id, result } or { type: 'error', id, error }
+//   { type: 'progress', id, checked, total }
+//   { type: 'storage', op: 'set' | 'remove' | 'clear', key, value }
+
+class MirroredStorage {
+  constructor(items) {
+    this.items = new Map(Object.entries(items));
+  }
+
+  get length() {
+    return this.items.size;
+  }
+
+  key(index) {
+    return [...this.items.keys()][index] ?? null;
+  }
+
+  getItem(key) {
+    return this.items.has(key) ? this.items.get(key) : null;
+  }
+
+  setItem(key, value) {
+    value = String(value);
+    this.items.set(key, value);
+    self.postMessage({ type: 'storage', op: 'set', key, value });
+  }
+
+  removeItem(key) {
+    this.items.delete(key);
+    self.postMessage({ type: 'storage', op: 'remove', key });
+  }
+
+  clear() {
+    this.items.clear();
+    self.postMessage({ type: 'storage', op: 'clear' });
+  }
+}
+
+let resolveModule;
+const module = new Promise((resolve) => (resolveModule = resolve));
+
+async function initialize({ moduleUrl, items }) {
+  const webmessage = await import(moduleUrl);
+  await webmessage.default();
+  webmessage.setStorage(new MirroredStorage(items));
+  resolveModule(webmessage);
+}
+
+async function validate(webmessage, { id, groupId, chunkSize }) {
+  let cursor = JSON.parse(webmessage.validateMessagesChunked(groupId, chunkSize));
+  while (cursor.result === null) {
+    self.postMessage({ type: 'progress', id, checked: cursor.checked, total: cursor.total });
+    // yield to the event loop, so that other calls are served between the chunks
+    await new Promise((resolve) => setTimeout(resolve));
+    cursor = JSON.parse(webmessage.resumeValidation(JSON.stringify(cursor), chunkSize));
+  }
+  self.postMessage({ type: 'progress', id, checked: cursor.checked, total: cursor.total });
+  return cursor.result;
+}
+
+self.onmessage = async ({ data }) => {
+  if (data.type === 'init') {
+    await initialize(data);
+    return;
+  }
+  const webmessage = await module;
+  try {
+    const result =
+      data.type === 'validate'
+        ? await validate(webmessage, data)
+        : webmessage[data.method](...data.args);
+    self.postMessage({ type: 'result', id: data.id, result });
+  } catch (error) {
+    self.postMessage({ type: 'error', id: data.id, error: String(error) });
+  }
+};
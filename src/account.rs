@@ -1,19 +1,46 @@
 //! Contains the implementation of the account system.
 
-use std::fmt::Display;
+use std::{cell::OnceCell, fmt::Display};
 
+use base64ct::{Base64, Encoding};
+use p256::{
+    elliptic_curve::sec1::ToEncodedPoint,
+    pkcs8::{DecodePublicKey, EncodePublicKey, LineEnding},
+};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
+use zeroize::Zeroizing;
 
-use crate::core::account::GenerateKeys;
+use crate::{
+    compact,
+    core::{
+        account::GenerateKeys,
+        compare::ct_eq,
+        message::{from_hex, to_hex, Message, MessageSigner as _, Verifiable},
+    },
+    encryption,
+    message::{with_scheme, MessageSigner},
+    rng::Rng,
+};
+
+/// The number of iterations of the key derivation from the passphrase of an exported secret.
+const EXPORT_ITERATIONS: u32 = 100_000;
 
 type PublicKey = schnorr_rs::PublicKey<schnorr_rs::SchnorrP256Group>;
 type SigningKey = schnorr_rs::SigningKey<schnorr_rs::SchnorrP256Group>;
 
 /// Identity is a wrapper around schnorr_rs::ec::PublicKey, which implements the trait [Identity](crate::core::account::Identity).
+/// The public key is parsed on the first use and kept, so that verifying the messages of a signer does
+/// not parse it again. It is serialized and displayed in the [compact](crate::compact) encoding, and it
+/// is parsed from either encoding. The bytes of an identity, which are hashed in messages, are the JSON
+/// of the public key.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Identity {
+    #[serde(with = "compact_public_key")]
     public_key: String,
+    #[serde(skip)]
+    parsed: OnceCell<Option<PublicKey>>,
 }
 
 impl Identity {
@@ -21,32 +48,182 @@ impl Identity {
         // TODO implement PartialEq, Eq, AsRef<[u8]> for schnorr_rs::ec::PublicKey
         Self {
             public_key: serde_json::to_string(&public_key).unwrap(),
+            parsed: OnceCell::from(Some(public_key)),
+        }
+    }
+
+    /// Creates the identity from the public key in either encoding.
+    fn from_string(public_key: String) -> Self {
+        Self {
+            public_key: compact::expand_public_key(&public_key).unwrap_or(public_key),
+            parsed: OnceCell::new(),
         }
     }
 
+    /// Returns the public key in the compact encoding. The identities which are not public keys are
+    /// returned as they are.
+    pub fn to_compact(&self) -> String {
+        compact::compact_public_key(&self.public_key).unwrap_or_else(|| self.public_key.clone())
+    }
+
     pub fn to_public_key(&self) -> PublicKey {
-        serde_json::from_str(&self.public_key).unwrap()
+        self.public_key().cloned().unwrap()
+    }
+
+    /// Returns the public key, or None if the identity is not a public key.
+    pub(crate) fn public_key(&self) -> Option<&PublicKey> {
+        self.parsed
+            .get_or_init(|| serde_json::from_str(&self.public_key).ok())
+            .as_ref()
+    }
+
+    /// Checks if the identity is a public key on the P-256 curve, so that it can be used to verify signatures.
+    pub fn is_valid(&self) -> bool {
+        self.public_key().is_some()
+    }
+
+    /// Creates the identity from the SEC1 encoding of the public key, compressed or uncompressed.
+    pub fn from_sec1_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::from_p256(&p256::PublicKey::from_sec1_bytes(bytes).ok()?)
+    }
+
+    /// Returns the compressed SEC1 encoding of the public key.
+    pub fn to_sec1_bytes(&self) -> Option<Vec<u8>> {
+        Some(self.to_p256()?.to_encoded_point(true).as_bytes().to_vec())
+    }
+
+    /// Creates the identity from the hex of the SEC1 encoding of the public key, e.g. the output of
+    /// `openssl ec -pubout -conv_form compressed -outform DER` without the SPKI header.
+    pub fn from_sec1_hex(hex: &str) -> Option<Self> {
+        Self::from_sec1_bytes(&from_hex(hex)?)
+    }
+
+    /// Returns the hex of the compressed SEC1 encoding of the public key.
+    pub fn to_sec1_hex(&self) -> Option<String> {
+        self.to_sec1_bytes().map(|bytes| to_hex(&bytes))
+    }
+
+    /// Creates the identity from the DER of the SubjectPublicKeyInfo of the public key, e.g. exported
+    /// with `crypto.subtle.exportKey("spki", key)` of WebCrypto.
+    pub fn from_public_key_der(der: &[u8]) -> Option<Self> {
+        Self::from_p256(&p256::PublicKey::from_public_key_der(der).ok()?)
+    }
+
+    /// Returns the DER of the SubjectPublicKeyInfo of the public key.
+    pub fn to_public_key_der(&self) -> Option<Vec<u8>> {
+        Some(
+            self.to_p256()?
+                .to_public_key_der()
+                .ok()?
+                .as_bytes()
+                .to_vec(),
+        )
+    }
+
+    /// Creates the identity from the PEM of the SubjectPublicKeyInfo of the public key, e.g. the output
+    /// of `openssl ec -pubout`.
+    pub fn from_public_key_pem(pem: &str) -> Option<Self> {
+        Self::from_p256(&p256::PublicKey::from_public_key_pem(pem).ok()?)
+    }
+
+    /// Returns the PEM of the SubjectPublicKeyInfo of the public key.
+    pub fn to_public_key_pem(&self) -> Option<String> {
+        self.to_p256()?.to_public_key_pem(LineEnding::LF).ok()
+    }
+
+    /// Creates the identity from the JSON Web Key of the public key, e.g. exported with
+    /// `crypto.subtle.exportKey("jwk", key)` of WebCrypto. The private key in the JWK is ignored.
+    pub fn from_jwk(jwk: &str) -> Option<Self> {
+        Self::from_p256(&p256::PublicKey::from_jwk_str(jwk).ok()?)
+    }
+
+    /// Returns the JSON Web Key of the public key.
+    pub fn to_jwk(&self) -> Option<String> {
+        Some(self.to_p256()?.to_jwk_string())
+    }
+
+    /// Creates the identity from the public key on the P-256 curve, whose compact encoding is the
+    /// compressed SEC1 encoding of the point.
+    fn from_p256(public_key: &p256::PublicKey) -> Option<Self> {
+        let point = public_key.to_encoded_point(true);
+        let compact = Base64::encode_string(point.as_bytes());
+        compact::expand_public_key(&compact).map(Self::from_string)
+    }
+
+    /// Returns the public key on the P-256 curve, or None if the identity is not a public key.
+    fn to_p256(&self) -> Option<p256::PublicKey> {
+        let bytes = Base64::decode_vec(&self.to_compact()).ok()?;
+        p256::PublicKey::from_sec1_bytes(&bytes).ok()
+    }
+}
+
+/// KeyFormat is a standard encoding of the public key of an identity, with which the keys generated by
+/// other tools, e.g. OpenSSL and WebCrypto, are used as identities.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// The hex of the compressed SEC1 encoding.
+    Sec1,
+    /// The base64 of the DER of the SubjectPublicKeyInfo.
+    Spki,
+    /// The PEM of the SubjectPublicKeyInfo.
+    Pem,
+    /// The JSON Web Key.
+    Jwk,
+}
+
+impl KeyFormat {
+    /// Parses the identity from the key in the format.
+    pub fn parse(&self, key: &str) -> Option<Identity> {
+        match self {
+            KeyFormat::Sec1 => Identity::from_sec1_hex(key.trim()),
+            KeyFormat::Spki => Identity::from_public_key_der(&Base64::decode_vec(key.trim()).ok()?),
+            KeyFormat::Pem => Identity::from_public_key_pem(key),
+            KeyFormat::Jwk => Identity::from_jwk(key),
+        }
+    }
+
+    /// Encodes the public key of the identity in the format.
+    pub fn encode(&self, identity: &Identity) -> Option<String> {
+        match self {
+            KeyFormat::Sec1 => identity.to_sec1_hex(),
+            KeyFormat::Spki => identity
+                .to_public_key_der()
+                .map(|der| Base64::encode_string(&der)),
+            KeyFormat::Pem => identity.to_public_key_pem(),
+            KeyFormat::Jwk => identity.to_jwk(),
+        }
+    }
+}
+
+impl TryFrom<&str> for KeyFormat {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "sec1" => Ok(KeyFormat::Sec1),
+            "spki" | "der" => Ok(KeyFormat::Spki),
+            "pem" => Ok(KeyFormat::Pem),
+            "jwk" => Ok(KeyFormat::Jwk),
+            _ => Err("Invalid key format".to_string()),
+        }
     }
 }
 
 impl Display for Identity {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.public_key)
+        write!(f, "{}", self.to_compact())
     }
 }
 
 impl TryFrom<&str> for Identity {
     type Error = ();
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        Ok(Self {
-            public_key: value.to_string(),
-        })
+        Ok(Self::from_string(value.to_string()))
     }
 }
 
 impl PartialEq for Identity {
     fn eq(&self, other: &Self) -> bool {
-        self.public_key == other.public_key
+        ct_eq(self.public_key.as_bytes(), other.public_key.as_bytes())
     }
 }
 impl Eq for Identity {}
@@ -59,30 +236,282 @@ impl AsRef<[u8]> for Identity {
 impl TryFrom<Vec<u8>> for Identity {
     type Error = ();
     fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        Ok(Self {
-            public_key: String::from_utf8(value).map_err(|_| ())?,
-        })
+        Ok(Self::from_string(String::from_utf8(value).map_err(|_| ())?))
     }
 }
 impl crate::core::account::Identity for Identity {}
 
-/// Secret is a wrapper around schnorr_rs::ec::SigningKey, which implements the trait [Secret](crate::core::account::Secret).
+/// Serializes the public key of an [Identity] in the compact encoding, and deserializes it from either
+/// encoding, so that the identities stored before the compact encoding are read.
+mod compact_public_key {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::compact;
+
+    pub(super) fn serialize<S: Serializer>(
+        public_key: &str,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match compact::compact_public_key(public_key) {
+            Some(compact) => serializer.serialize_str(&compact),
+            None => serializer.serialize_str(public_key),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<String, D::Error> {
+        let public_key = String::deserialize(deserializer)?;
+        Ok(compact::expand_public_key(&public_key).unwrap_or(public_key))
+    }
+}
+
+/// Secret is a wrapper around the serialized schnorr_rs::ec::SigningKey, which implements the trait [Secret](crate::core::account::Secret).
+/// The key material is wiped from memory when the secret is dropped.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Secret {
-    private_key: SigningKey,
+    #[serde(with = "zeroizing_key")]
+    private_key: Zeroizing<String>,
 }
 impl crate::core::account::Secret for Secret {}
 
 impl Secret {
-    pub fn as_private_key(&self) -> &SigningKey {
-        &self.private_key
+    fn new(private_key: SigningKey) -> Self {
+        Self {
+            private_key: Zeroizing::new(serde_json::to_string(&private_key).unwrap()),
+        }
+    }
+
+    /// Returns the signing key. The returned key is not wiped, so it should be dropped right after use.
+    pub fn to_private_key(&self) -> SigningKey {
+        serde_json::from_str(&self.private_key).unwrap()
     }
 }
 
 impl Display for Secret {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", serde_json::to_string(&self.private_key).unwrap())
+        write!(f, "{}", self.private_key.as_str())
+    }
+}
+
+impl TryFrom<&str> for Secret {
+    type Error = ();
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(Self::new(serde_json::from_str(value).map_err(|_| ())?))
+    }
+}
+
+/// Serializes the signing key of a [Secret] in the format of schnorr_rs::ec::SigningKey, so that the
+/// stored accounts are compatible with the secrets which held the key directly.
+mod zeroizing_key {
+    use serde::{
+        de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer,
+    };
+    use zeroize::Zeroizing;
+
+    use super::SigningKey;
+
+    pub(super) fn serialize<S: Serializer>(
+        private_key: &Zeroizing<String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde_json::from_str::<SigningKey>(private_key)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Zeroizing<String>, D::Error> {
+        let private_key = SigningKey::deserialize(deserializer)?;
+        serde_json::to_string(&private_key)
+            .map(Zeroizing::new)
+            .map_err(D::Error::custom)
+    }
+}
+
+/// KeyPair is the identity and the secret of an account, e.g. to import the account into another browser.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyPair {
+    pub identity: Identity,
+    pub secret: Secret,
+}
+
+impl KeyPair {
+    /// Checks if the secret is the signing key of the identity, by signing a challenge with the secret
+    /// and verifying the signature with the identity.
+    pub fn is_valid(&self) -> bool {
+        if !self.identity.is_valid() {
+            return false;
+        }
+        let challenge = Message::root(b"webmessage key pair".to_vec());
+        MessageSigner::sign(&self.identity, &self.secret, &challenge)
+            .verify(&self.identity, &challenge.to_hash::<Sha256>())
+    }
+}
+
+/// EncryptedSecret is a secret encrypted with a key derived from a passphrase, for exporting the secret
+/// out of the local storage.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EncryptedSecret {
+    pub identity: Identity,
+    pub salt: [u8; 16],
+    pub iterations: u32,
+    /// The serialized secret encrypted with the derived key.
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedSecret {
+    /// Encrypts the secret of the identity with the passphrase.
+    pub fn new(identity: Identity, secret: &Secret, passphrase: &str) -> Self {
+        let mut salt = [0u8; 16];
+        Rng.fill_bytes(&mut salt);
+        let key = encryption::derive_passphrase_key(passphrase, &salt, EXPORT_ITERATIONS);
+        Self {
+            identity,
+            salt,
+            iterations: EXPORT_ITERATIONS,
+            ciphertext: encryption::encrypt(&key, Zeroizing::new(secret.to_string()).as_bytes()),
+        }
     }
+
+    /// Decrypts the secret with the passphrase. It returns None if the passphrase is wrong.
+    pub fn decrypt(&self, passphrase: &str) -> Option<Secret> {
+        let key = encryption::derive_passphrase_key(passphrase, &self.salt, self.iterations);
+        let plaintext = Zeroizing::new(encryption::decrypt(&key, &self.ciphertext)?);
+        Secret::try_from(std::str::from_utf8(&plaintext).ok()?).ok()
+    }
+
+    /// Decrypts the secret with the passphrase and returns it with the identity.
+    pub fn decrypt_key_pair(&self, passphrase: &str) -> Option<KeyPair> {
+        Some(KeyPair {
+            identity: self.identity.clone(),
+            secret: self.decrypt(passphrase)?,
+        })
+    }
+}
+
+/// EncryptedAccounts is the key pairs of several accounts encrypted with a key derived from a passphrase,
+/// for moving the accounts to another browser without their message history.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EncryptedAccounts {
+    /// The identities of the accounts, which are readable without the passphrase.
+    pub identities: Vec<Identity>,
+    pub salt: [u8; 16],
+    pub iterations: u32,
+    /// The serialized key pairs encrypted with the derived key.
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedAccounts {
+    /// Encrypts the key pairs with the passphrase.
+    pub fn new(key_pairs: &[KeyPair], passphrase: &str) -> Self {
+        let mut salt = [0u8; 16];
+        Rng.fill_bytes(&mut salt);
+        let key = encryption::derive_passphrase_key(passphrase, &salt, EXPORT_ITERATIONS);
+        let plaintext = Zeroizing::new(serde_json::to_vec(key_pairs).unwrap());
+        Self {
+            identities: key_pairs
+                .iter()
+                .map(|key_pair| key_pair.identity.clone())
+                .collect(),
+            salt,
+            iterations: EXPORT_ITERATIONS,
+            ciphertext: encryption::encrypt(&key, &plaintext),
+        }
+    }
+
+    /// Decrypts the key pairs with the passphrase. It returns None if the passphrase is wrong.
+    pub fn decrypt(&self, passphrase: &str) -> Option<Vec<KeyPair>> {
+        let key = encryption::derive_passphrase_key(passphrase, &self.salt, self.iterations);
+        let plaintext = Zeroizing::new(encryption::decrypt(&key, &self.ciphertext)?);
+        serde_json::from_slice(&plaintext).ok()
+    }
+}
+
+/// AccountMetadata is the local metadata of an account, which is never shared with other identities.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AccountMetadata {
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub color: String,
+    /// The unix timestamp when the account was created, if it was created after the metadata was kept.
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    /// The unix timestamp when the account was last set as the current account.
+    #[serde(default)]
+    pub last_used: Option<u64>,
+    /// The unix timestamp when the secret of the account was last exported.
+    #[serde(default)]
+    pub exported_at: Option<u64>,
+}
+
+/// AccountUsage is the usage of a local account, which is maintained when the messages signed by the account
+/// are written, e.g. for the profile screens and the warnings before deleting the account.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AccountUsage {
+    /// The number of messages signed by the account in all groups.
+    pub message_count: u64,
+    /// The earliest timestamp of the messages signed by the account.
+    pub first_signed: Option<u64>,
+    /// The latest timestamp of the messages signed by the account.
+    pub last_signed: Option<u64>,
+    /// The groups in which the account signed messages, in the order they are first written.
+    pub groups: Vec<GroupUsage>,
+}
+
+/// GroupUsage is the usage of a local account in a group.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GroupUsage {
+    pub group_id: String,
+    pub message_count: u64,
+    pub first_signed: Option<u64>,
+    pub last_signed: Option<u64>,
+}
+
+impl AccountUsage {
+    /// Counts a message signed by the account in the group with the given timestamp.
+    pub fn add(&mut self, group_id: &str, timestamp: Option<u64>) {
+        let idx = match self
+            .groups
+            .iter()
+            .position(|group| group.group_id == group_id)
+        {
+            Some(idx) => idx,
+            None => {
+                self.groups.push(GroupUsage {
+                    group_id: group_id.to_string(),
+                    message_count: 0,
+                    first_signed: None,
+                    last_signed: None,
+                });
+                self.groups.len() - 1
+            }
+        };
+        let group = &mut self.groups[idx];
+        group.message_count += 1;
+        self.message_count += 1;
+        if let Some(timestamp) = timestamp {
+            for (first, last) in [
+                (&mut group.first_signed, &mut group.last_signed),
+                (&mut self.first_signed, &mut self.last_signed),
+            ] {
+                *first = Some(first.map_or(timestamp, |first| first.min(timestamp)));
+                *last = Some(last.map_or(timestamp, |last| last.max(timestamp)));
+            }
+        }
+    }
+}
+
+/// AccountInfo is an account with its metadata.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AccountInfo {
+    pub identity: Identity,
+    #[serde(flatten)]
+    pub metadata: AccountMetadata,
+    /// Whether the account is the current account.
+    pub current: bool,
 }
 
 /// GenKeysAlgorithm is a wrapper around schnorr_rs::SignatureSchemeECP256<Hasher>, which implements the trait [GenerateKeys](crate::core::account::GenerateKeys).
@@ -90,9 +519,8 @@ impl Display for Secret {
 pub struct GenKeysAlgorithm;
 impl GenerateKeys<Secret, Identity> for GenKeysAlgorithm {
     fn generate_keys() -> (Secret, Identity) {
-        let scheme = schnorr_rs::signature_scheme_p256::<Sha256>();
-        let (private_key, public_key) = scheme.generate_key(&mut rand::thread_rng());
+        let (private_key, public_key) = with_scheme(|scheme| scheme.generate_key(&mut Rng));
         let id = Identity::new(public_key);
-        (Secret { private_key }, id)
+        (Secret::new(private_key), id)
     }
 }
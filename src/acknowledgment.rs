@@ -0,0 +1,68 @@
+//! Provides the signed acknowledgments of messages, e.g. "I received message X" or "I approve message X".
+//!
+//! Acknowledgments reference a message by its hash and are stored alongside the chain, so they do not
+//! change the chain of the group.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::MessageHash,
+    detached,
+    message::Signature,
+};
+
+/// The domain separator of the signatures of the acknowledgments.
+const ACK_PREFIX: &[u8] = b"webmessage acknowledgment";
+
+/// AckKind tells what the signer of an acknowledgment states about the message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AckKind {
+    /// The signer received the message.
+    #[default]
+    Received,
+    /// The signer approves the message.
+    Approved,
+}
+
+/// AcknowledgmentBody is the content of an acknowledgment which is signed by the signer.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcknowledgmentBody {
+    pub group_id: String,
+    /// The hash of the acknowledged message.
+    pub hash: MessageHash,
+    pub signer: Identity,
+    pub kind: AckKind,
+}
+
+/// Acknowledgment is an acknowledgment of a message signed by the signer.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Acknowledgment {
+    pub body: AcknowledgmentBody,
+    pub signature: Signature,
+}
+
+impl Acknowledgment {
+    /// Creates an acknowledgment signed by the signer.
+    pub fn new(body: AcknowledgmentBody, secret: &Secret) -> Self {
+        let signature =
+            detached::sign_in_domain(ACK_PREFIX, &body.signer, secret, &signed_data(&body));
+        Self { body, signature }
+    }
+
+    /// Verifies the signature of the signer.
+    pub fn verify(&self) -> bool {
+        detached::verify_in_domain(
+            ACK_PREFIX,
+            &self.body.signer,
+            &signed_data(&self.body),
+            &self.signature,
+        )
+    }
+}
+
+/// Returns the data which the signer signs for the acknowledgment.
+fn signed_data(body: &AcknowledgmentBody) -> Vec<u8> {
+    serde_json::to_vec(body).unwrap()
+}
@@ -0,0 +1,133 @@
+//! Provides the key agreement (ECDH over P-256) used to distribute group content keys to members.
+//!
+//! Each account has an agreement key derived from its secret. Members announce the public part in
+//! a signed `KeyAnnouncement` record, and the group content key is wrapped for each member in a
+//! signed `KeyDistribution` record. When members are removed, a new group content key is wrapped
+//! for the remaining members in a signed `Rekey` record. The records are stored in the chain of the group.
+
+use hkdf::Hkdf;
+use p256::{ecdh::EphemeralSecret, elliptic_curve::sec1::ToEncodedPoint, PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::{MessageKind, SignedMessage},
+    encryption::{self, GroupKey},
+    message::Signature,
+    rng::Rng,
+};
+
+const AGREEMENT_KEY_INFO: &[u8] = b"webmessage agreement key";
+const KEY_WRAPPING_INFO: &[u8] = b"webmessage key wrapping";
+
+/// KeyAnnouncement is the data of a `KeyAnnouncement` record. It contains the SEC1 encoded agreement
+/// public key of the signer.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyAnnouncement {
+    pub agreement_key: Vec<u8>,
+}
+
+/// WrappedKey is a group content key encrypted with a key agreed between an ephemeral key and the
+/// agreement key of the recipient.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WrappedKey {
+    /// SEC1 encoded ephemeral public key.
+    pub ephemeral_key: Vec<u8>,
+    /// The encrypted group content key.
+    pub ciphertext: Vec<u8>,
+}
+
+/// KeyGrant is the group content key wrapped for one recipient.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyGrant {
+    pub recipient: Identity,
+    pub wrapped_key: WrappedKey,
+}
+
+/// KeyDistribution is the data of a `KeyDistribution` and a `Rekey` record.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyDistribution {
+    pub grants: Vec<KeyGrant>,
+    /// The epoch of the distributed group content key, i.e. the number of `Rekey` records up to the record.
+    /// A `Rekey` record starts the next epoch.
+    #[serde(default)]
+    pub epoch: u64,
+}
+
+/// Derives the agreement secret key of the account from its signing secret.
+pub fn agreement_secret(secret: &Secret) -> SecretKey {
+    let hkdf = Hkdf::<Sha256>::new(None, Zeroizing::new(secret.to_string()).as_bytes());
+    let mut counter = 0u8;
+    loop {
+        let mut okm = Zeroizing::new([0u8; 32]);
+        hkdf.expand_multi_info(&[AGREEMENT_KEY_INFO, &[counter]], okm.as_mut())
+            .expect("32 bytes is a valid output length");
+        // the output is out of the scalar range with negligible probability
+        if let Ok(key) = SecretKey::from_slice(okm.as_ref()) {
+            return key;
+        }
+        counter += 1;
+    }
+}
+
+/// Returns the SEC1 encoded agreement public key of the account.
+pub fn agreement_public_key(secret: &Secret) -> Vec<u8> {
+    agreement_secret(secret)
+        .public_key()
+        .to_encoded_point(true)
+        .as_bytes()
+        .to_vec()
+}
+
+/// Wraps the group key for the recipient with the SEC1 encoded agreement public key.
+pub fn wrap_key(group_key: &GroupKey, recipient_key: &[u8]) -> Result<WrappedKey, String> {
+    let recipient_key =
+        PublicKey::from_sec1_bytes(recipient_key).map_err(|_| "invalid agreement key")?;
+    let ephemeral = EphemeralSecret::random(&mut Rng);
+    let wrapping_key = wrapping_key(ephemeral.diffie_hellman(&recipient_key).raw_secret_bytes());
+
+    Ok(WrappedKey {
+        ephemeral_key: ephemeral
+            .public_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec(),
+        ciphertext: encryption::encrypt(&wrapping_key, group_key),
+    })
+}
+
+/// Unwraps the group key with the agreement secret key of the recipient.
+pub fn unwrap_key(wrapped_key: &WrappedKey, secret: &Secret) -> Option<GroupKey> {
+    let ephemeral_key = PublicKey::from_sec1_bytes(&wrapped_key.ephemeral_key).ok()?;
+    let shared_secret = p256::ecdh::diffie_hellman(
+        agreement_secret(secret).to_nonzero_scalar(),
+        ephemeral_key.as_affine(),
+    );
+    let wrapping_key = wrapping_key(shared_secret.raw_secret_bytes());
+    encryption::decrypt(&wrapping_key, &wrapped_key.ciphertext)?
+        .try_into()
+        .ok()
+}
+
+/// Returns the agreement public key announced by the identity. The messages are ordered from the
+/// latest, so the latest announcement is returned.
+pub(crate) fn announced_key(
+    messages: &[SignedMessage<Identity, Signature>],
+    identity: &Identity,
+) -> Option<Vec<u8>> {
+    messages
+        .iter()
+        .filter(|msg| msg.message.kind == MessageKind::KeyAnnouncement && &msg.id == identity)
+        .find_map(|msg| serde_json::from_slice::<KeyAnnouncement>(&msg.message.data).ok())
+        .map(|announcement| announcement.agreement_key)
+}
+
+fn wrapping_key(shared_secret: &[u8]) -> GroupKey {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared_secret)
+        .expand(KEY_WRAPPING_INFO, &mut key)
+        .expect("32 bytes is a valid output length");
+    key
+}
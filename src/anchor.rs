@@ -0,0 +1,146 @@
+//! Provides the anchoring of chain heads to external timestamping services, so that the existence of
+//! the messages up to a head can be proven to third parties as of the date in the returned proof.
+//!
+//! The proofs are opaque to this library. They are verified with the tools of the service, e.g.
+//! `openssl ts -verify` for RFC 3161 responses or `ots verify` for OpenTimestamps proofs.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "web")]
+use web_sys::XmlHttpRequest;
+
+#[cfg(feature = "web")]
+use crate::core::group::unix_timestamp;
+use crate::core::message::MessageHash;
+
+/// The DER encoding of the RFC 3161 `TimeStampReq` for a SHA-256 digest up to the digest, which is
+/// version 1 followed by the `messageImprint`.
+#[rustfmt::skip]
+const RFC3161_REQUEST_PREFIX: [u8; 24] = [
+    0x30, 0x39, // TimeStampReq
+    0x02, 0x01, 0x01, // version
+    0x30, 0x31, // messageImprint
+    0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, // sha256
+    0x04, 0x20, // hashedMessage
+];
+/// The DER encoding of the RFC 3161 `TimeStampReq` after the digest, which sets the `certReq` flag.
+const RFC3161_REQUEST_SUFFIX: [u8; 3] = [0x01, 0x01, 0xff];
+
+/// Anchor is implemented on the services which timestamp a digest and return a proof of it.
+pub trait Anchor {
+    /// Submits the digest to the service and returns the proof.
+    fn anchor(&self, digest: &MessageHash) -> Result<AnchorProof, String>;
+}
+
+/// AnchorService is the protocol of a timestamping service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorService {
+    /// A time-stamp authority of RFC 3161. The proof is the DER encoded `TimeStampResp`.
+    Rfc3161,
+    /// An OpenTimestamps calendar. The proof is the incomplete timestamp returned by the calendar,
+    /// which is upgraded by the OpenTimestamps client once it is committed to Bitcoin.
+    OpenTimestamps,
+}
+
+impl TryFrom<&str> for AnchorService {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(value.to_string()))
+            .map_err(|_| format!("unknown anchor service: {value}"))
+    }
+}
+
+/// AnchorProof is the proof returned by a timestamping service for a chain head.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnchorProof {
+    /// The protocol of the service.
+    pub service: AnchorService,
+    /// The url of the service.
+    pub url: String,
+    /// The hash of the chain head which is timestamped.
+    pub digest: MessageHash,
+    /// The proof in the format of the service.
+    pub proof: Vec<u8>,
+    /// The unix timestamp when the digest was submitted.
+    pub submitted_at: u64,
+}
+
+/// HttpAnchor submits the digest to a timestamping service over HTTP. The request is synchronous, so
+/// it is preferably used in a web worker.
+#[cfg(feature = "web")]
+#[derive(Clone)]
+pub struct HttpAnchor {
+    /// The protocol of the service.
+    pub service: AnchorService,
+    /// The url of the service, e.g. `https://a.pool.opentimestamps.org/digest`.
+    pub url: String,
+}
+
+#[cfg(feature = "web")]
+impl HttpAnchor {
+    /// Creates the anchor of the service at the url.
+    pub fn new(service: AnchorService, url: &str) -> Self {
+        Self {
+            service,
+            url: url.to_string(),
+        }
+    }
+
+    /// Returns the body and the content type of the request for the digest.
+    fn request(&self, digest: &MessageHash) -> (Vec<u8>, &'static str) {
+        match self.service {
+            AnchorService::Rfc3161 => (timestamp_request(digest), "application/timestamp-query"),
+            AnchorService::OpenTimestamps => (digest.to_vec(), "application/octet-stream"),
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+impl Anchor for HttpAnchor {
+    fn anchor(&self, digest: &MessageHash) -> Result<AnchorProof, String> {
+        let (body, content_type) = self.request(digest);
+        let submitted_at = unix_timestamp();
+
+        let request = XmlHttpRequest::new().map_err(|_| "Fail to create request".to_string())?;
+        request
+            .open_with_async("POST", &self.url, false)
+            .and_then(|_| request.set_request_header("Content-Type", content_type))
+            // binary responses are not available to synchronous requests, except as text in which
+            // each byte is a character
+            .and_then(|_| request.override_mime_type("text/plain; charset=x-user-defined"))
+            .and_then(|_| request.send_with_opt_u8_array(Some(&body)))
+            .map_err(|_| "Fail to send request".to_string())?;
+
+        let status = request.status().unwrap_or_default();
+        if !(200..300).contains(&status) {
+            return Err(format!("Anchor service responded with status {status}"));
+        }
+        let proof = request
+            .response_text()
+            .ok()
+            .flatten()
+            .ok_or("Fail to read response".to_string())?
+            .chars()
+            .map(|c| c as u32 as u8)
+            .collect();
+
+        Ok(AnchorProof {
+            service: self.service,
+            url: self.url.clone(),
+            digest: *digest,
+            proof,
+            submitted_at,
+        })
+    }
+}
+
+/// Returns the DER encoded RFC 3161 `TimeStampReq` for the SHA-256 digest.
+pub fn timestamp_request(digest: &MessageHash) -> Vec<u8> {
+    [
+        &RFC3161_REQUEST_PREFIX[..],
+        digest,
+        &RFC3161_REQUEST_SUFFIX[..],
+    ]
+    .concat()
+}
@@ -0,0 +1,84 @@
+//! Provides the challenge-response authentication with the identities of the accounts, e.g. for web apps
+//! which authenticate their users to a backend.
+//!
+//! The verifier creates a challenge with a random nonce and an expiry, the user signs it with the secret of
+//! the account, and the verifier checks the response. Each challenge is accepted once and before its expiry,
+//! so that the responses cannot be replayed.
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::to_hex,
+    detached,
+    message::Signature,
+    rng::Rng,
+};
+
+/// The number of seconds for which a challenge can be answered.
+pub const CHALLENGE_TTL: u64 = 300;
+
+/// The domain separator of the signatures of the challenges.
+const AUTH_PREFIX: &[u8] = b"webmessage auth challenge";
+
+/// AuthChallenge is the challenge which the verifier sends to the user.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthChallenge {
+    /// The random nonce in hex.
+    pub nonce: String,
+    /// Unix timestamp after which the challenge cannot be answered.
+    pub expiry: u64,
+}
+
+impl AuthChallenge {
+    /// Creates a challenge with a random nonce, created at the given unix timestamp.
+    pub fn new(timestamp: u64) -> Self {
+        let mut nonce = [0u8; 32];
+        Rng.fill_bytes(&mut nonce);
+        Self {
+            nonce: to_hex(&nonce),
+            expiry: timestamp + CHALLENGE_TTL,
+        }
+    }
+
+    /// Checks if the challenge is expired at the given unix timestamp.
+    pub fn is_expired_at(&self, timestamp: u64) -> bool {
+        timestamp > self.expiry
+    }
+}
+
+/// AuthResponse is the challenge signed by the user.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuthResponse {
+    pub challenge: AuthChallenge,
+    pub identity: Identity,
+    pub signature: Signature,
+}
+
+impl AuthResponse {
+    /// Signs the challenge with the secret of the identity.
+    pub fn new(challenge: AuthChallenge, identity: Identity, secret: &Secret) -> Self {
+        let signature = detached::sign_in_domain(
+            AUTH_PREFIX,
+            &identity,
+            secret,
+            &serde_json::to_vec(&challenge).unwrap(),
+        );
+        Self {
+            challenge,
+            identity,
+            signature,
+        }
+    }
+
+    /// Verifies the signature of the identity.
+    pub fn verify(&self) -> bool {
+        detached::verify_in_domain(
+            AUTH_PREFIX,
+            &self.identity,
+            &serde_json::to_vec(&self.challenge).unwrap(),
+            &self.signature,
+        )
+    }
+}
@@ -0,0 +1,74 @@
+//! Provides the signed capabilities which delegate limited rights in a group from one identity to another,
+//! e.g. to bots or secondary devices, without sharing the secret of the issuer.
+//!
+//! The delegate presents the capability in a `Delegation` record. Afterwards, the messages signed by the
+//! delegate of the kinds granted by the capability are validated with the role of the issuer until the
+//! capability expires.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::MessageKind,
+    detached,
+    message::Signature,
+};
+
+/// The domain separator of the signatures of the capabilities.
+const CAPABILITY_PREFIX: &[u8] = b"webmessage capability";
+
+/// CapabilityBody is the content of a capability which is signed by the issuer.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CapabilityBody {
+    /// The group in which the rights are delegated.
+    pub group_id: String,
+    /// The identity which delegates its rights. It must be a member of the group.
+    pub issuer: Identity,
+    /// The identity to which the rights are delegated.
+    pub delegate: Identity,
+    /// The kinds of messages which the delegate may sign on behalf of the issuer.
+    pub kinds: Vec<MessageKind>,
+    /// Unix timestamp after which the capability cannot be used.
+    pub expiry: u64,
+}
+
+/// Capability is a delegation of rights signed by the issuer.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Capability {
+    pub body: CapabilityBody,
+    pub signature: Signature,
+}
+
+impl Capability {
+    /// Creates a capability signed by the issuer.
+    pub fn new(body: CapabilityBody, secret: &Secret) -> Self {
+        let signature =
+            detached::sign_in_domain(CAPABILITY_PREFIX, &body.issuer, secret, &signed_data(&body));
+        Self { body, signature }
+    }
+
+    /// Verifies the signature of the issuer.
+    pub fn verify(&self) -> bool {
+        detached::verify_in_domain(
+            CAPABILITY_PREFIX,
+            &self.body.issuer,
+            &signed_data(&self.body),
+            &self.signature,
+        )
+    }
+
+    /// Checks if the capability grants the kind of messages.
+    pub fn allows(&self, kind: MessageKind) -> bool {
+        self.body.kinds.contains(&kind)
+    }
+
+    /// Checks if the capability is expired at the given unix timestamp.
+    pub fn is_expired_at(&self, timestamp: u64) -> bool {
+        timestamp > self.body.expiry
+    }
+}
+
+/// Returns the data which the issuer signs for the capability.
+fn signed_data(body: &CapabilityBody) -> Vec<u8> {
+    serde_json::to_vec(body).unwrap()
+}
@@ -0,0 +1,92 @@
+//! Provides the causal order of messages, so that apps can order the messages which are signed
+//! concurrently, e.g. by the devices of different members over the same previous message, in the same
+//! way on every device.
+//!
+//! The sequence number of a message is its Lamport timestamp, as it is one more than the sequence number
+//! of its only parent. A message happens before another if it is an ancestor of the other, and the
+//! messages where neither is an ancestor of the other are concurrent. The deterministic order sorts the
+//! messages by their sequence numbers, then their timestamps and then their hashes, which is consistent
+//! with the causal order.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    account::Identity,
+    core::{
+        group::HashAlgorithm,
+        message::{MessageHash, SignedMessage},
+    },
+    message::Signature,
+};
+
+/// CausalOrder is the order of a message relative to another message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CausalOrder {
+    /// The message is an ancestor of the other message.
+    Before,
+    /// The other message is an ancestor of the message.
+    After,
+    /// Neither message is an ancestor of the other, or their ancestry is not known.
+    Concurrent,
+    /// The messages are the same.
+    Equal,
+}
+
+/// Returns the causal order of the message `a` relative to the message `b`. The ancestors between them
+/// are looked up with `message`, e.g. in the stored chain. If an ancestor is not found, the messages are
+/// concurrent, as the order cannot be proved.
+pub fn causal_order(
+    a: &SignedMessage<Identity, Signature>,
+    b: &SignedMessage<Identity, Signature>,
+    message: impl Fn(&MessageHash) -> Option<SignedMessage<Identity, Signature>>,
+) -> CausalOrder {
+    if a.hash::<Sha256>() == b.hash::<Sha256>() {
+        CausalOrder::Equal
+    } else if is_ancestor(a, b, &message) {
+        CausalOrder::Before
+    } else if is_ancestor(b, a, &message) {
+        CausalOrder::After
+    } else {
+        CausalOrder::Concurrent
+    }
+}
+
+/// Compares the messages in the deterministic order: by their sequence numbers, then their timestamps
+/// and then their hashes.
+pub fn deterministic_cmp(
+    a: &SignedMessage<Identity, Signature>,
+    b: &SignedMessage<Identity, Signature>,
+) -> Ordering {
+    a.seq
+        .cmp(&b.seq)
+        .then(a.message.timestamp.cmp(&b.message.timestamp))
+        .then_with(|| a.hash::<Sha256>().cmp(&b.hash::<Sha256>()))
+}
+
+/// Checks if the message `ancestor` is an ancestor of the message `descendant`. The messages may be
+/// linked with any hash algorithm, as the chain may be migrated between them.
+fn is_ancestor(
+    ancestor: &SignedMessage<Identity, Signature>,
+    descendant: &SignedMessage<Identity, Signature>,
+    message: &impl Fn(&MessageHash) -> Option<SignedMessage<Identity, Signature>>,
+) -> bool {
+    if ancestor.seq >= descendant.seq || descendant.is_first_message() {
+        return false;
+    }
+    let mut previous_hash = descendant.message.previous_hash;
+    loop {
+        if HashAlgorithm::of(ancestor, &previous_hash).is_some() {
+            return true;
+        }
+        match message(&previous_hash) {
+            Some(parent) if parent.seq > ancestor.seq && !parent.is_first_message() => {
+                previous_hash = parent.message.previous_hash;
+            }
+            _ => return false,
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Provides the public generic API of the chain logic, so that Rust users can plug their own identity,
+//! signature scheme, hasher and storage backend while reusing the hashing, linking, signing and
+//! validation of the chains of messages.
+//!
+//! The [Writer] appends signed messages to the chains of groups in a [Backend], checking the signature,
+//! the sequence number and the previous hash of each message as the library does. The group features of
+//! the library, e.g. the group state, the keys and the indexes, are not maintained.
+//!
+//! ```ignore
+//! let mut writer: Writer<Identity, Signature, Sha256, _> = Writer::new(MemoryBackend::default());
+//! let (hash, signed_msg) = writer.sign::<Secret, MessageSigner>("group1", identity, &secret, data)?;
+//! assert!(writer.validate("group1"));
+//! ```
+
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::Digest;
+
+pub use crate::core::{
+    account::{Identity, Secret},
+    message::{MessageSigner, Verifiable},
+};
+use crate::{
+    core::{
+        compare::ct_eq,
+        message::{hash_to_string, Message, MessageHash, SignedMessage},
+    },
+    store,
+    writer::WriteError,
+};
+
+const KEY_MESSAGE: &str = "chain_msg";
+const KEY_LATEST_MESSAGEHASH: &str = "chain_latest_msghash";
+
+/// Backend is the storage of the items of the chains, which are strings under string keys. The writes
+/// fail with the reason, e.g. as the quota of the storage is exceeded.
+pub trait Backend {
+    fn get_item(&self, key: &str) -> Option<String>;
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), String>;
+    fn remove_item(&mut self, key: &str) -> Result<(), String>;
+}
+
+/// StorageBackend stores the items in the storage of the library, i.e. the local storage in the web
+/// builds and the in-memory storage in the native builds, under the current namespace.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StorageBackend;
+
+impl Backend for StorageBackend {
+    fn get_item(&self, key: &str) -> Option<String> {
+        store::get_namespaced_item(key)
+    }
+
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), String> {
+        store::set_namespaced_item(key, value)
+    }
+
+    fn remove_item(&mut self, key: &str) -> Result<(), String> {
+        store::remove_namespaced_item(key)
+    }
+}
+
+/// MemoryBackend stores the items in memory, e.g. for tests.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBackend {
+    pub items: BTreeMap<String, String>,
+}
+
+impl Backend for MemoryBackend {
+    fn get_item(&self, key: &str) -> Option<String> {
+        self.items.get(key).cloned()
+    }
+
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.items.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn remove_item(&mut self, key: &str) -> Result<(), String> {
+        self.items.remove(key);
+        Ok(())
+    }
+}
+
+// the writer owns no identity, signature or hasher, so it is Send and Sync whatever they are
+type Marker<I, S, H> = PhantomData<fn() -> (I, S, H)>;
+
+/// Writer appends signed messages to the chains of groups in the backend, where `I` is the identity, `S`
+/// is the signature, `H` is the hasher and `B` is the backend.
+pub struct Writer<I, S, H, B> {
+    backend: B,
+    _marker: Marker<I, S, H>,
+}
+
+impl<I, S, H, B> Writer<I, S, H, B>
+where
+    I: Identity + AsRef<[u8]> + Serialize + DeserializeOwned,
+    S: Verifiable<I> + Serialize + DeserializeOwned,
+    H: Digest,
+    B: Backend,
+{
+    /// Creates a writer over the backend.
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Returns the hash of the latest message of the group.
+    pub fn latest_hash(&self, group_id: &str) -> Option<MessageHash> {
+        self.get(&format!("{KEY_LATEST_MESSAGEHASH}_{group_id}"))
+    }
+
+    /// Returns the message of the group with the hash.
+    pub fn message(&self, group_id: &str, hash: &MessageHash) -> Option<SignedMessage<I, S>> {
+        self.get(&format!(
+            "{KEY_MESSAGE}_{group_id}_{}",
+            hash_to_string(hash)
+        ))
+    }
+
+    /// Returns the latest message of the group with its hash.
+    pub fn latest_message(&self, group_id: &str) -> Option<(MessageHash, SignedMessage<I, S>)> {
+        let hash = self.latest_hash(group_id)?;
+        self.message(group_id, &hash).map(|message| (hash, message))
+    }
+
+    /// Returns the messages of the group, ordered from the latest.
+    pub fn messages(&self, group_id: &str) -> Vec<SignedMessage<I, S>> {
+        let mut messages = vec![];
+        let mut hash = self.latest_hash(group_id);
+        while let Some(message) = hash.and_then(|hash| self.message(group_id, &hash)) {
+            hash = (!message.is_first_message()).then_some(message.message.previous_hash);
+            messages.push(message);
+        }
+        messages
+    }
+
+    /// Appends the signed message to the chain of the group. The message must be signed by its identity,
+    /// and it must be the first message of an empty chain, or follow the latest message of the chain.
+    /// It returns the hash of the message, and fails with [WriteError::Storage] if the backend fails to
+    /// write it.
+    pub fn write(
+        &mut self,
+        group_id: &str,
+        signed_msg: &SignedMessage<I, S>,
+    ) -> Result<MessageHash, WriteError> {
+        if !signed_msg.verify::<H>() {
+            return Err(WriteError::InvalidSignature);
+        }
+        let hash = signed_msg.hash::<H>();
+        if self.message(group_id, &hash).is_some() {
+            return Err(WriteError::AlreadyExists);
+        }
+        match self.latest_message(group_id) {
+            Some((latest_hash, latest)) => {
+                let next_seq = latest.next_seq().ok_or(WriteError::ChainFull)?;
+                if next_seq != signed_msg.seq {
+                    return Err(WriteError::WrongSequence);
+                }
+                if !ct_eq(&latest_hash, &signed_msg.message.previous_hash) {
+                    return Err(WriteError::WrongPreviousHash);
+                }
+            }
+            None if signed_msg.seq != 0 => return Err(WriteError::WrongSequence),
+            None if !signed_msg.is_first_message() => return Err(WriteError::WrongPreviousHash),
+            None => {}
+        }
+
+        self.set(
+            &format!("{KEY_MESSAGE}_{group_id}_{}", hash_to_string(&hash)),
+            signed_msg,
+        )
+        .map_err(WriteError::Storage)?;
+        self.set(&format!("{KEY_LATEST_MESSAGEHASH}_{group_id}"), hash)
+            .map_err(WriteError::Storage)?;
+        Ok(hash)
+    }
+
+    /// Signs the data as the next message of the chain of the group with the secret of the identity, and
+    /// appends it. It returns the hash and the signed message.
+    pub fn sign<K: Secret, A: MessageSigner<I, K, S>>(
+        &mut self,
+        group_id: &str,
+        id: I,
+        secret: &K,
+        data: Vec<u8>,
+    ) -> Result<(MessageHash, SignedMessage<I, S>), WriteError> {
+        let signed_msg = match self.latest_message(group_id) {
+            Some((hash, latest)) => {
+                let seq = latest.next_seq().ok_or(WriteError::ChainFull)?;
+                SignedMessage::sign::<K, A>(id, secret, Message::new(hash, data), seq)
+            }
+            None => SignedMessage::new_first_message::<K, A>(id, secret, data),
+        };
+        let hash = self.write(group_id, &signed_msg)?;
+        Ok((hash, signed_msg))
+    }
+
+    /// Validates the chain of the group from the latest message to the first message. Each message must
+    /// be signed by its identity and be the parent of the next message.
+    pub fn validate(&self, group_id: &str) -> bool {
+        let Some((latest_hash, latest)) = self.latest_message(group_id) else {
+            return true;
+        };
+        if !latest.verify::<H>() || !ct_eq(&latest.hash::<H>(), &latest_hash) {
+            return false;
+        }
+        let mut child = latest;
+        while !child.is_first_message() {
+            let Some(parent) = self.message(group_id, &child.message.previous_hash) else {
+                return false;
+            };
+            if !parent.is_valid_parent_of::<H>(&child) {
+                return false;
+            }
+            child = parent;
+        }
+        true
+    }
+
+    /// Removes the messages of the group. It fails if the backend fails to remove them.
+    pub fn clear(&mut self, group_id: &str) -> Result<(), String> {
+        let mut hash = self.latest_hash(group_id);
+        while let Some(current) = hash {
+            let Some(message) = self.message(group_id, &current) else {
+                break;
+            };
+            self.backend.remove_item(&format!(
+                "{KEY_MESSAGE}_{group_id}_{}",
+                hash_to_string(&current)
+            ))?;
+            hash = (!message.is_first_message()).then_some(message.message.previous_hash);
+        }
+        self.backend
+            .remove_item(&format!("{KEY_LATEST_MESSAGEHASH}_{group_id}"))
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_str(&self.backend.get_item(key)?).ok()
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), String> {
+        match serde_json::to_string(&value) {
+            Ok(str_value) => self.backend.set_item(key, &str_value),
+            Err(_) => Ok(()),
+        }
+    }
+}
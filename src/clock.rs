@@ -0,0 +1,203 @@
+//! Provides the clock of the library, which gives the timestamps of the groups and the messages.
+//!
+//! The clock is the system time by default, which users can trivially change. An app may replace it with
+//! [set_clock], e.g. with a callback to its own time service, or follow the time tokens signed by a trusted
+//! time authority with [set_time_authority]. The library reads the clock whenever it needs the current
+//! time, e.g. when it signs a message or checks the timestamps of received messages.
+//!
+//! ```ignore
+//! clock::set_time_authority(Some(authority));
+//! clock::accept_time_token(&token)?; // e.g. fetched from the authority
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use serde::{Deserialize, Serialize};
+use web_time::Instant;
+
+use crate::{
+    account::{Identity, Secret},
+    detached,
+    message::Signature,
+};
+
+/// The domain separator of the signatures of the time tokens.
+const TIME_TOKEN_PREFIX: &[u8] = b"webmessage time token";
+
+thread_local! {
+    static CLOCK: RefCell<Option<Rc<dyn Clock>>> = const { RefCell::new(None) };
+    static TOKEN_CLOCK: RefCell<Option<Rc<TokenClock>>> = const { RefCell::new(None) };
+}
+
+/// Clock is the source of the current time of the library.
+pub trait Clock {
+    /// Returns the current unix timestamp in seconds.
+    fn now(&self) -> u64;
+}
+
+impl<F: Fn() -> u64> Clock for F {
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+/// SystemClock is the time of the system, which is the default clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+}
+
+/// Replaces the clock of the library, or restores the [SystemClock] if it is None.
+pub fn set_clock(clock: Option<Rc<dyn Clock>>) {
+    CLOCK.with(|cell| *cell.borrow_mut() = clock);
+    TOKEN_CLOCK.with(|cell| *cell.borrow_mut() = None);
+}
+
+/// Replaces the clock of the library with a [TokenClock] which follows the time tokens signed by the
+/// authority, or restores the [SystemClock] if it is None.
+pub fn set_time_authority(authority: Option<Identity>) {
+    let token_clock = authority.map(|authority| Rc::new(TokenClock::new(authority)));
+    CLOCK.with(|cell| {
+        *cell.borrow_mut() = token_clock
+            .clone()
+            .map(|token_clock| token_clock as Rc<dyn Clock>)
+    });
+    TOKEN_CLOCK.with(|cell| *cell.borrow_mut() = token_clock);
+}
+
+/// Accepts the time token into the [TokenClock] set with [set_time_authority].
+pub fn accept_time_token(token: &TimeToken) -> Result<(), String> {
+    TOKEN_CLOCK
+        .with(|cell| cell.borrow().clone())
+        .ok_or("No time authority".to_string())?
+        .accept(token)
+}
+
+/// Returns the current unix timestamp in seconds from the clock of the library.
+pub(crate) fn now() -> u64 {
+    // the clock is taken out of the cell before it is called, so that it may call the library
+    match CLOCK.with(|cell| cell.borrow().clone()) {
+        Some(clock) => clock.now(),
+        None => SystemClock.now(),
+    }
+}
+
+/// TimeTokenBody is the content of a time token which is signed by the time authority.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeTokenBody {
+    /// The identity of the time authority.
+    pub authority: Identity,
+    /// The unix timestamp in seconds when the token is signed.
+    pub timestamp: u64,
+}
+
+/// TimeToken is the current time signed by a time authority.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TimeToken {
+    pub body: TimeTokenBody,
+    pub signature: Signature,
+}
+
+impl TimeToken {
+    /// Creates a time token signed by the authority.
+    pub fn new(body: TimeTokenBody, secret: &Secret) -> Self {
+        let signature = detached::sign_in_domain(
+            TIME_TOKEN_PREFIX,
+            &body.authority,
+            secret,
+            &signed_data(&body),
+        );
+        Self { body, signature }
+    }
+
+    /// Verifies the signature of the authority.
+    pub fn verify(&self) -> bool {
+        detached::verify_in_domain(
+            TIME_TOKEN_PREFIX,
+            &self.body.authority,
+            &signed_data(&self.body),
+            &self.signature,
+        )
+    }
+}
+
+/// TokenClock follows the time tokens signed by a time authority. The time is the timestamp of the
+/// latest token plus the time elapsed since it is accepted, measured by the monotonic clock of the
+/// system, so that changing the time of the system does not change it. It is the system time until the
+/// first token is accepted.
+pub struct TokenClock {
+    authority: Identity,
+    latest: Cell<Option<(u64, Instant)>>,
+}
+
+impl TokenClock {
+    /// Creates a clock which follows the time tokens of the authority.
+    pub fn new(authority: Identity) -> Self {
+        Self {
+            authority,
+            latest: Cell::new(None),
+        }
+    }
+
+    /// Accepts the time token if it is signed by the authority. A token older than the time of the clock is
+    /// ignored, so that the clock never goes back.
+    pub fn accept(&self, token: &TimeToken) -> Result<(), String> {
+        if token.body.authority != self.authority || !token.verify() {
+            return Err("Invalid time token".to_string());
+        }
+        if !self.is_synchronized() || token.body.timestamp > self.now() {
+            self.latest
+                .set(Some((token.body.timestamp, Instant::now())));
+        }
+        Ok(())
+    }
+
+    /// Checks if a token is accepted, i.e. the clock does not follow the system time.
+    pub fn is_synchronized(&self) -> bool {
+        self.latest.get().is_some()
+    }
+}
+
+impl Clock for TokenClock {
+    fn now(&self) -> u64 {
+        match self.latest.get() {
+            Some((timestamp, accepted_at)) => {
+                timestamp.saturating_add(accepted_at.elapsed().as_secs())
+            }
+            None => SystemClock.now(),
+        }
+    }
+}
+
+/// CallbackClock is the clock of a JavaScript function which returns the current unix timestamp in
+/// seconds. The system time is used if the function throws or returns no number.
+#[cfg(feature = "web")]
+pub(crate) struct CallbackClock(pub(crate) js_sys::Function);
+
+#[cfg(feature = "web")]
+impl Clock for CallbackClock {
+    fn now(&self) -> u64 {
+        self.0
+            .call0(&wasm_bindgen::JsValue::NULL)
+            .ok()
+            .and_then(|value| value.as_f64())
+            .filter(|timestamp| timestamp.is_finite() && *timestamp >= 0.0)
+            .map(|timestamp| timestamp as u64)
+            .unwrap_or_else(|| SystemClock.now())
+    }
+}
+
+/// Returns the data which the authority signs for the time token.
+fn signed_data(body: &TimeTokenBody) -> Vec<u8> {
+    serde_json::to_vec(body).unwrap()
+}
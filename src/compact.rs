@@ -0,0 +1,209 @@
+//! Provides the compact encoding of identities and signatures, which is the base64 of their bytes instead
+//! of the JSON of the schnorr_rs structures. Points on the P-256 curve are compressed (SEC1), so that an
+//! identity takes 33 bytes and a signature takes a fixed number of bytes.
+//!
+//! The bytes are the byte strings in the JSON of a structure, i.e. the arrays of bytes, the arrays of
+//! words and the hex strings, in order. They are decoded with the JSON of a structure of the same type as
+//! the template, which gives the positions, sizes and formats of the byte strings. A value is only
+//! encoded if it is decoded to the same JSON, so the JSON form, which is hashed and signed in messages,
+//! is always recovered from the compact form.
+
+use base64ct::{Base64, Encoding};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{core::message::to_hex, message::with_scheme};
+
+type PublicKey = schnorr_rs::PublicKey<schnorr_rs::SchnorrP256Group>;
+type SchnorrSignature = schnorr_rs::Signature<schnorr_rs::SchnorrP256Group>;
+
+/// The length of an uncompressed SEC1 point on the P-256 curve.
+const UNCOMPRESSED_POINT_LENGTH: usize = 65;
+/// The length of a compressed SEC1 point on the P-256 curve.
+const COMPRESSED_POINT_LENGTH: usize = 33;
+
+thread_local! {
+    /// The JSON of a public key and a signature, which are the templates of the decoding.
+    static TEMPLATES: (Value, Value) = templates();
+}
+
+fn templates() -> (Value, Value) {
+    let (signing_key, public_key) =
+        with_scheme(|scheme| scheme.generate_key(&mut rand::thread_rng()));
+    let digest: [u8; 32] = Sha256::digest([]).into();
+    let signature = with_scheme(|scheme| {
+        scheme.sign(&mut rand::thread_rng(), &signing_key, &public_key, digest)
+    });
+    (
+        serde_json::to_value(&public_key).unwrap(),
+        serde_json::to_value(&signature).unwrap(),
+    )
+}
+
+/// Returns the compact encoding of the JSON of a public key, or None if it is not a public key.
+pub fn compact_public_key(public_key: &str) -> Option<String> {
+    TEMPLATES.with(|(template, _)| compact::<PublicKey>(template, public_key))
+}
+
+/// Returns the JSON of the public key in the compact encoding, or None if it is not in the encoding.
+pub fn expand_public_key(compact: &str) -> Option<String> {
+    TEMPLATES.with(|(template, _)| expand::<PublicKey>(template, compact))
+}
+
+/// Returns the compact encoding of the JSON of a signature, or None if it is not a signature.
+pub fn compact_signature(signature: &str) -> Option<String> {
+    TEMPLATES.with(|(_, template)| compact::<SchnorrSignature>(template, signature))
+}
+
+/// Returns the JSON of the signature in the compact encoding, or None if it is not in the encoding.
+pub fn expand_signature(compact: &str) -> Option<String> {
+    TEMPLATES.with(|(_, template)| expand::<SchnorrSignature>(template, compact))
+}
+
+fn compact<T: Serialize + DeserializeOwned>(template: &Value, json: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    let mut bytes = vec![];
+    collect(&value, &mut bytes);
+    let compact = Base64::encode_string(&bytes);
+    (expand::<T>(template, &compact)? == json).then_some(compact)
+}
+
+fn expand<T: Serialize + DeserializeOwned>(template: &Value, compact: &str) -> Option<String> {
+    let bytes = Base64::decode_vec(compact).ok()?;
+    let mut rest = bytes.as_slice();
+    let value = fill(template, &mut rest)?;
+    if !rest.is_empty() {
+        return None;
+    }
+    // the JSON is serialized from the structure, so that its fields are in the order of the structure
+    let value: T = serde_json::from_value(value).ok()?;
+    serde_json::to_string(&value).ok()
+}
+
+/// The format of a byte string in JSON.
+enum Format {
+    /// An array of numbers less than 256.
+    Bytes,
+    /// An array of 64-bit numbers, each of which takes 8 bytes in big-endian.
+    Words,
+    /// A hex string in uppercase or lowercase.
+    Hex { upper: bool },
+}
+
+impl Format {
+    fn to_value(&self, bytes: &[u8]) -> Value {
+        match self {
+            Format::Bytes => Value::from(bytes.to_vec()),
+            Format::Words => Value::from(
+                bytes
+                    .chunks(8)
+                    .map(|word| u64::from_be_bytes(word.try_into().unwrap()))
+                    .collect::<Vec<_>>(),
+            ),
+            Format::Hex { upper: true } => Value::from(to_hex(bytes).to_uppercase()),
+            Format::Hex { upper: false } => Value::from(to_hex(bytes)),
+        }
+    }
+}
+
+/// Returns the format and the bytes if the value is a byte string.
+fn byte_string(value: &Value) -> Option<(Format, Vec<u8>)> {
+    match value {
+        Value::Array(items) if !items.is_empty() => {
+            let words = items
+                .iter()
+                .map(Value::as_u64)
+                .collect::<Option<Vec<u64>>>()?;
+            if words.iter().all(|word| *word <= u8::MAX as u64) {
+                Some((
+                    Format::Bytes,
+                    words.iter().map(|word| *word as u8).collect(),
+                ))
+            } else {
+                let bytes = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+                Some((Format::Words, bytes))
+            }
+        }
+        Value::String(hex) if !hex.is_empty() && hex.len() % 2 == 0 => {
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|idx| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok())
+                .collect::<Option<Vec<u8>>>()?;
+            let upper = hex.bytes().any(|c| c.is_ascii_uppercase());
+            Some((Format::Hex { upper }, bytes))
+        }
+        _ => None,
+    }
+}
+
+/// Appends the byte strings of the value, with the points compressed.
+fn collect(value: &Value, out: &mut Vec<u8>) {
+    if let Some((_, bytes)) = byte_string(value) {
+        match compress_point(&bytes) {
+            Some(point) => out.extend(point),
+            None => out.extend(bytes),
+        }
+        return;
+    }
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| collect(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect(item, out)),
+        _ => {}
+    }
+}
+
+/// Returns the template with its byte strings replaced with the bytes taken from the front of `rest`.
+fn fill(template: &Value, rest: &mut &[u8]) -> Option<Value> {
+    if let Some((format, template_bytes)) = byte_string(template) {
+        let is_point = compress_point(&template_bytes).is_some();
+        let len = if is_point {
+            COMPRESSED_POINT_LENGTH
+        } else {
+            template_bytes.len()
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let (bytes, remaining) = rest.split_at(len);
+        *rest = remaining;
+        let bytes = if is_point {
+            decompress_point(bytes)?
+        } else {
+            bytes.to_vec()
+        };
+        return Some(format.to_value(&bytes));
+    }
+    match template {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| fill(item, rest))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, item)| Some((key.clone(), fill(item, rest)?)))
+            .collect::<Option<Map<_, _>>>()
+            .map(Value::Object),
+        other => Some(other.clone()),
+    }
+}
+
+/// Returns the compressed point if the bytes are an uncompressed point on the P-256 curve.
+fn compress_point(bytes: &[u8]) -> Option<Vec<u8>> {
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+
+    if bytes.len() != UNCOMPRESSED_POINT_LENGTH {
+        return None;
+    }
+    let point = p256::PublicKey::from_sec1_bytes(bytes).ok()?;
+    Some(point.to_encoded_point(true).as_bytes().to_vec())
+}
+
+/// Returns the uncompressed point of a compressed point on the P-256 curve.
+fn decompress_point(bytes: &[u8]) -> Option<Vec<u8>> {
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+
+    let point = p256::PublicKey::from_sec1_bytes(bytes).ok()?;
+    Some(point.to_encoded_point(false).as_bytes().to_vec())
+}
@@ -0,0 +1,41 @@
+//! Provides the contacts, which are the known identities with local aliases, verification status and notes.
+//!
+//! Contacts are local to the device and are never written to the chain of a group.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{account::Identity, core::message::SignedMessage, message::Signature};
+
+/// VerificationStatus tells whether the identity of a contact is verified out of band.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    /// The identity is not verified.
+    #[default]
+    Unverified,
+    /// The identity is verified, e.g. by comparing fingerprints in person.
+    Verified,
+    /// The identity is known to be not trusted.
+    Distrusted,
+}
+
+/// Contact is a known identity.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Contact {
+    pub identity: Identity,
+    /// The local alias of the identity.
+    pub alias: String,
+    #[serde(default)]
+    pub verification: VerificationStatus,
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// AliasedMessage is a signed message with the alias of its signer, if the signer is a contact.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AliasedMessage {
+    #[serde(flatten)]
+    pub signed_message: SignedMessage<Identity, Signature>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
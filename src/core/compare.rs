@@ -0,0 +1,10 @@
+//! Provides the constant-time comparison of hashes and keys, so that the time taken by validation does
+//! not reveal how many leading bytes of the compared values match.
+
+use subtle::ConstantTimeEq;
+
+/// Returns true if the byte slices are equal. The time taken depends only on the lengths of the slices,
+/// which are not secret.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
@@ -1,6 +1,79 @@
 //! Group struct and its implementation.
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512_256};
+
+use super::{
+    account::Identity,
+    compare::ct_eq,
+    message::{to_hex, MessageHash, SignedMessage, Verifiable},
+};
+
+/// The name of the hash algorithm which links the messages of a group.
+pub const HASH_ALGORITHM_SHA256: &str = "sha256";
+/// The name of the hash algorithm SHA-512/256, to which the chains of groups may be migrated.
+pub const HASH_ALGORITHM_SHA512_256: &str = "sha512_256";
+
+/// The domain separator of the hash which derives the ids of direct groups.
+const DIRECT_GROUP_PREFIX: &[u8] = b"webmessage direct group";
+
+/// HashAlgorithm is the hash algorithm which links the messages of a group, i.e. which computes the
+/// previous hashes. The chain of a group starts with SHA-256, and a `HashMigration` record changes the
+/// algorithm with which the record and the messages after it are hashed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    #[default]
+    #[serde(rename = "sha256")]
+    Sha256,
+    #[serde(rename = "sha512_256")]
+    Sha512_256,
+}
+
+impl HashAlgorithm {
+    /// The supported algorithms.
+    pub const ALL: [HashAlgorithm; 2] = [HashAlgorithm::Sha256, HashAlgorithm::Sha512_256];
+
+    /// Returns the name of the algorithm.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => HASH_ALGORITHM_SHA256,
+            HashAlgorithm::Sha512_256 => HASH_ALGORITHM_SHA512_256,
+        }
+    }
+
+    /// Returns the hash of the signed message (see [SignedMessage::hash]) with the algorithm.
+    pub fn hash<I, S>(&self, signed_msg: &SignedMessage<I, S>) -> MessageHash
+    where
+        I: Identity + AsRef<[u8]>,
+        S: Verifiable<I>,
+    {
+        match self {
+            HashAlgorithm::Sha256 => signed_msg.hash::<Sha256>(),
+            HashAlgorithm::Sha512_256 => signed_msg.hash::<Sha512_256>(),
+        }
+    }
+
+    /// Returns the supported algorithm with which the signed message has the given hash.
+    pub fn of<I, S>(signed_msg: &SignedMessage<I, S>, hash: &MessageHash) -> Option<Self>
+    where
+        I: Identity + AsRef<[u8]>,
+        S: Verifiable<I>,
+    {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| ct_eq(&algorithm.hash(signed_msg), hash))
+    }
+}
+
+impl TryFrom<&str> for HashAlgorithm {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == value)
+            .ok_or("Invalid hash algorithm".to_string())
+    }
+}
 
 /// Defines a group for categorizing messages.
 #[derive(Clone, Serialize, Deserialize)]
@@ -9,19 +82,12 @@ pub struct Group {
     pub id: String,
     /// Unix timestamp
     pub timestamp: u64,
-}
-
-impl Group {
-    /// Create a new Group instance. The timestamp is set to the current time.
-    pub(crate) fn new(id: String) -> Self {
-        Self {
-            id,
-            timestamp: web_time::SystemTime::now()
-                .duration_since(web_time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        }
-    }
+    /// The unix timestamp when a message of the group was last written locally.
+    #[serde(default)]
+    pub last_activity: Option<u64>,
+    /// The local metadata of the group.
+    #[serde(flatten)]
+    pub metadata: GroupMetadata,
 }
 
 impl PartialEq for Group {
@@ -30,3 +96,221 @@ impl PartialEq for Group {
     }
 }
 impl Eq for Group {}
+
+/// GroupSortKey is the field by which groups are sorted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupSortKey {
+    /// The creation timestamp.
+    Created,
+    /// The last activity timestamp. Groups without activity are sorted by their creation timestamps.
+    Activity,
+    /// The display name.
+    Name,
+}
+
+impl TryFrom<&str> for GroupSortKey {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(value.to_string()))
+            .map_err(|_| format!("unknown sort key: {value}"))
+    }
+}
+
+/// SortOrder is the order in which groups are sorted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl TryFrom<&str> for SortOrder {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::String(value.to_string()))
+            .map_err(|_| format!("unknown sort order: {value}"))
+    }
+}
+
+/// Sorts the groups by the given key in the given order. Groups with equal keys keep their creation order.
+pub fn sort_groups(groups: &mut [Group], by: GroupSortKey, order: SortOrder) {
+    groups.sort_by(|a, b| {
+        let ordering = match by {
+            GroupSortKey::Created => a.timestamp.cmp(&b.timestamp),
+            GroupSortKey::Activity => a
+                .last_activity
+                .unwrap_or(a.timestamp)
+                .cmp(&b.last_activity.unwrap_or(b.timestamp)),
+            GroupSortKey::Name => a.metadata.display_name.cmp(&b.metadata.display_name),
+        };
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+}
+
+/// GroupMetadata is the local metadata of a group, which can be changed without signing messages and is
+/// never shared with other identities.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct GroupMetadata {
+    /// The display name of the group, which is the name in the genesis record unless it is renamed.
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub description: String,
+    /// The hash of the icon blob.
+    #[serde(default)]
+    pub icon: Option<MessageHash>,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// Defines the policy of a group, which is enforced when messages are written.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct GroupPolicy {
+    /// Requires the data of messages to be encrypted with the group content key.
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Requires messages to be signed by the members of the group at that point in the chain.
+    /// Sealed messages are rejected because their signers are ephemeral identities.
+    #[serde(default)]
+    pub members_only: bool,
+    /// How long the data of messages may be kept. The data of the messages which it expires may be purged
+    /// by the members, and the purged messages are still valid.
+    #[serde(default, skip_serializing_if = "RetentionPolicy::is_unlimited")]
+    pub retention: RetentionPolicy,
+}
+
+/// Defines how long the data of messages is kept in the local storage. The data of expired `Data`
+/// messages is purged by compaction, while the messages are kept with their hashes so that the chain
+/// remains linked. The latest message never expires.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// The maximum age (in seconds) of messages, by their timestamps.
+    #[serde(default)]
+    pub max_age: Option<u64>,
+    /// The maximum number of latest messages to keep.
+    #[serde(default)]
+    pub max_count: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Checks if the policy never expires messages.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age.is_none() && self.max_count.is_none()
+    }
+
+    /// Checks if the message is expired at the given unix timestamp, where the latest message of the
+    /// group has the given sequence number.
+    pub fn is_expired<I: Identity, S: Verifiable<I>>(
+        &self,
+        msg: &SignedMessage<I, S>,
+        latest_seq: u64,
+        timestamp: u64,
+    ) -> bool {
+        msg.message.kind.is_data()
+            && self.is_expired_at(msg.seq, msg.message.timestamp, latest_seq, timestamp)
+    }
+
+    /// Checks if the message with the given sequence number, which is signed at the given unix timestamp
+    /// at the latest, is expired at the given unix timestamp, where the latest message of the group has
+    /// the given sequence number. The latest message never expires.
+    pub fn is_expired_at(
+        &self,
+        seq: u64,
+        signed_at: Option<u64>,
+        latest_seq: u64,
+        timestamp: u64,
+    ) -> bool {
+        if seq >= latest_seq {
+            return false;
+        }
+        let too_many = self
+            .max_count
+            .is_some_and(|max_count| latest_seq - seq >= u64::from(max_count));
+        let too_old = self.max_age.is_some_and(|max_age| {
+            signed_at.is_some_and(|t| timestamp.saturating_sub(t) > max_age)
+        });
+        too_many || too_old
+    }
+}
+
+/// GroupGenesis is the data of the `Genesis` record, the first message of the chain of a group.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GroupGenesis<I> {
+    /// Group id uniquely identified
+    pub group_id: String,
+    /// Display name of the group
+    pub name: String,
+    /// The identity which created the group. It must be the signer of the record.
+    pub creator: I,
+    /// The hash algorithm which links the messages of the group.
+    pub hash_algorithm: String,
+    /// The policy of the group.
+    pub policy: GroupPolicy,
+    /// Unix timestamp
+    pub timestamp: u64,
+}
+
+impl<I: PartialEq> GroupGenesis<I> {
+    /// Creates a new genesis record created by the given identity. The timestamp is set to the current time.
+    pub fn new(group_id: String, name: String, creator: I, policy: GroupPolicy) -> Self {
+        Self {
+            group_id,
+            name,
+            creator,
+            hash_algorithm: HASH_ALGORITHM_SHA256.to_string(),
+            policy,
+            timestamp: unix_timestamp(),
+        }
+    }
+
+    /// Checks if the record is a valid genesis of the given group signed by the given identity.
+    pub fn is_valid_for(&self, group_id: &str, signer: &I) -> bool {
+        self.group_id == group_id
+            && &self.creator == signer
+            && self.hash_algorithm == HASH_ALGORITHM_SHA256
+    }
+
+    /// Returns the group created by the record.
+    pub fn to_group(&self) -> Group {
+        Group {
+            id: self.group_id.clone(),
+            timestamp: self.timestamp,
+            last_activity: None,
+            metadata: GroupMetadata {
+                display_name: self.name.clone(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Returns the canonical id of the group for the 1:1 conversation of the two identities, which is the
+/// same regardless of their order, so both peers derive it independently. The public keys are hashed
+/// in sorted order with their lengths.
+pub fn direct_group_id<I: AsRef<[u8]>>(identity: &I, other: &I) -> String {
+    let mut keys = [identity.as_ref(), other.as_ref()];
+    keys.sort();
+    let hash = keys
+        .iter()
+        .fold(
+            Sha256::new().chain_update(DIRECT_GROUP_PREFIX),
+            |hasher, key| {
+                hasher
+                    .chain_update((key.len() as u32).to_be_bytes())
+                    .chain_update(key)
+            },
+        )
+        .finalize();
+    format!("dm_{}", to_hex(&hash))
+}
+
+/// Returns the current unix timestamp in seconds from the clock of the library (see [crate::clock]).
+pub(crate) fn unix_timestamp() -> u64 {
+    crate::clock::now()
+}
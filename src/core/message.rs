@@ -1,18 +1,149 @@
 //! Contains the structs and traits that are used to represent messages in the system.
 
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
 use sha2::Digest;
 
-use super::account::{Identity, Secret};
+use super::{
+    account::{Identity, Secret},
+    compare::ct_eq,
+    group::GroupGenesis,
+};
 
 /// MessageHash is a type alias for a 32-byte array.
 pub type MessageHash = [u8; 32];
 
+/// The version of the format of the messages signed by this version of the library. Version 0 is the
+/// format of the messages signed before the version was recorded.
+///
+/// From version 1, the signed fields of a message are hashed as a JSON object with sorted keys, which
+/// includes the fields unknown to the verifier (see [Message::extensions]). Hence the fields added by
+/// later versions do not break the verification by earlier versions.
+pub const MESSAGE_VERSION: u32 = 1;
+
+/// The maximum sequence number of a message, so that a chain has at most 2^53 messages. It is the largest
+/// integer which the numbers of JavaScript represent exactly, so that web apps read the sequence numbers
+/// in the JSON of messages exactly.
+pub const MAX_SEQ: u64 = (1 << 53) - 1;
+
+/// Returns the canonical string of the hash, which is its lowercase hex encoding. It is also used in the
+/// keys of the stored items.
+pub fn hash_to_string(hash: &MessageHash) -> String {
+    to_hex(hash)
+}
+
+/// Parses the canonical string of a hash. It returns None if the string is not 64 hex digits.
+pub fn hash_from_string(value: &str) -> Option<MessageHash> {
+    if value.len() != 64 || !value.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (byte, digits) in hash.iter_mut().zip(value.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(hash)
+}
+
+/// Returns the lowercase hex encoding of the bytes.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Returns the bytes of the hex encoding in either case, or None if it is not hex.
+pub(crate) fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if value.len() % 2 == 1 || !value.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    value
+        .as_bytes()
+        .chunks(2)
+        .map(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok())
+        .collect()
+}
+
 /// The Verifiable is implemented on the types that can be verified, such as signature.
 pub trait Verifiable<I: Identity>: AsRef<[u8]> {
     fn verify(&self, id: &I, message: &[u8]) -> bool;
 }
 
+/// MessageKind tells how the data of a message is interpreted. Records other than `Data` carry
+/// serialized control records which are processed by the library.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    /// Application data.
+    #[default]
+    Data,
+    /// Creates the group. It is the first message of the chain.
+    Genesis,
+    /// Adds a member to the group.
+    AddMember,
+    /// Removes a member from the group.
+    RemoveMember,
+    /// Changes the policy of the group or the roles of the members.
+    Policy,
+    /// Joins the group with an invitation.
+    Join,
+    /// Presents a capability which delegates rights of the issuer to the signer.
+    Delegation,
+    /// Presents the certificate of the signing key of the signer, which is a device of a master identity.
+    DeviceCertificate,
+    /// Revokes a device of the signer.
+    DeviceRevocation,
+    /// Application data co-signed by a threshold of admins.
+    CoSigned,
+    /// Marks the data of an earlier message as deleted.
+    Redaction,
+    /// Summarizes the chain up to the previous message, so that older messages may be pruned.
+    Checkpoint,
+    /// Announces the key agreement public key of the signer.
+    KeyAnnouncement,
+    /// Distributes the group content key wrapped for members.
+    KeyDistribution,
+    /// Replaces the group content key with a new key wrapped for the remaining members.
+    Rekey,
+    /// Starts the ratchet over the group content key from the next message.
+    RatchetStart,
+    /// Seals the group by its creator. It is the last message of the chain.
+    Seal,
+    /// Pins a message of the group, so that it is listed in the pinned messages.
+    Pin,
+    /// Unpins a pinned message of the group.
+    Unpin,
+    /// Migrates the chain of the group to another hash algorithm. The record refers to the latest message
+    /// hashed with the previous algorithm, and it and the later messages are hashed with the new one.
+    HashMigration,
+}
+
+impl MessageKind {
+    /// Returns true if the kind is `Data`.
+    pub fn is_data(&self) -> bool {
+        *self == MessageKind::Data
+    }
+}
+
+/// MessageRef refers to a message in a group, which may be another group than the group of the referring
+/// message.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageRef {
+    /// The id of the group of the message.
+    pub group_id: String,
+    /// The hash of the message.
+    pub hash: MessageHash,
+}
+
+/// Tombstone is what is kept of a message whose data is purged, so that the stored message is still checked
+/// against the hash and the signature which it had before.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstone {
+    /// The hash of the signed message before its data is purged (see [SignedMessage::hash]).
+    pub hash: MessageHash,
+    /// The hash of the message which is signed (see [Message::to_hash]).
+    pub digest: MessageHash,
+}
+
 /// Message is a struct that represents a message.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -20,26 +151,158 @@ pub struct Message {
     pub previous_hash: MessageHash,
     /// data is the data of the message.
     pub data: Vec<u8>,
+    /// compressed indicates that `data` is stored in deflated form. It is a storage flag only,
+    /// so it is not covered by the message hash.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compressed: bool,
+    /// encrypted indicates that `data` is the ciphertext under the group content key.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+    /// kind of the message.
+    #[serde(default, skip_serializing_if = "MessageKind::is_data")]
+    pub kind: MessageKind,
+    /// sealed indicates that the signer is an ephemeral identity and the sender is sealed in the
+    /// encrypted data.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sealed: bool,
+    /// reply_to is the hash of the message to which this message replies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<MessageHash>,
+    /// supersedes is the hash of the message of which this message is an edit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supersedes: Option<MessageHash>,
+    /// timestamp is the unix timestamp when the message was signed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    /// tags of the message, which can be queried without reading the data.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// reference is the message in another group to which this message refers, e.g. as a quote or evidence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<MessageRef>,
+    /// redacted indicates that `data` is removed after a redaction record or by the retention policy.
+    /// It is a storage flag only, so it is not covered by the message hash.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+    /// tombstone of the message whose data is purged. It is a storage field only, so it is not covered by
+    /// the message hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tombstone: Option<Tombstone>,
+    /// version of the message format (see [MESSAGE_VERSION]).
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub version: u32,
+    /// extensions are the fields unknown to this version of the library, e.g. the fields added by later
+    /// versions. They are kept as they are, and are covered by the hash of messages from version 1.
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+/// SignedFields is a view of the optional fields of a message which are covered by the message hash.
+/// Fields with default values are skipped, so that messages without them hash the same as before.
+#[derive(Serialize)]
+struct SignedFields<'a> {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    encrypted: bool,
+    #[serde(skip_serializing_if = "MessageKind::is_data")]
+    kind: MessageKind,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    sealed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_to: Option<MessageHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supersedes: Option<MessageHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "is_empty")]
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reference: Option<&'a MessageRef>,
+    #[serde(skip_serializing_if = "is_zero")]
+    version: u32,
 }
 
 impl Message {
-    /// Creates a new message with the given data and a zero hash as the previous hash.
-    pub fn root(data: Vec<u8>) -> Self {
+    /// Creates a new message with the given previous hash and data.
+    pub fn new(previous_hash: MessageHash, data: Vec<u8>) -> Self {
         Self {
-            previous_hash: [0; 32],
+            previous_hash,
             data,
+            compressed: false,
+            encrypted: false,
+            kind: MessageKind::Data,
+            sealed: false,
+            reply_to: None,
+            supersedes: None,
+            timestamp: None,
+            tags: vec![],
+            reference: None,
+            redacted: false,
+            tombstone: None,
+            version: 0,
+            extensions: BTreeMap::new(),
         }
     }
 
-    /// Hash by hashing the previous hash and the data of the message.
+    /// Creates a new message with the given data and a zero hash as the previous hash.
+    pub fn root(data: Vec<u8>) -> Self {
+        Self::new([0; 32], data)
+    }
+
+    /// Hash by hashing the previous hash, the data and the optional signed fields of the message.
     pub fn to_hash<H: Digest>(&self) -> MessageHash {
         H::new()
-            .chain_update([self.previous_hash.to_vec(), self.data.clone()].concat())
+            .chain_update(self.previous_hash)
+            .chain_update(&self.data)
+            .chain_update(self.signed_fields())
             .finalize()
             .as_ref()
             .try_into()
             .unwrap()
     }
+
+    /// Returns the serialized optional signed fields, or empty bytes if all of them have default values.
+    /// The fields of messages from version 1 are serialized with sorted keys together with the extensions,
+    /// while the fields of version 0 messages are serialized in the order of the struct.
+    fn signed_fields(&self) -> Vec<u8> {
+        let fields = SignedFields {
+            encrypted: self.encrypted,
+            kind: self.kind,
+            sealed: self.sealed,
+            reply_to: self.reply_to,
+            supersedes: self.supersedes,
+            timestamp: self.timestamp,
+            tags: &self.tags,
+            reference: self.reference.as_ref(),
+            version: self.version,
+        };
+        if self.version == 0 {
+            return match serde_json::to_vec(&fields) {
+                Ok(bytes) if bytes != b"{}" => bytes,
+                _ => vec![],
+            };
+        }
+
+        let known = match serde_json::to_value(&fields) {
+            Ok(Value::Object(known)) => known,
+            _ => Map::new(),
+        };
+        // the known fields take precedence over the extensions with the same names
+        let canonical: BTreeMap<&str, &Value> = self
+            .extensions
+            .iter()
+            .chain(known.iter())
+            .map(|(key, value)| (key.as_str(), value))
+            .collect();
+        serde_json::to_vec(&canonical).unwrap()
+    }
+}
+
+fn is_empty(tags: &&[String]) -> bool {
+    tags.is_empty()
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
 pub trait MessageSigner<I: Identity, K: Secret, S: Verifiable<I>> {
@@ -54,9 +317,48 @@ pub struct SignedMessage<I: Identity, S: Verifiable<I>> {
     /// the identity of the signer.
     pub id: I,
     /// the sequence number in the chain.
-    pub seq: u32,
+    pub seq: u64,
     /// the signature of the message.
     pub signature: S,
+    /// the version of the format of the signed message, which is the version of the message when it is
+    /// signed by this library. It is not covered by the signature.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub version: u32,
+}
+
+impl<I, S> SignedMessage<I, S>
+where
+    I: Identity + Clone,
+    S: Verifiable<I> + Clone,
+{
+    /// Returns a copy of the signed message with the given data and `compressed` flag, e.g. the data as
+    /// it is stored. The data of this message is not copied.
+    pub fn with_data(&self, data: Vec<u8>, compressed: bool) -> Self {
+        let message = &self.message;
+        Self {
+            message: Message {
+                previous_hash: message.previous_hash,
+                data,
+                compressed,
+                encrypted: message.encrypted,
+                kind: message.kind,
+                sealed: message.sealed,
+                reply_to: message.reply_to,
+                supersedes: message.supersedes,
+                timestamp: message.timestamp,
+                tags: message.tags.clone(),
+                reference: message.reference.clone(),
+                redacted: message.redacted,
+                tombstone: message.tombstone,
+                version: message.version,
+                extensions: message.extensions.clone(),
+            },
+            id: self.id.clone(),
+            seq: self.seq,
+            signature: self.signature.clone(),
+            version: self.version,
+        }
+    }
 }
 
 impl<I, S> SignedMessage<I, S>
@@ -64,22 +366,48 @@ where
     I: Identity + AsRef<[u8]>,
     S: Verifiable<I>,
 {
-    /// Creates a new first message with the given data and signs it.
-    pub fn new_first_message<K: Secret, A: MessageSigner<I, K, S>>(
+    /// Signs the given message as the message at the given sequence number, in the format of
+    /// [MESSAGE_VERSION].
+    pub fn sign<K: Secret, A: MessageSigner<I, K, S>>(
         id: I,
         secret: &K,
-        data: Vec<u8>,
+        mut message: Message,
+        seq: u64,
     ) -> Self {
-        let message = Message::root(data);
+        message.version = MESSAGE_VERSION;
         let signature = A::sign(&id, secret, &message);
         Self {
             message,
             id,
-            seq: 0,
+            seq,
             signature,
+            version: MESSAGE_VERSION,
         }
     }
 
+    /// Creates the genesis record of a group and signs it as the first message.
+    pub fn new_genesis<K: Secret, A: MessageSigner<I, K, S>>(
+        id: I,
+        secret: &K,
+        genesis: &GroupGenesis<I>,
+    ) -> Self
+    where
+        I: Serialize,
+    {
+        let mut message = Message::root(serde_json::to_vec(genesis).unwrap());
+        message.kind = MessageKind::Genesis;
+        Self::sign::<K, A>(id, secret, message, 0)
+    }
+
+    /// Creates a new first message with the given data and signs it.
+    pub fn new_first_message<K: Secret, A: MessageSigner<I, K, S>>(
+        id: I,
+        secret: &K,
+        data: Vec<u8>,
+    ) -> Self {
+        Self::sign::<K, A>(id, secret, Message::root(data), 0)
+    }
+
     /// Creates a new message from the previous message with the given data and signs it.
     pub fn new_from_previous_message<K: Secret, A: MessageSigner<I, K, S>>(
         id: I,
@@ -88,17 +416,12 @@ where
         hash: MessageHash,
         signed_message: SignedMessage<I, S>,
     ) -> Self {
-        let message = Message {
-            previous_hash: hash,
-            data,
-        };
-        let signature = A::sign(&id, secret, &message);
-        Self {
-            message,
+        Self::sign::<K, A>(
             id,
-            seq: signed_message.seq + 1,
-            signature,
-        }
+            secret,
+            Message::new(hash, data),
+            signed_message.seq.saturating_add(1),
+        )
     }
 
     /// verifies if the signature of the message is valid.
@@ -107,19 +430,29 @@ where
             .verify(&self.id, &self.message.to_hash::<H>())
     }
 
+    /// Verifies the tombstone of the message whose data is purged. It must be kept for the given hash, and
+    /// the signature must be valid for the digest which it keeps.
+    pub fn verify_tombstone(&self, hash: &MessageHash) -> bool {
+        self.message.tombstone.is_some_and(|tombstone| {
+            ct_eq(&tombstone.hash, hash) && self.signature.verify(&self.id, &tombstone.digest)
+        })
+    }
+
     /// hash returns the hash of the signed message.
     /// The hash is calculated by hashing the data of the message, the id, the sequence number, and the signature.
+    /// The sequence numbers up to `u32::MAX` are hashed in 4 bytes, as they were before they were widened to
+    /// 64 bits, so that the hashes of the stored chains are unchanged.
     pub fn hash<H: Digest>(&self) -> MessageHash {
+        let seq = self.seq.to_le_bytes();
+        let seq = match u32::try_from(self.seq) {
+            Ok(_) => &seq[..4],
+            Err(_) => &seq[..],
+        };
         H::new()
-            .chain_update(
-                [
-                    &self.message.data,
-                    self.id.as_ref(),
-                    &self.seq.to_le_bytes(),
-                    self.signature.as_ref(),
-                ]
-                .concat(),
-            )
+            .chain_update(&self.message.data)
+            .chain_update(self.id.as_ref())
+            .chain_update(seq)
+            .chain_update(self.signature.as_ref())
             .finalize()
             .as_ref()
             .try_into()
@@ -129,13 +462,38 @@ where
     /// Checks if the message is a valid parent of the other message. It checks the conditions such as
     /// the hash of the message, the sequence number, and the signature validation of other message.
     pub fn is_valid_parent_of<H: Digest>(&self, other: &Self) -> bool {
-        self.hash::<H>() == other.message.previous_hash
-            && self.seq + 1 == other.seq
+        ct_eq(&self.hash::<H>(), &other.message.previous_hash)
+            && self.next_seq() == Some(other.seq)
             && other.verify::<H>()
     }
 
+    /// Returns the sequence number of the next message, or None if the chain has reached [MAX_SEQ].
+    pub fn next_seq(&self) -> Option<u64> {
+        (self.seq < MAX_SEQ).then_some(self.seq + 1)
+    }
+
     /// Checks if the message is the first message.
     pub fn is_first_message(&self) -> bool {
         self.seq == 0 && self.message.previous_hash == [0u8; 32]
     }
+
+    /// Returns the genesis record if the message is the first message and a `Genesis` record.
+    pub fn genesis(&self) -> Option<GroupGenesis<I>>
+    where
+        I: DeserializeOwned,
+    {
+        if !self.is_first_message() || self.message.kind != MessageKind::Genesis {
+            return None;
+        }
+        serde_json::from_slice(&self.message.data).ok()
+    }
+
+    /// Checks if the message is a valid genesis record of the given group.
+    pub fn is_genesis_of(&self, group_id: &str) -> bool
+    where
+        I: DeserializeOwned,
+    {
+        self.genesis()
+            .is_some_and(|genesis| genesis.is_valid_for(group_id, &self.id))
+    }
 }
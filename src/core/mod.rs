@@ -1,5 +1,6 @@
 //! Contains the core functionality of the library.
 
 pub(crate) mod account;
+pub(crate) mod compare;
 pub(crate) mod group;
 pub(crate) mod message;
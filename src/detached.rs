@@ -0,0 +1,51 @@
+//! Provides the detached signatures over arbitrary data with the keys of the accounts, so that apps can
+//! authenticate payloads out of band, e.g. login challenges or file hashes, with the identities of the chains.
+//!
+//! The data is signed as a message after the hash of a domain separator instead of the zero hash, so that a
+//! detached signature is never a valid signature of a message in a chain.
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::{Message, MessageSigner as _, Verifiable},
+    message::{MessageSigner, Signature},
+};
+
+/// The domain separator of the detached signatures.
+const DETACHED_PREFIX: &[u8] = b"webmessage detached signature";
+
+/// Signs the data with the secret of the identity.
+pub fn sign(identity: &Identity, secret: &Secret, data: &[u8]) -> Signature {
+    sign_in_domain(DETACHED_PREFIX, identity, secret, data)
+}
+
+/// Verifies the detached signature of the data by the identity.
+pub fn verify(identity: &Identity, data: &[u8], signature: &Signature) -> bool {
+    verify_in_domain(DETACHED_PREFIX, identity, data, signature)
+}
+
+/// Signs the data under the domain separator, so that the signature is valid in the domain only.
+pub(crate) fn sign_in_domain(
+    domain: &[u8],
+    identity: &Identity,
+    secret: &Secret,
+    data: &[u8],
+) -> Signature {
+    MessageSigner::sign(identity, secret, &signed_message(domain, data))
+}
+
+/// Verifies the signature of the data under the domain separator.
+pub(crate) fn verify_in_domain(
+    domain: &[u8],
+    identity: &Identity,
+    data: &[u8],
+    signature: &Signature,
+) -> bool {
+    signature.is_well_formed()
+        && signature.verify(identity, &signed_message(domain, data).to_hash::<Sha256>())
+}
+
+fn signed_message(domain: &[u8], data: &[u8]) -> Message {
+    Message::new(Sha256::digest(domain).into(), data.to_vec())
+}
@@ -0,0 +1,64 @@
+//! Provides the certificates with which a master identity certifies the signing keys of its devices.
+//!
+//! A device signs messages with its own key and presents its certificate in a `DeviceCertificate`
+//! record. Afterwards, the messages signed by the device are validated with the role of the master
+//! until the master revokes the device in a `DeviceRevocation` record.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Identity, Secret},
+    detached,
+    message::Signature,
+};
+
+/// The domain separator of the signatures of the device certificates.
+const DEVICE_PREFIX: &[u8] = b"webmessage device certificate";
+
+/// DeviceCertificateBody is the content of a device certificate which is signed by the master.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceCertificateBody {
+    /// The identity which certifies the device.
+    pub master: Identity,
+    /// The signing key of the device.
+    pub device: Identity,
+    /// The name of the device.
+    pub name: String,
+}
+
+/// DeviceCertificate is a device key certified by the master.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceCertificate {
+    pub body: DeviceCertificateBody,
+    pub signature: Signature,
+}
+
+impl DeviceCertificate {
+    /// Creates a device certificate signed by the master.
+    pub fn new(body: DeviceCertificateBody, secret: &Secret) -> Self {
+        let signature =
+            detached::sign_in_domain(DEVICE_PREFIX, &body.master, secret, &signed_data(&body));
+        Self { body, signature }
+    }
+
+    /// Verifies the signature of the master.
+    pub fn verify(&self) -> bool {
+        detached::verify_in_domain(
+            DEVICE_PREFIX,
+            &self.body.master,
+            &signed_data(&self.body),
+            &self.signature,
+        )
+    }
+}
+
+/// DeviceRevocation is the data of the `DeviceRevocation` record.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceRevocation {
+    pub device: Identity,
+}
+
+/// Returns the data which the master signs for the device certificate.
+fn signed_data(body: &DeviceCertificateBody) -> Vec<u8> {
+    serde_json::to_vec(body).unwrap()
+}
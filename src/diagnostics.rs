@@ -0,0 +1,136 @@
+//! Provides the logging of the library with levels, and the diagnostics, which are the recent warnings and
+//! errors, e.g. the validation failures and the storage errors.
+//!
+//! The entries at or above the level set with [set_level] are written to the console, or to the standard
+//! error in the native builds. Nothing is written by default. The warnings and errors are also kept in a
+//! ring buffer of the [CAPACITY] most recent entries, whatever the level of the console is.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fmt::Display,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::group::unix_timestamp;
+
+/// The maximum number of kept diagnostics.
+pub const CAPACITY: usize = 100;
+
+thread_local! {
+    static CONSOLE_LEVEL: Cell<Option<Level>> = const { Cell::new(None) };
+    static DIAGNOSTICS: RefCell<VecDeque<Diagnostic>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Level is the severity of a log entry, from the most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl TryFrom<&str> for Level {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err("Invalid log level".to_string()),
+        }
+    }
+}
+
+/// Category is the part of the library which writes a log entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// The validation of the chains of messages.
+    Validation,
+    /// The access to the local storage.
+    Storage,
+}
+
+/// Diagnostic is a warning or an error kept in the ring buffer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub category: Category,
+    pub message: String,
+    /// The unix timestamp when the entry is written.
+    pub timestamp: u64,
+}
+
+/// Sets the level of the entries written to the console. Nothing is written if it is None.
+pub fn set_level(level: Option<Level>) {
+    CONSOLE_LEVEL.set(level);
+}
+
+/// Returns the kept diagnostics, ordered from the oldest.
+pub fn diagnostics() -> Vec<Diagnostic> {
+    DIAGNOSTICS.with(|diagnostics| diagnostics.borrow().iter().cloned().collect())
+}
+
+/// Removes the kept diagnostics.
+pub fn clear() {
+    DIAGNOSTICS.with(|diagnostics| diagnostics.borrow_mut().clear());
+}
+
+/// Writes the log entry to the console if its level is enabled, and keeps it if it is a warning or an error.
+pub(crate) fn log(level: Level, category: Category, message: impl Display) {
+    let is_console = CONSOLE_LEVEL.get().is_some_and(|console| level <= console);
+    if !is_console && level > Level::Warn {
+        return;
+    }
+    let message = message.to_string();
+    if is_console {
+        write_console(level, category, &message);
+    }
+    if level <= Level::Warn {
+        DIAGNOSTICS.with(|diagnostics| {
+            let mut diagnostics = diagnostics.borrow_mut();
+            if diagnostics.len() == CAPACITY {
+                diagnostics.pop_front();
+            }
+            diagnostics.push_back(Diagnostic {
+                level,
+                category,
+                message,
+                timestamp: unix_timestamp(),
+            });
+        });
+    }
+}
+
+pub(crate) fn error(category: Category, message: impl Display) {
+    log(Level::Error, category, message)
+}
+
+pub(crate) fn warn(category: Category, message: impl Display) {
+    log(Level::Warn, category, message)
+}
+
+pub(crate) fn debug(category: Category, message: impl Display) {
+    log(Level::Debug, category, message)
+}
+
+#[cfg(feature = "web")]
+fn write_console(level: Level, category: Category, message: &str) {
+    let line = wasm_bindgen::JsValue::from_str(&format!("[webmessage] {category:?}: {message}"));
+    match level {
+        Level::Error => web_sys::console::error_1(&line),
+        Level::Warn => web_sys::console::warn_1(&line),
+        Level::Info => web_sys::console::info_1(&line),
+        Level::Debug => web_sys::console::debug_1(&line),
+    }
+}
+
+#[cfg(not(feature = "web"))]
+fn write_console(level: Level, category: Category, message: &str) {
+    eprintln!("[webmessage] {level:?} {category:?}: {message}");
+}
@@ -0,0 +1,33 @@
+//! Provides the drafts, which are unsigned messages in progress.
+//!
+//! Drafts are local to the device and are never written to the chain of a group until they are signed.
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::rng::Rng;
+
+/// Draft is an unsigned message in progress in a group.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Draft {
+    /// The random id of the draft, unique in the group.
+    pub id: String,
+    /// The data of the message.
+    pub data: String,
+    /// The unix timestamp when the draft was created.
+    pub created_at: u64,
+    /// The unix timestamp when the draft was last saved.
+    pub updated_at: u64,
+}
+
+impl Draft {
+    /// Creates a new draft with a random id, created at the given unix timestamp.
+    pub fn new(data: String, timestamp: u64) -> Self {
+        Self {
+            id: format!("{:016x}", Rng.next_u64()),
+            data,
+            created_at: timestamp,
+            updated_at: timestamp,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Provides the symmetric encryption of message data with the group content key (AES-256-GCM).
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit},
+    Aes256Gcm, Key, Nonce,
+};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::rng::Rng;
+
+/// GroupKey is the symmetric content key of a group.
+pub type GroupKey = [u8; 32];
+
+/// The length of the nonce which is prepended to the ciphertext.
+const NONCE_LENGTH: usize = 12;
+
+/// Generates a new random group key.
+pub fn generate_group_key() -> GroupKey {
+    let mut key = [0u8; 32];
+    Rng.fill_bytes(&mut key);
+    key
+}
+
+/// Derives a key from the passphrase with PBKDF2-HMAC-SHA256, e.g. to encrypt an exported secret.
+pub fn derive_passphrase_key(passphrase: &str, salt: &[u8], iterations: u32) -> GroupKey {
+    let prf = <Hmac<Sha256> as Mac>::new_from_slice(passphrase.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    // the key is a single block of the output
+    let mut block: [u8; 32] = prf
+        .clone()
+        .chain_update(salt)
+        .chain_update(1u32.to_be_bytes())
+        .finalize()
+        .into_bytes()
+        .into();
+    let mut key = block;
+    for _ in 1..iterations {
+        block = prf
+            .clone()
+            .chain_update(block)
+            .finalize()
+            .into_bytes()
+            .into();
+        key.iter_mut().zip(block).for_each(|(k, b)| *k ^= b);
+    }
+    key
+}
+
+/// Encrypts the plaintext with the group key. The output is the random nonce followed by the ciphertext.
+pub fn encrypt(key: &GroupKey, plaintext: &[u8]) -> Vec<u8> {
+    let cipher = Aes256Gcm::new(&Key::<Aes256Gcm>::from(*key));
+    let nonce = Aes256Gcm::generate_nonce(&mut Rng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .expect("encryption of in-memory data should not fail");
+    [nonce.to_vec(), ciphertext].concat()
+}
+
+/// Decrypts the output of [encrypt] with the group key. It returns None if the data is malformed or
+/// was not encrypted with the given key.
+pub fn decrypt(key: &GroupKey, data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < NONCE_LENGTH {
+        return None;
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+    let nonce = Nonce::from(<[u8; NONCE_LENGTH]>::try_from(nonce).ok()?);
+    let cipher = Aes256Gcm::new(&Key::<Aes256Gcm>::from(*key));
+    cipher.decrypt(&nonce, ciphertext).ok()
+}
+
+/// Decrypts the output of [encrypt] with the first of the given keys that succeeds.
+pub fn decrypt_with_any<'a>(
+    keys: impl IntoIterator<Item = &'a GroupKey>,
+    data: &[u8],
+) -> Option<Vec<u8>> {
+    keys.into_iter().find_map(|key| decrypt(key, data))
+}
@@ -0,0 +1,124 @@
+//! Provides the evidence bundle of a message, which is a self-contained archive that a third party
+//! verifies offline with [Evidence::verify] (or `verifyEvidence`) without access to the group.
+//!
+//! The bundle contains the message and its ancestors up to the genesis record or a checkpoint, the
+//! public keys of their signers, and the proofs that the message is part of the chain at the time of
+//! export and that the chain at export extends the chain at the message.
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, MessageKind, SignedMessage},
+    message::Signature,
+    mmr::{ConsistencyProof, InclusionProof, MerkleMountainRange, MerklePeaks},
+    state::{Checkpoint, GroupState},
+};
+
+/// Evidence is the evidence bundle of a message.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Evidence {
+    /// The group of the message.
+    pub group_id: String,
+    /// The hash of the message.
+    pub hash: MessageHash,
+    /// The message and its ancestors from the genesis record or a checkpoint, from the oldest.
+    pub messages: Vec<SignedMessage<Identity, Signature>>,
+    /// The public keys of the signers of the messages.
+    pub keys: Vec<Identity>,
+    /// The merkle root of the chain at the time of export.
+    pub root: MessageHash,
+    /// The proof that the message is included in the chain with the root.
+    pub inclusion: Option<InclusionProof>,
+    /// The proof that the chain with the root extends the chain at the message.
+    pub consistency: Option<ConsistencyProof>,
+}
+
+impl Evidence {
+    /// Creates the evidence bundle of the last message in `messages`, which are ordered from the oldest.
+    /// The proofs are taken from the Merkle Mountain Range of the group, if it contains the message.
+    pub fn new(
+        group_id: &str,
+        messages: Vec<SignedMessage<Identity, Signature>>,
+        mmr: &MerkleMountainRange,
+    ) -> Option<Self> {
+        let message = messages.last()?;
+        let hash = message.hash::<Sha256>();
+        let inclusion = mmr.prove(&hash);
+        let consistency = inclusion
+            .as_ref()
+            .and_then(|_| mmr.prove_consistency(message.seq + 1));
+
+        let mut keys: Vec<Identity> = vec![];
+        messages.iter().for_each(|message| {
+            if !keys.contains(&message.id) {
+                keys.push(message.id.clone());
+            }
+        });
+
+        Some(Self {
+            group_id: group_id.to_string(),
+            hash,
+            messages,
+            keys,
+            root: mmr.root(),
+            inclusion,
+            consistency,
+        })
+    }
+
+    /// Verifies the evidence bundle. The first message must be the genesis record of the group or a
+    /// checkpoint, and the messages must be signed by the given keys, linked in order, and follow the
+    /// group policy and membership. The proofs, if any, must be valid for the message against the root.
+    pub fn verify(&self) -> Result<(), String> {
+        let first = self.messages.first().ok_or("no messages".to_string())?;
+        let mut state = match first.message.kind {
+            MessageKind::Genesis => GroupState::default(),
+            MessageKind::Checkpoint => {
+                serde_json::from_slice::<Checkpoint>(&first.message.data)
+                    .map_err(|_| "invalid checkpoint record".to_string())?
+                    .state
+            }
+            _ => return Err("messages start from neither genesis nor checkpoint".to_string()),
+        };
+
+        let mut previous: Option<&SignedMessage<Identity, Signature>> = None;
+        for message in self.messages.iter() {
+            if !self.keys.contains(&message.id) {
+                return Err("signer key is missing".to_string());
+            }
+            let is_linked = match previous {
+                Some(previous) => previous.is_valid_parent_of::<Sha256>(message),
+                None => message.verify::<Sha256>(),
+            };
+            if !is_linked {
+                return Err("messages are not linked".to_string());
+            }
+            state.check(&self.group_id, message)?;
+            state.update(message);
+            previous = Some(message);
+        }
+        if previous.map(|message| message.hash::<Sha256>()) != Some(self.hash) {
+            return Err("message hash does not match".to_string());
+        }
+
+        if let Some(inclusion) = &self.inclusion {
+            if inclusion.leaf != self.hash || !inclusion.verify(&self.root) {
+                return Err("invalid inclusion proof".to_string());
+            }
+        }
+        if let Some(consistency) = &self.consistency {
+            let old_head = &consistency.old_head;
+            let old_root = MerklePeaks {
+                size: old_head.size,
+                peaks: old_head.peaks.clone(),
+            }
+            .root();
+            if old_head.leaf != self.hash || !consistency.verify(&old_root, &self.root) {
+                return Err("invalid consistency proof".to_string());
+            }
+        }
+        Ok(())
+    }
+}
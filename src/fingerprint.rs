@@ -0,0 +1,56 @@
+//! Provides the fingerprints of identities and the safety numbers of pairs of identities, which are
+//! short enough for users to compare out of band, e.g. read aloud or side by side on two screens.
+//!
+//! The fingerprint of an identity is 30 digits derived from its public key by iterated hashing, in
+//! groups of 5 digits. The safety number of two identities is their fingerprints in sorted order, so
+//! both parties compute the same 60 digits.
+
+use sha2::{Digest, Sha256};
+
+use crate::account::Identity;
+
+/// The version of the fingerprint format, which is hashed with the public key.
+const FINGERPRINT_VERSION: u16 = 0;
+/// The number of iterations of the hash, which makes finding a public key with a given fingerprint costly.
+const FINGERPRINT_ITERATIONS: usize = 5200;
+/// The number of groups of 5 digits in a fingerprint.
+const FINGERPRINT_GROUPS: usize = 6;
+
+/// Returns the fingerprint of the identity, which is 30 digits in groups of 5 separated by spaces.
+pub fn fingerprint(identity: &Identity) -> String {
+    let mut hash: [u8; 32] = Sha256::new()
+        .chain_update(FINGERPRINT_VERSION.to_be_bytes())
+        .chain_update(identity)
+        .finalize()
+        .into();
+    for _ in 1..FINGERPRINT_ITERATIONS {
+        hash = Sha256::new()
+            .chain_update(hash)
+            .chain_update(identity)
+            .finalize()
+            .into();
+    }
+
+    hash.chunks(5)
+        .take(FINGERPRINT_GROUPS)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, byte| acc << 8 | *byte as u64);
+            format!("{:05}", value % 100000)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the safety number of the two identities, which is the same regardless of their order.
+pub fn safety_number(identity: &Identity, other: &Identity) -> String {
+    let mut fingerprints = [fingerprint(identity), fingerprint(other)];
+    fingerprints.sort();
+    fingerprints.join(" ")
+}
+
+/// Compares two fingerprints or safety numbers, ignoring spaces and other separators.
+pub fn compare_fingerprint(a: &str, b: &str) -> bool {
+    let digits = |s: &str| -> String { s.chars().filter(char::is_ascii_digit).collect() };
+    let (a, b) = (digits(a), digits(b));
+    !a.is_empty() && a == b
+}
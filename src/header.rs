@@ -0,0 +1,94 @@
+//! Provides the headers of the messages of a group, which are kept in a compact index maintained when
+//! messages are saved, so that the conversation lists are rendered without reading the messages.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, MessageKind, SignedMessage},
+    message::Signature,
+    state::Redaction,
+};
+
+/// The maximum number of characters of the preview of a message.
+pub const PREVIEW_LENGTH: usize = 100;
+
+/// MessageHeader is the summary of a message.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MessageHeader {
+    pub seq: u64,
+    /// The hash of the message in the chain.
+    pub hash: MessageHash,
+    /// The signer of the message.
+    pub author: Identity,
+    pub timestamp: Option<u64>,
+    pub kind: MessageKind,
+    /// The text of the data message truncated to [PREVIEW_LENGTH] characters, or None if the message is
+    /// a record, encrypted or redacted.
+    pub preview: Option<String>,
+}
+
+impl MessageHeader {
+    /// Creates the header of the message with the given hash.
+    pub fn new(hash: MessageHash, signed_msg: &SignedMessage<Identity, Signature>) -> Self {
+        let message = &signed_msg.message;
+        let preview =
+            (message.kind == MessageKind::Data && !message.encrypted && !message.redacted)
+                .then(|| preview(&String::from_utf8_lossy(&message.data)));
+        Self {
+            seq: signed_msg.seq,
+            hash,
+            author: signed_msg.id.clone(),
+            timestamp: message.timestamp,
+            kind: message.kind,
+            preview,
+        }
+    }
+}
+
+/// HeaderIndex keeps the headers of the messages of a group, ordered from the oldest.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct HeaderIndex {
+    pub headers: Vec<MessageHeader>,
+}
+
+impl HeaderIndex {
+    /// Adds the header of the message with the given hash, if it is not added yet. The preview of the
+    /// message redacted by a redaction record is removed.
+    pub fn add(&mut self, hash: MessageHash, signed_msg: &SignedMessage<Identity, Signature>) {
+        if self.headers.iter().any(|header| header.hash == hash) {
+            return;
+        }
+        if signed_msg.message.kind == MessageKind::Redaction {
+            if let Ok(redaction) = serde_json::from_slice::<Redaction>(&signed_msg.message.data) {
+                self.redact(&redaction.hash);
+            }
+        }
+        self.headers.push(MessageHeader::new(hash, signed_msg));
+    }
+
+    /// Removes the preview of the message with the given hash, e.g. after its data is purged.
+    pub fn redact(&mut self, hash: &MessageHash) {
+        if let Some(header) = self.headers.iter_mut().find(|header| &header.hash == hash) {
+            header.preview = None;
+        }
+    }
+
+    /// Removes the header of the message with the given hash, e.g. after the message is pruned.
+    pub fn remove(&mut self, hash: &MessageHash) {
+        self.headers.retain(|header| &header.hash != hash);
+    }
+
+    /// Returns the headers of the latest messages, at most `limit`, ordered from the latest.
+    pub fn latest(&self, limit: usize) -> Vec<&MessageHeader> {
+        self.headers.iter().rev().take(limit).collect()
+    }
+}
+
+/// Returns the text truncated to [PREVIEW_LENGTH] characters, with an ellipsis if it is truncated.
+pub(crate) fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
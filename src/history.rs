@@ -0,0 +1,41 @@
+//! Provides the import of legacy history, which are the messages of a previous system without signatures,
+//! e.g. to migrate a conversation to a group.
+//!
+//! The imported messages are signed by the importing account when they are imported, so they only prove
+//! that the account imported them. The original timestamps and authors are recorded in the tags of the
+//! messages, which are covered by the signatures.
+
+use serde::{Deserialize, Serialize};
+
+/// The tag of the imported messages, which can be queried with `messagesByTag`.
+pub const IMPORTED_TAG: &str = "imported";
+/// The prefix of the tag of the original timestamp of an imported message.
+pub const ORIGINAL_TIMESTAMP_TAG_PREFIX: &str = "original_timestamp:";
+/// The prefix of the tag of the original author of an imported message.
+pub const ORIGINAL_AUTHOR_TAG_PREFIX: &str = "original_author:";
+
+/// HistoryEntry is a message of the legacy history.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub data: String,
+    /// The unix timestamp when the message was originally sent.
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    /// The name of the original author in the previous system.
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Returns the tags of the imported message, which record the original timestamp and author.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = vec![IMPORTED_TAG.to_string()];
+        if let Some(timestamp) = self.timestamp {
+            tags.push(format!("{ORIGINAL_TIMESTAMP_TAG_PREFIX}{timestamp}"));
+        }
+        if let Some(author) = &self.author {
+            tags.push(format!("{ORIGINAL_AUTHOR_TAG_PREFIX}{author}"));
+        }
+        tags
+    }
+}
@@ -0,0 +1,110 @@
+//! Provides the secondary index over the messages of a group, which is maintained when messages are
+//! saved so that queries do not deserialize the entire chain.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, MessageKind, SignedMessage},
+    message::Signature,
+    state::{PinChange, Redaction},
+};
+
+/// MessageIndex indexes the message hashes of a group by their signers, timestamps and tags, and keeps
+/// the hashes of the messages redacted by redaction records and pinned by pin records. The hashes are ordered from the oldest.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MessageIndex {
+    /// The hashes of the messages of each signer.
+    pub authors: Vec<(Identity, Vec<MessageHash>)>,
+    /// The hashes of the messages with timestamps, paired with the timestamps.
+    pub timestamps: Vec<(MessageHash, u64)>,
+    /// The hashes of the messages of each tag.
+    pub tags: Vec<(String, Vec<MessageHash>)>,
+    /// The hashes of the redacted messages.
+    pub redacted: Vec<MessageHash>,
+    /// The hashes of the pinned messages, ordered by the time they are pinned.
+    #[serde(default)]
+    pub pinned: Vec<MessageHash>,
+}
+
+impl MessageIndex {
+    /// Adds the message with the given hash to the index, if it is not indexed yet.
+    pub fn add(&mut self, hash: MessageHash, signed_msg: &SignedMessage<Identity, Signature>) {
+        match self.authors.iter_mut().find(|(id, _)| id == &signed_msg.id) {
+            Some((_, hashes)) if hashes.contains(&hash) => return,
+            Some((_, hashes)) => hashes.push(hash),
+            None => self.authors.push((signed_msg.id.clone(), vec![hash])),
+        }
+
+        if let Some(timestamp) = signed_msg.message.timestamp {
+            self.timestamps.push((hash, timestamp));
+        }
+        for tag in signed_msg.message.tags.iter() {
+            match self.tags.iter_mut().find(|(t, _)| t == tag) {
+                Some((_, hashes)) => hashes.push(hash),
+                None => self.tags.push((tag.clone(), vec![hash])),
+            }
+        }
+        match signed_msg.message.kind {
+            MessageKind::Redaction => {
+                if let Ok(redaction) = serde_json::from_slice::<Redaction>(&signed_msg.message.data)
+                {
+                    self.redacted.push(redaction.hash);
+                }
+            }
+            MessageKind::Pin => {
+                if let Ok(change) = serde_json::from_slice::<PinChange>(&signed_msg.message.data) {
+                    self.pinned.retain(|hash| hash != &change.hash);
+                    self.pinned.push(change.hash);
+                }
+            }
+            MessageKind::Unpin => {
+                if let Ok(change) = serde_json::from_slice::<PinChange>(&signed_msg.message.data) {
+                    self.pinned.retain(|hash| hash != &change.hash);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Removes the message with the given hash from the index, e.g. after the message is pruned.
+    pub fn remove(&mut self, hash: &MessageHash) {
+        self.authors
+            .iter_mut()
+            .for_each(|(_, hashes)| hashes.retain(|h| h != hash));
+        self.authors.retain(|(_, hashes)| !hashes.is_empty());
+        self.timestamps.retain(|(h, _)| h != hash);
+        self.tags
+            .iter_mut()
+            .for_each(|(_, hashes)| hashes.retain(|h| h != hash));
+        self.tags.retain(|(_, hashes)| !hashes.is_empty());
+        self.redacted.retain(|h| h != hash);
+        self.pinned.retain(|h| h != hash);
+    }
+
+    /// Returns the hashes of the messages signed by the identity, ordered from the oldest.
+    pub fn authored_by(&self, identity: &Identity) -> &[MessageHash] {
+        self.authors
+            .iter()
+            .find(|(id, _)| id == identity)
+            .map_or(&[], |(_, hashes)| hashes.as_slice())
+    }
+
+    /// Returns the hashes of the messages with timestamps between `from` and `to` inclusively, ordered
+    /// from the oldest in the chain. The timestamps are set by the signers, so they may not be ordered.
+    pub fn between(&self, from: u64, to: u64) -> Vec<MessageHash> {
+        self.timestamps
+            .iter()
+            .filter(|(_, timestamp)| (from..=to).contains(timestamp))
+            .map(|(hash, _)| *hash)
+            .collect()
+    }
+
+    /// Returns the hashes of the messages with the tag, ordered from the oldest.
+    pub fn tagged(&self, tag: &str) -> &[MessageHash] {
+        self.tags
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map_or(&[], |(_, hashes)| hashes.as_slice())
+    }
+}
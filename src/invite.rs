@@ -0,0 +1,73 @@
+//! Provides the signed invitations which authorize identities to join membership-restricted groups.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Identity, Secret},
+    detached,
+    message::Signature,
+    state::Role,
+};
+
+/// The domain separator of the signatures of the invitations.
+const INVITE_PREFIX: &[u8] = b"webmessage invite";
+
+/// InviteBody is the content of an invitation which is signed by the inviter.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InviteBody {
+    /// The group to join.
+    pub group_id: String,
+    /// The identity which is invited.
+    pub invitee: Identity,
+    /// The identity which invites. It must be an admin of the group.
+    pub inviter: Identity,
+    /// The role of the invitee after joining.
+    #[serde(default)]
+    pub role: Role,
+    /// Unix timestamp after which the invitation cannot be used.
+    pub expiry: u64,
+}
+
+/// Invite is an invitation signed by the inviter.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Invite {
+    pub body: InviteBody,
+    pub signature: Signature,
+}
+
+impl Invite {
+    /// Creates an invitation signed by the inviter.
+    pub fn new(body: InviteBody, secret: &Secret) -> Self {
+        let signature =
+            detached::sign_in_domain(INVITE_PREFIX, &body.inviter, secret, &signed_data(&body));
+        Self { body, signature }
+    }
+
+    /// Verifies the signature of the inviter.
+    pub fn verify(&self) -> bool {
+        detached::verify_in_domain(
+            INVITE_PREFIX,
+            &self.body.inviter,
+            &signed_data(&self.body),
+            &self.signature,
+        )
+    }
+
+    /// Checks if the invitation is expired at the given unix timestamp.
+    pub fn is_expired_at(&self, timestamp: u64) -> bool {
+        timestamp > self.body.expiry
+    }
+}
+
+/// JoinRequest is the data of the `Join` record, the first message of the invitee in the group.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JoinRequest {
+    pub invite: Invite,
+    /// Unix timestamp when the invitee joined. It must not be after the expiry of the invitation.
+    pub timestamp: u64,
+}
+
+/// Returns the data which the inviter signs for the invitation.
+fn signed_data(body: &InviteBody) -> Vec<u8> {
+    serde_json::to_vec(body).unwrap()
+}
@@ -5,36 +5,197 @@
 //! and the non-repudiation of the messages.
 
 pub mod account;
+pub mod acknowledgment;
 mod core;
-pub use core::{account::GenerateKeys, group::Group, message::SignedMessage};
+pub use core::{
+    account::GenerateKeys,
+    group::{
+        direct_group_id, sort_groups, Group, GroupGenesis, GroupMetadata, GroupPolicy,
+        GroupSortKey, HashAlgorithm, RetentionPolicy, SortOrder,
+    },
+    message::{
+        hash_from_string, hash_to_string, Message, MessageHash, MessageKind, MessageRef,
+        SignedMessage, Tombstone, MAX_SEQ, MESSAGE_VERSION,
+    },
+};
 
+pub mod agreement;
+pub mod anchor;
+pub mod auth;
+pub mod capability;
+pub mod causal;
+pub mod chain;
+pub mod clock;
+pub mod compact;
+pub mod contact;
+pub mod detached;
+pub mod device;
+pub mod diagnostics;
+pub mod draft;
+pub mod encryption;
+pub mod evidence;
+pub mod fingerprint;
+pub mod header;
+pub mod history;
+pub mod index;
+pub mod invite;
+pub mod light;
+#[cfg(feature = "web")]
+pub mod lock;
 pub mod message;
+pub mod metrics;
+pub mod mmr;
+pub mod multisig;
+pub mod notary;
+pub mod notification;
+pub mod outbox;
+pub mod profile;
+pub mod quarantine;
+pub mod ratchet;
+pub mod reaction;
+mod reader;
+pub mod rng;
+pub mod schema;
+pub mod sealed;
+pub mod search;
+pub mod settings;
+pub mod share;
 pub mod signer;
+pub mod state;
+pub mod stats;
 pub mod store;
+pub mod tamper;
+pub mod thread;
+pub mod transcript;
+pub mod transparency;
+pub mod unread;
+pub mod validation;
 pub mod writer;
 
-use account::Identity;
+use account::{
+    AccountInfo, AccountUsage, EncryptedAccounts, EncryptedSecret, Identity, KeyFormat, KeyPair,
+};
 use sha2::Sha256;
 use store::group::GroupStore;
+#[cfg(feature = "web")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "web")]
+use std::rc::Rc;
+
+#[cfg(feature = "web")]
+use crate::{
+    anchor::{Anchor, AnchorService, HttpAnchor},
+    clock::{CallbackClock, Clock},
+    store::{
+        local_storage::{self, StorageLike},
+        transfer,
+    },
+};
+
 use crate::{
     account::GenKeysAlgorithm,
+    acknowledgment::Acknowledgment,
+    anchor::AnchorProof,
+    auth::{AuthChallenge, AuthResponse},
+    capability::Capability,
+    clock::TimeToken,
+    contact::{AliasedMessage, Contact},
+    core::group::unix_timestamp,
+    device::DeviceCertificate,
+    diagnostics::Level,
+    draft::Draft,
+    evidence::Evidence,
+    history::HistoryEntry,
+    invite::Invite,
+    light::LightState,
+    message::Signature,
+    mmr::{ConsistencyProof, InclusionProof},
+    multisig::{CoSignedContent, SignerSet},
+    notary::{Attestation, NotarizationRequest},
+    notification::Notification,
+    outbox::OutboxEntry,
+    profile::Profile,
+    quarantine::QuarantineEntry,
+    reaction::Reaction,
+    reader::Reader,
+    sealed::SenderProof,
+    search::SearchResult,
+    settings::GroupSettings,
+    share::ShareToken,
     signer::Signer,
-    store::{account::AccountStore, message::SignedMessageStore},
-    writer::Writer,
+    state::{Member, PolicyUpdate, Redaction, Role},
+    stats::{ChainStats, GcReport},
+    store::{
+        account::AccountStore, acknowledgment::AcknowledgmentStore, anchor::AnchorStore,
+        attestation::AttestationStore, auth::AuthChallengeStore, cache, contact::ContactStore,
+        draft::DraftStore, journal, key::GroupKeyStore, light::LightStore, limit::LimitStore,
+        merkle::MerkleStore, message::SignedMessageStore, migration, namespace::NamespaceStore,
+        outbox::OutboxStore, pending::PendingStore, pin::PinStore, profile::ProfileStore,
+        quarantine::QuarantineStore, ratchet::RatchetStore, reaction::ReactionStore,
+        reference::ReferenceStore, search::SearchStore, settings::GroupSettingsStore,
+        transparency::KeyLogStore, unread::SeenStore,
+    },
+    tamper::TamperFinding,
+    transcript::{TranscriptEntry, TranscriptFormat},
+    transparency::KeyEvent,
+    unread::{unread_count, SeenMarker},
+    validation::{ValidationCursor, ValidationReport},
+    writer::{WriteError, WriteLimits, Writer, WRITE_ATTEMPTS},
 };
 
-/// Initializes an account and returns the public and secret keys.
+/// Returns the names of the storage profiles, starting with "default". Each profile has its own
+/// accounts, groups and chains.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
-pub fn initAccount() -> Vec<String> {
-    let (public_key, secret_key) = AccountStore::default().initialize::<GenKeysAlgorithm>();
-    vec![public_key.to_string(), secret_key.to_string()]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn listProfiles() -> Vec<String> {
+    NamespaceStore::default().namespaces()
 }
 
+/// Returns the name of the current storage profile.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn currentProfile() -> String {
+    NamespaceStore::default().current_namespace()
+}
+
+/// Switches to the storage profile with the given name, creating it if it does not exist.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn switchProfile(name: &str) -> Result<(), String> {
+    if !NamespaceStore::default().switch_namespace(name)? {
+        return Err("Invalid profile name".to_string());
+    }
+    Ok(())
+}
+
+/// Deletes the storage profile with the given name and all of its data. If it is the current profile,
+/// the default profile becomes current. The default profile cannot be deleted.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn deleteProfile(name: &str) -> Result<(), String> {
+    if !NamespaceStore::default().delete_namespace(name)? {
+        return Err("Profile not found".to_string());
+    }
+    Ok(())
+}
+
+/// Initializes an account and returns its identity. The secret is kept in the local storage, and is
+/// exported with `exportSecret`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn initAccount() -> Result<String, String> {
+    let (public_key, _) = AccountStore::default().initialize::<GenKeysAlgorithm>()?;
+    if KeyLogStore::default().key_log(&public_key).is_empty() {
+        append_key_event(KeyEvent::Created {
+            timestamp: unix_timestamp(),
+        })?;
+    }
+    Ok(public_key.to_string())
+}
+
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
 pub fn allAccounts() -> Vec<String> {
     AccountStore::default()
         .accounts()
@@ -43,38 +204,690 @@ pub fn allAccounts() -> Vec<String> {
         .collect()
 }
 
+/// Exports the secret of the account encrypted with a key derived from the passphrase. The export must
+/// be confirmed explicitly, so that the secret is not exposed by accident.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
-pub fn setCurrentAccount(identity: &str) {
-    AccountStore::default().set_current_account(Identity::try_from(identity).unwrap());
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn exportSecret(identity: &str, passphrase: &str, confirm: bool) -> Result<String, String> {
+    if !confirm {
+        return Err("Export of the secret is not confirmed".to_string());
+    }
+    let identity = Identity::try_from(identity).map_err(|_| "Fail to parse".to_string())?;
+    let (identity, secret) = AccountStore::default()
+        .account(&identity)
+        .ok_or("Account not found".to_string())?;
+    let now = unix_timestamp();
+    AccountStore::default()
+        .update_metadata(&identity, |metadata| metadata.exported_at = Some(now))?;
+    let encrypted_secret = EncryptedSecret::new(identity, &secret, passphrase);
+    Ok(serde_json::to_string(&encrypted_secret).unwrap())
 }
 
+/// Imports an existing account into the local storage and sets it as the current account, so that the
+/// identity is moved to this browser without re-keying its groups. The key material is either the
+/// output of `exportSecret` with its passphrase, or the identity and the secret in the form
+/// `{"identity": .., "secret": ..}`. It fails if the secret is not the signing key of the identity.
+/// It returns the identity.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
-pub fn newAccount() -> Vec<String> {
-    let (public_key, secret_key) = AccountStore::default().new_account::<GenKeysAlgorithm>();
-    vec![public_key.to_string(), secret_key.to_string()]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn importAccount(key_material: &str, passphrase: Option<String>) -> Result<String, String> {
+    let key_pair = match serde_json::from_str::<EncryptedSecret>(key_material) {
+        Ok(encrypted_secret) => encrypted_secret
+            .decrypt_key_pair(&passphrase.ok_or("Passphrase is required".to_string())?)
+            .ok_or("Wrong passphrase".to_string())?,
+        Err(_) => serde_json::from_str::<KeyPair>(key_material)
+            .map_err(|_| "Fail to parse".to_string())?,
+    };
+    if !key_pair.is_valid() {
+        return Err("Secret does not match the identity".to_string());
+    }
+
+    AccountStore::default().import_account(key_pair.identity.clone(), key_pair.secret)?;
+    if KeyLogStore::default()
+        .key_log(&key_pair.identity)
+        .is_empty()
+    {
+        append_key_event(KeyEvent::Created {
+            timestamp: unix_timestamp(),
+        })?;
+    }
+    Ok(key_pair.identity.to_string())
 }
 
+/// Exports the identities and the secrets of all accounts, encrypted with a key derived from the
+/// passphrase, as JSON of [EncryptedAccounts]. The message history is not included, e.g. for the users
+/// who sync their chains with a relay but keep their keys local. The accounts are imported into another
+/// browser with `importAccounts`.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
-pub fn deleteAccount(identity: &str) {
-    AccountStore::default().delete_account(&Identity::try_from(identity).unwrap());
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn exportAccounts(passphrase: &str) -> Result<String, String> {
+    let mut account_store = AccountStore::default();
+    let key_pairs: Vec<KeyPair> = account_store
+        .accounts()
+        .into_iter()
+        .map(|(identity, secret)| KeyPair { identity, secret })
+        .collect();
+    if key_pairs.is_empty() {
+        return Err("No accounts".to_string());
+    }
+    let now = unix_timestamp();
+    for key_pair in key_pairs.iter() {
+        account_store.update_metadata(&key_pair.identity, |metadata| {
+            metadata.exported_at = Some(now)
+        })?;
+    }
+    let encrypted_accounts = EncryptedAccounts::new(&key_pairs, passphrase);
+    Ok(serde_json::to_string(&encrypted_accounts).unwrap())
 }
 
-/// Returns the stored messages for the given group ID.
+/// Imports the accounts exported with `exportAccounts` with its passphrase. Nothing is imported if any
+/// secret is not the signing key of its identity, or if the accounts cannot be written. The accounts which
+/// already exist are kept, and the current account is unchanged unless there is none, in which case the
+/// first imported account becomes current. It returns the identities of the imported accounts.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn importAccounts(blob: &str, passphrase: &str) -> Result<Vec<String>, String> {
+    let encrypted_accounts: EncryptedAccounts =
+        serde_json::from_str(blob).map_err(|_| "Fail to parse".to_string())?;
+    let key_pairs = encrypted_accounts
+        .decrypt(passphrase)
+        .ok_or("Wrong passphrase".to_string())?;
+    if !key_pairs.iter().all(KeyPair::is_valid) {
+        return Err("Secret does not match the identity".to_string());
+    }
+
+    // the accounts are written together, so that none is imported if the write fails
+    journal::atomically(|| {
+        let current = AccountStore::default().current_account();
+        for key_pair in key_pairs.iter() {
+            // the account is set as current, so that its key log is signed by itself
+            AccountStore::default()
+                .import_account(key_pair.identity.clone(), key_pair.secret.clone())?;
+            if KeyLogStore::default()
+                .key_log(&key_pair.identity)
+                .is_empty()
+            {
+                append_key_event(KeyEvent::Created {
+                    timestamp: unix_timestamp(),
+                })?;
+            }
+        }
+        let current = current
+            .map(|(identity, _)| identity)
+            .or_else(|| key_pairs.first().map(|key_pair| key_pair.identity.clone()));
+        match current {
+            Some(identity) => AccountStore::default().set_current_account(identity),
+            None => Ok(()),
+        }
+    })??;
+    Ok(key_pairs
+        .iter()
+        .map(|key_pair| key_pair.identity.to_string())
+        .collect())
+}
+
+/// Returns the accounts with their local metadata (label, color, created_at and last_used), and
+/// whether each is the current account.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn accountInfos() -> Vec<String> {
+    let account_store = AccountStore::default();
+    let current = account_store.current_account().map(|(id, _)| id);
+    account_store
+        .accounts()
+        .into_iter()
+        .map(|(identity, _)| {
+            let info = AccountInfo {
+                metadata: account_store.metadata(&identity),
+                current: current.as_ref() == Some(&identity),
+                identity,
+            };
+            serde_json::to_string(&info).unwrap()
+        })
+        .collect()
+}
+
+/// Sets the local label and color of the account.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setAccountMetadata(identity: &str, label: &str, color: &str) -> Result<(), String> {
+    AccountStore::default().update_metadata(&parse_identity(identity)?, |metadata| {
+        metadata.label = label.to_string();
+        metadata.color = color.to_string();
+    })
+}
+
+/// Returns the local metadata of the account.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn accountMetadata(identity: &str) -> Result<String, String> {
+    let metadata = AccountStore::default().metadata(&parse_identity(identity)?);
+    Ok(serde_json::to_string(&metadata).unwrap())
+}
+
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setCurrentAccount(identity: &str) -> Result<(), String> {
+    AccountStore::default().set_current_account(parse_identity(identity)?)
+}
+
+/// Creates a new account, sets it as the current account and returns its identity.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn newAccount() -> Result<String, String> {
+    let (public_key, _) = AccountStore::default().new_account::<GenKeysAlgorithm>()?;
+    append_key_event(KeyEvent::Created {
+        timestamp: unix_timestamp(),
+    })?;
+    Ok(public_key.to_string())
+}
+
+/// Returns the ids of the groups in which the identity signed messages.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn authoredGroups(identity: &str) -> Result<Vec<String>, String> {
+    let identity = parse_identity(identity)?;
+    let message_store = SignedMessageStore::default();
+    Ok(GroupStore::default()
+        .groups()
+        .into_iter()
+        .map(|group| group.id)
+        .filter(|group_id| message_store.has_author(group_id, &identity))
+        .collect())
+}
+
+/// Returns the usage of the local account as JSON of [AccountUsage]: the groups in which it signed
+/// messages with the message counts, and the first and last signing times. The usage is maintained when
+/// the messages signed by the account are written, including those synced from its other browsers, and it
+/// keeps counting the messages which are pruned later.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn accountUsage(identity: &str) -> Result<String, String> {
+    let identity = Identity::try_from(identity).map_err(|_| "Fail to parse".to_string())?;
+    if AccountStore::default().account(&identity).is_none() {
+        return Err("Account not found".to_string());
+    }
+    Ok(serde_json::to_string(&account_usage(&identity)).unwrap())
+}
+
+/// Returns the usage of the local account. If it is not maintained yet, e.g. the account was created
+/// before the usage was kept or was imported, it is rebuilt from the stored chains once.
+fn account_usage(identity: &Identity) -> AccountUsage {
+    let mut account_store = AccountStore::default();
+    if let Some(usage) = account_store.usage(identity) {
+        return usage;
+    }
+    let message_store = SignedMessageStore::default();
+    let mut usage = AccountUsage::default();
+    for group in GroupStore::default().groups() {
+        for message in message_store.messages(&group.id).iter().rev() {
+            if &message.id == identity {
+                usage.add(&group.id, message.message.timestamp);
+            }
+        }
+    }
+    // the usage is counted again on the next access if it is not cached
+    let _ = account_store.set_usage(identity, usage.clone());
+    usage
+}
+
+/// Deletes the account. If the account signed messages in stored groups and its secret was never
+/// exported with `exportSecret`, the chains of those groups cannot be continued by the account after
+/// the deletion, so it fails with the list of the groups unless `force` is set. The groups are read from
+/// the usage of the account (see `accountUsage`) instead of the chains.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn deleteAccount(identity: &str, force: bool) -> Result<(), String> {
+    let mut account_store = AccountStore::default();
+    let id = parse_identity(identity)?;
+    if !force && account_store.metadata(&id).exported_at.is_none() {
+        let stored: Vec<String> = GroupStore::default()
+            .groups()
+            .into_iter()
+            .map(|group| group.id)
+            .collect();
+        let groups: Vec<String> = account_usage(&id)
+            .groups
+            .into_iter()
+            .map(|usage| usage.group_id)
+            .filter(|group_id| stored.contains(group_id))
+            .collect();
+        if !groups.is_empty() {
+            return Err(format!(
+                "Account signed messages in groups: {}",
+                groups.join(", ")
+            ));
+        }
+    }
+    account_store.delete_account(&id)?;
+    Ok(())
+}
+
+/// Returns the fingerprint of the identity, which is 30 digits for comparing out of band.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn fingerprint(identity: &str) -> Result<String, String> {
+    Ok(fingerprint::fingerprint(&parse_identity(identity)?))
+}
+
+/// Returns the safety number of the two identities, which is 60 digits and the same for both parties.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn safetyNumber(identity: &str, other: &str) -> Result<String, String> {
+    Ok(fingerprint::safety_number(
+        &parse_identity(identity)?,
+        &parse_identity(other)?,
+    ))
+}
+
+/// Parses the identity given to a binding, in either encoding. It fails if it is not a valid public key.
+fn parse_identity(identity: &str) -> Result<Identity, String> {
+    Identity::try_from(identity)
+        .ok()
+        .filter(Identity::is_valid)
+        .ok_or("Fail to parse identity".to_string())
+}
+
+/// Returns the canonical group id for the 1:1 conversation of the two identities, which is derived
+/// from their sorted public keys, so both peers arrive at the same group independently.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn directGroupId(identity_a: &str, identity_b: &str) -> Result<String, String> {
+    Ok(direct_group_id(
+        &parse_identity(identity_a)?,
+        &parse_identity(identity_b)?,
+    ))
+}
+
+/// Returns the identity in the compact encoding, which is the base64 of its compressed public key. The
+/// identities returned by the library are in this encoding, and either encoding is accepted as input.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn compactIdentity(identity: &str) -> Result<String, String> {
+    parse_identity(identity).map(|identity| identity.to_compact())
+}
+
+/// Returns the identity in the JSON encoding of the public key, which is the encoding of the identities
+/// before the compact encoding.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn expandIdentity(identity: &str) -> Result<String, String> {
+    parse_identity(identity).map(|identity| String::from_utf8(identity.as_ref().to_vec()).unwrap())
+}
+
+/// Returns the identity of the public key generated by other tools, e.g. OpenSSL or WebCrypto, in the
+/// format: "sec1" (the hex of the SEC1 point), "spki" (the base64 of the DER of the SubjectPublicKeyInfo),
+/// "pem" or "jwk". The key must be on the P-256 curve.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn importIdentity(key: &str, format: &str) -> Result<String, String> {
+    KeyFormat::try_from(format)?
+        .parse(key)
+        .map(|identity| identity.to_compact())
+        .ok_or("Fail to parse identity".to_string())
+}
+
+/// Returns the public key of the identity in the format (see `importIdentity`), e.g. to verify the
+/// signatures of the identity with other tools.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn exportIdentity(identity: &str, format: &str) -> Result<String, String> {
+    let format = KeyFormat::try_from(format)?;
+    format
+        .encode(&parse_identity(identity)?)
+        .ok_or("Fail to parse identity".to_string())
+}
+
+/// Returns the canonical string of the hash, which is its lowercase hex encoding.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn hashToString(hash: &str) -> Result<String, String> {
+    let hash: MessageHash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    Ok(hash_to_string(&hash))
+}
+
+/// Parses the canonical string of a hash, and returns the hash as JSON.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn hashFromString(value: &str) -> Result<String, String> {
+    let hash = hash_from_string(value).ok_or("Fail to parse hash".to_string())?;
+    Ok(serde_json::to_string(&hash).unwrap())
+}
+
+/// Compares two fingerprints or safety numbers, ignoring spaces and other separators.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn compareFingerprint(a: &str, b: &str) -> bool {
+    fingerprint::compare_fingerprint(a, b)
+}
+
+/// Publishes the profile of the current account with the display name, the hash of the avatar blob
+/// and links. It returns the signed profile update, which is shared with other identities.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn publishProfile(
+    display_name: &str,
+    avatar: Option<String>,
+    links: Vec<String>,
+) -> Result<String, String> {
+    let avatar = avatar
+        .map(|avatar| serde_json::from_str(&avatar))
+        .transpose()
+        .map_err(|_| "Fail to parse".to_string())?;
+    let profile = Profile {
+        display_name: display_name.to_string(),
+        avatar,
+        links,
+    };
+    let update = Signer::default().sign_profile(&profile)?;
+    ProfileStore::default().add_profile_update(update.clone())?;
+    Ok(serde_json::to_string(&update).unwrap())
+}
+
+/// Adds the profile update published by another identity. It fails if the update is not the valid next
+/// update of the profile chain of the identity.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addProfileUpdate(update: &str) -> Result<(), String> {
+    let update: SignedMessage<Identity, Signature> =
+        serde_json::from_str(update).map_err(|_| "Fail to parse".to_string())?;
+    let mut profile_store = ProfileStore::default();
+    let latest = profile_store.profile_updates(&update.id).pop();
+    if !profile::is_valid_update(latest.as_ref(), &update) {
+        return Err("Invalid profile update".to_string());
+    }
+    profile_store.add_profile_update(update)
+}
+
+/// Returns the latest profile of the identity, if any.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn profileOf(identity: &str) -> Result<Option<String>, String> {
+    Ok(ProfileStore::default()
+        .profile_updates(&parse_identity(identity)?)
+        .pop()
+        .and_then(|update| serde_json::from_slice::<Profile>(&update.message.data).ok())
+        .map(|profile| serde_json::to_string(&profile).unwrap()))
+}
+
+/// Returns the profile updates of the identity, from the oldest, e.g. to share them with another identity.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn profileUpdates(identity: &str) -> Result<Vec<String>, String> {
+    Ok(ProfileStore::default()
+        .profile_updates(&parse_identity(identity)?)
+        .iter()
+        .map(|update| serde_json::to_string(update).unwrap())
+        .collect())
+}
+
+/// Returns the share token of the identity, which is a compact checksummed string for the exchange of
+/// contacts in person, e.g. in a QR code. It contains the hash of the latest profile update of the identity,
+/// if any.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn identityShareToken(identity: &str) -> Result<String, String> {
+    let identity = Identity::try_from(identity).map_err(|_| "Fail to parse".to_string())?;
+    let profile = ProfileStore::default()
+        .profile_updates(&identity)
+        .pop()
+        .map(|update| update.hash::<Sha256>());
+    ShareToken { identity, profile }
+        .encode()
+        .ok_or("Invalid identity".to_string())
+}
+
+/// Parses the share token created with `identityShareToken`. It returns the identity and the hash of its
+/// profile update, if any. It fails if the token is malformed or its checksum does not match.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn parseShareToken(token: &str) -> Result<String, String> {
+    let token = ShareToken::decode(token).ok_or("Invalid share token".to_string())?;
+    Ok(serde_json::to_string(&token).unwrap())
+}
+
+/// Adds the contact of the identity with the local alias, verification status ("unverified", "verified"
+/// or "distrusted") and notes. It replaces the existing contact of the identity.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addContact(
+    identity: &str,
+    alias: &str,
+    verification: &str,
+    notes: &str,
+) -> Result<(), String> {
+    let identity = Identity::try_from(identity).map_err(|_| "Fail to parse".to_string())?;
+    let verification = serde_json::from_value(serde_json::Value::String(verification.to_string()))
+        .map_err(|_| "Fail to parse verification status".to_string())?;
+    ContactStore::default().set_contact(Contact {
+        identity,
+        alias: alias.to_string(),
+        verification,
+        notes: notes.to_string(),
+    })
+}
+
+/// Returns all the contacts.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn contacts() -> Vec<String> {
+    ContactStore::default()
+        .contacts()
+        .iter()
+        .map(|contact| serde_json::to_string(contact).unwrap())
+        .collect()
+}
+
+/// Removes the contact of the identity.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn removeContact(identity: &str) -> Result<(), String> {
+    ContactStore::default().remove_contact(&parse_identity(identity)?)
+}
+
+/// Signs the key event by the current account and appends it to the key transparency log of the account.
+fn append_key_event(event: KeyEvent) -> Result<(), String> {
+    let entry = Signer::default().sign_key_event(&event)?;
+    KeyLogStore::default().append_key_log(entry)
+}
+
+/// Returns the key transparency log of the account, from the oldest, which peers verify with `verifyKeyLog`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn keyLog(identity: &str) -> Result<Vec<String>, String> {
+    Ok(KeyLogStore::default()
+        .key_log(&parse_identity(identity)?)
+        .iter()
+        .map(|entry| serde_json::to_string(entry).unwrap())
+        .collect())
+}
+
+/// Verifies the key transparency log returned by `keyLog`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifyKeyLog(entries: Vec<String>) -> bool {
+    let Ok(entries) = entries
+        .iter()
+        .map(|entry| serde_json::from_str(entry))
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return false;
+    };
+    transparency::verify_log(&entries)
+}
+
+/// Returns the stored messages for the given group ID. If the group content key is held, the data
+/// of encrypted messages is decrypted. Note that decrypted messages no longer match their signatures.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
 pub fn messages(group_id: &str) -> Vec<String> {
-    SignedMessageStore::default()
+    Reader::default()
         .messages(group_id)
         .iter()
         .map(|msg| serde_json::to_string(msg).unwrap())
         .collect()
 }
 
+/// Returns the messages of the given group as `messages` does, with the alias of the signer of each
+/// message if the signer is a contact.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn aliasedMessages(group_id: &str) -> Vec<String> {
+    let contact_store = ContactStore::default();
+    Reader::default()
+        .messages(group_id)
+        .into_iter()
+        .map(|signed_message| {
+            let alias = contact_store
+                .contact(&signed_message.id)
+                .map(|contact| contact.alias);
+            let aliased_msg = AliasedMessage {
+                signed_message,
+                alias,
+            };
+            serde_json::to_string(&aliased_msg).unwrap()
+        })
+        .collect()
+}
+
+/// Returns the headers of the latest messages of the given group, at most `limit`, ordered from the latest.
+/// Each header has the sequence number, the hash, the author, the timestamp, the kind and a preview of
+/// the message, which is its text truncated, or null if it is a record, encrypted or redacted. The
+/// headers are read from a compact index, so that the conversation lists are rendered without reading
+/// the messages.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn messageHeaders(group_id: &str, limit: usize) -> Vec<String> {
+    SignedMessageStore::default()
+        .headers(group_id)
+        .latest(limit)
+        .iter()
+        .map(|header| serde_json::to_string(header).unwrap())
+        .collect()
+}
+
+/// Exports the data messages of the given group as a transcript in the format, which is "markdown", "html"
+/// or "csv". The messages are listed from the oldest with their timestamps, the aliases of their signers
+/// if they are contacts, and their hashes in footnotes.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn exportTranscript(group_id: &str, format: &str) -> Result<String, String> {
+    let format = TranscriptFormat::try_from(format)?;
+    let contact_store = ContactStore::default();
+    let entries: Vec<TranscriptEntry> = Reader::default()
+        .messages_with_hashes(group_id)
+        .into_iter()
+        .rev()
+        .filter(|(_, signed_msg)| signed_msg.message.kind.is_data())
+        .map(|(hash, signed_msg)| {
+            let alias = contact_store
+                .contact(&signed_msg.id)
+                .map(|contact| contact.alias);
+            TranscriptEntry::new(hash, &signed_msg, alias)
+        })
+        .collect();
+
+    Ok(transcript::render(group_id, &entries, format))
+}
+
+/// Returns the messages of the given group as `messages` does, but collapsed: each edited message is
+/// replaced by its latest edit, and the edits are not returned separately. The original messages remain
+/// in the chain.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn collapsedMessages(group_id: &str) -> Vec<String> {
+    Reader::default()
+        .collapsed_messages(group_id)
+        .iter()
+        .map(|msg| serde_json::to_string(msg).unwrap())
+        .collect()
+}
+
+/// Returns the messages signed by the identity as `messages` does, ordered from the latest, in the
+/// given group or in all stored groups if no group ID is given. The messages are looked up in the
+/// per-author index maintained on write, so the chains are not read entirely.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn messagesByAuthor(identity: &str, group_id: Option<String>) -> Result<Vec<String>, String> {
+    let identity = parse_identity(identity)?;
+    let group_ids = match group_id {
+        Some(group_id) => vec![group_id],
+        None => GroupStore::default()
+            .groups()
+            .into_iter()
+            .map(|group| group.id)
+            .collect(),
+    };
+    let mut reader = Reader::default();
+    Ok(group_ids
+        .iter()
+        .flat_map(|group_id| reader.messages_by_author(group_id, &identity))
+        .map(|msg| serde_json::to_string(&msg).unwrap())
+        .collect())
+}
+
+/// Returns the messages of the given group signed with timestamps between `from_ts` and `to_ts`
+/// inclusively as `messages` does, ordered from the latest. The timestamps are set by the signers.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn messagesBetween(group_id: &str, from_ts: u64, to_ts: u64) -> Vec<String> {
+    Reader::default()
+        .messages_between(group_id, from_ts, to_ts)
+        .iter()
+        .map(|msg| serde_json::to_string(msg).unwrap())
+        .collect()
+}
+
+/// Returns the messages of the given group with the tag as `messages` does, ordered from the latest.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn messagesByTag(group_id: &str, tag: &str) -> Vec<String> {
+    Reader::default()
+        .messages_by_tag(group_id, tag)
+        .iter()
+        .map(|msg| serde_json::to_string(msg).unwrap())
+        .collect()
+}
+
+/// Builds the full-text search index of the given group from its messages, replacing the existing index.
+/// Once built, the index is updated when unencrypted messages are written, and encrypted messages are
+/// indexed when it is rebuilt.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn rebuildSearchIndex(group_id: &str) -> Result<(), String> {
+    let index = Reader::default().build_search_index(group_id);
+    SearchStore::default().set_search_index(group_id, &index)
+}
+
+/// Removes the full-text search index of the given group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn removeSearchIndex(group_id: &str) -> Result<(), String> {
+    SearchStore::default().remove_search_index(group_id)
+}
+
+/// Searches the messages of the given group which contain all the words of the query, case-insensitively.
+/// It returns the hashes of the matching messages with snippets of their text, ordered from the latest.
+/// It fails if the search index of the group is not built with `rebuildSearchIndex`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn search(group_id: &str, query: &str) -> Result<Vec<String>, String> {
+    let messages = Reader::default()
+        .search(group_id, query)
+        .ok_or("search index not built".to_string())?;
+    Ok(messages
+        .iter()
+        .map(|(hash, msg)| {
+            let text = String::from_utf8_lossy(&msg.message.data);
+            serde_json::to_string(&SearchResult::new(*hash, &text, query)).unwrap()
+        })
+        .collect())
+}
+
+/// Returns the stored groups in the order of creation, each with its id, creation timestamp and local
+/// metadata.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
 pub fn groups() -> Vec<String> {
     GroupStore::default()
         .groups()
@@ -83,43 +896,2134 @@ pub fn groups() -> Vec<String> {
         .collect()
 }
 
+/// Returns the groups which are not archived, sorted by "created", "activity" or "name" in "asc" or "desc"
+/// order. The activity of a group is the last time a message of the group was written locally.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn groupsSorted(by: &str, order: &str) -> Result<Vec<String>, String> {
+    let by = GroupSortKey::try_from(by)?;
+    let order = SortOrder::try_from(order)?;
+    let mut groups: Vec<Group> = GroupStore::default()
+        .groups()
+        .into_iter()
+        .filter(|group| !group.metadata.archived)
+        .collect();
+    sort_groups(&mut groups, by, order);
+    Ok(groups
+        .iter()
+        .map(|group| serde_json::to_string(group).unwrap())
+        .collect())
+}
+
+/// Returns the archived groups in the order of creation.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn archivedGroups() -> Vec<String> {
+    GroupStore::default()
+        .groups()
+        .iter()
+        .filter(|group| group.metadata.archived)
+        .map(|group| serde_json::to_string(group).unwrap())
+        .collect()
+}
+
+/// Returns the statistics of the stored messages of the given group: the message count, the head hash and
+/// sequence number, the first and last timestamps, the distinct authors and the storage bytes used. They
+/// are read from the counters maintained on write instead of the chain.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn groupInfo(group_id: &str) -> String {
+    serde_json::to_string(&SignedMessageStore::default().group_info(group_id)).unwrap()
+}
+
+/// Returns the statistics of the chain of the given group for debugging the sync, as JSON of [ChainStats]:
+/// the average message size, the number of messages per signature scheme and hash algorithm, the position
+/// of the validation watermark of `validateAll`, the checkpoints and the number of forks, i.e. the stored
+/// or quarantined messages which branch off the chain before its head. Unlike `groupInfo`, it walks the
+/// chain.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn chainStats(group_id: &str) -> String {
+    let quarantined: Vec<SignedMessage<Identity, Signature>> = QuarantineStore::default()
+        .entries(group_id)
+        .into_iter()
+        .map(|entry| entry.signed_message)
+        .collect();
+    let stats: ChainStats = SignedMessageStore::default().chain_stats(group_id, &quarantined);
+    serde_json::to_string(&stats).unwrap()
+}
+
+/// Returns the number of messages of the group after the latest message seen by the current account
+/// (see `markSeen`), or the number of stored messages, including the records, if nothing is seen. It is
+/// read from the counters maintained on write instead of the chain.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn unreadCount(group_id: &str) -> u64 {
+    let marker = AccountStore::default()
+        .current_account()
+        .and_then(|(identity, _)| SeenStore::default().marker(group_id, &identity));
+    unread_count(
+        &SignedMessageStore::default().group_info(group_id),
+        marker.as_ref(),
+    )
+}
+
+/// Marks the message with the given hash, and the messages before it, as seen by the current account.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn markSeen(group_id: &str, hash: &str) -> Result<(), String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let (identity, _) = AccountStore::default()
+        .current_account()
+        .ok_or("No current account".to_string())?;
+    let signed_msg = SignedMessageStore::default()
+        .message(group_id, &hash)
+        .ok_or("Message not found".to_string())?;
+    SeenStore::default().set_marker(
+        group_id,
+        SeenMarker {
+            identity,
+            hash,
+            seq: signed_msg.seq,
+        },
+    )
+}
+
+/// Replaces the local metadata of the group, e.g. to rename or archive it. The id of the group and its
+/// genesis record are unchanged. It returns the updated group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn updateGroup(group_id: &str, meta: &str) -> Result<String, String> {
+    let metadata: GroupMetadata =
+        serde_json::from_str(meta).map_err(|_| "Fail to parse".to_string())?;
+    let group = GroupStore::default()
+        .update_metadata(group_id, |group_metadata| *group_metadata = metadata)?
+        .ok_or("group not found".to_string())?;
+    Ok(serde_json::to_string(&group).unwrap())
+}
+
 /// Validates the stored messages for the given group ID.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "web", wasm_bindgen)]
 pub fn validateMessages(group_id: &str) -> bool {
-    SignedMessageStore::default().validate_messages::<Sha256>(group_id)
+    let settings = GroupSettingsStore::default().settings(group_id);
+    SignedMessageStore::default().validate_messages::<Sha256>(group_id, &settings)
 }
 
-/// Signs a message with the given group ID and data. It returns the signed message.
-/// This method does not validate the message.
+/// Validates the stored messages of every known group, e.g. as an integrity check when the app starts. Only
+/// the messages after the head of the previous successful validation by `validateAll` are validated, if
+/// that head is still on the chain. It returns the reports of the groups as JSON of [ValidationReport].
 #[allow(non_snake_case)]
-#[wasm_bindgen]
-pub fn signMessage(group_id: &str, data: &str) -> String {
-    let signed_msg = Signer::default().sign(group_id, data.as_bytes().to_vec());
-    let (_, wrote_signed_msg) = Writer::default().write(group_id, signed_msg);
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn validateAll() -> Result<Vec<String>, String> {
+    let settings_store = GroupSettingsStore::default();
+    let mut message_store = SignedMessageStore::default();
+    GroupStore::default()
+        .groups()
+        .iter()
+        .map(|group| {
+            let settings = settings_store.settings(&group.id);
+            let report: ValidationReport =
+                message_store.validate_from_watermark::<Sha256>(&group.id, &settings)?;
+            Ok(serde_json::to_string(&report).unwrap())
+        })
+        .collect()
+}
 
-    serde_json::to_string(&wrote_signed_msg).unwrap()
+/// Detects the stored items of every known group which are modified outside the library, e.g. with the
+/// developer tools or by another script: messages which do not match the hashes in their keys, missing
+/// or orphaned messages, and head pointers which are dangling or rolled back. It returns the findings as
+/// JSON of [TamperFinding] with the keys of the items and the positions in the chains if known.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn detectTampering() -> Vec<String> {
+    let message_store = SignedMessageStore::default();
+    GroupStore::default()
+        .groups()
+        .iter()
+        .flat_map(|group| message_store.detect_tampering::<Sha256>(&group.id))
+        .map(|finding: TamperFinding| serde_json::to_string(&finding).unwrap())
+        .collect()
 }
 
-/// Adds a signed message to the store for the given group ID. It returns the hash of the message.
+/// Rebuilds the pointer to the latest message of the given group from the stored messages, e.g. after the
+/// pointer is lost or corrupted so that the chain is not visible. The stored messages are linked by their
+/// previous hashes, and the latest message of the longest chain becomes the head. It returns the hash of
+/// the head.
 #[allow(non_snake_case)]
-#[wasm_bindgen]
-pub fn addSignedMessage(group_id: &str, signed_msg_str: &str) -> Result<String, String> {
-    let signed_msg =
-        serde_json::from_str(signed_msg_str).map_err(|_| "Fail to parse".to_string())?;
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn rebuildHead(group_id: &str) -> Result<String, String> {
+    let head = SignedMessageStore::default()
+        .rebuild_head::<Sha256>(group_id)?
+        .ok_or("No chain found".to_string())?;
+    Ok(serde_json::to_string(&head).unwrap())
+}
 
-    let (hash, _) = Writer::default().write_with_validation(group_id, signed_msg)?;
-    Ok(serde_json::to_string(&hash).unwrap())
+/// Removes the stored messages which are not in the chain from the head of any group, e.g. the leftovers
+/// of aborted writes or resolved forks. The groups without a head pointer or with a broken chain are
+/// skipped (see `rebuildHead`). It returns the number of removed messages and the reclaimed bytes as JSON
+/// of [GcReport].
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn gc() -> Result<String, String> {
+    let report: GcReport = SignedMessageStore::default().collect_garbage()?;
+    Ok(serde_json::to_string(&report).unwrap())
 }
 
-/// Clears the local storage.
+/// The number of checks between the progress reports of `validateMessagesWithProgress`.
+#[cfg(feature = "web")]
+const PROGRESS_CHUNK_SIZE: usize = 100;
+
+/// Validates the stored messages for the given group ID, and calls `on_progress` with the number of checks
+/// done and the total number of checks after each chunk of messages. The chain of n messages takes 2n
+/// checks. The validation runs to the end in one call, so the page is not repainted in between unless it
+/// runs in a web worker (see `validateMessagesChunked`).
+#[cfg(feature = "web")]
+#[allow(non_snake_case)]
 #[wasm_bindgen]
+pub fn validateMessagesWithProgress(group_id: &str, on_progress: &js_sys::Function) -> bool {
+    let settings = GroupSettingsStore::default().settings(group_id);
+    SignedMessageStore::default().validate_messages_with_progress::<Sha256>(
+        group_id,
+        &settings,
+        PROGRESS_CHUNK_SIZE,
+        |cursor| {
+            let _ = on_progress.call2(
+                &JsValue::NULL,
+                &JsValue::from(cursor.checked as f64),
+                &JsValue::from(cursor.total as f64),
+            );
+        },
+    )
+}
+
+/// Validates the stored messages for the given group ID as `validateMessages` does, but stops after
+/// `chunk_size` messages, so that long chains are validated without blocking the thread. It returns the
+/// cursor as JSON of [ValidationCursor], whose `result` is set once the validation is complete. Otherwise,
+/// the validation continues with `resumeValidation`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn validateMessagesChunked(group_id: &str, chunk_size: usize) -> String {
+    let message_store = SignedMessageStore::default();
+    let mut cursor = message_store.start_validation(group_id);
+    let settings = GroupSettingsStore::default().settings(group_id);
+    message_store.resume_validation::<Sha256>(&mut cursor, &settings, chunk_size);
+    serde_json::to_string(&cursor).unwrap()
+}
+
+/// Continues the validation of the cursor returned by `validateMessagesChunked` for at most `chunk_size`
+/// messages. It returns the updated cursor.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn resumeValidation(cursor: &str, chunk_size: usize) -> Result<String, String> {
+    let mut cursor: ValidationCursor =
+        serde_json::from_str(cursor).map_err(|_| "Fail to parse".to_string())?;
+    let settings = GroupSettingsStore::default().settings(&cursor.group_id);
+    SignedMessageStore::default().resume_validation::<Sha256>(&mut cursor, &settings, chunk_size);
+    Ok(serde_json::to_string(&cursor).unwrap())
+}
+
+/// Creates a group with the given group ID, name and policy (JSON of [GroupPolicy]) by signing its
+/// genesis record. If the policy requires encryption, a group content key is generated.
+/// It returns the signed genesis record.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn createGroup(group_id: &str, name: &str, policy: &str) -> Result<String, String> {
+    let policy: GroupPolicy =
+        serde_json::from_str(policy).map_err(|_| "Fail to parse".to_string())?;
+    let encrypted = policy.encrypted;
+    let (_, wrote_signed_msg) = sign_and_write(group_id, || {
+        Signer::default().sign_genesis(group_id, name.to_string(), policy.clone())
+    })?;
+    if encrypted {
+        GroupKeyStore::default().set_group_key(group_id, encryption::generate_group_key())?;
+    }
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Signs a record that adds the given member to the group. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addMember(group_id: &str, identity: &str) -> Result<String, String> {
+    let member = Identity::try_from(identity).map_err(|_| "Fail to parse identity".to_string())?;
+    let (_, wrote_signed_msg) = sign_checked_and_write(group_id, || {
+        Signer::default().sign_membership_change(
+            group_id,
+            MessageKind::AddMember,
+            member.clone(),
+            Role::Member,
+        )
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Signs a record that removes the given member from the group. If the group is encrypted, the group
+/// content key should be rotated with `rotateGroupKey`. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn removeMember(group_id: &str, identity: &str) -> Result<String, String> {
+    let member = Identity::try_from(identity).map_err(|_| "Fail to parse identity".to_string())?;
+    let (_, wrote_signed_msg) = sign_checked_and_write(group_id, || {
+        Signer::default().sign_membership_change(
+            group_id,
+            MessageKind::RemoveMember,
+            member.clone(),
+            Role::Member,
+        )
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Returns the current members of the group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn members(group_id: &str) -> Vec<String> {
+    GroupStore::default()
+        .state(group_id)
+        .members
+        .iter()
+        .map(|member| member.identity.to_string())
+        .collect()
+}
+
+/// Returns the current members of the group with their roles, as JSON of [Member].
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn memberRoles(group_id: &str) -> Vec<String> {
+    GroupStore::default()
+        .state(group_id)
+        .members
+        .iter()
+        .map(|member| serde_json::to_string(member).unwrap())
+        .collect()
+}
+
+/// Signs a record that assigns the role ("admin", "member" or "read_only") to the member of the group.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setRole(group_id: &str, identity: &str, role: &str) -> Result<String, String> {
+    let identity =
+        Identity::try_from(identity).map_err(|_| "Fail to parse identity".to_string())?;
+    let role = serde_json::from_value(serde_json::Value::String(role.to_string()))
+        .map_err(|_| "Fail to parse role".to_string())?;
+    let update = PolicyUpdate {
+        policy: None,
+        roles: vec![Member { identity, role }],
+    };
+    let (_, wrote_signed_msg) = sign_checked_and_write(group_id, || {
+        Signer::default().sign_policy_update(group_id, &update)
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Signs a record that replaces the policy (JSON of [GroupPolicy]) of the group.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn updatePolicy(group_id: &str, policy: &str) -> Result<String, String> {
+    let policy = serde_json::from_str(policy).map_err(|_| "Fail to parse".to_string())?;
+    let update = PolicyUpdate {
+        policy: Some(policy),
+        roles: vec![],
+    };
+    let (_, wrote_signed_msg) = sign_checked_and_write(group_id, || {
+        Signer::default().sign_policy_update(group_id, &update)
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Creates the signing keys of a new device certified by the current account. It returns the identity
+/// and the secret of the device, and the certificate which the device presents with `presentDevice`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addDevice(name: &str) -> Result<Vec<String>, String> {
+    let (secret, certificate) = AccountStore::default()
+        .add_device::<GenKeysAlgorithm>(name)?
+        .ok_or("No current account".to_string())?;
+    append_key_event(KeyEvent::DeviceAdded {
+        certificate: certificate.clone(),
+        timestamp: unix_timestamp(),
+    })?;
+    Ok(vec![
+        certificate.body.device.to_string(),
+        secret.to_string(),
+        serde_json::to_string(&certificate).unwrap(),
+    ])
+}
+
+/// Returns the certificates of the devices of the current account.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn devices() -> Vec<String> {
+    let account_store = AccountStore::default();
+    let Some((master, _)) = account_store.current_account() else {
+        return vec![];
+    };
+    account_store
+        .devices(&master)
+        .iter()
+        .map(|certificate| serde_json::to_string(certificate).unwrap())
+        .collect()
+}
+
+/// Revokes the device of the current account. The device is revoked in the groups in which it presented
+/// its certificate, so that its subsequent messages are rejected. It returns the signed revocation records.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn revokeDevice(device: &str) -> Result<Vec<String>, String> {
+    let device = Identity::try_from(device).map_err(|_| "Fail to parse identity".to_string())?;
+    let mut account_store = AccountStore::default();
+    let (master, _) = account_store
+        .current_account()
+        .ok_or("No current account".to_string())?;
+    if !account_store
+        .devices(&master)
+        .iter()
+        .any(|certificate| certificate.body.device == device)
+    {
+        return Err("Unknown device".to_string());
+    }
+    account_store.revoke_device(&device)?;
+    append_key_event(KeyEvent::DeviceRevoked {
+        device: device.clone(),
+        timestamp: unix_timestamp(),
+    })?;
+
+    let group_store = GroupStore::default();
+    let mut wrote_signed_msgs = vec![];
+    for group in group_store.groups() {
+        if group_store.state(&group.id).master_of(&device) != Some(&master) {
+            continue;
+        }
+        let (_, wrote_signed_msg) = sign_and_write(&group.id, || {
+            Signer::default().sign_device_revocation(&group.id, device.clone())
+        })?;
+        wrote_signed_msgs.push(serde_json::to_string(&wrote_signed_msg).unwrap());
+    }
+    Ok(wrote_signed_msgs)
+}
+
+/// Presents the device certificate for the current account, which is the device, by signing a record
+/// which contains it. Afterwards, the messages of the device are validated with the role of its master.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn presentDevice(group_id: &str, certificate: &str) -> Result<String, String> {
+    let certificate: DeviceCertificate =
+        serde_json::from_str(certificate).map_err(|_| "Fail to parse".to_string())?;
+    let signed_msg = Signer::default().sign_device_certificate(group_id, &certificate)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Creates an invitation signed by the current account for the invitee to join the group, valid
+/// until the expiry (unix timestamp in seconds). The current account must be an admin of the group.
+/// It returns the invitation, which is presented by the invitee with `joinGroup`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn createInvite(group_id: &str, invitee: &str, expiry: u64) -> Result<String, String> {
+    let invitee = Identity::try_from(invitee).map_err(|_| "Fail to parse identity".to_string())?;
+    let invite = Signer::default().sign_invite(group_id, invitee, Role::Member, expiry)?;
+    Ok(serde_json::to_string(&invite).unwrap())
+}
+
+/// Joins the group with the invitation for the current account by signing a record which presents it.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn joinGroup(group_id: &str, invite: &str) -> Result<String, String> {
+    let invite: Invite = serde_json::from_str(invite).map_err(|_| "Fail to parse".to_string())?;
+    let signed_msg = Signer::default().sign_join(group_id, invite)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Creates a capability signed by the current account which delegates its rights to sign the given
+/// kinds of messages (e.g. `data`) in the group to the delegate, valid until the expiry (unix timestamp
+/// in seconds). It returns the capability, which is presented by the delegate with `acceptCapability`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn delegateCapability(
+    group_id: &str,
+    delegate: &str,
+    kinds: Vec<String>,
+    expiry: u64,
+) -> Result<String, String> {
+    let delegate =
+        Identity::try_from(delegate).map_err(|_| "Fail to parse identity".to_string())?;
+    let kinds = kinds
+        .iter()
+        .map(|kind| serde_json::from_value(serde_json::Value::String(kind.clone())))
+        .collect::<Result<Vec<MessageKind>, _>>()
+        .map_err(|_| "Fail to parse kind".to_string())?;
+    let capability = Signer::default().sign_capability(group_id, delegate, kinds, expiry)?;
+    Ok(serde_json::to_string(&capability).unwrap())
+}
+
+/// Presents the capability for the current account by signing a record which contains it. Afterwards,
+/// the current account may sign the delegated kinds of messages with the rights of the issuer.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn acceptCapability(group_id: &str, capability: &str) -> Result<String, String> {
+    let capability: Capability =
+        serde_json::from_str(capability).map_err(|_| "Fail to parse".to_string())?;
+    let signed_msg = Signer::default().sign_delegation(group_id, &capability)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Proposes the data at the next position of the group, which must be co-signed by the threshold of the
+/// signers (admins of the group). The partial signature of the current account is added. It returns
+/// the proposal, which is passed to the other signers for `coSign`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn proposeCoSigned(
+    group_id: &str,
+    data: &str,
+    signers: Vec<String>,
+    threshold: usize,
+) -> Result<String, String> {
+    let signers = signers
+        .iter()
+        .map(|signer| Identity::try_from(signer.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| "Fail to parse identity".to_string())?;
+    let content = Signer::default().propose_cosigned(
+        group_id,
+        SignerSet { signers, threshold },
+        data.as_bytes().to_vec(),
+    )?;
+    Ok(serde_json::to_string(&content).unwrap())
+}
+
+/// Adds the partial signature of the current account to the proposal. It returns the proposal.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn coSign(proposal: &str) -> Result<String, String> {
+    let mut content: CoSignedContent =
+        serde_json::from_str(proposal).map_err(|_| "Fail to parse".to_string())?;
+    Signer::default().cosign(&mut content)?;
+    Ok(serde_json::to_string(&content).unwrap())
+}
+
+/// Submits the proposal which reached the threshold to the group. It must be submitted before other
+/// messages are added to the group. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn submitCoSigned(group_id: &str, proposal: &str) -> Result<String, String> {
+    let content: CoSignedContent =
+        serde_json::from_str(proposal).map_err(|_| "Fail to parse".to_string())?;
+    let signed_msg = Signer::default().sign_cosigned(group_id, &content)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Sets the identities allowed to sign the messages added to the group with `addSignedMessage`.
+/// Messages from other signers are rejected.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setAllowedSigners(group_id: &str, signers: Vec<String>) -> Result<(), String> {
+    let signers = signers
+        .iter()
+        .map(|signer| Identity::try_from(signer.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| "Fail to parse identity".to_string())?;
+    GroupSettingsStore::default().update_settings(group_id, |settings| {
+        settings.allowed_signers = Some(signers)
+    })
+}
+
+/// Returns the identities allowed to sign the messages of the group. It is empty if any signer is allowed.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn allowedSigners(group_id: &str) -> Vec<String> {
+    GroupSettingsStore::default()
+        .settings(group_id)
+        .allowed_signers
+        .unwrap_or_default()
+        .iter()
+        .map(|signer| signer.to_string())
+        .collect()
+}
+
+/// Removes the allowed signers of the group, so that any signer is allowed.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn clearAllowedSigners(group_id: &str) -> Result<(), String> {
+    GroupSettingsStore::default()
+        .update_settings(group_id, |settings| settings.allowed_signers = None)
+}
+
+/// Writes the genesis record signed by the current account if the group has no messages yet.
+fn ensure_genesis(group_id: &str) -> Result<(), String> {
+    ensure_genesis_by(&mut Signer::default(), group_id)
+}
+
+/// Writes the genesis record signed by the signer if the group has no messages yet. It fails if the
+/// genesis record is not written and the group still has no messages, e.g. on a storage failure.
+fn ensure_genesis_by(signer: &mut Signer, group_id: &str) -> Result<(), String> {
+    match sign_and_write(group_id, || {
+        signer.sign_genesis(group_id, group_id.to_string(), GroupPolicy::default())
+    }) {
+        // the group may be created by another tab in the meantime
+        Err(err)
+            if SignedMessageStore::default()
+                .latest_message_hash(group_id)
+                .is_none() =>
+        {
+            Err(err)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Signs a message with `sign` over the latest message of the group and writes it. If the head of the
+/// group is changed by another tab before the message is written, the message is signed again over the
+/// new head, up to [WRITE_ATTEMPTS] times.
+fn sign_and_write(
+    group_id: &str,
+    mut sign: impl FnMut() -> Result<SignedMessage<Identity, Signature>, String>,
+) -> Result<(MessageHash, SignedMessage<Identity, Signature>), String> {
+    let mut attempts = 1;
+    loop {
+        match Writer::default().write(group_id, sign()?) {
+            Err(WriteError::HeadChanged) if attempts < WRITE_ATTEMPTS => attempts += 1,
+            result => return result.map_err(String::from),
+        }
+    }
+}
+
+/// Signs a record with `sign` and writes it as [sign_and_write] does, but checks it against the state of
+/// the group first, so that a record which the signer may not write, e.g. a membership change by a member
+/// who is not an admin, fails instead of being written and rejected by the other members.
+fn sign_checked_and_write(
+    group_id: &str,
+    mut sign: impl FnMut() -> Result<SignedMessage<Identity, Signature>, String>,
+) -> Result<(MessageHash, SignedMessage<Identity, Signature>), String> {
+    sign_and_write(group_id, || {
+        let signed_msg = sign()?;
+        GroupStore::default()
+            .state(group_id)
+            .check(group_id, &signed_msg)?;
+        Ok(signed_msg)
+    })
+}
+
+/// Signs a message with the given group ID and data. It returns the signed message.
+/// If the group has no messages yet, its genesis record is signed first.
+/// This method does not validate the message. It fails with "head of group changed" if the head of the
+/// group is changed by another tab on every attempt, in which case the message may be signed again.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signMessage(group_id: &str, data: &str) -> Result<String, String> {
+    ensure_genesis(group_id)?;
+    let (_, wrote_signed_msg) = sign_and_write(group_id, || {
+        Signer::default().sign(group_id, data.as_bytes().to_vec())
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Signs a message with the given group ID and data as `signMessage` does, but with the account of the
+/// given identity instead of the current account, e.g. for apps which post as multiple identities. If the
+/// group has no messages yet, its genesis record is signed by the account. It fails if the account does
+/// not exist.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signMessageAs(identity: &str, group_id: &str, data: &str) -> Result<String, String> {
+    let identity = Identity::try_from(identity).map_err(|_| "Fail to parse".to_string())?;
+    let account = AccountStore::default()
+        .account(&identity)
+        .ok_or("Account not found".to_string())?;
+    let mut signer = Signer::with_account(account);
+    ensure_genesis_by(&mut signer, group_id)?;
+    let (_, wrote_signed_msg) =
+        sign_and_write(group_id, || signer.sign(group_id, data.as_bytes().to_vec()))?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Imports the legacy history of the given group, which is a JSON array of entries with the data and
+/// optionally the original timestamp and author of each message, ordered from the oldest. Each entry is
+/// signed by the current account and appended as a message tagged "imported", with the original timestamp
+/// and author recorded in its tags. It returns the signed messages. If a message is rejected, the import
+/// stops and the messages before it remain written.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn importHistory(group_id: &str, entries: &str) -> Result<Vec<String>, String> {
+    let entries: Vec<HistoryEntry> =
+        serde_json::from_str(entries).map_err(|_| "Fail to parse".to_string())?;
+    AccountStore::default()
+        .current_account()
+        .ok_or("No current account".to_string())?;
+    ensure_genesis(group_id)?;
+
+    let mut signed_msgs = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let signed_msg = Signer::default().sign_tagged(
+            group_id,
+            entry.data.as_bytes().to_vec(),
+            entry.tags(),
+        )?;
+        let (_, wrote_signed_msg) =
+            Writer::default().write_with_validation(group_id, signed_msg)?;
+        signed_msgs.push(serde_json::to_string(&wrote_signed_msg).unwrap());
+    }
+    Ok(signed_msgs)
+}
+
+/// Signs a message with the given group ID, data and tags as `signMessage` does. The tags are covered
+/// by the signature and can be queried with `messagesByTag`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signTaggedMessage(group_id: &str, data: &str, tags: Vec<String>) -> Result<String, String> {
+    ensure_genesis(group_id)?;
+    let (_, wrote_signed_msg) = sign_and_write(group_id, || {
+        Signer::default().sign_tagged(group_id, data.as_bytes().to_vec(), tags.clone())
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Saves the unsigned draft of a message in the given group, which is not written to the chain until it
+/// is signed with `signDraft`. If the draft id is given, the draft is updated. It returns the draft id.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn saveDraft(group_id: &str, data: &str, draft_id: Option<String>) -> Result<String, String> {
+    let mut draft_store = DraftStore::default();
+    let now = unix_timestamp();
+    let draft = match draft_id {
+        Some(draft_id) => {
+            let mut draft = draft_store
+                .draft(group_id, &draft_id)
+                .ok_or("draft not found".to_string())?;
+            draft.data = data.to_string();
+            draft.updated_at = now;
+            draft
+        }
+        None => Draft::new(data.to_string(), now),
+    };
+    let draft_id = draft.id.clone();
+    draft_store.set_draft(group_id, draft)?;
+    Ok(draft_id)
+}
+
+/// Returns the drafts of the given group in the order of creation.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn listDrafts(group_id: &str) -> Vec<String> {
+    DraftStore::default()
+        .drafts(group_id)
+        .iter()
+        .map(|draft| serde_json::to_string(draft).unwrap())
+        .collect()
+}
+
+/// Deletes the draft. It returns false if the draft does not exist.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn deleteDraft(group_id: &str, draft_id: &str) -> Result<bool, String> {
+    DraftStore::default().remove_draft(group_id, draft_id)
+}
+
+/// Signs the draft as `signMessage` does and deletes it, atomically. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signDraft(group_id: &str, draft_id: &str) -> Result<String, String> {
+    let mut draft_store = DraftStore::default();
+    let draft = draft_store
+        .draft(group_id, draft_id)
+        .ok_or("draft not found".to_string())?;
+    journal::atomically(|| {
+        let signed_msg = signMessage(group_id, &draft.data)?;
+        draft_store.remove_draft(group_id, draft_id)?;
+        Ok(signed_msg)
+    })?
+}
+
+/// Signs the data with the current account, detached from any chain, e.g. to authenticate a login challenge
+/// or a file hash. It returns the signature, which is verified with `verifyData`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signData(data: &str) -> Result<String, String> {
+    let (identity, secret) = AccountStore::default()
+        .current_account()
+        .ok_or("No current account".to_string())?;
+    let signature = detached::sign(&identity, &secret, data.as_bytes());
+    Ok(serde_json::to_string(&signature).unwrap())
+}
+
+/// Verifies the signature of the data by the identity, which is signed with `signData`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifyData(identity: &str, data: &str, signature: &str) -> bool {
+    let (Ok(identity), Ok(signature)) = (
+        Identity::try_from(identity),
+        serde_json::from_str::<Signature>(signature),
+    ) else {
+        return false;
+    };
+    detached::verify(&identity, data.as_bytes(), &signature)
+}
+
+/// Creates a challenge with a random nonce to authenticate a user, which expires after
+/// `auth::CHALLENGE_TTL` seconds. The user answers it with `signAuthChallenge`, and the response is checked
+/// with `verifyAuthResponse` by the same verifier, e.g. the native build on a backend.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn createAuthChallenge() -> Result<String, String> {
+    let now = unix_timestamp();
+    let challenge = AuthChallenge::new(now);
+    AuthChallengeStore::default().add_challenge(challenge.clone(), now)?;
+    Ok(serde_json::to_string(&challenge).unwrap())
+}
+
+/// Signs the challenge created with `createAuthChallenge` with the current account. It returns the response.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signAuthChallenge(challenge: &str) -> Result<String, String> {
+    let challenge: AuthChallenge =
+        serde_json::from_str(challenge).map_err(|_| "Fail to parse".to_string())?;
+    if challenge.is_expired_at(unix_timestamp()) {
+        return Err("Challenge is expired".to_string());
+    }
+    let (identity, secret) = AccountStore::default()
+        .current_account()
+        .ok_or("No current account".to_string())?;
+    let response = AuthResponse::new(challenge, identity, &secret);
+    Ok(serde_json::to_string(&response).unwrap())
+}
+
+/// Verifies that the response answers the challenge and is signed by the identity. The challenge must be
+/// created by this verifier and not be expired, and it is accepted once, so that responses cannot be replayed.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifyAuthResponse(identity: &str, challenge: &str, response: &str) -> Result<bool, String> {
+    let (Ok(identity), Ok(challenge), Ok(response)) = (
+        Identity::try_from(identity),
+        serde_json::from_str::<AuthChallenge>(challenge),
+        serde_json::from_str::<AuthResponse>(response),
+    ) else {
+        return Ok(false);
+    };
+    if response.identity != identity || response.challenge != challenge || !response.verify() {
+        return Ok(false);
+    }
+    AuthChallengeStore::default().take_challenge(&challenge, unix_timestamp())
+}
+
+/// Signs a message with the given group ID and data, and queues it in the outbox instead of writing it,
+/// e.g. while sync is unavailable. It is signed on top of the chain head, or the previous queued message
+/// of the group. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn queueMessage(group_id: &str, data: &str) -> Result<String, String> {
+    let mut outbox_store = OutboxStore::default();
+    let previous = outbox_store
+        .last_entry(group_id)
+        .map(|entry| entry.signed_message);
+    let hash_algorithm = GroupStore::default().state(group_id).hash_algorithm;
+    let signed_msg = Signer::default().sign_after(
+        group_id,
+        data.as_bytes().to_vec(),
+        previous.as_ref(),
+        hash_algorithm,
+    )?;
+    let signed_msg_str = serde_json::to_string(&signed_msg).unwrap();
+    outbox_store.push_entry(OutboxEntry {
+        group_id: group_id.to_string(),
+        data: data.to_string(),
+        signed_message: signed_msg,
+        queued_at: unix_timestamp(),
+    })?;
+    Ok(signed_msg_str)
+}
+
+/// Returns the messages queued in the outbox, in the order they are queued.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn outbox() -> Vec<String> {
+    OutboxStore::default()
+        .entries()
+        .iter()
+        .map(|entry| serde_json::to_string(entry).unwrap())
+        .collect()
+}
+
+/// Appends the messages queued in the outbox to their groups in order, with validation. A message
+/// whose position is taken, e.g. because the head moved after syncing, is re-signed onto the new head.
+/// If the group has no messages yet, its genesis record is signed first. A message which is rejected
+/// stays in the outbox with the later messages of its group. The messages and the outbox are written
+/// atomically, so that a message is not written again by the next flush if the writes fail. It returns the
+/// written messages.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn flushOutbox() -> Result<Vec<String>, String> {
+    journal::atomically(|| {
+        let mut outbox_store = OutboxStore::default();
+        let mut written = vec![];
+        let mut remaining: Vec<OutboxEntry> = vec![];
+        for entry in outbox_store.entries() {
+            let group_id = entry.group_id.as_str();
+            if remaining.iter().any(|e| e.group_id == group_id) || ensure_genesis(group_id).is_err()
+            {
+                remaining.push(entry);
+                continue;
+            }
+
+            let (head, seq) = SignedMessageStore::default()
+                .latest_message(group_id)
+                .map(|(hash, msg)| (hash, msg.next_seq()))
+                .unwrap_or(([0u8; 32], Some(0)));
+            let signed_msg = if entry.signed_message.message.previous_hash == head
+                && Some(entry.signed_message.seq) == seq
+            {
+                Ok(entry.signed_message.clone())
+            } else {
+                Signer::default().sign(group_id, entry.data.as_bytes().to_vec())
+            };
+            match signed_msg.and_then(|signed_msg| {
+                Ok(Writer::default().write_with_validation(group_id, signed_msg)?)
+            }) {
+                Ok((_, wrote_signed_msg)) => {
+                    written.push(serde_json::to_string(&wrote_signed_msg).unwrap())
+                }
+                Err(_) => remaining.push(entry),
+            }
+        }
+        outbox_store.set_entries(remaining)?;
+        Ok(written)
+    })?
+}
+
+/// Adds a signed message to the store for the given group ID. It returns the hash of the message.
+/// The input is rejected with a specific error if it is too large, its previous hash, identity or
+/// signature is malformed, or it sets a storage flag, before the signature is verified. A message rejected for a wrong sequence number or previous hash, e.g. delivered out of order, is
+/// kept in the quarantine, from which it can be retried with `retryQuarantined`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addSignedMessage(group_id: &str, signed_msg_str: &str) -> Result<String, String> {
+    let signed_msg = schema::parse_signed_message(signed_msg_str)?;
+
+    let (hash, _) = Writer::default()
+        .write_with_validation(group_id, signed_msg.clone())
+        .map_err(|err| {
+            if matches!(
+                err,
+                WriteError::WrongSequence | WriteError::WrongPreviousHash
+            ) {
+                // the message is rejected either way, and a failed write is reported to the diagnostics
+                let _ = QuarantineStore::default().add_entry(
+                    group_id,
+                    QuarantineEntry {
+                        signed_message: signed_msg,
+                        reason: err.to_string(),
+                        received_at: unix_timestamp(),
+                    },
+                );
+            }
+            err
+        })?;
+    notify_received(group_id, &[hash]);
+    Ok(serde_json::to_string(&hash).unwrap())
+}
+
+/// Adds a signed message to the store for the given group ID as `addSignedMessage` does, but a message
+/// which arrives before its ancestors is held in a pending buffer, and is added with validation once the
+/// gap is filled. The held messages are limited by the write limits and by the capacity of the buffer
+/// ([MAX_PENDING_MESSAGES](writer::MAX_PENDING_MESSAGES) per group), and the messages beyond them are
+/// rejected. It returns the hashes of the added messages in order, which are empty if the message is held.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn ingestSignedMessage(group_id: &str, signed_msg_str: &str) -> Result<Vec<String>, String> {
+    let signed_msg = schema::parse_signed_message(signed_msg_str)?;
+
+    let written = Writer::default().write_in_order(group_id, signed_msg)?;
+    notify_received(group_id, &written);
+    Ok(written
+        .iter()
+        .map(|hash| serde_json::to_string(hash).unwrap())
+        .collect())
+}
+
+/// Returns the messages of the given group held in the pending buffer, ordered by their sequence numbers.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn pendingMessages(group_id: &str) -> Vec<String> {
+    PendingStore::default()
+        .pending(group_id)
+        .iter()
+        .map(|msg| serde_json::to_string(msg).unwrap())
+        .collect()
+}
+
+/// Returns the quarantined messages of the given group, in the order they are received.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn quarantined(group_id: &str) -> Vec<String> {
+    QuarantineStore::default()
+        .entries(group_id)
+        .iter()
+        .map(|entry| serde_json::to_string(entry).unwrap())
+        .collect()
+}
+
+/// Retries to add the quarantined messages of the given group in the order of their sequence numbers,
+/// e.g. after the missing messages are synced, until no more message can be added. The messages which
+/// are still rejected stay in the quarantine with the latest reasons. It returns the hashes of the added
+/// messages.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn retryQuarantined(group_id: &str) -> Result<Vec<String>, String> {
+    let mut quarantine_store = QuarantineStore::default();
+    let mut entries = quarantine_store.entries(group_id);
+    entries.sort_by_key(|entry| entry.signed_message.seq);
+
+    let mut added = vec![];
+    loop {
+        let count = entries.len();
+        entries.retain_mut(|entry| {
+            match Writer::default().write_with_validation(group_id, entry.signed_message.clone()) {
+                Ok((hash, _)) => {
+                    added.push(hash);
+                    false
+                }
+                Err(err) => {
+                    entry.reason = err.to_string();
+                    true
+                }
+            }
+        });
+        if entries.len() == count {
+            break;
+        }
+    }
+    notify_received(group_id, &added);
+    quarantine_store.set_entries(group_id, entries)?;
+    Ok(added
+        .iter()
+        .map(|hash| serde_json::to_string(hash).unwrap())
+        .collect())
+}
+
+/// Makes the notifications of the data messages with the hashes, which are ordered from the oldest, if a
+/// notifier is set (see [notification]). The messages signed by the local accounts are skipped.
+fn notify_received(group_id: &str, hashes: &[MessageHash]) {
+    if !notification::is_enabled() || hashes.is_empty() {
+        return;
+    }
+    let account_store = AccountStore::default();
+    let contact_store = ContactStore::default();
+    let messages = Reader::default().messages_by_hash(group_id, hashes);
+    for (hash, signed_msg) in messages.iter().rev() {
+        if signed_msg.message.kind != MessageKind::Data
+            || account_store.account(&signed_msg.id).is_some()
+        {
+            continue;
+        }
+        let alias = contact_store
+            .contact(&signed_msg.id)
+            .map(|contact| contact.alias);
+        notification::notify(&Notification::new(group_id, *hash, signed_msg, alias));
+    }
+}
+
+/// Discards the quarantined message with the given hash. It returns false if the message is not quarantined.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn discardQuarantined(group_id: &str, hash: &str) -> Result<bool, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    QuarantineStore::default().remove_entry(group_id, &hash)
+}
+
+/// Returns the agreement key of the identity pinned on first use in the group, if any. A message which
+/// announces another key is rejected by `addSignedMessage` with an error starting with "key changed".
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn pinnedKey(group_id: &str, identity: &str) -> Result<Option<String>, String> {
+    let identity = parse_identity(identity)?;
+    Ok(PinStore::default()
+        .pinned_key(group_id, &identity)
+        .map(|key| serde_json::to_string(&key).unwrap()))
+}
+
+/// Removes the pinned agreement key of the identity in the group, e.g. after the new key is verified
+/// out of band, so that the next announced key is pinned.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn unpinKey(group_id: &str, identity: &str) -> Result<(), String> {
+    let identity = parse_identity(identity)?;
+    PinStore::default().unpin_key(group_id, &identity)
+}
+
+/// Generates a new content key for the given group and returns it. Subsequent messages signed
+/// in the group are encrypted. The key must be shared with the members with `setGroupKey`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn newGroupKey(group_id: &str) -> Result<String, String> {
+    let key = encryption::generate_group_key();
+    GroupKeyStore::default().set_group_key(group_id, key)?;
+    Ok(serde_json::to_string(&key).unwrap())
+}
+
+/// Sets the content key for the given group, e.g. the key shared by another member.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setGroupKey(group_id: &str, key: &str) -> Result<(), String> {
+    let key = serde_json::from_str(key).map_err(|_| "Fail to parse".to_string())?;
+    GroupKeyStore::default().set_group_key(group_id, key)
+}
+
+/// Removes the content key of the given group, and the data of its messages deciphered with it.
+/// Subsequent messages are not encrypted.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn removeGroupKey(group_id: &str) -> Result<(), String> {
+    GroupKeyStore::default().remove_group_key(group_id)?;
+    RatchetStore::default().remove_deciphered(group_id)
+}
+
+/// Signs a record that announces the key agreement public key of the current account in the given
+/// group. It returns the signed message, which should be shared with the members holding the group key.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn announceAgreementKey(group_id: &str) -> Result<String, String> {
+    let (_, wrote_signed_msg) = sign_and_write(group_id, || {
+        Signer::default().sign_key_announcement(group_id)
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Signs a record that grants the group content key to the given members. Each member must have
+/// announced the agreement key in the group. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn grantGroupKey(group_id: &str, members: Vec<String>) -> Result<String, String> {
+    let members = members
+        .iter()
+        .map(|member| Identity::try_from(member.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| "Fail to parse identity".to_string())?;
+    let (_, wrote_signed_msg) = sign_checked_and_write(group_id, || {
+        Signer::default().sign_key_distribution(group_id, &members)
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Rotates the group content key after removing the given members. The new key is granted to the
+/// remaining members in a signed record, so the removed members cannot read subsequent messages.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn rotateGroupKey(group_id: &str, removed_members: Vec<String>) -> Result<String, String> {
+    let removed_members = removed_members
+        .iter()
+        .map(|member| Identity::try_from(member.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| "Fail to parse identity".to_string())?;
+    // the new key replaces the current one only if the record is written, together with it
+    let wrote_signed_msg = journal::atomically(|| {
+        let mut group_key = None;
+        let (_, wrote_signed_msg) = sign_checked_and_write(group_id, || {
+            let (signed_msg, key) = Signer::default().sign_rekey(group_id, &removed_members)?;
+            group_key = Some(key);
+            Ok(signed_msg)
+        })?;
+        if let Some(group_key) = group_key {
+            Writer::default().adopt_group_key(group_id, &wrote_signed_msg, group_key)?;
+        }
+        Ok::<_, String>(wrote_signed_msg)
+    })??;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Seals the group by signing a `Seal` record, which must be signed by the creator of the group. No
+/// message may be appended after the seal, e.g. to close an audit trail or a finished negotiation.
+/// It returns the signed record.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn sealGroup(group_id: &str) -> Result<String, String> {
+    let signed_msg = Signer::default().sign_seal(group_id)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Checks if the group is sealed by a `Seal` record.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn isGroupSealed(group_id: &str) -> bool {
+    GroupStore::default().state(group_id).sealed
+}
+
+/// Starts the ratchet over the group content key from the next message, so that each message is
+/// encrypted with a fresh key and older messages stay protected if the current key is compromised.
+/// It returns the signed record.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn startRatchet(group_id: &str) -> Result<String, String> {
+    if GroupKeyStore::default().group_key(group_id).is_none() {
+        return Err("no group key".to_string());
+    }
+    let (_, wrote_signed_msg) = sign_checked_and_write(group_id, || {
+        Signer::default().sign_record(group_id, MessageKind::RatchetStart, vec![])
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Signs a sealed message with the given group ID and data. The message is signed by an ephemeral
+/// identity and the sender is only readable by the members holding the group content key.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signSealedMessage(group_id: &str, data: &str) -> Result<String, String> {
+    ensure_genesis(group_id)?;
+    let (_, wrote_signed_msg) = sign_and_write(group_id, || {
+        Signer::default().sign_sealed(group_id, data.as_bytes().to_vec())
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Signs a message which replies to the message with the given hash and writes it to the group.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signReply(group_id: &str, data: &str, reply_to: &str) -> Result<String, String> {
+    let reply_to = serde_json::from_str(reply_to).map_err(|_| "Fail to parse".to_string())?;
+    ensure_genesis(group_id)?;
+    let (_, wrote_signed_msg) = sign_and_write(group_id, || {
+        Signer::default().sign_reply(group_id, data.as_bytes().to_vec(), reply_to)
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Signs a message which refers to the message with the given hash in the referred group, e.g. to quote it
+/// or link it as evidence, and writes it to the group. The reference is covered by the signature.
+/// It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signReference(
+    group_id: &str,
+    data: &str,
+    ref_group_id: &str,
+    ref_hash: &str,
+) -> Result<String, String> {
+    let reference = MessageRef {
+        group_id: ref_group_id.to_string(),
+        hash: serde_json::from_str(ref_hash).map_err(|_| "Fail to parse".to_string())?,
+    };
+    ensure_genesis(group_id)?;
+    let (_, wrote_signed_msg) = sign_and_write(group_id, || {
+        Signer::default().sign_reference(group_id, data.as_bytes().to_vec(), reference.clone())
+    })?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Returns the messages which refer to the message with the given hash in the given group, as JSON of
+/// [MessageRef] with the group IDs and the hashes of the referring messages.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn referencesTo(group_id: &str, hash: &str) -> Result<Vec<String>, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    Ok(ReferenceStore::default()
+        .references_to(group_id, &hash)
+        .iter()
+        .map(|reference| serde_json::to_string(reference).unwrap())
+        .collect())
+}
+
+/// Signs an edit of the message with the given hash, which must be signed by the current account, and
+/// writes it to the group. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn editMessage(group_id: &str, hash: &str, data: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let signed_msg = Signer::default().sign_edit(group_id, data.as_bytes().to_vec(), hash)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Redacts the message with the given hash, which must be signed by the current account unless it is an
+/// admin, by writing a redaction record. Reads return a tombstone instead of the data. If `purge` is true,
+/// the data is also removed from the local storage. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn redactMessage(group_id: &str, hash: &str, purge: bool) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let signed_msg = Signer::default().sign_redaction(group_id, hash)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+    if purge {
+        SignedMessageStore::default().purge_message(group_id, &hash)?;
+        forget_deciphered(group_id)?;
+    }
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Pins the message with the given hash by writing a pin record, so that it is listed by
+/// `pinnedMessages`. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn pinMessage(group_id: &str, hash: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let signed_msg = Signer::default().sign_pin_change(group_id, MessageKind::Pin, hash)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Unpins the message with the given hash by writing an unpin record. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn unpinMessage(group_id: &str, hash: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let signed_msg = Signer::default().sign_pin_change(group_id, MessageKind::Unpin, hash)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Returns the pinned messages of the given group as `messages` does, ordered from the latest pinned.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn pinnedMessages(group_id: &str) -> Vec<String> {
+    Reader::default()
+        .pinned_messages(group_id)
+        .iter()
+        .map(|msg| serde_json::to_string(msg).unwrap())
+        .collect()
+}
+
+/// Removes the data of the message with the given hash from the local storage. The message must be
+/// redacted by a redaction record in the group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn purgeMessage(group_id: &str, hash: &str) -> Result<(), String> {
+    let hash: MessageHash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let mut message_store = SignedMessageStore::default();
+    let is_redacted = message_store.messages(group_id).iter().any(|msg| {
+        msg.message.kind == MessageKind::Redaction
+            && serde_json::from_slice::<Redaction>(&msg.message.data)
+                .is_ok_and(|redaction| redaction.hash == hash)
+    });
+    if !is_redacted {
+        return Err("Message is not redacted".to_string());
+    }
+    message_store.purge_message(group_id, &hash)?;
+    forget_deciphered(group_id)
+}
+
+/// Removes the data kept for the encrypted messages of the group which are no longer stored, e.g. after
+/// their data is purged or they are pruned.
+fn forget_deciphered(group_id: &str) -> Result<(), String> {
+    let messages = SignedMessageStore::default().messages(group_id);
+    let ciphertexts = messages
+        .iter()
+        .filter(|msg| msg.message.encrypted)
+        .map(|msg| msg.message.data.as_slice());
+    RatchetStore::default().retain_deciphered(group_id, ciphertexts)
+}
+
+/// Returns the replies to the message with the given hash, ordered from the oldest.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn replies(group_id: &str, hash: &str) -> Result<Vec<String>, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    Ok(Reader::default()
+        .replies(group_id, &hash)
+        .iter()
+        .map(|msg| serde_json::to_string(msg).unwrap())
+        .collect())
+}
+
+/// Returns the thread tree rooted at the message with the given hash, in which each message is
+/// paired with its hash and the replies to it.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn thread(group_id: &str, hash: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let thread = Reader::default()
+        .thread(group_id, &hash)
+        .ok_or("Message not found".to_string())?;
+    Ok(serde_json::to_string(&thread).unwrap())
+}
+
+/// Returns the causal order of the signed message `a` relative to the signed message `b`, e.g. a message
+/// received from another device, as a JSON string: "before" if `a` is an ancestor of `b`, "after" if `b`
+/// is an ancestor of `a`, "equal", or "concurrent". The ancestors are looked up in the stored chain of
+/// the group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn causalOrder(group_id: &str, a: &str, b: &str) -> Result<String, String> {
+    let a: SignedMessage<Identity, Signature> =
+        serde_json::from_str(a).map_err(|_| "Fail to parse".to_string())?;
+    let b: SignedMessage<Identity, Signature> =
+        serde_json::from_str(b).map_err(|_| "Fail to parse".to_string())?;
+    let message_store = SignedMessageStore::default();
+    let order = causal::causal_order(&a, &b, |hash| message_store.message(group_id, hash));
+    Ok(serde_json::to_string(&order).unwrap())
+}
+
+/// Sorts the signed messages in the deterministic order, by their sequence numbers, then their
+/// timestamps and then their hashes. The order is the same on every device, and each message comes after
+/// its ancestors, so the concurrent messages are ordered in the same way everywhere.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn sortCausally(messages: Vec<String>) -> Result<Vec<String>, String> {
+    let mut parsed = messages
+        .into_iter()
+        .map(|msg_str| {
+            serde_json::from_str::<SignedMessage<Identity, Signature>>(&msg_str)
+                .map(|signed_msg| (signed_msg, msg_str))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| "Fail to parse".to_string())?;
+    parsed.sort_by(|(a, _), (b, _)| causal::deterministic_cmp(a, b));
+    Ok(parsed.into_iter().map(|(_, msg_str)| msg_str).collect())
+}
+
+/// Reveals the sender of the sealed message with the given hash, e.g. for a dispute. It returns a
+/// proof which can be verified with `verifySenderProof` without the group content key.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn revealSender(group_id: &str, hash: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let proof = Reader::default().reveal_sender(group_id, &hash)?;
+    Ok(serde_json::to_string(&proof).unwrap())
+}
+
+/// Verifies the proof returned by `revealSender`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifySenderProof(proof: &str) -> bool {
+    serde_json::from_str::<SenderProof>(proof).is_ok_and(|proof| proof.verify())
+}
+
+/// Acknowledges the message with the given hash by the current account, where the kind is `received`
+/// or `approved`. The acknowledgment is stored alongside the chain. It returns the acknowledgment.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn acknowledge(group_id: &str, hash: &str, kind: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let kind = serde_json::from_value(serde_json::Value::String(kind.to_string()))
+        .map_err(|_| "Fail to parse kind".to_string())?;
+    if SignedMessageStore::default()
+        .message(group_id, &hash)
+        .is_none()
+    {
+        return Err("Message not found".to_string());
+    }
+    let ack = Signer::default().sign_acknowledgment(group_id, hash, kind)?;
+    AcknowledgmentStore::default().add_acknowledgment(ack.clone())?;
+    Ok(serde_json::to_string(&ack).unwrap())
+}
+
+/// Adds the acknowledgment received from another member. It fails if the signature is invalid or
+/// the acknowledged message is not found.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addAcknowledgment(ack: &str) -> Result<(), String> {
+    let ack: Acknowledgment = serde_json::from_str(ack).map_err(|_| "Fail to parse".to_string())?;
+    if !ack.verify() {
+        return Err("Invalid signature".to_string());
+    }
+    if SignedMessageStore::default()
+        .message(&ack.body.group_id, &ack.body.hash)
+        .is_none()
+    {
+        return Err("Message not found".to_string());
+    }
+    AcknowledgmentStore::default().add_acknowledgment(ack)
+}
+
+/// Returns the acknowledgments of the message with the given hash.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn acknowledgments(group_id: &str, hash: &str) -> Result<Vec<String>, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    Ok(AcknowledgmentStore::default()
+        .acknowledgments(group_id, &hash)
+        .iter()
+        .map(|ack| serde_json::to_string(ack).unwrap())
+        .collect())
+}
+
+/// Verifies the signature of the acknowledgment.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifyAcknowledgment(ack: &str) -> bool {
+    serde_json::from_str::<Acknowledgment>(ack).is_ok_and(|ack| ack.verify())
+}
+
+/// Reacts to the message with the given hash by the current account. The reaction is stored outside
+/// the chain. It returns the reaction.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn react(group_id: &str, hash: &str, emoji: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    if SignedMessageStore::default()
+        .message(group_id, &hash)
+        .is_none()
+    {
+        return Err("Message not found".to_string());
+    }
+    let reaction = Signer::default().sign_reaction(group_id, hash, emoji)?;
+    ReactionStore::default().add_reaction(reaction.clone())?;
+    Ok(serde_json::to_string(&reaction).unwrap())
+}
+
+/// Adds the reaction received from another member. It fails if the signature is invalid or the
+/// message is not found.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addReaction(reaction: &str) -> Result<(), String> {
+    let reaction: Reaction =
+        serde_json::from_str(reaction).map_err(|_| "Fail to parse".to_string())?;
+    if !reaction.verify() {
+        return Err("Invalid signature".to_string());
+    }
+    if SignedMessageStore::default()
+        .message(&reaction.body.group_id, &reaction.body.hash)
+        .is_none()
+    {
+        return Err("Message not found".to_string());
+    }
+    ReactionStore::default().add_reaction(reaction)
+}
+
+/// Returns the reactions to the message with the given hash.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn reactions(group_id: &str, hash: &str) -> Result<Vec<String>, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    Ok(ReactionStore::default()
+        .reactions(group_id, &hash)
+        .iter()
+        .map(|reaction| serde_json::to_string(reaction).unwrap())
+        .collect())
+}
+
+/// Signs a checkpoint record which summarizes the chain of the group up to the latest message, including
+/// the state of the group and the merkle root. The current account must be an admin. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn checkpoint(group_id: &str) -> Result<String, String> {
+    let state = GroupStore::default().state(group_id);
+    if state.genesis.is_none() {
+        return Err("Group not found".to_string());
+    }
+    let mmr = MerkleStore::default().mmr(group_id);
+    let merkle_root = (mmr.leaf_count() > 0).then(|| mmr.root());
+    let signed_msg = Signer::default().sign_checkpoint(group_id, state, merkle_root)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Migrates the chain of the group to the hash algorithm with the given name, e.g. "sha512_256", by signing
+/// a `HashMigration` record which binds the latest message, hashed with the current algorithm, to the new
+/// algorithm. The record and the messages after it are hashed with the new algorithm. The current
+/// account must be an admin. It returns the signed message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn migrateHashAlgorithm(group_id: &str, algorithm: &str) -> Result<String, String> {
+    let to = HashAlgorithm::try_from(algorithm)?;
+    let state = GroupStore::default().state(group_id);
+    if state.genesis.is_none() {
+        return Err("Group not found".to_string());
+    }
+    let signed_msg = Signer::default().sign_hash_migration(group_id, state.hash_algorithm, to)?;
+    let (_, wrote_signed_msg) = Writer::default().write_with_validation(group_id, signed_msg)?;
+
+    Ok(serde_json::to_string(&wrote_signed_msg).unwrap())
+}
+
+/// Returns the name of the hash algorithm with which the next message of the group is hashed.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn hashAlgorithm(group_id: &str) -> String {
+    GroupStore::default()
+        .state(group_id)
+        .hash_algorithm
+        .name()
+        .to_string()
+}
+
+/// Returns the root of the Merkle Mountain Range over the message hashes of the group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn merkleRoot(group_id: &str) -> String {
+    serde_json::to_string(&MerkleStore::default().mmr(group_id).root()).unwrap()
+}
+
+/// Returns the proof that the message with the given hash is part of the chain of the group, which
+/// can be verified against the merkle root with `verifyInclusion`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn proveInclusion(group_id: &str, hash: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let proof = MerkleStore::default()
+        .mmr(group_id)
+        .prove(&hash)
+        .ok_or("Message not found".to_string())?;
+    Ok(serde_json::to_string(&proof).unwrap())
+}
+
+/// Verifies the proof returned by `proveInclusion` against the merkle root.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifyInclusion(proof: &str, root: &str) -> bool {
+    let (Ok(proof), Ok(root)) = (
+        serde_json::from_str::<InclusionProof>(proof),
+        serde_json::from_str(root),
+    ) else {
+        return false;
+    };
+    proof.verify(&root)
+}
+
+/// Returns the proof that the chain of the group at the new head extends the chain at the old head
+/// without rewrites, which can be verified against the merkle roots at both heads with `verifyConsistency`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn consistencyProof(group_id: &str, old_head: &str, new_head: &str) -> Result<String, String> {
+    let message_store = SignedMessageStore::default();
+    let leaf_count = |head: &str| -> Result<(MessageHash, u64), String> {
+        let head = serde_json::from_str(head).map_err(|_| "Fail to parse".to_string())?;
+        let message = message_store
+            .message(group_id, &head)
+            .ok_or("Message not found".to_string())?;
+        Ok((head, message.seq + 1))
+    };
+    let (old_head, old_leaf_count) = leaf_count(old_head)?;
+    let (new_head, new_leaf_count) = leaf_count(new_head)?;
+
+    let proof = MerkleStore::default()
+        .mmr(group_id)
+        .prefix(new_leaf_count)
+        .prove_consistency(old_leaf_count)
+        .filter(|proof| proof.old_head.leaf == old_head && proof.new_head.leaf == new_head)
+        .ok_or("Fail to prove".to_string())?;
+    Ok(serde_json::to_string(&proof).unwrap())
+}
+
+/// Verifies the proof returned by `consistencyProof` against the merkle roots at the old and new heads.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifyConsistency(proof: &str, old_root: &str, new_root: &str) -> bool {
+    let (Ok(proof), Ok(old_root), Ok(new_root)) = (
+        serde_json::from_str::<ConsistencyProof>(proof),
+        serde_json::from_str(old_root),
+        serde_json::from_str(new_root),
+    ) else {
+        return false;
+    };
+    proof.verify(&old_root, &new_root)
+}
+
+/// Exports the evidence bundle of the message with the given hash: the message, its ancestors up to
+/// the genesis record or the latest checkpoint before it, the public keys of the signers, and the
+/// inclusion and consistency proofs against the current merkle root. The bundle is verified offline
+/// with `verifyEvidence`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn exportEvidence(group_id: &str, hash: &str) -> Result<String, String> {
+    let hash = serde_json::from_str(hash).map_err(|_| "Fail to parse".to_string())?;
+    let message_store = SignedMessageStore::default();
+    let mut messages = vec![];
+    let mut current = hash;
+    loop {
+        let message = message_store
+            .message(group_id, &current)
+            .ok_or("Message not found".to_string())?;
+        if message.message.redacted {
+            return Err("The chain contains redacted messages".to_string());
+        }
+        let is_start =
+            message.is_first_message() || message.message.kind == MessageKind::Checkpoint;
+        current = message.message.previous_hash;
+        messages.push(message);
+        if is_start {
+            break;
+        }
+    }
+    messages.reverse();
+
+    let evidence = Evidence::new(group_id, messages, &MerkleStore::default().mmr(group_id))
+        .ok_or("Message not found".to_string())?;
+    Ok(serde_json::to_string(&evidence).unwrap())
+}
+
+/// Verifies the evidence bundle returned by `exportEvidence`. It does not access the local storage.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifyEvidence(evidence: &str) -> bool {
+    serde_json::from_str::<Evidence>(evidence).is_ok_and(|evidence| evidence.verify().is_ok())
+}
+
+/// Anchors the chain head of the group to the timestamping service at the url, which is either
+/// "rfc3161" or "open_timestamps". The request is synchronous. It stores and returns the proof.
+#[cfg(feature = "web")]
+#[allow(non_snake_case)]
+#[wasm_bindgen]
+pub fn anchor(group_id: &str, service: &str, url: &str) -> Result<String, String> {
+    let head = SignedMessageStore::default()
+        .latest_message_hash(group_id)
+        .ok_or("Group not found".to_string())?;
+    let proof = HttpAnchor::new(AnchorService::try_from(service)?, url).anchor(&head)?;
+    AnchorStore::default().add_anchor(group_id, proof.clone())?;
+    Ok(serde_json::to_string(&proof).unwrap())
+}
+
+/// Adds the anchor proof of a chain head of the group, e.g. obtained by submitting the head to the
+/// service outside of this library. It fails if the head is not a message of the group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addAnchorProof(group_id: &str, proof: &str) -> Result<(), String> {
+    let proof: AnchorProof =
+        serde_json::from_str(proof).map_err(|_| "Fail to parse".to_string())?;
+    if SignedMessageStore::default()
+        .message(group_id, &proof.digest)
+        .is_none()
+    {
+        return Err("Message not found".to_string());
+    }
+    AnchorStore::default().add_anchor(group_id, proof)
+}
+
+/// Returns the anchor proofs of the group, from the oldest.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn anchors(group_id: &str) -> Vec<String> {
+    AnchorStore::default()
+        .anchors(group_id)
+        .iter()
+        .map(|proof| serde_json::to_string(proof).unwrap())
+        .collect()
+}
+
+/// Creates the request to the notary with the given identity to countersign the chain head of the
+/// group. The request is sent to the notary, who returns the attestation with `notarize`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn requestNotarization(group_id: &str, notary: &str) -> Result<String, String> {
+    let notary = Identity::try_from(notary).map_err(|_| "Fail to parse".to_string())?;
+    let (requester, _) = AccountStore::default()
+        .current_account()
+        .ok_or("Account not found".to_string())?;
+    let (head, latest_msg) = SignedMessageStore::default()
+        .latest_message(group_id)
+        .ok_or("Group not found".to_string())?;
+    let request = NotarizationRequest {
+        group_id: group_id.to_string(),
+        head,
+        seq: latest_msg.seq,
+        merkle_root: MerkleStore::default().mmr(group_id).root(),
+        notary,
+        requester,
+    };
+    Ok(serde_json::to_string(&request).unwrap())
+}
+
+/// Countersigns the chain head in the request by the current account, which must be the notary of the
+/// request. It returns the attestation, which is sent back to the requester.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn notarize(request: &str) -> Result<String, String> {
+    let request: NotarizationRequest =
+        serde_json::from_str(request).map_err(|_| "Fail to parse".to_string())?;
+    let attestation = Signer::default()
+        .sign_attestation(request)
+        .ok_or("The current account is not the notary".to_string())?;
+    Ok(serde_json::to_string(&attestation).unwrap())
+}
+
+/// Adds the attestation returned by the notary. It fails if the signature is invalid or the attested
+/// chain head is not a message of the group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn addAttestation(attestation: &str) -> Result<(), String> {
+    let attestation: Attestation =
+        serde_json::from_str(attestation).map_err(|_| "Fail to parse".to_string())?;
+    if !attestation.verify() {
+        return Err("Invalid signature".to_string());
+    }
+    let request = &attestation.body.request;
+    if SignedMessageStore::default()
+        .message(&request.group_id, &request.head)
+        .filter(|message| message.seq == request.seq)
+        .is_none()
+    {
+        return Err("Message not found".to_string());
+    }
+    AttestationStore::default().add_attestation(attestation)
+}
+
+/// Returns the attestations of the chain heads of the group, from the oldest.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn attestations(group_id: &str) -> Vec<String> {
+    AttestationStore::default()
+        .attestations(group_id)
+        .iter()
+        .map(|attestation| serde_json::to_string(attestation).unwrap())
+        .collect()
+}
+
+/// Verifies the signature of the notary on the attestation.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn verifyAttestation(attestation: &str) -> bool {
+    serde_json::from_str::<Attestation>(attestation).is_ok_and(|attestation| attestation.verify())
+}
+
+/// Starts the light verification mode for the group from a genesis record, or from a checkpoint record
+/// with the proof of its head returned by `proveInclusion`. Only the head, the group state and the
+/// merkle peaks are kept. It returns the hash of the message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn lightStart(
+    group_id: &str,
+    signed_msg: &str,
+    proof: Option<String>,
+) -> Result<String, String> {
+    let signed_msg = serde_json::from_str(signed_msg).map_err(|_| "Fail to parse".to_string())?;
+    let proof = proof
+        .map(|proof| serde_json::from_str::<InclusionProof>(&proof))
+        .transpose()
+        .map_err(|_| "Fail to parse".to_string())?;
+    let light_state = LightState::new(group_id, &signed_msg, proof.as_ref())?;
+    LightStore::default().set_light_state(group_id, &light_state)?;
+    Ok(serde_json::to_string(&light_state.head).unwrap())
+}
+
+/// Verifies the signed message as the next message of the group in the light verification mode, and
+/// advances the head. It returns the hash of the message.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn lightVerify(group_id: &str, signed_msg: &str) -> Result<String, String> {
+    let signed_msg = serde_json::from_str(signed_msg).map_err(|_| "Fail to parse".to_string())?;
+    let mut light_store = LightStore::default();
+    let mut light_state = light_store
+        .light_state(group_id)
+        .ok_or("Light verification is not started".to_string())?;
+    let hash = light_state.verify_next(group_id, &signed_msg)?;
+    light_store.set_light_state(group_id, &light_state)?;
+    Ok(serde_json::to_string(&hash).unwrap())
+}
+
+/// Returns the merkle root of the group in the light verification mode, against which inclusion
+/// proofs can be verified with `verifyInclusion`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn lightRoot(group_id: &str) -> Option<String> {
+    LightStore::default()
+        .light_state(group_id)
+        .map(|light_state| serde_json::to_string(&light_state.root()).unwrap())
+}
+
+/// Removes the messages before the latest checkpoint record of the group from the local storage. The
+/// chain is validated from the checkpoint afterwards. It returns the number of removed messages.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn prune(group_id: &str) -> Result<usize, String> {
+    let removed = SignedMessageStore::default().prune(group_id)?;
+    forget_deciphered(group_id)?;
+    Ok(removed)
+}
+
+/// Sets the retention policy of the group, e.g. `{"max_age":86400,"max_count":100}`. The policy is local,
+/// expired messages are purged by `compact` if the retention of the group policy expires them as well, as
+/// the purged messages are validated against the group policy.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setRetentionPolicy(group_id: &str, policy: &str) -> Result<(), String> {
+    let policy: RetentionPolicy =
+        serde_json::from_str(policy).map_err(|_| "Fail to parse".to_string())?;
+    GroupSettingsStore::default().update_settings(group_id, |settings| settings.retention = policy)
+}
+
+/// Returns the retention policy of the group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn retentionPolicy(group_id: &str) -> String {
+    serde_json::to_string(&GroupSettingsStore::default().settings(group_id).retention).unwrap()
+}
+
+/// Sets the local settings of the group, e.g.
+/// `{"encryption":true,"retention":{"max_age":86400},"allowed_signers":null,"strict_timestamps":true,`
+/// `"max_backward_skew":60}`.
+/// The missing fields take their default values.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setGroupSettings(group_id: &str, settings: &str) -> Result<(), String> {
+    let settings: GroupSettings =
+        serde_json::from_str(settings).map_err(|_| "Fail to parse".to_string())?;
+    GroupSettingsStore::default().set_settings(group_id, &settings)
+}
+
+/// Returns the local settings of the group.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn groupSettings(group_id: &str) -> String {
+    serde_json::to_string(&GroupSettingsStore::default().settings(group_id)).unwrap()
+}
+
+/// Purges the data of the messages of the group which are expired by its local retention policy and by
+/// the retention of its group policy. The messages are kept with their hashes so that the chain can still
+/// be validated. It returns the number of purged
+/// messages.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn compact(group_id: &str) -> Result<usize, String> {
+    let settings = GroupSettingsStore::default().settings(group_id);
+    let state = GroupStore::default().state(group_id);
+    let purged = SignedMessageStore::default().compact(
+        group_id,
+        &settings.retention,
+        &state.policy.retention,
+    )?;
+    forget_deciphered(group_id)?;
+    Ok(purged)
+}
+
+/// Sets the size (in bytes) of message data above which the data is compressed before it is
+/// saved to the local storage. Compression is transparent, messages are decompressed on read.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setCompressionThreshold(threshold: usize) -> Result<(), String> {
+    SignedMessageStore::default().set_compression_threshold(threshold)
+}
+
+/// Returns the write limits enforced when signed messages are added, as JSON of [WriteLimits].
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn writeLimits() -> String {
+    serde_json::to_string(&LimitStore::default().limits()).unwrap()
+}
+
+/// Sets the write limits enforced when signed messages are added: the maximum size of message data, the
+/// maximum number of messages per group and the maximum number of messages per signer in a time window.
+/// Messages beyond the limits are rejected with "message too large", "too many messages in group" or
+/// "too many messages from signer".
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setWriteLimits(limits: &str) -> Result<(), String> {
+    let limits: WriteLimits =
+        serde_json::from_str(limits).map_err(|_| "Fail to parse".to_string())?;
+    LimitStore::default().set_limits(&limits)
+}
+
+/// Sets the function called with a notification object for each data message received from other parties,
+/// e.g. with `addSignedMessage`, `ingestSignedMessage` or `retryQuarantined`. The object has the fields
+/// `group_id`, `hash`, `seq`, `author`, `alias`, `timestamp` and `preview`, which is the truncated text of
+/// the message, or null if it cannot be decrypted. If it is null, the notifications are stopped.
+#[cfg(feature = "web")]
+#[allow(non_snake_case)]
+#[wasm_bindgen]
+pub fn setNotifier(callback: Option<js_sys::Function>) {
+    notification::set_notifier(callback.map(|callback| {
+        Box::new(move |notification: &Notification| {
+            let json = serde_json::to_string(notification).unwrap();
+            if let Ok(value) = js_sys::JSON::parse(&json) {
+                let _ = callback.call1(&JsValue::NULL, &value);
+            }
+        }) as notification::Notifier
+    }));
+}
+
+/// Sets the clock of the library, which gives the timestamps of the groups and the messages, to a function
+/// which returns the current unix timestamp in seconds, e.g. from a time service of the app. If it is null,
+/// the system time is used, which is the default.
+#[cfg(feature = "web")]
+#[allow(non_snake_case)]
+#[wasm_bindgen]
+pub fn setClock(callback: Option<js_sys::Function>) {
+    clock::set_clock(callback.map(|callback| Rc::new(CallbackClock(callback)) as Rc<dyn Clock>));
+}
+
+/// Sets the clock of the library to follow the time tokens signed by the time authority with the given
+/// identity (see `acceptTimeToken`). Until a token is accepted, the system time is used. If it is null,
+/// the system time is used, which is the default.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setTimeAuthority(identity: Option<String>) -> Result<(), String> {
+    let authority = identity
+        .map(|identity| Identity::try_from(identity.as_str()))
+        .transpose()
+        .map_err(|_| "Fail to parse".to_string())?;
+    clock::set_time_authority(authority);
+    Ok(())
+}
+
+/// Accepts the time token signed by the time authority set with `setTimeAuthority`, e.g. fetched from it
+/// periodically. The clock continues from the time of the latest token, and never goes back.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn acceptTimeToken(token: &str) -> Result<(), String> {
+    let token: TimeToken = serde_json::from_str(token).map_err(|_| "Fail to parse".to_string())?;
+    clock::accept_time_token(&token)
+}
+
+/// Signs a time token with the current time by the current account as a time authority. It returns the
+/// token, which is sent to the apps which trust the account.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn signTimeToken() -> Result<String, String> {
+    let token = Signer::default()
+        .sign_time_token()
+        .ok_or("No current account".to_string())?;
+    Ok(serde_json::to_string(&token).unwrap())
+}
+
+/// Sets the storage of the items, which is an object with the methods of the Web Storage API: `getItem`,
+/// `setItem`, `removeItem`, `key`, `clear` and the property `length`. It allows the library to run where
+/// `localStorage` is not available, e.g. in web workers and Node. If it is null, the `localStorage` of
+/// the global scope is used, which is the default.
+#[cfg(feature = "web")]
+#[allow(non_snake_case)]
+#[wasm_bindgen]
+pub fn setStorage(storage: Option<StorageLike>) {
+    local_storage::set_storage(storage);
+    migration::reset();
+}
+
+/// Copies all the items of the storage `from` to the storage `to`, which are objects as for `setStorage`,
+/// e.g. from `localStorage` to the IndexedDB storage of `js/indexeddb-storage.js`, so that all the
+/// accounts, groups and chains are moved to the new storage. The copied items are read back to verify
+/// them. If a write or a verification fails, the items of `to` are restored and the error is returned.
+/// The callback `on_progress` is called with the stage, "copy" or "verify", the number of items done and
+/// the total number of items. It returns the object with the fields `items` and `bytes` copied. Call
+/// `setStorage(to)` afterwards to use the new storage. `from` is not changed.
+#[cfg(feature = "web")]
+#[allow(non_snake_case)]
+#[wasm_bindgen]
+pub fn migrateStorage(
+    from: StorageLike,
+    to: StorageLike,
+    on_progress: Option<js_sys::Function>,
+) -> Result<String, String> {
+    let report = transfer::transfer(&from, &to, |stage, done, total| {
+        if let Some(callback) = &on_progress {
+            let stage = serde_json::to_value(stage).unwrap();
+            let _ = callback.call3(
+                &JsValue::NULL,
+                &JsValue::from_str(stage.as_str().unwrap()),
+                &JsValue::from(done as u32),
+                &JsValue::from(total as u32),
+            );
+        }
+    })?;
+    Ok(serde_json::to_string(&report).unwrap())
+}
+
+/// Invalidates the cached value of the item with the key in the local storage, or all the cached values
+/// if the key is null. It is needed after the local storage is changed without this library in the same
+/// page, e.g. by the writes of a web worker, or a custom storage object is changed by other parties.
+/// The changes by other tabs are seen without it.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn invalidateCache(key: Option<String>) {
+    match key {
+        Some(key) => cache::invalidate(&key),
+        None => cache::clear(),
+    }
+}
+
+/// Runs the callback while holding the lock of the group across the tabs and workers of the same origin,
+/// with the Web Locks API, so that the messages written to the group in the callback, e.g. with
+/// `signMessage`, do not race with the writes of other tabs. The callback may return a promise, and the
+/// lock is held until it settles. It returns the promise of the result of the callback. Where the Web
+/// Locks API is not available, the callback runs without the lock.
+#[cfg(feature = "web")]
+#[allow(non_snake_case)]
+#[wasm_bindgen]
+pub fn withGroupLock(group_id: &str, callback: js_sys::Function) -> js_sys::Promise {
+    lock::with_group_lock(group_id, callback)
+}
+
+/// Returns the version of the layout of the local storage. The items stored by earlier versions of the
+/// library are migrated to the current layout on the first access.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn schemaVersion() -> u32 {
+    migration::ensure_migrated();
+    migration::schema_version()
+}
+
+/// Sets the level of the log entries written to the console: "error", "warn", "info" or "debug". Nothing
+/// is written if it is null, which is the default.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setLogLevel(level: Option<String>) -> Result<(), String> {
+    let level = level.as_deref().map(Level::try_from).transpose()?;
+    diagnostics::set_level(level);
+    Ok(())
+}
+
+/// Returns the recent warnings and errors, e.g. the validation failures and the storage errors, ordered
+/// from the oldest. Each is the JSON of the level, the category, the message and the unix timestamp.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn diagnostics() -> Vec<String> {
+    diagnostics::diagnostics()
+        .iter()
+        .map(|diagnostic| serde_json::to_string(diagnostic).unwrap())
+        .collect()
+}
+
+/// Removes the recent warnings and errors.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn clearDiagnostics() {
+    diagnostics::clear();
+}
+
+/// Enables or disables the metrics of signing, verifying, and reading and writing the local storage. They
+/// are disabled by default.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn setMetricsEnabled(enabled: bool) {
+    metrics::set_enabled(enabled);
+}
+
+/// Returns the metrics recorded since they are enabled or reset, which are the count and the total time in
+/// milliseconds of each operation, e.g. `{"sign":{"count":2,"total_ms":1.5},"verify":..,"storage_read":..,
+/// "storage_write":..}`.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn metrics() -> String {
+    serde_json::to_string(&metrics::metrics()).unwrap()
+}
+
+/// Resets the recorded metrics to zero.
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub fn resetMetrics() {
+    metrics::reset();
+}
+
+/// Clears the local storage.
+#[cfg_attr(feature = "web", wasm_bindgen)]
 pub fn clear() -> Result<(), String> {
-    web_sys::window()
-        .ok_or("Fail to get window".to_string())?
-        .local_storage()
-        .map_err(|_| "Fail to get local storage".to_string())?
-        .ok_or("Fail to unwrap local storage".to_string())?
-        .clear()
-        .map_err(|_| "Fail to clear local storage".to_string())
+    store::clear_storage()
 }
@@ -0,0 +1,118 @@
+//! Provides the light verification mode, in which a device keeps only the chain head, the state of the
+//! group and the peaks of the Merkle Mountain Range instead of the full history.
+//!
+//! The verification starts from a genesis record, or from a checkpoint record with the proof that its
+//! head is the last leaf of the range with the merkle root in the checkpoint. Each incoming message is
+//! then verified against the head and the state, as [Writer](crate::writer) does for stored chains.
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    account::Identity,
+    core::{
+        compare::ct_eq,
+        message::{MessageHash, MessageKind, SignedMessage},
+    },
+    message::Signature,
+    mmr::{InclusionProof, MerklePeaks},
+    state::{Checkpoint, GroupState},
+    writer::WriteError,
+};
+
+/// LightState is the state kept by a device in the light verification mode.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LightState {
+    /// The hash of the latest verified message.
+    pub head: MessageHash,
+    /// The sequence number of the latest verified message.
+    pub seq: u64,
+    /// The peaks of the Merkle Mountain Range over the message hashes.
+    pub peaks: MerklePeaks,
+    /// The state of the group after the latest verified message.
+    pub state: GroupState,
+}
+
+impl LightState {
+    /// Starts the light verification from a genesis record, or from a checkpoint record with the proof
+    /// of its head.
+    pub fn new(
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+        proof: Option<&InclusionProof>,
+    ) -> Result<Self, WriteError> {
+        if !signed_msg.verify::<Sha256>() {
+            return Err(WriteError::InvalidSignature);
+        }
+
+        let (state, peaks) = match signed_msg.message.kind {
+            MessageKind::Genesis => (GroupState::default(), MerklePeaks::default()),
+            MessageKind::Checkpoint => {
+                let checkpoint = serde_json::from_slice::<Checkpoint>(&signed_msg.message.data)
+                    .map_err(|_| WriteError::Rejected("invalid checkpoint record".to_string()))?;
+                let peaks = proof
+                    .filter(|proof| proof.leaf == checkpoint.head && proof.is_last_leaf())
+                    .zip(checkpoint.merkle_root)
+                    .and_then(|(proof, root)| MerklePeaks::from_proof(proof, &root))
+                    .ok_or(WriteError::Rejected("invalid checkpoint proof".to_string()))?;
+                (checkpoint.state, peaks)
+            }
+            _ => {
+                return Err(WriteError::Rejected(
+                    "light verification starts from a genesis or checkpoint record".to_string(),
+                ))
+            }
+        };
+
+        let mut light_state = Self {
+            head: signed_msg.message.previous_hash,
+            seq: signed_msg.seq.wrapping_sub(1),
+            peaks,
+            state,
+        };
+        light_state.append(group_id, signed_msg)?;
+        Ok(light_state)
+    }
+
+    /// Verifies the message as the next message of the chain and advances the head. It returns the
+    /// hash of the message.
+    pub fn verify_next(
+        &mut self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<MessageHash, WriteError> {
+        if !signed_msg.verify::<Sha256>() {
+            return Err(WriteError::InvalidSignature);
+        }
+        if signed_msg.seq != self.seq.wrapping_add(1) {
+            return Err(WriteError::WrongSequence);
+        }
+        if !ct_eq(&signed_msg.message.previous_hash, &self.head) {
+            return Err(WriteError::WrongPreviousHash);
+        }
+        self.append(group_id, signed_msg)
+    }
+
+    /// Returns the merkle root over the message hashes up to the head.
+    pub fn root(&self) -> MessageHash {
+        self.peaks.root()
+    }
+
+    fn append(
+        &mut self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<MessageHash, WriteError> {
+        self.state
+            .check(group_id, signed_msg)
+            .map_err(WriteError::Rejected)?;
+        self.state.update(signed_msg);
+        // the message is hashed with the algorithm of the state after it
+        let hash = self.state.hash_algorithm.hash(signed_msg);
+
+        self.peaks.append(hash);
+        self.head = hash;
+        self.seq = signed_msg.seq;
+        Ok(hash)
+    }
+}
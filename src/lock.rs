@@ -0,0 +1,66 @@
+//! Provides the locks of the groups across the tabs and workers of the same origin, with the Web Locks
+//! API, so that the writes to the same group are serialized instead of racing on its head.
+//!
+//! A lock is held while the callback of [with_group_lock] runs, and is released when the promise which
+//! it returns settles, so the callback may be asynchronous, e.g. to sign a message after fetching its
+//! data. Where the Web Locks API is not available, e.g. in old browsers and Node, the callback runs
+//! without the lock, and the writes are still protected by comparing the head of the group before they
+//! are written.
+
+use wasm_bindgen::{prelude::*, JsCast};
+
+use crate::store::{cache, namespace::NamespaceStore};
+
+#[wasm_bindgen]
+extern "C" {
+    /// LockManager is the `navigator.locks` object of the Web Locks API.
+    type LockManager;
+
+    #[wasm_bindgen(method, catch)]
+    fn request(
+        this: &LockManager,
+        name: &str,
+        callback: &JsValue,
+    ) -> Result<js_sys::Promise, JsValue>;
+}
+
+/// Returns the lock manager of the global scope, i.e. the window or the worker, if it exists.
+fn lock_manager() -> Option<LockManager> {
+    let navigator =
+        js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("navigator")).ok()?;
+    if navigator.is_undefined() || navigator.is_null() {
+        return None;
+    }
+    let locks = js_sys::Reflect::get(&navigator, &JsValue::from_str("locks")).ok()?;
+    if locks.is_undefined() || locks.is_null() {
+        return None;
+    }
+    Some(locks.unchecked_into())
+}
+
+/// Returns the name of the lock of the group in the current namespace. The namespace is included as
+/// groups with the same id in different namespaces are different chains.
+pub(crate) fn lock_name(group_id: &str) -> String {
+    let namespace = NamespaceStore::default().current_namespace();
+    format!("webmessage:{namespace}:{group_id}")
+}
+
+/// Runs the callback while holding the lock of the group, and returns the promise of its result. The
+/// cached values are dropped when the lock is acquired, as the writes of the previous holder in other
+/// tabs may not be notified yet.
+pub(crate) fn with_group_lock(group_id: &str, callback: js_sys::Function) -> js_sys::Promise {
+    let task = move || -> Result<JsValue, JsValue> {
+        cache::clear();
+        callback.call0(&JsValue::NULL)
+    };
+    let Some(locks) = lock_manager() else {
+        return match task() {
+            Ok(value) => js_sys::Promise::resolve(&value),
+            Err(err) => js_sys::Promise::reject(&err),
+        };
+    };
+    let task = Closure::once_into_js(move |_lock: JsValue| task());
+    locks
+        .request(&lock_name(group_id), &task)
+        .unwrap_or_else(|err| js_sys::Promise::reject(&err))
+}
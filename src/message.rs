@@ -3,6 +3,8 @@
 use crate::{
     account::{Identity, Secret},
     core::message::{Message, Verifiable},
+    metrics::{self, Operation},
+    rng::Rng,
 };
 
 use sha2::Sha256;
@@ -10,10 +12,28 @@ use sha2::Sha256;
 use serde::{Deserialize, Serialize};
 
 type SchnorrSignature = schnorr_rs::Signature<schnorr_rs::SchnorrP256Group>;
+type SignatureScheme = schnorr_rs::SignatureScheme<schnorr_rs::SchnorrP256Group, Sha256>;
+
+/// The name of the signature scheme of the messages: Schnorr signatures over the P-256 curve with
+/// SHA-256.
+pub const SIGNATURE_SCHEME: &str = "schnorr_p256_sha256";
+
+thread_local! {
+    /// The signature scheme, which is created once instead of on every signing and verification.
+    static SCHEME: SignatureScheme = schnorr_rs::signature_scheme_p256::<Sha256>();
+}
+
+/// Calls the function with the signature scheme over the P-256 curve with SHA-256.
+pub(crate) fn with_scheme<R>(f: impl FnOnce(&SignatureScheme) -> R) -> R {
+    SCHEME.with(f)
+}
 
 /// Signature is a wrapper around schnorr_rs::ec::Signature, which implements the trait [Verifiable](crate::core::message::Verifiable).
+/// It is serialized in the [compact](crate::compact) encoding, and it is parsed from either encoding. The
+/// bytes of a signature, which are hashed in messages, are the JSON of the Schnorr signature.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Signature {
+    #[serde(with = "compact_signature")]
     signature: String,
 }
 
@@ -23,6 +43,11 @@ impl Signature {
             signature: serde_json::to_string(&signature).unwrap(),
         }
     }
+
+    /// Checks if the signature is a serialized Schnorr signature, so that it can be verified.
+    pub fn is_well_formed(&self) -> bool {
+        serde_json::from_str::<SchnorrSignature>(&self.signature).is_ok()
+    }
 }
 
 impl AsRef<[u8]> for Signature {
@@ -33,11 +58,15 @@ impl AsRef<[u8]> for Signature {
 
 impl Verifiable<Identity> for Signature {
     fn verify(&self, id: &Identity, message: &[u8]) -> bool {
-        let signature: schnorr_rs::Signature<schnorr_rs::SchnorrP256Group> =
-            serde_json::from_str(&self.signature).unwrap();
-        let public_key = id.to_public_key();
-        let scheme = schnorr_rs::signature_scheme_p256::<Sha256>();
-        scheme.verify(&public_key, message, &signature)
+        let Ok(signature) = serde_json::from_str::<SchnorrSignature>(&self.signature) else {
+            return false;
+        };
+        let Some(public_key) = id.public_key() else {
+            return false;
+        };
+        metrics::measure(Operation::Verify, || {
+            with_scheme(|scheme| scheme.verify(public_key, message, &signature))
+        })
     }
 }
 
@@ -46,14 +75,42 @@ pub struct MessageSigner {}
 impl crate::core::message::MessageSigner<Identity, Secret, Signature> for MessageSigner {
     fn sign(id: &Identity, secret: &Secret, message: &Message) -> Signature {
         let public_key = &id.to_public_key();
-        let private_key = secret.as_private_key();
-        let scheme = schnorr_rs::signature_scheme_p256::<Sha256>();
-        let signature = scheme.sign(
-            &mut rand::thread_rng(),
-            private_key,
-            public_key,
-            message.to_hash::<Sha256>(),
-        );
+        let private_key = &secret.to_private_key();
+        let signature = metrics::measure(Operation::Sign, || {
+            with_scheme(|scheme| {
+                scheme.sign(
+                    &mut Rng,
+                    private_key,
+                    public_key,
+                    message.to_hash::<Sha256>(),
+                )
+            })
+        });
         Signature::new(signature)
     }
 }
+
+/// Serializes a [Signature] in the compact encoding, and deserializes it from either encoding, so that
+/// the signatures stored before the compact encoding are read.
+mod compact_signature {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::compact;
+
+    pub(super) fn serialize<S: Serializer>(
+        signature: &str,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match compact::compact_signature(signature) {
+            Some(compact) => serializer.serialize_str(&compact),
+            None => serializer.serialize_str(signature),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<String, D::Error> {
+        let signature = String::deserialize(deserializer)?;
+        Ok(compact::expand_signature(&signature).unwrap_or(signature))
+    }
+}
@@ -0,0 +1,90 @@
+//! Provides the optional metrics of the library, which are the counts and the cumulative timings of
+//! signing, verifying, and reading and writing the local storage, so that the performance of apps can
+//! be diagnosed in the field.
+//!
+//! The metrics are disabled by default, so that the operations are not timed. They are kept in memory
+//! from when they are enabled until they are reset.
+
+use std::cell::{Cell, RefCell};
+
+use serde::{Deserialize, Serialize};
+use web_time::Instant;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+    static METRICS: RefCell<Metrics> = RefCell::new(Metrics::default());
+}
+
+/// Operation is an operation of which the metrics are recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Sign,
+    Verify,
+    StorageRead,
+    StorageWrite,
+}
+
+/// OperationMetrics is the metrics of an operation.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationMetrics {
+    /// The number of times the operation is done.
+    pub count: u64,
+    /// The total time spent on the operation, in milliseconds.
+    pub total_ms: f64,
+}
+
+/// Metrics is the metrics of all the recorded operations.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    /// Signing messages.
+    pub sign: OperationMetrics,
+    /// Verifying signatures.
+    pub verify: OperationMetrics,
+    /// Reading items from the local storage. The values read from the cache are not counted.
+    pub storage_read: OperationMetrics,
+    /// Writing and removing items of the local storage.
+    pub storage_write: OperationMetrics,
+}
+
+impl Metrics {
+    fn of_mut(&mut self, operation: Operation) -> &mut OperationMetrics {
+        match operation {
+            Operation::Sign => &mut self.sign,
+            Operation::Verify => &mut self.verify,
+            Operation::StorageRead => &mut self.storage_read,
+            Operation::StorageWrite => &mut self.storage_write,
+        }
+    }
+}
+
+/// Enables or disables the recording of the metrics. The recorded metrics are kept.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.set(enabled);
+}
+
+/// Returns the recorded metrics.
+pub fn metrics() -> Metrics {
+    METRICS.with(|metrics| metrics.borrow().clone())
+}
+
+/// Resets the recorded metrics to zero.
+pub fn reset() {
+    METRICS.with(|metrics| *metrics.borrow_mut() = Metrics::default());
+}
+
+/// Runs the function as the operation, and records its metrics if the metrics are enabled.
+pub(crate) fn measure<R>(operation: Operation, f: impl FnOnce() -> R) -> R {
+    if !ENABLED.get() {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        let operation_metrics = metrics.of_mut(operation);
+        operation_metrics.count += 1;
+        operation_metrics.total_ms += elapsed.as_secs_f64() * 1000.0;
+    });
+    result
+}
@@ -0,0 +1,342 @@
+//! Provides the Merkle Mountain Range (MMR) over the message hashes of a group, which proves that a
+//! single message is part of the chain without the entire history.
+//!
+//! The nodes are kept in post-order: each leaf is the hash of the message at the sequence number of its
+//! leaf index, and a parent follows its two children. The root bags the peaks of the mountains.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::{compare::ct_eq, message::MessageHash};
+
+const NODE_PREFIX: &[u8] = &[1];
+const ROOT_PREFIX: &[u8] = &[2];
+
+/// MerkleMountainRange is an append-only accumulator over message hashes.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MerkleMountainRange {
+    nodes: Vec<MessageHash>,
+}
+
+impl MerkleMountainRange {
+    /// Returns the number of leaves.
+    pub fn leaf_count(&self) -> u64 {
+        peaks(self.size()).iter().map(|pos| 1 << height(*pos)).sum()
+    }
+
+    /// Appends the message hash as the next leaf.
+    pub fn append(&mut self, leaf: MessageHash) {
+        let mut pos = self.size();
+        let mut current = leaf;
+        self.nodes.push(leaf);
+        let mut h = 0;
+        while height(pos + 1) > h {
+            let left = pos + 1 - (2 << h);
+            current = hash_node(&self.nodes[left as usize], &current);
+            self.nodes.push(current);
+            pos += 1;
+            h += 1;
+        }
+    }
+
+    /// Returns the root over all the leaves.
+    pub fn root(&self) -> MessageHash {
+        let peaks: Vec<MessageHash> = peaks(self.size())
+            .iter()
+            .map(|pos| self.nodes[*pos as usize])
+            .collect();
+        bag_peaks(self.size(), &peaks)
+    }
+
+    /// Returns the proof that the message hash is a leaf, if it is.
+    pub fn prove(&self, leaf: &MessageHash) -> Option<InclusionProof> {
+        let leaf_pos =
+            (0..self.size()).find(|pos| height(*pos) == 0 && &self.nodes[*pos as usize] == leaf)?;
+        let peak_positions = peaks(self.size());
+
+        let mut siblings = vec![];
+        let mut pos = leaf_pos;
+        while !peak_positions.contains(&pos) {
+            let (sibling, parent) = sibling_and_parent(pos);
+            siblings.push(self.nodes[sibling as usize]);
+            pos = parent;
+        }
+
+        Some(InclusionProof {
+            size: self.size(),
+            leaf_pos,
+            leaf: *leaf,
+            siblings,
+            peaks: peak_positions
+                .iter()
+                .map(|pos| self.nodes[*pos as usize])
+                .collect(),
+        })
+    }
+
+    /// Returns the proof that the range of the given number of leaves is a prefix of this range, where
+    /// the last leaf of each range is the head of the chain at that point.
+    pub fn prove_consistency(&self, old_leaf_count: u64) -> Option<ConsistencyProof> {
+        if old_leaf_count == 0 || old_leaf_count > self.leaf_count() {
+            return None;
+        }
+        let old = self.prefix(old_leaf_count);
+        let old_head = old.prove(&old.nodes[leaf_position(old_leaf_count - 1) as usize])?;
+        let new_head = self.prove(&self.nodes[leaf_position(self.leaf_count() - 1) as usize])?;
+
+        let new_peaks = peaks(self.size());
+        let paths = peaks(old.size())
+            .into_iter()
+            .map(|mut pos| {
+                let mut path = vec![];
+                while !new_peaks.contains(&pos) {
+                    let (sibling, parent) = sibling_and_parent(pos);
+                    path.push(self.nodes[sibling as usize]);
+                    pos = parent;
+                }
+                path
+            })
+            .collect();
+
+        Some(ConsistencyProof {
+            old_head,
+            new_head,
+            paths,
+        })
+    }
+
+    /// Returns the range of the first leaves.
+    pub fn prefix(&self, leaf_count: u64) -> Self {
+        let size = (2 * leaf_count - leaf_count.count_ones() as u64).min(self.size());
+        Self {
+            nodes: self.nodes[..size as usize].to_vec(),
+        }
+    }
+
+    fn size(&self) -> u64 {
+        self.nodes.len() as u64
+    }
+}
+
+/// MerklePeaks is the compact form of a [MerkleMountainRange] which keeps only its peaks. It can append
+/// leaves and compute the root, but not prove inclusion.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MerklePeaks {
+    /// The number of nodes of the range.
+    pub size: u64,
+    /// The peaks of the range, from the left.
+    pub peaks: Vec<MessageHash>,
+}
+
+impl MerklePeaks {
+    /// Returns the peaks of the range in the proof, if the proof is valid against the root.
+    pub fn from_proof(proof: &InclusionProof, root: &MessageHash) -> Option<Self> {
+        proof.verify(root).then(|| Self {
+            size: proof.size,
+            peaks: proof.peaks.clone(),
+        })
+    }
+
+    /// Appends the message hash as the next leaf.
+    pub fn append(&mut self, leaf: MessageHash) {
+        let mut pos = self.size;
+        let mut current = leaf;
+        self.size += 1;
+        let mut h = 0;
+        while height(pos + 1) > h {
+            // the left sibling is the previous peak
+            let left = self.peaks.pop().unwrap_or_default();
+            current = hash_node(&left, &current);
+            self.size += 1;
+            pos += 1;
+            h += 1;
+        }
+        self.peaks.push(current);
+    }
+
+    /// Returns the root over all the leaves.
+    pub fn root(&self) -> MessageHash {
+        bag_peaks(self.size, &self.peaks)
+    }
+}
+
+/// InclusionProof proves that a message hash is a leaf of the MMR with a given root.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// The number of nodes of the MMR.
+    pub size: u64,
+    /// The position of the leaf among the nodes.
+    pub leaf_pos: u64,
+    /// The message hash.
+    pub leaf: MessageHash,
+    /// The siblings on the path from the leaf to its peak, from the bottom.
+    pub siblings: Vec<MessageHash>,
+    /// The peaks of the MMR, from the left.
+    pub peaks: Vec<MessageHash>,
+}
+
+impl InclusionProof {
+    /// Checks if the leaf is the last leaf of the range, e.g. the head of the chain.
+    pub fn is_last_leaf(&self) -> bool {
+        is_last_leaf(self.leaf_pos, self.size)
+    }
+
+    /// Verifies that the leaf is included in the MMR with the given root.
+    pub fn verify(&self, root: &MessageHash) -> bool {
+        let peak_positions = peaks(self.size);
+        if peak_positions.len() != self.peaks.len()
+            || self.leaf_pos >= self.size
+            || height(self.leaf_pos) != 0
+        {
+            return false;
+        }
+
+        climb_to_peak(self.leaf_pos, self.leaf, &self.siblings, &peak_positions)
+            .is_some_and(|(idx, peak)| ct_eq(&self.peaks[idx], &peak))
+            && ct_eq(&bag_peaks(self.size, &self.peaks), root)
+    }
+}
+
+/// ConsistencyProof proves that a range is an append-only extension of an older range, i.e. the chain at
+/// the new head extends the chain at the old head without rewrites.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    /// The proof that the old head is the last leaf of the old range.
+    pub old_head: InclusionProof,
+    /// The proof that the new head is the last leaf of the new range.
+    pub new_head: InclusionProof,
+    /// The siblings on the path from each peak of the old range to a peak of the new range.
+    pub paths: Vec<Vec<MessageHash>>,
+}
+
+impl ConsistencyProof {
+    /// Verifies that the range with the new root extends the range with the old root.
+    pub fn verify(&self, old_root: &MessageHash, new_root: &MessageHash) -> bool {
+        let (old_size, new_size) = (self.old_head.size, self.new_head.size);
+        if old_size > new_size
+            || !self.old_head.verify(old_root)
+            || !self.new_head.verify(new_root)
+            || !self.old_head.is_last_leaf()
+            || !self.new_head.is_last_leaf()
+        {
+            return false;
+        }
+
+        let old_peaks = peaks(old_size);
+        let new_peaks = peaks(new_size);
+        old_peaks.len() == self.paths.len()
+            && old_peaks
+                .iter()
+                .zip(self.old_head.peaks.iter())
+                .zip(self.paths.iter())
+                .all(|((pos, peak), path)| {
+                    climb_to_peak(*pos, *peak, path, &new_peaks)
+                        .is_some_and(|(idx, peak)| ct_eq(&self.new_head.peaks[idx], &peak))
+                })
+    }
+}
+
+/// Computes the hashes on the path from the node at the given position with the siblings until a peak.
+/// It returns the index of the peak and its computed hash, or None if the siblings do not end at a peak.
+fn climb_to_peak(
+    mut pos: u64,
+    mut current: MessageHash,
+    siblings: &[MessageHash],
+    peaks: &[u64],
+) -> Option<(usize, MessageHash)> {
+    let mut siblings = siblings.iter();
+    while !peaks.contains(&pos) {
+        let sibling_hash = siblings.next()?;
+        let (sibling, parent) = sibling_and_parent(pos);
+        current = if sibling < pos {
+            hash_node(sibling_hash, &current)
+        } else {
+            hash_node(&current, sibling_hash)
+        };
+        pos = parent;
+    }
+    if siblings.next().is_some() {
+        return None;
+    }
+    peaks
+        .iter()
+        .position(|p| *p == pos)
+        .map(|idx| (idx, current))
+}
+
+/// Returns the position of the leaf with the given index.
+fn leaf_position(index: u64) -> u64 {
+    2 * index - index.count_ones() as u64
+}
+
+/// Checks if the position is the last leaf of the range with the given number of nodes.
+fn is_last_leaf(pos: u64, size: u64) -> bool {
+    let leaf_count: u64 = peaks(size).iter().map(|pos| 1 << height(*pos)).sum();
+    leaf_count > 0 && pos == leaf_position(leaf_count - 1)
+}
+
+/// Returns the position of the sibling and the parent of the node at the given position.
+fn sibling_and_parent(pos: u64) -> (u64, u64) {
+    let h = height(pos);
+    if height(pos + 1) > h {
+        // right child
+        (pos + 1 - (2 << h), pos + 1)
+    } else {
+        // left child
+        let sibling = pos + (2 << h) - 1;
+        (sibling, sibling + 1)
+    }
+}
+
+/// Returns the height of the node at the given position, where leaves have height 0.
+fn height(pos: u64) -> u32 {
+    let mut num = pos + 1;
+    while !all_ones(num) {
+        num -= (1 << (bit_length(num) - 1)) - 1;
+    }
+    bit_length(num) - 1
+}
+
+/// Returns the positions of the peaks of the MMR with the given number of nodes, from the left.
+fn peaks(size: u64) -> Vec<u64> {
+    let mut peaks = vec![];
+    let mut offset = 0;
+    while offset < size {
+        let remaining = size - offset;
+        // the largest perfect tree which fits in the remaining nodes
+        let tree_size = (1u64 << (bit_length(remaining + 1) - 1)) - 1;
+        offset += tree_size;
+        peaks.push(offset - 1);
+    }
+    peaks
+}
+
+fn bit_length(num: u64) -> u32 {
+    u64::BITS - num.leading_zeros()
+}
+
+fn all_ones(num: u64) -> bool {
+    num != 0 && num.count_ones() == bit_length(num)
+}
+
+fn hash_node(left: &MessageHash, right: &MessageHash) -> MessageHash {
+    Sha256::new()
+        .chain_update(NODE_PREFIX)
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+fn bag_peaks(size: u64, peaks: &[MessageHash]) -> MessageHash {
+    peaks
+        .iter()
+        .fold(
+            Sha256::new()
+                .chain_update(ROOT_PREFIX)
+                .chain_update(size.to_le_bytes()),
+            |hasher, peak| hasher.chain_update(peak),
+        )
+        .finalize()
+        .into()
+}
@@ -0,0 +1,154 @@
+//! Provides the messages which require the signatures of k out of n signers, e.g. group announcements
+//! co-signed by several admins.
+//!
+//! A proposal binds the data to a position in the chain. The signers add their partial signatures to
+//! the proposal until the threshold is reached, and then it is submitted in a `CoSigned` record.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::MessageHash,
+    detached,
+    message::Signature,
+};
+
+/// The domain separator of the partial signatures.
+const COSIGN_PREFIX: &[u8] = b"webmessage co-signature";
+
+/// SignerSet is the identity of n signers of which `threshold` must sign.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerSet {
+    pub signers: Vec<Identity>,
+    pub threshold: usize,
+}
+
+impl SignerSet {
+    /// Returns the distinct signers sorted by their identities, so that the order in which the signers
+    /// are listed does not change what they sign.
+    fn sorted_signers(&self) -> Vec<Identity> {
+        let mut signers = self.signers.clone();
+        signers.sort_by_key(|signer| signer.to_string());
+        signers.dedup();
+        signers
+    }
+}
+
+/// PartialSignature is the signature of one signer of a [SignerSet].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartialSignature {
+    pub signer: Identity,
+    pub signature: Signature,
+}
+
+/// ThresholdSignature is a collection of partial signatures of the signers of a [SignerSet].
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ThresholdSignature {
+    partials: Vec<PartialSignature>,
+}
+
+impl ThresholdSignature {
+    /// Returns the collected partial signatures.
+    pub fn partials(&self) -> &[PartialSignature] {
+        &self.partials
+    }
+
+    /// Adds the partial signature. It replaces the previous partial signature of the same signer.
+    pub fn add(&mut self, partial: PartialSignature) {
+        self.partials.retain(|p| p.signer != partial.signer);
+        self.partials.push(partial);
+    }
+
+    /// Verifies that the threshold of the signer set signed the body, each signer at its position in the
+    /// sorted signer set.
+    pub fn verify(&self, signer_set: &SignerSet, body: &[u8]) -> bool {
+        let valid_signers = signer_set
+            .sorted_signers()
+            .iter()
+            .enumerate()
+            .filter(|(position, signer)| {
+                self.partials.iter().any(|partial| {
+                    &partial.signer == *signer
+                        && detached::verify_in_domain(
+                            COSIGN_PREFIX,
+                            signer,
+                            &partial_data(body, *position),
+                            &partial.signature,
+                        )
+                })
+            })
+            .count();
+        signer_set.threshold > 0 && valid_signers >= signer_set.threshold
+    }
+}
+
+/// CoSignedContent is the data of a `CoSigned` record. It is the proposal which collects the partial
+/// signatures over the data at the position after the previous hash.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CoSignedContent {
+    pub signer_set: SignerSet,
+    pub previous_hash: MessageHash,
+    pub data: Vec<u8>,
+    pub signature: ThresholdSignature,
+}
+
+/// CoSignedBody is what the signers sign for a proposal. It covers the signer set and the threshold, so
+/// that the partial signatures are not valid for a proposal with fewer signers or a lower threshold.
+#[derive(Serialize)]
+struct CoSignedBody<'a> {
+    previous_hash: &'a MessageHash,
+    data: &'a [u8],
+    signers: Vec<Identity>,
+    threshold: usize,
+}
+
+impl CoSignedContent {
+    /// Creates a proposal without signatures.
+    pub fn new(signer_set: SignerSet, previous_hash: MessageHash, data: Vec<u8>) -> Self {
+        Self {
+            signer_set,
+            previous_hash,
+            data,
+            signature: ThresholdSignature::default(),
+        }
+    }
+
+    /// Adds the partial signature of the signer, which must be in the signer set.
+    pub fn sign(&mut self, signer: Identity, secret: &Secret) -> Result<(), String> {
+        let position = self
+            .signer_set
+            .sorted_signers()
+            .iter()
+            .position(|s| s == &signer)
+            .ok_or("signer is not in the signer set".to_string())?;
+        let signature = detached::sign_in_domain(
+            COSIGN_PREFIX,
+            &signer,
+            secret,
+            &partial_data(&self.signed_body(), position),
+        );
+        self.signature.add(PartialSignature { signer, signature });
+        Ok(())
+    }
+
+    /// Verifies that the threshold of the signer set signed the proposal.
+    pub fn verify(&self) -> bool {
+        self.signature.verify(&self.signer_set, &self.signed_body())
+    }
+
+    /// Returns the body which the signers sign.
+    fn signed_body(&self) -> Vec<u8> {
+        serde_json::to_vec(&CoSignedBody {
+            previous_hash: &self.previous_hash,
+            data: &self.data,
+            signers: self.signer_set.sorted_signers(),
+            threshold: self.signer_set.threshold,
+        })
+        .unwrap()
+    }
+}
+
+/// Returns the data which the signer at the position in the sorted signer set signs for the body.
+fn partial_data(body: &[u8], position: usize) -> Vec<u8> {
+    [body, &(position as u64).to_le_bytes()].concat()
+}
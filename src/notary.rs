@@ -0,0 +1,76 @@
+//! Provides the notarization of chain heads, in which a designated notary identity countersigns the
+//! head of a group as of a time.
+//!
+//! A member creates a [NotarizationRequest] over the head and sends it to the notary out of band. The
+//! notary returns an [Attestation], which is stored alongside the chain of the group.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::MessageHash,
+    detached,
+    message::Signature,
+};
+
+/// The domain separator of the signatures of the attestations.
+const NOTARY_PREFIX: &[u8] = b"webmessage notary attestation";
+
+/// NotarizationRequest is the payload which is sent to the notary to countersign a chain head.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotarizationRequest {
+    pub group_id: String,
+    /// The hash of the chain head.
+    pub head: MessageHash,
+    /// The sequence number of the chain head.
+    pub seq: u64,
+    /// The merkle root of the chain up to the head.
+    pub merkle_root: MessageHash,
+    /// The identity of the notary.
+    pub notary: Identity,
+    /// The identity which requests the notarization.
+    pub requester: Identity,
+}
+
+/// AttestationBody is the content of an attestation which is signed by the notary.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationBody {
+    pub request: NotarizationRequest,
+    /// The unix timestamp when the notary attests the chain head.
+    pub timestamp: u64,
+}
+
+/// Attestation is the countersignature of the notary over a chain head.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Attestation {
+    pub body: AttestationBody,
+    pub signature: Signature,
+}
+
+impl Attestation {
+    /// Creates an attestation signed by the notary.
+    pub fn new(body: AttestationBody, secret: &Secret) -> Self {
+        let signature = detached::sign_in_domain(
+            NOTARY_PREFIX,
+            &body.request.notary,
+            secret,
+            &signed_data(&body),
+        );
+        Self { body, signature }
+    }
+
+    /// Verifies the signature of the notary.
+    pub fn verify(&self) -> bool {
+        detached::verify_in_domain(
+            NOTARY_PREFIX,
+            &self.body.request.notary,
+            &signed_data(&self.body),
+            &self.signature,
+        )
+    }
+}
+
+/// Returns the data which the notary signs for the attestation.
+fn signed_data(body: &AttestationBody) -> Vec<u8> {
+    serde_json::to_vec(body).unwrap()
+}
@@ -0,0 +1,92 @@
+//! Provides the notifications of the data messages received from other parties, e.g. added with
+//! `addSignedMessage` or from sync, so that apps can show them, e.g. with the Notifications API, without
+//! polling the chains.
+//!
+//! A notification is made for each data message which is added with validation and is not signed by a
+//! local account. It is passed to the function set with [set_notifier], and nothing is made if it is not
+//! set.
+
+use std::cell::RefCell;
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::header::PREVIEW_LENGTH;
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, SignedMessage},
+    header::preview,
+    message::Signature,
+};
+
+/// Notifier is the function called with each notification.
+pub type Notifier = Box<dyn FnMut(&Notification)>;
+
+thread_local! {
+    static NOTIFIER: RefCell<Option<Notifier>> = const { RefCell::new(None) };
+}
+
+/// Notification is a data message received from another party.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub group_id: String,
+    /// The hash of the message in the chain.
+    pub hash: MessageHash,
+    pub seq: u64,
+    /// The signer of the message, or the sender if the message is sealed and the sender is revealed.
+    pub author: Identity,
+    /// The alias of the author if it is a contact.
+    pub alias: Option<String>,
+    pub timestamp: Option<u64>,
+    /// The text of the message truncated to [PREVIEW_LENGTH] characters, or None if it cannot be
+    /// decrypted.
+    pub preview: Option<String>,
+}
+
+impl Notification {
+    /// Creates the notification of the revealed message, i.e. decrypted if the key is held, with the
+    /// alias of its author.
+    pub fn new(
+        group_id: &str,
+        hash: MessageHash,
+        signed_msg: &SignedMessage<Identity, Signature>,
+        alias: Option<String>,
+    ) -> Self {
+        let message = &signed_msg.message;
+        let preview = (!message.encrypted && !message.redacted)
+            .then(|| preview(&String::from_utf8_lossy(&message.data)));
+        Self {
+            group_id: group_id.to_string(),
+            hash,
+            seq: signed_msg.seq,
+            author: signed_msg.id.clone(),
+            alias,
+            timestamp: message.timestamp,
+            preview,
+        }
+    }
+}
+
+/// Sets the function called with each notification, or stops the notifications if it is None.
+pub fn set_notifier(notifier: Option<Notifier>) {
+    NOTIFIER.with(|cell| *cell.borrow_mut() = notifier);
+}
+
+/// Checks if a notifier is set, so that the notifications are made.
+pub(crate) fn is_enabled() -> bool {
+    NOTIFIER.with(|cell| cell.borrow().is_some())
+}
+
+/// Calls the notifier with the notification. The notifier is taken while it runs, so that it may call
+/// the library, e.g. to read the messages.
+pub(crate) fn notify(notification: &Notification) {
+    let Some(mut notifier) = NOTIFIER.with(|cell| cell.borrow_mut().take()) else {
+        return;
+    };
+    notifier(notification);
+    NOTIFIER.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            *cell = Some(notifier);
+        }
+    });
+}
@@ -0,0 +1,22 @@
+//! Provides the outbox, which queues the messages signed while sync is unavailable.
+//!
+//! A queued message is signed on top of the chain head, or on top of the previous queued message of the
+//! same group. When the outbox is flushed, the messages are appended in order. If the head moved in the
+//! meantime, e.g. messages of other members were synced, the message is re-signed onto the new head.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{account::Identity, core::message::SignedMessage, message::Signature};
+
+/// OutboxEntry is a message queued in the outbox.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    /// The id of the group to which the message is appended.
+    pub group_id: String,
+    /// The data of the message, which is kept to re-sign the message onto a new head.
+    pub data: String,
+    /// The message signed when it was queued.
+    pub signed_message: SignedMessage<Identity, Signature>,
+    /// The unix timestamp when the message was queued.
+    pub queued_at: u64,
+}
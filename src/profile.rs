@@ -0,0 +1,40 @@
+//! Provides the self-signed profiles of identities.
+//!
+//! Each identity publishes its profile as a small chain of signed messages, in which each update links
+//! to the previous one. The latest update in the chain is the current profile of the identity.
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, SignedMessage},
+    message::Signature,
+};
+
+/// Profile is the data of a profile update.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub display_name: String,
+    /// The hash of the avatar blob, which is shared outside of the library.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<MessageHash>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
+}
+
+/// Checks if the update is a valid next update of the profile chain whose latest update is given.
+/// The first update must be the first message of the chain, and every update must be signed by the
+/// identity of the chain.
+pub fn is_valid_update(
+    latest: Option<&SignedMessage<Identity, Signature>>,
+    update: &SignedMessage<Identity, Signature>,
+) -> bool {
+    if serde_json::from_slice::<Profile>(&update.message.data).is_err() {
+        return false;
+    }
+    match latest {
+        Some(latest) => latest.id == update.id && latest.is_valid_parent_of::<Sha256>(update),
+        None => update.is_first_message() && update.verify::<Sha256>(),
+    }
+}
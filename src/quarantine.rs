@@ -0,0 +1,19 @@
+//! Provides the quarantine, which keeps the inbound messages rejected for their positions in the chain.
+//!
+//! Messages delivered out of order by the network are rejected because of a wrong sequence number or
+//! previous hash. They are kept in the quarantine instead of being dropped, so that they can be retried
+//! once the missing messages are synced.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{account::Identity, core::message::SignedMessage, message::Signature};
+
+/// QuarantineEntry is a rejected message kept in the quarantine.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub signed_message: SignedMessage<Identity, Signature>,
+    /// The reason of the latest rejection.
+    pub reason: String,
+    /// The unix timestamp when the message was first received.
+    pub received_at: u64,
+}
@@ -0,0 +1,109 @@
+//! Provides the symmetric ratchet over the group content key for forward secrecy.
+//!
+//! Once a `RatchetStart` record is in the chain, the data of each subsequent message is encrypted
+//! with a message key derived for its sequence number. The chain key only moves forward and the
+//! previous chain keys are discarded, so the compromise of the current chain key does not reveal the
+//! keys of older messages. Each message key is deleted once it is used, and the keys of the skipped
+//! messages, which are not read yet, are kept up to [MAX_SKIPPED_KEYS].
+
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::encryption::GroupKey;
+
+const RATCHET_INFO: &[u8] = b"webmessage ratchet";
+const MESSAGE_KEY_CONSTANT: u8 = 1;
+const CHAIN_KEY_CONSTANT: u8 = 2;
+
+/// The maximum number of the message keys of the skipped messages kept by a ratchet. The ratchet does not
+/// advance further than it at once, and the oldest keys are deleted beyond it.
+pub const MAX_SKIPPED_KEYS: usize = 1000;
+
+/// RatchetState is the state of the ratchet of a group.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RatchetState {
+    /// The chain key for the message at `index`.
+    chain_key: [u8; 32],
+    /// The sequence number of the next message key to derive.
+    index: u64,
+    /// The sequence number of the first message of the current chain.
+    start: u64,
+    /// The message keys of the skipped messages by sequence number, which are not used yet.
+    message_keys: Vec<(u64, GroupKey)>,
+}
+
+impl RatchetState {
+    /// Creates a ratchet from the group key, starting at the message with the given sequence number.
+    pub fn new(group_key: &GroupKey, start: u64) -> Self {
+        let mut chain_key = [0u8; 32];
+        Hkdf::<Sha256>::new(None, group_key)
+            .expand(RATCHET_INFO, &mut chain_key)
+            .expect("32 bytes is a valid output length");
+        Self {
+            chain_key,
+            index: start,
+            start,
+            message_keys: vec![],
+        }
+    }
+
+    /// Restarts the ratchet with a new group key, keeping the unused message keys of the skipped messages
+    /// before the new start.
+    pub fn restart(&mut self, group_key: &GroupKey, start: u64) {
+        let message_keys = std::mem::take(&mut self.message_keys);
+        *self = Self::new(group_key, start);
+        self.message_keys = message_keys
+            .into_iter()
+            .filter(|(seq, _)| *seq < start)
+            .collect();
+    }
+
+    /// Returns the message key for the message with the given sequence number, and deletes it, so that it
+    /// is used once. The ratchet is advanced if the key has not been derived yet, keeping the keys of the
+    /// skipped messages. It returns None if the message is before the start of the ratchet, its key is
+    /// used or deleted, or it is more than [MAX_SKIPPED_KEYS] messages ahead.
+    pub fn message_key(&mut self, seq: u64) -> Option<GroupKey> {
+        if let Some(idx) = self.message_keys.iter().position(|(s, _)| *s == seq) {
+            return Some(self.message_keys.remove(idx).1);
+        }
+        if seq < self.start || seq < self.index || seq - self.index > MAX_SKIPPED_KEYS as u64 {
+            return None;
+        }
+        loop {
+            let message_key = prf(&self.chain_key, MESSAGE_KEY_CONSTANT);
+            self.chain_key = prf(&self.chain_key, CHAIN_KEY_CONSTANT);
+            self.index += 1;
+            if self.index > seq {
+                let excess = self.message_keys.len().saturating_sub(MAX_SKIPPED_KEYS);
+                self.message_keys.drain(..excess);
+                return Some(message_key);
+            }
+            self.message_keys.push((self.index - 1, message_key));
+        }
+    }
+
+    /// Returns the message key for the message to send with the given sequence number, as
+    /// [message_key](Self::message_key) does, but keeps it until it is discarded with
+    /// [discard](Self::discard) once the message is written.
+    pub fn sending_key(&mut self, seq: u64) -> Option<GroupKey> {
+        let message_key = self.message_key(seq)?;
+        self.message_keys.push((seq, message_key));
+        Some(message_key)
+    }
+
+    /// Deletes the message key for the message with the given sequence number, if it is kept.
+    pub fn discard(&mut self, seq: u64) {
+        self.message_keys.retain(|(s, _)| *s != seq);
+    }
+}
+
+fn prf(key: &[u8; 32], constant: u8) -> [u8; 32] {
+    Hmac::<Sha256>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length")
+        .chain_update([constant])
+        .finalize()
+        .into_bytes()
+        .into()
+}
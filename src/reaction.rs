@@ -0,0 +1,55 @@
+//! Provides the reactions to messages, which are signed annotations stored outside the chain so that
+//! they do not bloat it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::MessageHash,
+    detached,
+    message::Signature,
+};
+
+/// The domain separator of the signatures of the reactions.
+const REACTION_PREFIX: &[u8] = b"webmessage reaction";
+
+/// ReactionBody is the content of a reaction which is signed by the signer.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionBody {
+    pub group_id: String,
+    /// The hash of the message to which the signer reacts.
+    pub hash: MessageHash,
+    pub signer: Identity,
+    pub emoji: String,
+}
+
+/// Reaction is a reaction to a message signed by the signer.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Reaction {
+    pub body: ReactionBody,
+    pub signature: Signature,
+}
+
+impl Reaction {
+    /// Creates a reaction signed by the signer.
+    pub fn new(body: ReactionBody, secret: &Secret) -> Self {
+        let signature =
+            detached::sign_in_domain(REACTION_PREFIX, &body.signer, secret, &signed_data(&body));
+        Self { body, signature }
+    }
+
+    /// Verifies the signature of the signer.
+    pub fn verify(&self) -> bool {
+        detached::verify_in_domain(
+            REACTION_PREFIX,
+            &self.body.signer,
+            &signed_data(&self.body),
+            &self.signature,
+        )
+    }
+}
+
+/// Returns the data which the signer signs for the reaction.
+fn signed_data(body: &ReactionBody) -> Vec<u8> {
+    serde_json::to_vec(body).unwrap()
+}
@@ -0,0 +1,354 @@
+//! Reader module provides a reader struct to read messages from the store.
+
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, MessageKind, SignedMessage},
+    encryption::{self, GroupKey},
+    index::MessageIndex,
+    message::Signature,
+    ratchet::RatchetState,
+    sealed::{SealedContent, SenderProof},
+    search::SearchIndex,
+    state::Redaction,
+    store::{
+        key::GroupKeyStore, message::SignedMessageStore, ratchet::RatchetStore, search::SearchStore,
+    },
+    thread::{self, ThreadNode},
+};
+
+/// Reader is a struct that defines the reading process involved with the stores such as `SignedMessageStore` and `GroupKeyStore`.
+#[derive(Default)]
+pub(crate) struct Reader {
+    pub(crate) message_store: SignedMessageStore,
+    pub(crate) key_store: GroupKeyStore,
+    pub(crate) ratchet_store: RatchetStore,
+    pub(crate) search_store: SearchStore,
+}
+
+impl Reader {
+    /// Returns the stored messages for the given group ID, ordered from the latest. The data of
+    /// encrypted messages is decrypted with the ratchet message keys or the group content keys held,
+    /// and the sender of sealed messages replaces the ephemeral identity if the seal is valid.
+    /// Messages which cannot be decrypted are returned as they are. Redacted messages are returned as
+    /// tombstones, which have empty data and the `redacted` flag.
+    pub(crate) fn messages(&mut self, group_id: &str) -> Vec<SignedMessage<Identity, Signature>> {
+        self.messages_with_hashes(group_id)
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect()
+    }
+
+    /// Returns the messages as [Reader::messages] does, paired with their hashes in the chain.
+    pub(crate) fn messages_with_hashes(
+        &mut self,
+        group_id: &str,
+    ) -> Vec<(MessageHash, SignedMessage<Identity, Signature>)> {
+        // the hashes are taken from the links of the chain, as the hash algorithm may be migrated
+        let mut hash = self.message_store.latest_message_hash(group_id);
+        let mut messages = vec![];
+        for msg in self.message_store.messages(group_id) {
+            let Some(current) = hash else {
+                break;
+            };
+            hash = Some(msg.message.previous_hash);
+            messages.push((current, msg));
+        }
+
+        let redacted: Vec<MessageHash> = messages
+            .iter()
+            .filter(|(_, msg)| msg.message.kind == MessageKind::Redaction)
+            .filter_map(|(_, msg)| serde_json::from_slice::<Redaction>(&msg.message.data).ok())
+            .map(|redaction| redaction.hash)
+            .collect();
+        self.reveal(group_id, &mut messages, &redacted);
+        messages
+    }
+
+    /// Returns the messages signed by the identity in the given group as [Reader::messages] does, ordered
+    /// from the latest. The messages are looked up in the [MessageIndex] instead of the chain. Sealed
+    /// messages are indexed by their ephemeral identities, so they are not returned for their senders.
+    pub(crate) fn messages_by_author(
+        &mut self,
+        group_id: &str,
+        identity: &Identity,
+    ) -> Vec<SignedMessage<Identity, Signature>> {
+        let index = self.message_store.index(group_id);
+        self.indexed_messages(group_id, index.authored_by(identity), &index)
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect()
+    }
+
+    /// Returns the messages of the given group with the hashes, which are ordered from the oldest, as
+    /// [Reader::messages] does, paired with their hashes and ordered from the latest.
+    pub(crate) fn messages_by_hash(
+        &mut self,
+        group_id: &str,
+        hashes: &[MessageHash],
+    ) -> Vec<(MessageHash, SignedMessage<Identity, Signature>)> {
+        let index = self.message_store.index(group_id);
+        self.indexed_messages(group_id, hashes, &index)
+    }
+
+    /// Returns the messages of the given group signed with timestamps between `from` and `to` inclusively
+    /// as [Reader::messages] does, ordered from the latest. The messages are looked up in the [MessageIndex].
+    pub(crate) fn messages_between(
+        &mut self,
+        group_id: &str,
+        from: u64,
+        to: u64,
+    ) -> Vec<SignedMessage<Identity, Signature>> {
+        let index = self.message_store.index(group_id);
+        self.indexed_messages(group_id, &index.between(from, to), &index)
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect()
+    }
+
+    /// Returns the messages of the given group with the tag as [Reader::messages] does, ordered from the
+    /// latest. The messages are looked up in the [MessageIndex].
+    pub(crate) fn messages_by_tag(
+        &mut self,
+        group_id: &str,
+        tag: &str,
+    ) -> Vec<SignedMessage<Identity, Signature>> {
+        let index = self.message_store.index(group_id);
+        self.indexed_messages(group_id, index.tagged(tag), &index)
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect()
+    }
+
+    /// Returns the pinned messages of the given group as [Reader::messages] does, ordered from the latest
+    /// pinned. The messages are looked up in the [MessageIndex].
+    pub(crate) fn pinned_messages(
+        &mut self,
+        group_id: &str,
+    ) -> Vec<SignedMessage<Identity, Signature>> {
+        let index = self.message_store.index(group_id);
+        self.indexed_messages(group_id, &index.pinned, &index)
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect()
+    }
+
+    /// Builds the search index of the given group over the readable text of the data messages, including
+    /// the encrypted messages which can be decrypted. Redacted messages are not indexed.
+    pub(crate) fn build_search_index(&mut self, group_id: &str) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for (hash, msg) in self.messages_with_hashes(group_id).iter().rev() {
+            if !msg.message.kind.is_data() || msg.message.redacted || msg.message.encrypted {
+                continue;
+            }
+            if let Ok(text) = std::str::from_utf8(&msg.message.data) {
+                index.add(*hash, text);
+            }
+        }
+        index
+    }
+
+    /// Returns the messages of the given group which match the query in the search index as [Reader::messages]
+    /// does, paired with their hashes and ordered from the latest. It returns None if the group has no search
+    /// index. Redacted messages are skipped.
+    pub(crate) fn search(
+        &mut self,
+        group_id: &str,
+        query: &str,
+    ) -> Option<Vec<(MessageHash, SignedMessage<Identity, Signature>)>> {
+        let hashes = self.search_store.search_index(group_id)?.search(query);
+        let index = self.message_store.index(group_id);
+        let mut messages = self.indexed_messages(group_id, &hashes, &index);
+        messages.retain(|(_, msg)| !msg.message.redacted);
+        Some(messages)
+    }
+
+    /// Returns the stored messages with the given hashes from the index, which are ordered from the oldest.
+    /// The messages which are not stored, e.g. pruned, are skipped.
+    fn indexed_messages(
+        &mut self,
+        group_id: &str,
+        hashes: &[MessageHash],
+        index: &MessageIndex,
+    ) -> Vec<(MessageHash, SignedMessage<Identity, Signature>)> {
+        let mut messages: Vec<_> = hashes
+            .iter()
+            .rev()
+            .filter_map(|hash| {
+                self.message_store
+                    .message(group_id, hash)
+                    .map(|msg| (*hash, msg))
+            })
+            .collect();
+        self.reveal(group_id, &mut messages, &index.redacted);
+        messages
+    }
+
+    /// Replaces the data of the redacted messages with tombstones, and decrypts the data of the encrypted
+    /// messages. The messages must be ordered from the latest.
+    fn reveal(
+        &mut self,
+        group_id: &str,
+        messages: &mut [(MessageHash, SignedMessage<Identity, Signature>)],
+        redacted: &[MessageHash],
+    ) {
+        // replace the data of the redacted messages with tombstones
+        for (hash, msg) in messages.iter_mut() {
+            if msg.message.redacted || redacted.contains(hash) {
+                msg.message.data = vec![];
+                msg.message.encrypted = false;
+                msg.message.redacted = true;
+            }
+        }
+
+        let group_key = self.key_store.group_key(group_id);
+        let mut ratchet = self.ratchet_store.ratchet(group_id);
+        let mut all_kept = true;
+
+        // decrypt from the oldest message so that the ratchet advances in order
+        for (_, msg) in messages
+            .iter_mut()
+            .rev()
+            .filter(|(_, msg)| msg.message.encrypted)
+        {
+            let Some((plaintext, kept)) =
+                self.decipher(group_id, ratchet.as_mut(), group_key.as_ref(), msg)
+            else {
+                continue;
+            };
+            all_kept &= kept;
+            if !msg.message.sealed {
+                msg.message.data = plaintext;
+                continue;
+            }
+            if let Ok(content) = serde_json::from_slice::<SealedContent>(&plaintext) {
+                if content.verify(&msg.id, msg.message.previous_hash) {
+                    msg.id = content.sender;
+                    msg.message.data = content.data;
+                }
+            }
+        }
+
+        self.save_ratchet(group_id, ratchet.filter(|_| all_kept));
+    }
+
+    /// Returns the messages as [Reader::messages] does, but collapsed: each edited message is replaced by
+    /// its latest edit, and the edits are not returned separately.
+    pub(crate) fn collapsed_messages(
+        &mut self,
+        group_id: &str,
+    ) -> Vec<SignedMessage<Identity, Signature>> {
+        let messages = self.messages_with_hashes(group_id);
+        let original_of = |mut hash: MessageHash| {
+            while let Some(supersedes) = messages
+                .iter()
+                .find(|(h, _)| h == &hash)
+                .and_then(|(_, msg)| msg.message.supersedes)
+            {
+                hash = supersedes;
+            }
+            hash
+        };
+
+        // the messages are ordered from the latest, so the first edit of an original is the latest
+        let mut latest_edits: Vec<(MessageHash, &SignedMessage<Identity, Signature>)> = vec![];
+        for (hash, msg) in messages.iter() {
+            if msg.message.supersedes.is_some() {
+                let original = original_of(*hash);
+                if !latest_edits.iter().any(|(h, _)| h == &original) {
+                    latest_edits.push((original, msg));
+                }
+            }
+        }
+
+        messages
+            .iter()
+            .filter(|(_, msg)| msg.message.supersedes.is_none())
+            .map(|(hash, msg)| {
+                latest_edits
+                    .iter()
+                    .find(|(original, _)| original == hash)
+                    .map_or(msg, |(_, edit)| edit)
+                    .clone()
+            })
+            .collect()
+    }
+
+    /// Returns the replies to the message with the given hash, ordered from the oldest.
+    pub(crate) fn replies(
+        &mut self,
+        group_id: &str,
+        hash: &MessageHash,
+    ) -> Vec<SignedMessage<Identity, Signature>> {
+        self.messages(group_id)
+            .into_iter()
+            .rev()
+            .filter(|msg| msg.message.reply_to.as_ref() == Some(hash))
+            .collect()
+    }
+
+    /// Returns the thread tree rooted at the message with the given hash.
+    pub(crate) fn thread(&mut self, group_id: &str, hash: &MessageHash) -> Option<ThreadNode> {
+        thread::thread(&self.messages_with_hashes(group_id), hash)
+    }
+
+    /// Returns the proof of the sender of the sealed message with the given hash.
+    pub(crate) fn reveal_sender(
+        &mut self,
+        group_id: &str,
+        hash: &MessageHash,
+    ) -> Result<SenderProof, String> {
+        let msg = self
+            .message_store
+            .message(group_id, hash)
+            .filter(|msg| msg.message.sealed)
+            .ok_or("no sealed message".to_string())?;
+
+        let group_key = self.key_store.group_key(group_id);
+        let mut ratchet = self.ratchet_store.ratchet(group_id);
+        let (plaintext, kept) = self
+            .decipher(group_id, ratchet.as_mut(), group_key.as_ref(), &msg)
+            .ok_or("fail to decrypt".to_string())?;
+        self.save_ratchet(group_id, ratchet.filter(|_| kept));
+
+        let content: SealedContent =
+            serde_json::from_slice(&plaintext).map_err(|_| "Fail to parse".to_string())?;
+        Ok(SenderProof {
+            ephemeral_id: msg.id,
+            previous_hash: msg.message.previous_hash,
+            content,
+        })
+    }
+
+    /// Returns the data of the encrypted message: the data kept when it was signed or first read, or the
+    /// data decrypted with the ratchet message key or the group key, which is kept for the next reads as
+    /// the message key is deleted once it is used. It returns whether the data is kept as well.
+    fn decipher(
+        &mut self,
+        group_id: &str,
+        ratchet: Option<&mut RatchetState>,
+        group_key: Option<&GroupKey>,
+        msg: &SignedMessage<Identity, Signature>,
+    ) -> Option<(Vec<u8>, bool)> {
+        if let Some(data) = self.ratchet_store.deciphered(group_id, &msg.message.data) {
+            return Some((data, true));
+        }
+        let ratchet_key = ratchet.and_then(|ratchet| ratchet.message_key(msg.seq));
+        let data =
+            encryption::decrypt_with_any(ratchet_key.iter().chain(group_key), &msg.message.data)?;
+        let kept = self
+            .ratchet_store
+            .set_deciphered(group_id, &msg.message.data, &data)
+            .is_ok();
+        Some((data, kept))
+    }
+
+    /// Saves the ratchet after its message keys are used. It is None if the data of a message decrypted
+    /// with it is not kept, e.g. as the quota is exceeded, so that its message key is kept to decrypt the
+    /// message again. If the ratchet is not saved, the used message keys are deleted on a later read.
+    fn save_ratchet(&mut self, group_id: &str, ratchet: Option<RatchetState>) {
+        if let Some(ratchet) = ratchet {
+            // the failure is reported to the diagnostics, and the messages are still read
+            let _ = self.ratchet_store.set_ratchet(group_id, &ratchet);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Provides the source of randomness of the library, which is used to generate keys, sign messages and
+//! encrypt data.
+//!
+//! The randomness comes from the operating system, or `crypto.getRandomValues` in browsers. For tests,
+//! the source can be replaced with a generator seeded with [set_seed], so that the keys and the signatures
+//! are reproducible. A seeded source is predictable, so it must never be used in production.
+
+use std::cell::RefCell;
+
+use rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+
+thread_local! {
+    static SEEDED: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+/// Replaces the source of randomness with a generator seeded with the seed, or restores the source of
+/// the operating system if it is None. It is for tests only.
+pub fn set_seed(seed: Option<u64>) {
+    SEEDED.with(|seeded| *seeded.borrow_mut() = seed.map(StdRng::seed_from_u64));
+}
+
+/// Rng is the source of randomness of the library, which is the seeded generator if it is set by
+/// [set_seed], or the generator of the operating system.
+pub(crate) struct Rng;
+
+impl Rng {
+    fn with<R>(f: impl FnOnce(&mut dyn RngCore) -> R) -> R {
+        SEEDED.with(|seeded| match seeded.borrow_mut().as_mut() {
+            Some(rng) => f(rng),
+            None => f(&mut rand::thread_rng()),
+        })
+    }
+}
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        Self::with(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        Self::with(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        Self::with(|rng| rng.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        Self::with(|rng| rng.try_fill_bytes(dest))
+    }
+}
+
+// both sources are cryptographically secure generators
+impl CryptoRng for Rng {}
@@ -0,0 +1,91 @@
+//! Provides the strict parsing of signed messages received from peers. The input is checked against the
+//! expected shape before it is deserialized, and the identity and the signature are checked to be well
+//! formed before any signature verification is attempted. The storage flags of messages, which are not
+//! covered by their signatures, are rejected, so that peers cannot set them.
+
+use std::fmt::Display;
+
+use serde_json::Value;
+
+use crate::{account::Identity, core::message::SignedMessage, message::Signature};
+
+/// The maximum length (in bytes) of the JSON of a signed message.
+pub const MAX_PAYLOAD_LENGTH: usize = 4 * 1024 * 1024;
+
+/// SchemaError is the error returned when the JSON of a signed message is rejected by [parse_signed_message].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaError {
+    /// The JSON is longer than [MAX_PAYLOAD_LENGTH].
+    PayloadTooLarge,
+    /// The JSON is not a signed message.
+    Malformed,
+    /// The previous hash is not an array of exactly 32 bytes.
+    InvalidPreviousHash,
+    /// The identity of the signer is not a public key on the P-256 curve.
+    InvalidIdentity,
+    /// The signature is not a well-formed Schnorr signature.
+    InvalidSignature,
+    /// The message sets a storage flag, i.e. `compressed`, `redacted` or `tombstone`, which only the
+    /// local store sets.
+    StorageField,
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::PayloadTooLarge => write!(f, "payload too large"),
+            SchemaError::Malformed => write!(f, "malformed signed message"),
+            SchemaError::InvalidPreviousHash => write!(f, "invalid previous hash"),
+            SchemaError::InvalidIdentity => write!(f, "invalid identity"),
+            SchemaError::InvalidSignature => write!(f, "malformed signature"),
+            SchemaError::StorageField => write!(f, "storage field in signed message"),
+        }
+    }
+}
+
+impl From<SchemaError> for String {
+    fn from(error: SchemaError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Parses the JSON of a signed message received from a peer. It does not verify the signature.
+pub fn parse_signed_message(
+    input: &str,
+) -> Result<SignedMessage<Identity, Signature>, SchemaError> {
+    if input.len() > MAX_PAYLOAD_LENGTH {
+        return Err(SchemaError::PayloadTooLarge);
+    }
+    let value: Value = serde_json::from_str(input).map_err(|_| SchemaError::Malformed)?;
+    let previous_hash = value
+        .get("message")
+        .and_then(|message| message.get("previous_hash"))
+        .ok_or(SchemaError::Malformed)?;
+    if !is_hash(previous_hash) {
+        return Err(SchemaError::InvalidPreviousHash);
+    }
+
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_value(value).map_err(|_| SchemaError::Malformed)?;
+    if !signed_msg.id.is_valid() {
+        return Err(SchemaError::InvalidIdentity);
+    }
+    if !signed_msg.signature.is_well_formed() {
+        return Err(SchemaError::InvalidSignature);
+    }
+    let message = &signed_msg.message;
+    if message.compressed || message.redacted || message.tombstone.is_some() {
+        return Err(SchemaError::StorageField);
+    }
+    Ok(signed_msg)
+}
+
+/// Returns true if the value is an array of exactly 32 bytes.
+fn is_hash(value: &Value) -> bool {
+    value.as_array().is_some_and(|bytes| {
+        bytes.len() == 32
+            && bytes
+                .iter()
+                .all(|byte| byte.as_u64().is_some_and(|byte| byte <= u8::MAX as u64))
+    })
+}
@@ -0,0 +1,80 @@
+//! Provides the sealed sender mode, which hides the author of a message from non-members.
+//!
+//! A sealed message is signed by an ephemeral identity generated for the message, which preserves the
+//! integrity of the chain. The identity of the sender and the sender's signature binding the content
+//! to the ephemeral identity are encrypted together with the data for the group members.
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    account::{Identity, Secret},
+    core::message::{Message, MessageHash, MessageSigner as _, Verifiable},
+    message::{MessageSigner, Signature},
+};
+
+/// SealedContent is the plaintext of the data of a sealed message.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SealedContent {
+    /// The identity of the sender.
+    pub sender: Identity,
+    /// The signature of the sender over the binding of the data to the ephemeral identity.
+    pub sender_signature: Signature,
+    /// The data of the message.
+    pub data: Vec<u8>,
+}
+
+impl SealedContent {
+    /// Seals the data by the sender for the message signed by the ephemeral identity.
+    pub fn new(
+        sender: Identity,
+        secret: &Secret,
+        ephemeral_id: &Identity,
+        previous_hash: MessageHash,
+        data: Vec<u8>,
+    ) -> Self {
+        let sender_signature = MessageSigner::sign(
+            &sender,
+            secret,
+            &binding(ephemeral_id, previous_hash, &data),
+        );
+        Self {
+            sender,
+            sender_signature,
+            data,
+        }
+    }
+
+    /// Verifies that the sender signed the data for the message signed by the ephemeral identity.
+    pub fn verify(&self, ephemeral_id: &Identity, previous_hash: MessageHash) -> bool {
+        self.sender_signature.verify(
+            &self.sender,
+            &binding(ephemeral_id, previous_hash, &self.data).to_hash::<Sha256>(),
+        )
+    }
+}
+
+/// SenderProof reveals the sender of a sealed message for disputes. It can be verified by anyone
+/// without the group content key.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SenderProof {
+    /// The ephemeral identity which signed the sealed message.
+    pub ephemeral_id: Identity,
+    /// The previous hash of the sealed message.
+    pub previous_hash: MessageHash,
+    /// The decrypted content of the sealed message.
+    pub content: SealedContent,
+}
+
+impl SenderProof {
+    /// Verifies that the sender signed the content of the sealed message.
+    pub fn verify(&self) -> bool {
+        self.content.verify(&self.ephemeral_id, self.previous_hash)
+    }
+}
+
+/// Returns the message that the sender signs, which binds the data to the ephemeral identity and
+/// the position in the chain.
+fn binding(ephemeral_id: &Identity, previous_hash: MessageHash, data: &[u8]) -> Message {
+    Message::new(previous_hash, [ephemeral_id.as_ref(), data].concat())
+}
@@ -0,0 +1,124 @@
+//! Provides the full-text search over the data of the messages of a group, with an inverted index from
+//! the terms in the text to the message hashes.
+//!
+//! The text is split into terms at characters which are not alphanumeric, and the terms are lowercased.
+//! A query matches the messages which contain all of its terms.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::message::MessageHash;
+
+/// The number of characters kept on each side of the first match in a snippet.
+const SNIPPET_CONTEXT: usize = 32;
+
+/// SearchIndex is the inverted index over the text of the messages of a group. The hashes of each term
+/// are ordered from the oldest.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    /// The hashes of the messages containing each term.
+    pub terms: Vec<(String, Vec<MessageHash>)>,
+}
+
+impl SearchIndex {
+    /// Adds the terms of the text of the message with the given hash.
+    pub fn add(&mut self, hash: MessageHash, text: &str) {
+        for term in terms(text) {
+            match self.terms.iter_mut().find(|(t, _)| t == &term) {
+                Some((_, hashes)) if hashes.contains(&hash) => {}
+                Some((_, hashes)) => hashes.push(hash),
+                None => self.terms.push((term, vec![hash])),
+            }
+        }
+    }
+
+    /// Removes the message with the given hash, e.g. after it is redacted.
+    pub fn remove(&mut self, hash: &MessageHash) {
+        self.terms
+            .iter_mut()
+            .for_each(|(_, hashes)| hashes.retain(|h| h != hash));
+        self.terms.retain(|(_, hashes)| !hashes.is_empty());
+    }
+
+    /// Returns the hashes of the messages which contain all the terms of the query, ordered from the oldest.
+    pub fn search(&self, query: &str) -> Vec<MessageHash> {
+        let query_terms = terms(query);
+        let Some((first, others)) = query_terms.split_first() else {
+            return vec![];
+        };
+        let hashes_of = |term: &String| {
+            self.terms
+                .iter()
+                .find(|(t, _)| t == term)
+                .map_or(&[][..], |(_, hashes)| hashes.as_slice())
+        };
+        hashes_of(first)
+            .iter()
+            .filter(|hash| others.iter().all(|term| hashes_of(term).contains(hash)))
+            .copied()
+            .collect()
+    }
+}
+
+/// SearchResult is a message which matches a query, with a snippet of its text around the first match.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// The hash of the message.
+    pub hash: MessageHash,
+    /// The text around the first term of the query in the message.
+    pub snippet: String,
+}
+
+impl SearchResult {
+    /// Creates the result for the message with the given hash and text.
+    pub fn new(hash: MessageHash, text: &str, query: &str) -> Self {
+        Self {
+            hash,
+            snippet: snippet(text, query),
+        }
+    }
+}
+
+/// Returns the distinct lowercased terms of the text.
+pub fn terms(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = vec![];
+    for term in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+    {
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+/// Returns the text around the first occurrence of the first term of the query, or the beginning of the
+/// text if the term is not found. The omitted text is marked with an ellipsis.
+pub fn snippet(text: &str, query: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lowercased: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let start = terms(query)
+        .first()
+        .and_then(|term| {
+            let term: Vec<char> = term.chars().collect();
+            lowercased
+                .windows(term.len())
+                .position(|window| window == term.as_slice())
+        })
+        .unwrap_or(0);
+
+    let from = start.saturating_sub(SNIPPET_CONTEXT);
+    let to = (start + SNIPPET_CONTEXT).min(chars.len());
+    let mut snippet: String = chars[from..to].iter().collect();
+    if from > 0 {
+        snippet.insert(0, '…');
+    }
+    if to < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
@@ -0,0 +1,95 @@
+//! Provides the local settings of groups, which are kept in the local storage and are not shared with the
+//! other members, e.g. how long the data of messages is kept and which signers are accepted.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::Identity,
+    core::{
+        group::RetentionPolicy,
+        message::{MessageKind, SignedMessage},
+    },
+    message::Signature,
+};
+
+/// The number of seconds by which the timestamp of a message may be ahead of the local clock when the
+/// timestamps are checked strictly.
+pub const MAX_CLOCK_SKEW: u64 = 300;
+
+/// GroupSettings are the local settings of a group.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GroupSettings {
+    /// Encrypts the data of the messages signed locally with the group content key, if the group has one.
+    /// If the group policy requires encryption, the unencrypted messages are rejected.
+    #[serde(default = "default_encryption")]
+    pub encryption: bool,
+    /// How long the data of messages is kept.
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    /// The identities allowed to sign the messages added to the group, or None if any signer is allowed.
+    #[serde(default)]
+    pub allowed_signers: Option<Vec<Identity>>,
+    /// Requires every message to have a timestamp which is not before the timestamp of its parent by more
+    /// than the backward skew, and not more than [MAX_CLOCK_SKEW] seconds ahead of the local clock.
+    #[serde(default)]
+    pub strict_timestamps: bool,
+    /// The number of seconds by which the timestamp of a message may be before the timestamp of its parent
+    /// when the timestamps are checked strictly, e.g. to tolerate the clocks of members which differ.
+    #[serde(default)]
+    pub max_backward_skew: u64,
+}
+
+impl Default for GroupSettings {
+    fn default() -> Self {
+        Self {
+            encryption: default_encryption(),
+            retention: RetentionPolicy::default(),
+            allowed_signers: None,
+            strict_timestamps: false,
+            max_backward_skew: 0,
+        }
+    }
+}
+
+impl GroupSettings {
+    /// Checks if the signer is allowed to sign the messages of the group.
+    pub fn is_allowed_signer(&self, signer: &Identity) -> bool {
+        match &self.allowed_signers {
+            Some(signers) => signers.contains(signer),
+            None => true,
+        }
+    }
+
+    /// Checks the timestamp of the message against the timestamp of its parent, if any, at the given
+    /// unix timestamp. Any timestamp is accepted if the timestamps are not checked strictly.
+    pub fn check_timestamp(
+        &self,
+        message: &SignedMessage<Identity, Signature>,
+        parent_timestamp: Option<u64>,
+        now: u64,
+    ) -> Result<(), String> {
+        if !self.strict_timestamps {
+            return Ok(());
+        }
+        let Some(timestamp) = message.message.timestamp else {
+            // the genesis records are signed without timestamps
+            if message.message.kind == MessageKind::Genesis {
+                return Ok(());
+            }
+            return Err("timestamp is missing".to_string());
+        };
+        if timestamp > now.saturating_add(MAX_CLOCK_SKEW) {
+            return Err("timestamp is in the future".to_string());
+        }
+        if parent_timestamp
+            .is_some_and(|parent| timestamp.saturating_add(self.max_backward_skew) < parent)
+        {
+            return Err("timestamp is before the previous message".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn default_encryption() -> bool {
+    true
+}
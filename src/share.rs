@@ -0,0 +1,78 @@
+//! Provides the share tokens of identities, which are compact strings for the exchange of contacts in
+//! person, e.g. encoded in QR codes.
+//!
+//! A token is the prefix [TOKEN_PREFIX] followed by the unpadded base64url of the version, the length and
+//! the bytes of the compact public key, the optional hash of the latest profile update of the identity, and a checksum of them,
+//! so that mistyped or truncated tokens are rejected.
+
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{account::Identity, core::message::MessageHash};
+
+/// The prefix of the share tokens.
+pub const TOKEN_PREFIX: &str = "wm:";
+/// The version of the token format.
+const TOKEN_VERSION: u8 = 0;
+/// The length of the checksum, which is the prefix of the SHA-256 hash of the other bytes.
+const CHECKSUM_LENGTH: usize = 4;
+
+/// ShareToken is the content of a share token.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShareToken {
+    pub identity: Identity,
+    /// The hash of the latest profile update of the identity, so that the receiver can check that the
+    /// profile updates shared later are from the same chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<MessageHash>,
+}
+
+impl ShareToken {
+    /// Encodes the token. It returns None if the identity is not a public key.
+    pub fn encode(&self) -> Option<String> {
+        if !self.identity.is_valid() {
+            return None;
+        }
+        let key = Base64::decode_vec(&self.identity.to_compact()).ok()?;
+        let mut bytes = vec![TOKEN_VERSION, u8::try_from(key.len()).ok()?];
+        bytes.extend_from_slice(&key);
+        if let Some(profile) = &self.profile {
+            bytes.extend_from_slice(profile);
+        }
+        bytes.extend_from_slice(&checksum(&bytes));
+        Some(format!(
+            "{TOKEN_PREFIX}{}",
+            Base64UrlUnpadded::encode_string(&bytes)
+        ))
+    }
+
+    /// Decodes the token. It returns None if the token is malformed or its checksum does not match.
+    pub fn decode(token: &str) -> Option<Self> {
+        let bytes = Base64UrlUnpadded::decode_vec(token.trim().strip_prefix(TOKEN_PREFIX)?).ok()?;
+        let (bytes, sum) = bytes.split_at(bytes.len().checked_sub(CHECKSUM_LENGTH)?);
+        if sum != checksum(bytes) {
+            return None;
+        }
+        let [version, key_len, rest @ ..] = bytes else {
+            return None;
+        };
+        if *version != TOKEN_VERSION || rest.len() < *key_len as usize {
+            return None;
+        }
+        let (key, profile) = rest.split_at(*key_len as usize);
+        let identity = Identity::try_from(Base64::encode_string(key).as_str()).ok()?;
+        if !identity.is_valid() {
+            return None;
+        }
+        let profile = match profile.len() {
+            0 => None,
+            _ => Some(profile.try_into().ok()?),
+        };
+        Some(Self { identity, profile })
+    }
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LENGTH] {
+    Sha256::digest(bytes)[..CHECKSUM_LENGTH].try_into().unwrap()
+}
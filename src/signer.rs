@@ -1,10 +1,38 @@
 //! Defines the `Signer` struct and its implementation.
 
+use sha2::Sha256;
+
 use crate::{
-    account::{Identity, Secret},
-    core::message::SignedMessage,
+    account::{GenKeysAlgorithm, Identity, Secret},
+    acknowledgment::{AckKind, Acknowledgment, AcknowledgmentBody},
+    agreement::{self, KeyAnnouncement, KeyDistribution, KeyGrant},
+    capability::{Capability, CapabilityBody},
+    clock::{TimeToken, TimeTokenBody},
+    core::{
+        account::GenerateKeys,
+        group::{unix_timestamp, GroupGenesis, GroupPolicy, HashAlgorithm},
+        message::{Message, MessageHash, MessageKind, MessageRef, SignedMessage},
+    },
+    device::{DeviceCertificate, DeviceRevocation},
+    encryption::{self, GroupKey},
+    invite::{Invite, InviteBody, JoinRequest},
     message::{MessageSigner, Signature},
-    store::{account::AccountStore, message::SignedMessageStore},
+    multisig::{CoSignedContent, SignerSet},
+    notary::{Attestation, AttestationBody, NotarizationRequest},
+    profile::Profile,
+    reaction::{Reaction, ReactionBody},
+    sealed::SealedContent,
+    state::{
+        Checkpoint, GroupState, HashMigration, MembershipChange, PinChange, PolicyUpdate,
+        Redaction, Role,
+    },
+    store::{
+        account::AccountStore, group::GroupStore, key::GroupKeyStore, message::SignedMessageStore,
+        profile::ProfileStore, ratchet::RatchetStore, settings::GroupSettingsStore,
+        transparency::KeyLogStore,
+    },
+    transparency::KeyEvent,
+    writer::WriteError,
 };
 
 /// Signer is a struct that defines the signing process involved with the stores such as `AccountStore` and `SignedMessageStore`.
@@ -12,32 +40,662 @@ use crate::{
 pub(crate) struct Signer {
     pub(crate) account_store: AccountStore,
     pub(crate) message_store: SignedMessageStore,
+    pub(crate) group_store: GroupStore,
+    pub(crate) key_store: GroupKeyStore,
+    pub(crate) ratchet_store: RatchetStore,
+    pub(crate) profile_store: ProfileStore,
+    pub(crate) key_log_store: KeyLogStore,
+    pub(crate) settings_store: GroupSettingsStore,
+    /// The account which signs instead of the current account.
+    account: Option<(Identity, Secret)>,
 }
 
 impl Signer {
+    /// Creates a signer which signs with the given account instead of the current account.
+    pub(crate) fn with_account(account: (Identity, Secret)) -> Self {
+        Self {
+            account: Some(account),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the account which signs, which is the current account unless another is given.
+    fn account(&self) -> Option<(Identity, Secret)> {
+        self.account
+            .clone()
+            .or_else(|| self.account_store.current_account())
+    }
+
     /// Signs a message with the given group id and data.
     /// The message is signed with the secret key from the `AccountStore`.
     /// Depends on the latest message stored, it signs the message as the first message or a subsequent message.
+    /// If the group has a content key, the data is encrypted before signing, with the ratchet message key if the ratchet is started.
     pub(crate) fn sign(
         &mut self,
         group_id: &str,
         data: Vec<u8>,
-    ) -> SignedMessage<Identity, Signature> {
-        let (identity, secret) = self.account_store.current_account().unwrap();
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(group_id, MessageKind::Data, data)
+    }
 
-        match self.message_store.latest_message(group_id) {
-            Some((previous_hash, prev_message)) => {
-                SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
-                    identity,
-                    &secret,
-                    data,
-                    previous_hash,
-                    prev_message,
-                )
-            }
+    /// Signs a message of the given kind. Only `Data` messages are encrypted with the group content key,
+    /// records are kept readable so that they can be processed by every member.
+    pub(crate) fn sign_record(
+        &mut self,
+        group_id: &str,
+        kind: MessageKind,
+        data: Vec<u8>,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let mut message = Message::root(data);
+        message.kind = kind;
+        self.sign_at_next_position(group_id, message)
+    }
+
+    /// Signs the message at the next position of the group by the current account. The data of `Data`
+    /// messages is encrypted if the group is encrypted.
+    fn sign_at_next_position(
+        &mut self,
+        group_id: &str,
+        message: Message,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let position = self.next_position(group_id)?;
+        self.sign_at_position(group_id, message, position)
+    }
+
+    /// Signs the data as a `Data` message after the given previous message, which may not be written yet,
+    /// e.g. a message in the outbox, which is hashed with the hash algorithm of the group. If no previous
+    /// message is given, it is signed at the next position.
+    pub(crate) fn sign_after(
+        &mut self,
+        group_id: &str,
+        data: Vec<u8>,
+        previous: Option<&SignedMessage<Identity, Signature>>,
+        hash_algorithm: HashAlgorithm,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let position = match previous {
+            Some(previous) => (
+                hash_algorithm.hash(previous),
+                previous.next_seq().ok_or(WriteError::ChainFull)?,
+            ),
+            None => self.next_position(group_id)?,
+        };
+        self.sign_at_position(group_id, Message::root(data), position)
+    }
+
+    /// Signs the message at the given position, which is the previous hash and the sequence number. The data
+    /// of `Data` messages is encrypted if the group is encrypted.
+    fn sign_at_position(
+        &mut self,
+        group_id: &str,
+        mut message: Message,
+        (previous_hash, seq): (MessageHash, u64),
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let (identity, secret) = self.account().ok_or("No current account".to_string())?;
+
+        message.previous_hash = previous_hash;
+        message.timestamp = Some(unix_timestamp());
+        let key = message
+            .kind
+            .is_data()
+            .then(|| self.encryption_key(group_id, seq))
+            .flatten();
+        if let Some(key) = key {
+            let data = std::mem::take(&mut message.data);
+            message.data = encryption::encrypt(&key, &data);
+            message.encrypted = true;
+            // the data is kept, as the key of the message is deleted once it is written. If it cannot be
+            // kept, the key is not deleted (see `Writer::discard_message_key`), so the data is decrypted
+            // again with it.
+            let _ = self
+                .ratchet_store
+                .set_deciphered(group_id, &message.data, &data);
+        }
+
+        Ok(SignedMessage::sign::<Secret, MessageSigner>(
+            identity, &secret, message, seq,
+        ))
+    }
+
+    /// Signs the genesis record of a new group created by the current account. It fails if the group
+    /// already has messages.
+    pub(crate) fn sign_genesis(
+        &mut self,
+        group_id: &str,
+        name: String,
+        policy: GroupPolicy,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        if self.message_store.latest_message_hash(group_id).is_some() {
+            return Err("group already exists".to_string());
+        }
+        let (identity, secret) = self.account().ok_or("No current account".to_string())?;
+        let genesis = GroupGenesis::new(group_id.to_string(), name, identity.clone(), policy);
+        Ok(SignedMessage::new_genesis::<Secret, MessageSigner>(
+            identity, &secret, &genesis,
+        ))
+    }
+
+    /// Signs an `AddMember` or `RemoveMember` record for the given member.
+    pub(crate) fn sign_membership_change(
+        &mut self,
+        group_id: &str,
+        kind: MessageKind,
+        member: Identity,
+        role: Role,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let change = MembershipChange { member, role };
+        self.sign_record(group_id, kind, serde_json::to_vec(&change).unwrap())
+    }
+
+    /// Signs a `Policy` record which changes the group policy or the roles of the members.
+    pub(crate) fn sign_policy_update(
+        &mut self,
+        group_id: &str,
+        update: &PolicyUpdate,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(
+            group_id,
+            MessageKind::Policy,
+            serde_json::to_vec(update).unwrap(),
+        )
+    }
+
+    /// Signs an invitation from the current account for the invitee to join the group.
+    pub(crate) fn sign_invite(
+        &self,
+        group_id: &str,
+        invitee: Identity,
+        role: Role,
+        expiry: u64,
+    ) -> Result<Invite, String> {
+        let (inviter, secret) = self.account().ok_or("No current account".to_string())?;
+        let body = InviteBody {
+            group_id: group_id.to_string(),
+            invitee,
+            inviter,
+            role,
+            expiry,
+        };
+        Ok(Invite::new(body, &secret))
+    }
+
+    /// Signs a `Join` record which presents the invitation for the current account.
+    pub(crate) fn sign_join(
+        &mut self,
+        group_id: &str,
+        invite: Invite,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let request = JoinRequest {
+            invite,
+            timestamp: unix_timestamp(),
+        };
+        self.sign_record(
+            group_id,
+            MessageKind::Join,
+            serde_json::to_vec(&request).unwrap(),
+        )
+    }
+
+    /// Signs a capability from the current account which delegates the kinds of messages to the delegate.
+    pub(crate) fn sign_capability(
+        &self,
+        group_id: &str,
+        delegate: Identity,
+        kinds: Vec<MessageKind>,
+        expiry: u64,
+    ) -> Result<Capability, String> {
+        let (issuer, secret) = self.account().ok_or("No current account".to_string())?;
+        let body = CapabilityBody {
+            group_id: group_id.to_string(),
+            issuer,
+            delegate,
+            kinds,
+            expiry,
+        };
+        Ok(Capability::new(body, &secret))
+    }
+
+    /// Signs a `Delegation` record which presents the capability for the current account.
+    pub(crate) fn sign_delegation(
+        &mut self,
+        group_id: &str,
+        capability: &Capability,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(
+            group_id,
+            MessageKind::Delegation,
+            serde_json::to_vec(capability).unwrap(),
+        )
+    }
+
+    /// Signs a `DeviceCertificate` record which presents the certificate of the current account.
+    pub(crate) fn sign_device_certificate(
+        &mut self,
+        group_id: &str,
+        certificate: &DeviceCertificate,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(
+            group_id,
+            MessageKind::DeviceCertificate,
+            serde_json::to_vec(certificate).unwrap(),
+        )
+    }
+
+    /// Signs a `DeviceRevocation` record which revokes the device of the current account.
+    pub(crate) fn sign_device_revocation(
+        &mut self,
+        group_id: &str,
+        device: Identity,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(
+            group_id,
+            MessageKind::DeviceRevocation,
+            serde_json::to_vec(&DeviceRevocation { device }).unwrap(),
+        )
+    }
+
+    /// Creates a proposal of the data at the next position of the group, which requires the signatures
+    /// of the threshold of the signers, and adds the partial signature of the current account.
+    pub(crate) fn propose_cosigned(
+        &self,
+        group_id: &str,
+        signer_set: SignerSet,
+        data: Vec<u8>,
+    ) -> Result<CoSignedContent, String> {
+        let (previous_hash, _) = self.next_position(group_id)?;
+        let mut content = CoSignedContent::new(signer_set, previous_hash, data);
+        self.cosign(&mut content)?;
+        Ok(content)
+    }
+
+    /// Adds the partial signature of the current account to the proposal.
+    pub(crate) fn cosign(&self, content: &mut CoSignedContent) -> Result<(), String> {
+        let (identity, secret) = self.account().ok_or("No current account".to_string())?;
+        content.sign(identity, &secret)
+    }
+
+    /// Signs a `CoSigned` record which submits the proposal.
+    pub(crate) fn sign_cosigned(
+        &mut self,
+        group_id: &str,
+        content: &CoSignedContent,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(
+            group_id,
+            MessageKind::CoSigned,
+            serde_json::to_vec(content).unwrap(),
+        )
+    }
+
+    /// Signs a message which replies to the message with the given hash.
+    pub(crate) fn sign_reply(
+        &mut self,
+        group_id: &str,
+        data: Vec<u8>,
+        reply_to: MessageHash,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let mut message = Message::root(data);
+        message.reply_to = Some(reply_to);
+        self.sign_at_next_position(group_id, message)
+    }
+
+    /// Signs a message with the given tags, which are covered by the signature.
+    pub(crate) fn sign_tagged(
+        &mut self,
+        group_id: &str,
+        data: Vec<u8>,
+        tags: Vec<String>,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let mut message = Message::root(data);
+        message.tags = tags;
+        self.sign_at_next_position(group_id, message)
+    }
+
+    /// Signs a message which refers to the given message, which may be in another group.
+    pub(crate) fn sign_reference(
+        &mut self,
+        group_id: &str,
+        data: Vec<u8>,
+        reference: MessageRef,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let mut message = Message::root(data);
+        message.reference = Some(reference);
+        self.sign_at_next_position(group_id, message)
+    }
+
+    /// Signs a message which is an edit of the message with the given hash.
+    pub(crate) fn sign_edit(
+        &mut self,
+        group_id: &str,
+        data: Vec<u8>,
+        supersedes: MessageHash,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let mut message = Message::root(data);
+        message.supersedes = Some(supersedes);
+        self.sign_at_next_position(group_id, message)
+    }
+
+    /// Signs a `Redaction` record which marks the data of the message with the given hash as deleted.
+    pub(crate) fn sign_redaction(
+        &mut self,
+        group_id: &str,
+        hash: MessageHash,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(
+            group_id,
+            MessageKind::Redaction,
+            serde_json::to_vec(&Redaction { hash }).unwrap(),
+        )
+    }
+
+    /// Signs a `Pin` or `Unpin` record for the message with the given hash.
+    pub(crate) fn sign_pin_change(
+        &mut self,
+        group_id: &str,
+        kind: MessageKind,
+        hash: MessageHash,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(
+            group_id,
+            kind,
+            serde_json::to_vec(&PinChange { hash }).unwrap(),
+        )
+    }
+
+    /// Signs a `HashMigration` record which binds the latest message of the group, hashed with the given
+    /// previous algorithm, to the new algorithm.
+    pub(crate) fn sign_hash_migration(
+        &mut self,
+        group_id: &str,
+        from: HashAlgorithm,
+        to: HashAlgorithm,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let (head, _) = self.next_position(group_id)?;
+        self.sign_record(
+            group_id,
+            MessageKind::HashMigration,
+            serde_json::to_vec(&HashMigration { head, from, to }).unwrap(),
+        )
+    }
+
+    /// Signs a `Seal` record which closes the group, so that no message may be appended after it.
+    pub(crate) fn sign_seal(
+        &mut self,
+        group_id: &str,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        self.sign_record(group_id, MessageKind::Seal, vec![])
+    }
+
+    /// Signs a `Checkpoint` record which summarizes the chain of the group up to the latest message with
+    /// the given state and the root of the Merkle Mountain Range.
+    pub(crate) fn sign_checkpoint(
+        &mut self,
+        group_id: &str,
+        state: GroupState,
+        merkle_root: Option<MessageHash>,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let (head, seq) = self.next_position(group_id)?;
+        let checkpoint = Checkpoint {
+            head,
+            seq: seq.saturating_sub(1),
+            state,
+            merkle_root,
+        };
+        self.sign_record(
+            group_id,
+            MessageKind::Checkpoint,
+            serde_json::to_vec(&checkpoint).unwrap(),
+        )
+    }
+
+    /// Signs an acknowledgment of the message with the given hash by the current account.
+    pub(crate) fn sign_acknowledgment(
+        &self,
+        group_id: &str,
+        hash: MessageHash,
+        kind: AckKind,
+    ) -> Result<Acknowledgment, String> {
+        let (signer, secret) = self.account().ok_or("No current account".to_string())?;
+        let body = AcknowledgmentBody {
+            group_id: group_id.to_string(),
+            hash,
+            signer,
+            kind,
+        };
+        Ok(Acknowledgment::new(body, &secret))
+    }
+
+    /// Signs a reaction to the message with the given hash by the current account.
+    pub(crate) fn sign_reaction(
+        &self,
+        group_id: &str,
+        hash: MessageHash,
+        emoji: &str,
+    ) -> Result<Reaction, String> {
+        let (signer, secret) = self.account().ok_or("No current account".to_string())?;
+        let body = ReactionBody {
+            group_id: group_id.to_string(),
+            hash,
+            signer,
+            emoji: emoji.to_string(),
+        };
+        Ok(Reaction::new(body, &secret))
+    }
+
+    /// Signs an attestation over the chain head in the request, if the current account is the notary
+    /// of the request.
+    pub(crate) fn sign_attestation(&self, request: NotarizationRequest) -> Option<Attestation> {
+        let (notary, secret) = self.account()?;
+        if notary != request.notary {
+            return None;
+        }
+        let body = AttestationBody {
+            request,
+            timestamp: unix_timestamp(),
+        };
+        Some(Attestation::new(body, &secret))
+    }
+
+    /// Signs a time token with the current time by the current account as the time authority.
+    pub(crate) fn sign_time_token(&self) -> Option<TimeToken> {
+        let (authority, secret) = self.account()?;
+        let body = TimeTokenBody {
+            authority,
+            timestamp: unix_timestamp(),
+        };
+        Some(TimeToken::new(body, &secret))
+    }
+
+    /// Signs the profile as the next update of the profile chain of the current account.
+    pub(crate) fn sign_profile(
+        &self,
+        profile: &Profile,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let (signer, _) = self.account().ok_or("No current account".to_string())?;
+        let latest = self.profile_store.profile_updates(&signer).pop();
+        self.sign_next_in_chain(latest, serde_json::to_vec(profile).unwrap())
+    }
+
+    /// Signs the key event as the next entry of the key transparency log of the current account.
+    pub(crate) fn sign_key_event(
+        &self,
+        event: &KeyEvent,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let (signer, _) = self.account().ok_or("No current account".to_string())?;
+        let latest = self.key_log_store.key_log(&signer).pop();
+        self.sign_next_in_chain(latest, serde_json::to_vec(event).unwrap())
+    }
+
+    /// Signs the data by the current account as the message after the latest message of a chain of
+    /// the account, or as the first message if the chain is empty.
+    fn sign_next_in_chain(
+        &self,
+        latest: Option<SignedMessage<Identity, Signature>>,
+        data: Vec<u8>,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let (signer, secret) = self.account().ok_or("No current account".to_string())?;
+        Ok(match latest {
+            Some(latest) => SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+                signer,
+                &secret,
+                data,
+                latest.hash::<Sha256>(),
+                latest,
+            ),
             None => {
-                SignedMessage::new_first_message::<Secret, MessageSigner>(identity, &secret, data)
+                SignedMessage::new_first_message::<Secret, MessageSigner>(signer, &secret, data)
             }
+        })
+    }
+
+    /// Signs a sealed message with the given group id and data. The message is signed by a new ephemeral
+    /// identity, and the current account is sealed together with the data, encrypted for the group members.
+    pub(crate) fn sign_sealed(
+        &mut self,
+        group_id: &str,
+        data: Vec<u8>,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let (identity, secret) = self.account().ok_or("No current account".to_string())?;
+        let (previous_hash, seq) = self.next_position(group_id)?;
+        let key = self
+            .encryption_key(group_id, seq)
+            .ok_or("no group key".to_string())?;
+
+        let (ephemeral_secret, ephemeral_id) = GenKeysAlgorithm::generate_keys();
+        let content = SealedContent::new(identity, &secret, &ephemeral_id, previous_hash, data);
+
+        let plaintext = serde_json::to_vec(&content).unwrap();
+        let mut message = Message::new(previous_hash, encryption::encrypt(&key, &plaintext));
+        // the key is not deleted if the data cannot be kept, as in `sign_at_position`
+        let _ = self
+            .ratchet_store
+            .set_deciphered(group_id, &message.data, &plaintext);
+        message.encrypted = true;
+        message.sealed = true;
+        message.timestamp = Some(unix_timestamp());
+
+        Ok(SignedMessage::sign::<Secret, MessageSigner>(
+            ephemeral_id,
+            &ephemeral_secret,
+            message,
+            seq,
+        ))
+    }
+
+    /// Returns the previous hash and the sequence number of the next message in the group. It fails if the
+    /// group has reached its maximum length.
+    fn next_position(&self, group_id: &str) -> Result<(MessageHash, u64), String> {
+        match self.message_store.latest_message(group_id) {
+            Some((hash, msg)) => Ok((hash, msg.next_seq().ok_or(WriteError::ChainFull)?)),
+            None => Ok(([0u8; 32], 0)),
+        }
+    }
+
+    /// Returns the key to encrypt the message with the given sequence number: the ratchet message key
+    /// if the ratchet is started, otherwise the group content key. The message key is kept until the message
+    /// is written, so that the message is signed again with it if the write fails. The group content key is
+    /// used if the message key is not available, so that the data is never sent in the clear. There is no
+    /// key if the encryption is turned off in the settings of the group.
+    fn encryption_key(&mut self, group_id: &str, seq: u64) -> Option<GroupKey> {
+        if !self.settings_store.settings(group_id).encryption {
+            return None;
         }
+        let message_key = self
+            .ratchet_store
+            .ratchet(group_id)
+            .and_then(|mut ratchet| {
+                let message_key = ratchet.sending_key(seq);
+                // if the ratchet cannot be saved, the same key is derived again from the stored one
+                let _ = self.ratchet_store.set_ratchet(group_id, &ratchet);
+                message_key
+            });
+        message_key.or_else(|| self.key_store.group_key(group_id))
+    }
+
+    /// Signs a `KeyAnnouncement` record with the agreement public key of the current account, so that
+    /// other members can grant the group content key to it.
+    pub(crate) fn sign_key_announcement(
+        &mut self,
+        group_id: &str,
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let (_, secret) = self.account().ok_or("No current account".to_string())?;
+        let announcement = KeyAnnouncement {
+            agreement_key: agreement::agreement_public_key(&secret),
+        };
+        self.sign_record(
+            group_id,
+            MessageKind::KeyAnnouncement,
+            serde_json::to_vec(&announcement).unwrap(),
+        )
+    }
+
+    /// Signs a `KeyDistribution` record which wraps the group content key for the given members.
+    /// The agreement keys of the members are taken from their latest `KeyAnnouncement` records in the chain.
+    pub(crate) fn sign_key_distribution(
+        &mut self,
+        group_id: &str,
+        members: &[Identity],
+    ) -> Result<SignedMessage<Identity, Signature>, String> {
+        let group_key = self
+            .key_store
+            .group_key(group_id)
+            .ok_or("no group key".to_string())?;
+        let grants = self.key_grants(group_id, &group_key, members)?;
+        let epoch = self.group_store.state(group_id).key_epoch;
+
+        self.sign_record(
+            group_id,
+            MessageKind::KeyDistribution,
+            serde_json::to_vec(&KeyDistribution { grants, epoch }).unwrap(),
+        )
+    }
+
+    /// Signs a `Rekey` record which replaces the group content key with a new key in the next epoch. The
+    /// new key is wrapped for the current key holders except the removed members and the current account.
+    /// It returns the record with the new key, which is saved as the current group key only once the
+    /// record is written (see `Writer::adopt_group_key`).
+    pub(crate) fn sign_rekey(
+        &mut self,
+        group_id: &str,
+        removed_members: &[Identity],
+    ) -> Result<(SignedMessage<Identity, Signature>, GroupKey), String> {
+        let (identity, _) = self.account().ok_or("No current account".to_string())?;
+        let state = self.group_store.state(group_id);
+        let remaining_members = state
+            .key_holders
+            .into_iter()
+            .filter(|id| id != &identity && !removed_members.contains(id))
+            .collect::<Vec<_>>();
+
+        let group_key = encryption::generate_group_key();
+        let grants = self.key_grants(group_id, &group_key, &remaining_members)?;
+        let epoch = state.key_epoch + 1;
+        let signed_msg = self.sign_record(
+            group_id,
+            MessageKind::Rekey,
+            serde_json::to_vec(&KeyDistribution { grants, epoch }).unwrap(),
+        )?;
+
+        Ok((signed_msg, group_key))
+    }
+
+    /// Wraps the group key for the members with their announced agreement keys.
+    fn key_grants(
+        &self,
+        group_id: &str,
+        group_key: &GroupKey,
+        members: &[Identity],
+    ) -> Result<Vec<KeyGrant>, String> {
+        let messages = self.message_store.messages(group_id);
+        members
+            .iter()
+            .map(|member| {
+                let agreement_key = agreement::announced_key(&messages, member)
+                    .ok_or(format!("no announced agreement key for {member}"))?;
+                Ok(KeyGrant {
+                    recipient: member.clone(),
+                    wrapped_key: agreement::wrap_key(group_key, &agreement_key)?,
+                })
+            })
+            .collect()
     }
 }
@@ -0,0 +1,526 @@
+//! Provides the state of a group replayed from its chain, which is used to validate the records.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::Identity,
+    agreement::KeyDistribution,
+    capability::Capability,
+    core::{
+        compare::ct_eq,
+        group::{GroupGenesis, GroupPolicy, HashAlgorithm},
+        message::{MessageHash, MessageKind, SignedMessage},
+    },
+    device::{DeviceCertificate, DeviceRevocation},
+    invite::JoinRequest,
+    message::Signature,
+    multisig::CoSignedContent,
+};
+
+/// Role defines the permissions of a member of a group.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// May append messages, change the membership and the policy of the group.
+    Admin,
+    /// May append messages.
+    #[default]
+    Member,
+    /// May not append messages.
+    ReadOnly,
+}
+
+/// Member is a member of a group with its role.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Member {
+    pub identity: Identity,
+    pub role: Role,
+}
+
+/// MembershipChange is the data of the `AddMember` and `RemoveMember` records.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MembershipChange {
+    pub member: Identity,
+    /// The role of the added member.
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// PolicyUpdate is the data of the `Policy` record. It replaces the group policy if it is given,
+/// and assigns the roles to the members.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct PolicyUpdate {
+    #[serde(default)]
+    pub policy: Option<GroupPolicy>,
+    #[serde(default)]
+    pub roles: Vec<Member>,
+}
+
+/// Redaction is the data of the `Redaction` record.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Redaction {
+    /// The hash of the redacted message.
+    pub hash: MessageHash,
+}
+
+/// PinChange is the data of the `Pin` and `Unpin` records.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PinChange {
+    /// The hash of the pinned or unpinned message.
+    pub hash: MessageHash,
+}
+
+/// HashMigration is the data of the `HashMigration` record. It binds the head of the chain hashed with the
+/// previous hash algorithm to the new algorithm, with which the record and the messages after it are hashed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HashMigration {
+    /// The hash of the previous message with the previous algorithm.
+    pub head: MessageHash,
+    /// The previous algorithm.
+    pub from: HashAlgorithm,
+    /// The new algorithm.
+    pub to: HashAlgorithm,
+}
+
+/// Checkpoint is the data of the `Checkpoint` record. It summarizes the chain up to the previous message,
+/// so that the chain can be validated from the checkpoint after the older messages are pruned.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The hash of the previous message.
+    pub head: MessageHash,
+    /// The sequence number of the previous message.
+    pub seq: u64,
+    /// The state of the group after the previous message.
+    pub state: GroupState,
+    /// The merkle root over the hashes of the messages up to the previous message.
+    #[serde(default)]
+    pub merkle_root: Option<MessageHash>,
+}
+
+/// GroupState is the state of a group after applying the messages of its chain in order.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct GroupState {
+    /// The genesis record of the group.
+    pub genesis: Option<GroupGenesis<Identity>>,
+    /// The current policy of the group, initially the policy of the genesis record.
+    pub policy: GroupPolicy,
+    /// The current members of the group. The creator is the first member, as an admin.
+    pub members: Vec<Member>,
+    /// The capabilities presented by the delegates, at most one for each delegate.
+    #[serde(default)]
+    pub delegations: Vec<Capability>,
+    /// The certificates presented by the devices which are not revoked.
+    #[serde(default)]
+    pub devices: Vec<DeviceCertificate>,
+    /// The revoked devices, which may not present their certificates again.
+    #[serde(default)]
+    pub revoked_devices: Vec<Identity>,
+    /// Whether the group is sealed by a `Seal` record, after which no message may be appended.
+    #[serde(default)]
+    pub sealed: bool,
+    /// The hash algorithm with which the messages are hashed, as changed by the `HashMigration` records.
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// The identities holding the current group content key: the signers and recipients of the
+    /// `KeyDistribution` records since the latest `Rekey` record, and the signer and recipients of that
+    /// `Rekey` record.
+    #[serde(default)]
+    pub key_holders: Vec<Identity>,
+    /// The epoch of the current group content key, i.e. the number of `Rekey` records.
+    #[serde(default)]
+    pub key_epoch: u64,
+    /// Whether the ratchet is started by a `RatchetStart` record. It is restarted by the `Rekey` records.
+    #[serde(default)]
+    pub ratchet_started: bool,
+}
+
+impl GroupState {
+    /// Checks if the message is valid as the next message of the group. It checks the genesis record,
+    /// the group policy and the permission of the signer: read-only members may not append messages,
+    /// and only admins may change the membership or the policy. Identities which are not members may
+    /// join with an invitation from an admin. Only the creator may seal the group, and no message may
+    /// follow the seal. Only admins may rotate the group content key, while admins and the key holders may
+    /// distribute it and start the ratchet, and the epoch of the key only moves forward.
+    pub fn check(
+        &self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let kind = signed_msg.message.kind;
+        if self.genesis.is_none() {
+            if !signed_msg.is_genesis_of(group_id) {
+                return Err("invalid genesis record".to_string());
+            }
+            return Ok(());
+        }
+        if kind == MessageKind::Genesis {
+            return Err("unexpected genesis record".to_string());
+        }
+        if self.sealed {
+            return Err("group is sealed".to_string());
+        }
+
+        if self.policy.encrypted && kind.is_data() && !signed_msg.message.encrypted {
+            return Err("message must be encrypted".to_string());
+        }
+
+        if kind == MessageKind::Join {
+            return self.check_join(group_id, signed_msg);
+        }
+        if kind == MessageKind::Delegation {
+            return self.check_delegation(group_id, signed_msg);
+        }
+        if kind == MessageKind::DeviceCertificate {
+            return self.check_device_certificate(signed_msg);
+        }
+
+        let role = self.effective_role_of(&signed_msg.id, kind);
+        if self.policy.members_only && role.is_none() {
+            return Err("signer is not a member".to_string());
+        }
+        if role == Some(Role::ReadOnly) {
+            return Err("signer has no write permission".to_string());
+        }
+
+        match kind {
+            MessageKind::AddMember | MessageKind::RemoveMember => {
+                if role != Some(Role::Admin) {
+                    return Err("signer is not an admin".to_string());
+                }
+                serde_json::from_slice::<MembershipChange>(&signed_msg.message.data)
+                    .map_err(|_| "invalid membership record".to_string())?;
+            }
+            MessageKind::DeviceRevocation => {
+                let revocation =
+                    serde_json::from_slice::<DeviceRevocation>(&signed_msg.message.data)
+                        .map_err(|_| "invalid device revocation record".to_string())?;
+                if self.master_of(&revocation.device) != Some(&signed_msg.id) {
+                    return Err("signer is not the master of the device".to_string());
+                }
+            }
+            MessageKind::CoSigned => {
+                let content = serde_json::from_slice::<CoSignedContent>(&signed_msg.message.data)
+                    .map_err(|_| "invalid co-signed record".to_string())?;
+                if !ct_eq(&content.previous_hash, &signed_msg.message.previous_hash) {
+                    return Err("co-signed content is for another position".to_string());
+                }
+                if content
+                    .signer_set
+                    .signers
+                    .iter()
+                    .any(|signer| self.role_of(signer) != Some(Role::Admin))
+                {
+                    return Err("co-signer is not an admin".to_string());
+                }
+                if !content.verify() {
+                    return Err("co-signed content is below the threshold".to_string());
+                }
+            }
+            MessageKind::Checkpoint => {
+                if role != Some(Role::Admin) {
+                    return Err("signer is not an admin".to_string());
+                }
+                let checkpoint = serde_json::from_slice::<Checkpoint>(&signed_msg.message.data)
+                    .map_err(|_| "invalid checkpoint record".to_string())?;
+                if checkpoint.head != signed_msg.message.previous_hash
+                    || checkpoint.seq.checked_add(1) != Some(signed_msg.seq)
+                {
+                    return Err("checkpoint is for another position".to_string());
+                }
+                if serde_json::to_value(&checkpoint.state).ok() != serde_json::to_value(self).ok() {
+                    return Err("checkpoint state does not match".to_string());
+                }
+            }
+            MessageKind::HashMigration => {
+                if role != Some(Role::Admin) {
+                    return Err("signer is not an admin".to_string());
+                }
+                let migration = serde_json::from_slice::<HashMigration>(&signed_msg.message.data)
+                    .map_err(|_| "invalid hash migration record".to_string())?;
+                if !ct_eq(&migration.head, &signed_msg.message.previous_hash) {
+                    return Err("hash migration is for another position".to_string());
+                }
+                if migration.from != self.hash_algorithm {
+                    return Err("hash migration is from another algorithm".to_string());
+                }
+                if migration.to == migration.from {
+                    return Err("hash algorithm is unchanged".to_string());
+                }
+            }
+            MessageKind::Redaction => {
+                serde_json::from_slice::<Redaction>(&signed_msg.message.data)
+                    .map_err(|_| "invalid redaction record".to_string())?;
+            }
+            MessageKind::Pin | MessageKind::Unpin => {
+                serde_json::from_slice::<PinChange>(&signed_msg.message.data)
+                    .map_err(|_| "invalid pin record".to_string())?;
+            }
+            MessageKind::Policy => {
+                if role != Some(Role::Admin) {
+                    return Err("signer is not an admin".to_string());
+                }
+                serde_json::from_slice::<PolicyUpdate>(&signed_msg.message.data)
+                    .map_err(|_| "invalid policy record".to_string())?;
+            }
+            MessageKind::KeyDistribution | MessageKind::Rekey => {
+                let distribution = parse::<KeyDistribution>(signed_msg)
+                    .ok_or("invalid key distribution record")?;
+                self.check_key_authority(&signed_msg.id, role, kind)?;
+                let epoch = match kind {
+                    MessageKind::Rekey => self.key_epoch.checked_add(1),
+                    _ => Some(self.key_epoch),
+                };
+                if Some(distribution.epoch) != epoch {
+                    return Err("key distribution is for another epoch".to_string());
+                }
+            }
+            MessageKind::RatchetStart => {
+                self.check_key_authority(&signed_msg.id, role, kind)?;
+                if self.ratchet_started {
+                    return Err("ratchet is already started".to_string());
+                }
+            }
+            MessageKind::Seal
+                if self.genesis.as_ref().map(|genesis| &genesis.creator)
+                    != Some(&signed_msg.id) =>
+            {
+                return Err("signer is not the creator".to_string());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Checks if the signer of a `KeyDistribution`, `Rekey` or `RatchetStart` record with the role may
+    /// manage the group content key. Only admins may rotate the key, while the key holders may also
+    /// distribute it and start the ratchet.
+    fn check_key_authority(
+        &self,
+        signer: &Identity,
+        role: Option<Role>,
+        kind: MessageKind,
+    ) -> Result<(), String> {
+        if role == Some(Role::Admin) {
+            return Ok(());
+        }
+        if kind != MessageKind::Rekey && self.key_holders.contains(signer) {
+            return Ok(());
+        }
+        Err("signer may not manage the group key".to_string())
+    }
+
+    /// Checks the `Join` record: the invitation must be signed by a current admin for the signer of the
+    /// record, and the signer must join before the invitation expires.
+    fn check_join(
+        &self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let request = parse::<JoinRequest>(signed_msg).ok_or("invalid join record")?;
+        let invite = &request.invite;
+        if invite.body.group_id != group_id || invite.body.invitee != signed_msg.id {
+            return Err("invitation is not for the signer".to_string());
+        }
+        if self.role_of(&invite.body.inviter) != Some(Role::Admin) {
+            return Err("inviter is not an admin".to_string());
+        }
+        if !invite.verify() {
+            return Err("invalid invitation signature".to_string());
+        }
+        if invite.is_expired_at(request.timestamp) {
+            return Err("invitation is expired".to_string());
+        }
+        Ok(())
+    }
+
+    /// Checks the `Delegation` record: the capability must be signed by a current member for the
+    /// signer of the record.
+    fn check_delegation(
+        &self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let capability = parse::<Capability>(signed_msg).ok_or("invalid delegation record")?;
+        if capability.body.group_id != group_id || capability.body.delegate != signed_msg.id {
+            return Err("capability is not for the signer".to_string());
+        }
+        if !self.is_member(&capability.body.issuer) {
+            return Err("issuer is not a member".to_string());
+        }
+        if !capability.verify() {
+            return Err("invalid capability signature".to_string());
+        }
+        Ok(())
+    }
+
+    /// Checks the `DeviceCertificate` record: the certificate must be signed by the master for the signer
+    /// of the record, which is not revoked.
+    fn check_device_certificate(
+        &self,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let certificate =
+            parse::<DeviceCertificate>(signed_msg).ok_or("invalid device certificate record")?;
+        if certificate.body.device != signed_msg.id {
+            return Err("certificate is not for the signer".to_string());
+        }
+        if self.revoked_devices.contains(&signed_msg.id) {
+            return Err("device is revoked".to_string());
+        }
+        if !certificate.verify() {
+            return Err("invalid certificate signature".to_string());
+        }
+        Ok(())
+    }
+
+    /// Applies the message to the state. The message is assumed to be checked.
+    pub fn update(&mut self, signed_msg: &SignedMessage<Identity, Signature>) {
+        match signed_msg.message.kind {
+            MessageKind::Genesis => {
+                if let Some(genesis) = signed_msg.genesis() {
+                    self.policy = genesis.policy.clone();
+                    self.members = vec![Member {
+                        identity: genesis.creator.clone(),
+                        role: Role::Admin,
+                    }];
+                    self.genesis = Some(genesis);
+                }
+            }
+            MessageKind::AddMember => {
+                if let Some(change) = parse::<MembershipChange>(signed_msg) {
+                    if !self.is_member(&change.member) {
+                        self.members.push(Member {
+                            identity: change.member,
+                            role: change.role,
+                        });
+                    }
+                }
+            }
+            MessageKind::RemoveMember => {
+                if let Some(change) = parse::<MembershipChange>(signed_msg) {
+                    self.members
+                        .retain(|member| member.identity != change.member);
+                }
+            }
+            MessageKind::Join => {
+                if let Some(request) = parse::<JoinRequest>(signed_msg) {
+                    if !self.is_member(&signed_msg.id) {
+                        self.members.push(Member {
+                            identity: signed_msg.id.clone(),
+                            role: request.invite.body.role,
+                        });
+                    }
+                }
+            }
+            MessageKind::Delegation => {
+                if let Some(capability) = parse::<Capability>(signed_msg) {
+                    self.delegations
+                        .retain(|delegation| delegation.body.delegate != signed_msg.id);
+                    self.delegations.push(capability);
+                }
+            }
+            MessageKind::DeviceCertificate => {
+                if let Some(certificate) = parse::<DeviceCertificate>(signed_msg) {
+                    self.devices
+                        .retain(|device| device.body.device != signed_msg.id);
+                    self.devices.push(certificate);
+                }
+            }
+            MessageKind::DeviceRevocation => {
+                if let Some(revocation) = parse::<DeviceRevocation>(signed_msg) {
+                    self.devices
+                        .retain(|device| device.body.device != revocation.device);
+                    self.revoked_devices.push(revocation.device);
+                }
+            }
+            MessageKind::Policy => {
+                if let Some(update) = parse::<PolicyUpdate>(signed_msg) {
+                    if let Some(policy) = update.policy {
+                        self.policy = policy;
+                    }
+                    for assignment in update.roles {
+                        match self
+                            .members
+                            .iter_mut()
+                            .find(|member| member.identity == assignment.identity)
+                        {
+                            Some(member) => member.role = assignment.role,
+                            None => self.members.push(assignment),
+                        }
+                    }
+                }
+            }
+            MessageKind::HashMigration => {
+                if let Some(migration) = parse::<HashMigration>(signed_msg) {
+                    self.hash_algorithm = migration.to;
+                }
+            }
+            MessageKind::KeyDistribution | MessageKind::Rekey => {
+                if let Some(distribution) = parse::<KeyDistribution>(signed_msg) {
+                    if signed_msg.message.kind == MessageKind::Rekey {
+                        self.key_holders.clear();
+                        self.key_epoch = distribution.epoch;
+                    }
+                    let recipients = distribution.grants.into_iter().map(|grant| grant.recipient);
+                    for id in std::iter::once(signed_msg.id.clone()).chain(recipients) {
+                        if !self.key_holders.contains(&id) {
+                            self.key_holders.push(id);
+                        }
+                    }
+                }
+            }
+            MessageKind::RatchetStart => self.ratchet_started = true,
+            MessageKind::Seal => self.sealed = true,
+            _ => {}
+        }
+    }
+
+    /// Checks if the identity is a current member of the group.
+    pub fn is_member(&self, identity: &Identity) -> bool {
+        self.role_of(identity).is_some()
+    }
+
+    /// Returns the role of the identity if it is a current member of the group.
+    pub fn role_of(&self, identity: &Identity) -> Option<Role> {
+        self.members
+            .iter()
+            .find(|member| &member.identity == identity)
+            .map(|member| member.role)
+    }
+
+    /// Returns the capability presented by the delegate which grants the kind of messages.
+    pub fn delegation_for(&self, delegate: &Identity, kind: MessageKind) -> Option<&Capability> {
+        self.delegations
+            .iter()
+            .find(|delegation| &delegation.body.delegate == delegate && delegation.allows(kind))
+    }
+
+    /// Returns the master of the device if the device presented its certificate and is not revoked.
+    pub fn master_of(&self, device: &Identity) -> Option<&Identity> {
+        self.devices
+            .iter()
+            .find(|certificate| &certificate.body.device == device)
+            .map(|certificate| &certificate.body.master)
+    }
+
+    /// Returns the role with which the message of the kind signed by the identity is checked. It is
+    /// the role of the identity if it is a member, otherwise the role of the master of the device, or
+    /// the role of the issuer of its capability.
+    fn effective_role_of(&self, identity: &Identity, kind: MessageKind) -> Option<Role> {
+        self.role_of(identity)
+            .or_else(|| {
+                self.master_of(identity)
+                    .and_then(|master| self.role_of(master))
+            })
+            .or_else(|| {
+                self.delegation_for(identity, kind)
+                    .and_then(|delegation| self.role_of(&delegation.body.issuer))
+            })
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(
+    signed_msg: &SignedMessage<Identity, Signature>,
+) -> Option<T> {
+    serde_json::from_slice(&signed_msg.message.data).ok()
+}
@@ -0,0 +1,103 @@
+//! Provides the statistics of a group, which are maintained as counters when messages are saved so that
+//! they are read without scanning the chain, and the statistics of a chain, which are computed by walking
+//! it.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, SignedMessage},
+    message::Signature,
+};
+
+/// GroupInfo is the statistics of the stored messages of a group.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct GroupInfo {
+    /// The number of stored messages.
+    pub message_count: u64,
+    /// The hash of the latest message.
+    pub head: Option<MessageHash>,
+    /// The sequence number of the latest message.
+    pub seq: Option<u64>,
+    /// The timestamp of the first message signed with a timestamp.
+    pub first_timestamp: Option<u64>,
+    /// The timestamp of the latest message signed with a timestamp.
+    pub last_timestamp: Option<u64>,
+    /// The distinct signers of the messages, in the order they are first seen.
+    pub authors: Vec<Identity>,
+    /// The number of bytes of the stored messages, as serialized in the local storage.
+    pub storage_bytes: u64,
+}
+
+impl GroupInfo {
+    /// Counts the message with the given hash as the latest message, which takes the given number of
+    /// bytes in the local storage.
+    pub fn add(
+        &mut self,
+        hash: MessageHash,
+        signed_msg: &SignedMessage<Identity, Signature>,
+        bytes: u64,
+    ) {
+        self.message_count += 1;
+        self.storage_bytes += bytes;
+        self.head = Some(hash);
+        self.seq = Some(signed_msg.seq);
+        if let Some(timestamp) = signed_msg.message.timestamp {
+            self.first_timestamp.get_or_insert(timestamp);
+            self.last_timestamp = Some(timestamp);
+        }
+        if !self.authors.contains(&signed_msg.id) {
+            self.authors.push(signed_msg.id.clone());
+        }
+    }
+
+    /// Uncounts a message which took the given number of bytes, e.g. after it is pruned.
+    pub fn remove(&mut self, bytes: u64) {
+        self.message_count = self.message_count.saturating_sub(1);
+        self.storage_bytes = self.storage_bytes.saturating_sub(bytes);
+    }
+}
+
+/// GcReport is the result of the garbage collection of the stored messages which are not in any chain.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct GcReport {
+    /// The number of removed messages.
+    pub removed: u64,
+    /// The number of bytes of the removed items, as the lengths of their keys and values.
+    pub reclaimed_bytes: u64,
+}
+
+/// ChainStats is the statistics of the chain of a group for debugging the sync, e.g. in dashboards. They
+/// are computed by walking the chain from the head.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ChainStats {
+    pub group_id: String,
+    /// The number of messages in the chain.
+    pub message_count: u64,
+    /// The average number of bytes of the messages in the chain, as serialized in the local storage.
+    pub average_message_bytes: u64,
+    /// The number of messages in the chain signed with each signature scheme.
+    pub signature_schemes: BTreeMap<String, u64>,
+    /// The number of messages in the chain hashed with each hash algorithm, which differ if the chain is
+    /// migrated.
+    pub hash_algorithms: BTreeMap<String, u64>,
+    /// The head of the latest successful validation with `validateAll`, after which the messages are
+    /// validated again.
+    pub validated_head: Option<MessageHash>,
+    /// The sequence number of the validated head, or None if it is not in the chain.
+    pub validated_seq: Option<u64>,
+    /// The checkpoint records in the chain, ordered from the oldest.
+    pub checkpoints: Vec<ChainPosition>,
+    /// The number of messages which branch off the chain before its head, i.e. the stored or received
+    /// messages which are not in the chain but whose parents are.
+    pub forks: u64,
+}
+
+/// ChainPosition is a message in a chain.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChainPosition {
+    pub seq: u64,
+    pub hash: MessageHash,
+}
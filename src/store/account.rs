@@ -1,14 +1,18 @@
 //! Provides a struct `AccountStore` for storing account related data.
 
 use crate::{
-    account::{Identity, Secret},
-    core::account::GenerateKeys,
+    account::{AccountMetadata, AccountUsage, Identity, Secret},
+    core::{account::GenerateKeys, group::unix_timestamp},
+    device::{DeviceCertificate, DeviceCertificateBody},
 };
 
 use super::SerdeLocalStore;
 
 const KEY_ACCOUNT_CURRENT_IDX: &str = "accidx";
 const KEY_ACCOUNT_LIST: &str = "accs";
+const KEY_DEVICE_LIST: &str = "devices";
+const KEY_ACCOUNT_METADATA: &str = "accmeta";
+const KEY_ACCOUNT_USAGE: &str = "accusage";
 
 /// AccountStore is a store for account related data. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
 #[derive(Default)]
@@ -16,43 +20,82 @@ pub(crate) struct AccountStore {}
 
 impl AccountStore {
     /// Initializes an account and returns the public and secret keys. If the account already exists, it returns the existing keys.
-    pub(crate) fn initialize<G: GenerateKeys<Secret, Identity>>(&mut self) -> (Identity, Secret) {
-        self.current_account()
-            .map(|(id, secret)| (id, secret))
-            .unwrap_or_else(|| self.new_account::<G>())
+    pub(crate) fn initialize<G: GenerateKeys<Secret, Identity>>(
+        &mut self,
+    ) -> Result<(Identity, Secret), String> {
+        match self.current_account() {
+            Some(account) => Ok(account),
+            None => self.new_account::<G>(),
+        }
     }
 
     /// Creates a new account and returns the public and secret keys.
-    pub(crate) fn new_account<G: GenerateKeys<Secret, Identity>>(&mut self) -> (Identity, Secret) {
+    pub(crate) fn new_account<G: GenerateKeys<Secret, Identity>>(
+        &mut self,
+    ) -> Result<(Identity, Secret), String> {
         let (private_key, public_key) = G::generate_keys();
         let mut accounts = self.accounts();
         let idx = accounts.len();
-        self.set_current_index(idx);
         accounts.push((public_key.clone(), private_key.clone()));
-        self.set_accounts(accounts);
-        (public_key, private_key)
+        // the account is added before it becomes current, so that a failed write leaves no dangling index
+        self.set_accounts(accounts)?;
+        self.set_current_index(idx)?;
+
+        let now = unix_timestamp();
+        self.update_metadata(&public_key, |metadata| {
+            metadata.created_at = Some(now);
+            metadata.last_used = Some(now);
+        })?;
+        self.set_usage(&public_key, AccountUsage::default())?;
+        Ok((public_key, private_key))
+    }
+
+    /// Adds the account with the given keys, if it does not exist, and sets it as the current account.
+    pub(crate) fn import_account(
+        &mut self,
+        identity: Identity,
+        secret: Secret,
+    ) -> Result<(), String> {
+        let mut accounts = self.accounts();
+        if !accounts.iter().any(|(id, _)| id == &identity) {
+            accounts.push((identity.clone(), secret));
+            self.set_accounts(accounts)?;
+            let now = unix_timestamp();
+            self.update_metadata(&identity, |metadata| metadata.created_at = Some(now))?;
+        }
+        self.set_current_account(identity)
     }
 
     /// Deletes an account with the given identity. If the account is the current account, it sets the current account to the previous account.
-    pub(crate) fn delete_account(&mut self, identity: &Identity) {
+    pub(crate) fn delete_account(&mut self, identity: &Identity) -> Result<(), String> {
         let accounts = self.accounts();
         let target_idx = accounts
             .iter()
             .enumerate()
             .find_map(|(idx, (id, _))| (id == identity).then_some(idx));
 
+        // the usage is removed even without the account, e.g. if it was rebuilt for the identity
+        let mut all_usage = self.all_usage();
+        all_usage.retain(|(id, _)| id != identity);
+        self.set(KEY_ACCOUNT_USAGE, all_usage)?;
+
         if let Some(idx) = target_idx {
             let mut accounts = self.accounts();
             accounts.remove(idx);
-            self.set_accounts(accounts);
+            self.set_accounts(accounts)?;
+
+            let mut all_metadata = self.all_metadata();
+            all_metadata.retain(|(id, _)| id != identity);
+            self.set(KEY_ACCOUNT_METADATA, all_metadata)?;
 
             let current_idx = self.current_index();
             if current_idx == idx {
-                self.set_current_index(current_idx.saturating_sub(1));
+                self.set_current_index(current_idx.saturating_sub(1))?;
             } else if current_idx > idx {
-                self.set_current_index(current_idx - 1);
+                self.set_current_index(current_idx - 1)?;
             }
         }
+        Ok(())
     }
 
     /// Returns the current account.
@@ -62,8 +105,13 @@ impl AccountStore {
         accounts.get(idx).cloned()
     }
 
-    /// Sets the current account with the given identity.
-    pub(crate) fn set_current_account(&mut self, identity: Identity) {
+    /// Returns the account with the given identity.
+    pub(crate) fn account(&self, identity: &Identity) -> Option<(Identity, Secret)> {
+        self.accounts().into_iter().find(|(id, _)| id == identity)
+    }
+
+    /// Sets the current account with the given identity, and records the time as its last use.
+    pub(crate) fn set_current_account(&mut self, identity: Identity) -> Result<(), String> {
         let target_idx = self
             .accounts()
             .into_iter()
@@ -71,24 +119,156 @@ impl AccountStore {
             .find_map(|(idx, (id, _))| (id == identity).then_some(idx));
 
         if let Some(idx) = target_idx {
-            self.set_current_index(idx);
+            self.set_current_index(idx)?;
+            let now = unix_timestamp();
+            self.update_metadata(&identity, |metadata| metadata.last_used = Some(now))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the metadata of the account with the given identity.
+    pub(crate) fn metadata(&self, identity: &Identity) -> AccountMetadata {
+        self.all_metadata()
+            .into_iter()
+            .find(|(id, _)| id == identity)
+            .map(|(_, metadata)| metadata)
+            .unwrap_or_default()
+    }
+
+    /// Updates the metadata of the account with the given identity.
+    pub(crate) fn update_metadata(
+        &mut self,
+        identity: &Identity,
+        update: impl FnOnce(&mut AccountMetadata),
+    ) -> Result<(), String> {
+        let mut all_metadata = self.all_metadata();
+        let idx = match all_metadata.iter().position(|(id, _)| id == identity) {
+            Some(idx) => idx,
+            None => {
+                all_metadata.push((identity.clone(), AccountMetadata::default()));
+                all_metadata.len() - 1
+            }
+        };
+        update(&mut all_metadata[idx].1);
+        self.set(KEY_ACCOUNT_METADATA, all_metadata)
+    }
+
+    fn all_metadata(&self) -> Vec<(Identity, AccountMetadata)> {
+        self.get(KEY_ACCOUNT_METADATA).unwrap_or_default()
+    }
+
+    /// Returns the usage of the account with the given identity, or None if it is not maintained, e.g. the
+    /// account was created before the usage was kept, or was imported.
+    pub(crate) fn usage(&self, identity: &Identity) -> Option<AccountUsage> {
+        self.all_usage()
+            .into_iter()
+            .find(|(id, _)| id == identity)
+            .map(|(_, usage)| usage)
+    }
+
+    /// Sets the usage of the account with the given identity, which is then maintained with [add_usage].
+    ///
+    /// [add_usage]: AccountStore::add_usage
+    pub(crate) fn set_usage(
+        &mut self,
+        identity: &Identity,
+        usage: AccountUsage,
+    ) -> Result<(), String> {
+        let mut all_usage = self.all_usage();
+        all_usage.retain(|(id, _)| id != identity);
+        all_usage.push((identity.clone(), usage));
+        self.set(KEY_ACCOUNT_USAGE, all_usage)
+    }
+
+    /// Counts a message signed by the identity in the group, if the usage of the identity is maintained,
+    /// i.e. it is a local account. The usage is read without the secrets of the accounts.
+    pub(crate) fn add_usage(
+        &mut self,
+        identity: &Identity,
+        group_id: &str,
+        timestamp: Option<u64>,
+    ) -> Result<(), String> {
+        let mut all_usage = self.all_usage();
+        if let Some((_, usage)) = all_usage.iter_mut().find(|(id, _)| id == identity) {
+            usage.add(group_id, timestamp);
+            self.set(KEY_ACCOUNT_USAGE, all_usage)?;
         }
+        Ok(())
+    }
+
+    fn all_usage(&self) -> Vec<(Identity, AccountUsage)> {
+        self.get(KEY_ACCOUNT_USAGE).unwrap_or_default()
+    }
+
+    /// Creates the signing keys of a new device certified by the current account. It returns the
+    /// secret and the certificate of the device, or None if there is no current account.
+    pub(crate) fn add_device<G: GenerateKeys<Secret, Identity>>(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<(Secret, DeviceCertificate)>, String> {
+        let Some((master, master_secret)) = self.current_account() else {
+            return Ok(None);
+        };
+        let (secret, device) = G::generate_keys();
+        let certificate = DeviceCertificate::new(
+            DeviceCertificateBody {
+                master,
+                device,
+                name: name.to_string(),
+            },
+            &master_secret,
+        );
+        let mut devices = self.all_devices();
+        devices.push((certificate.clone(), secret.clone()));
+        self.set_secret(KEY_DEVICE_LIST, devices)?;
+        Ok(Some((secret, certificate)))
+    }
+
+    /// Returns the certificates of the devices of the master.
+    pub(crate) fn devices(&self, master: &Identity) -> Vec<DeviceCertificate> {
+        self.all_devices()
+            .into_iter()
+            .filter(|(certificate, _)| &certificate.body.master == master)
+            .map(|(certificate, _)| certificate)
+            .collect()
+    }
+
+    /// Revokes the device by removing its keys. It returns the certificate of the revoked device, or None
+    /// if the device is not found.
+    pub(crate) fn revoke_device(
+        &mut self,
+        device: &Identity,
+    ) -> Result<Option<DeviceCertificate>, String> {
+        let mut devices = self.all_devices();
+        let Some(idx) = devices
+            .iter()
+            .position(|(certificate, _)| &certificate.body.device == device)
+        else {
+            return Ok(None);
+        };
+        let (certificate, _) = devices.remove(idx);
+        self.set_secret(KEY_DEVICE_LIST, devices)?;
+        Ok(Some(certificate))
+    }
+
+    fn all_devices(&self) -> Vec<(DeviceCertificate, Secret)> {
+        self.get_secret(KEY_DEVICE_LIST).unwrap_or_default()
     }
 
     pub(crate) fn current_index(&self) -> usize {
         self.get(KEY_ACCOUNT_CURRENT_IDX).unwrap_or_default()
     }
 
-    pub(crate) fn set_current_index(&mut self, value: usize) {
+    pub(crate) fn set_current_index(&mut self, value: usize) -> Result<(), String> {
         self.set(KEY_ACCOUNT_CURRENT_IDX, value)
     }
 
     pub(crate) fn accounts(&self) -> Vec<(Identity, Secret)> {
-        self.get(KEY_ACCOUNT_LIST).unwrap_or_default()
+        self.get_secret(KEY_ACCOUNT_LIST).unwrap_or_default()
     }
 
-    pub(crate) fn set_accounts(&mut self, value: Vec<(Identity, Secret)>) {
-        self.set(KEY_ACCOUNT_LIST, value)
+    pub(crate) fn set_accounts(&mut self, value: Vec<(Identity, Secret)>) -> Result<(), String> {
+        self.set_secret(KEY_ACCOUNT_LIST, value)
     }
 }
 
@@ -0,0 +1,37 @@
+//! Provides a struct `AcknowledgmentStore` for storing the acknowledgments of messages.
+
+use crate::{acknowledgment::Acknowledgment, core::message::MessageHash};
+
+use super::SerdeLocalStore;
+
+const KEY_ACKNOWLEDGMENTS: &str = "acks";
+
+/// AcknowledgmentStore is a store for acknowledgments. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct AcknowledgmentStore {}
+
+impl AcknowledgmentStore {
+    /// Returns the acknowledgments of the message with the given hash.
+    pub(crate) fn acknowledgments(
+        &self,
+        group_id: &str,
+        hash: &MessageHash,
+    ) -> Vec<Acknowledgment> {
+        self.get::<Vec<Acknowledgment>>(format!("{KEY_ACKNOWLEDGMENTS}_{group_id}").as_str())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|ack| &ack.body.hash == hash)
+            .collect()
+    }
+
+    /// Adds the acknowledgment. It replaces the acknowledgment with the same content.
+    pub(crate) fn add_acknowledgment(&mut self, ack: Acknowledgment) -> Result<(), String> {
+        let key = format!("{KEY_ACKNOWLEDGMENTS}_{}", ack.body.group_id);
+        let mut acks: Vec<Acknowledgment> = self.get(key.as_str()).unwrap_or_default();
+        acks.retain(|a| a.body != ack.body);
+        acks.push(ack);
+        self.set(key.as_str(), acks)
+    }
+}
+
+impl SerdeLocalStore for AcknowledgmentStore {}
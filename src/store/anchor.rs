@@ -0,0 +1,29 @@
+//! Provides a struct `AnchorStore` for storing the proofs of the chain heads anchored to timestamping services.
+
+use crate::anchor::AnchorProof;
+
+use super::SerdeLocalStore;
+
+const KEY_ANCHORS: &str = "anchors";
+
+/// AnchorStore is a store for anchor proofs. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct AnchorStore {}
+
+impl AnchorStore {
+    /// Returns the anchor proofs of the group, from the oldest.
+    pub(crate) fn anchors(&self, group_id: &str) -> Vec<AnchorProof> {
+        self.get(format!("{KEY_ANCHORS}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+
+    /// Adds the anchor proof to the group.
+    pub(crate) fn add_anchor(&mut self, group_id: &str, proof: AnchorProof) -> Result<(), String> {
+        let key = format!("{KEY_ANCHORS}_{group_id}");
+        let mut anchors: Vec<AnchorProof> = self.get(key.as_str()).unwrap_or_default();
+        anchors.push(proof);
+        self.set(key.as_str(), anchors)
+    }
+}
+
+impl SerdeLocalStore for AnchorStore {}
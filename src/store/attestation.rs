@@ -0,0 +1,30 @@
+//! Provides a struct `AttestationStore` for storing the attestations of notaries over chain heads.
+
+use crate::notary::Attestation;
+
+use super::SerdeLocalStore;
+
+const KEY_ATTESTATIONS: &str = "attestations";
+
+/// AttestationStore is a store for attestations. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct AttestationStore {}
+
+impl AttestationStore {
+    /// Returns the attestations of the group, from the oldest.
+    pub(crate) fn attestations(&self, group_id: &str) -> Vec<Attestation> {
+        self.get(format!("{KEY_ATTESTATIONS}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+
+    /// Adds the attestation. It replaces the attestation with the same content.
+    pub(crate) fn add_attestation(&mut self, attestation: Attestation) -> Result<(), String> {
+        let key = format!("{KEY_ATTESTATIONS}_{}", attestation.body.request.group_id);
+        let mut attestations: Vec<Attestation> = self.get(key.as_str()).unwrap_or_default();
+        attestations.retain(|a| a.body != attestation.body);
+        attestations.push(attestation);
+        self.set(key.as_str(), attestations)
+    }
+}
+
+impl SerdeLocalStore for AttestationStore {}
@@ -0,0 +1,48 @@
+//! Provides a struct `AuthChallengeStore` for storing the challenges which are not answered yet.
+
+use crate::auth::AuthChallenge;
+
+use super::SerdeLocalStore;
+
+const KEY_AUTH_CHALLENGES: &str = "authchallenges";
+
+/// AuthChallengeStore is a store for the challenges created by this verifier which are not answered yet.
+/// It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct AuthChallengeStore {}
+
+impl AuthChallengeStore {
+    /// Adds the challenge, and removes the challenges expired at the given unix timestamp.
+    pub(crate) fn add_challenge(
+        &mut self,
+        challenge: AuthChallenge,
+        timestamp: u64,
+    ) -> Result<(), String> {
+        let mut challenges = self.challenges();
+        challenges.retain(|challenge| !challenge.is_expired_at(timestamp));
+        challenges.push(challenge);
+        self.set(KEY_AUTH_CHALLENGES, challenges)
+    }
+
+    /// Removes the challenge, so that it is answered once. It returns true if the challenge was created by
+    /// this verifier and is not expired at the given unix timestamp.
+    pub(crate) fn take_challenge(
+        &mut self,
+        challenge: &AuthChallenge,
+        timestamp: u64,
+    ) -> Result<bool, String> {
+        let mut challenges = self.challenges();
+        let Some(idx) = challenges.iter().position(|c| c == challenge) else {
+            return Ok(false);
+        };
+        challenges.remove(idx);
+        self.set(KEY_AUTH_CHALLENGES, challenges)?;
+        Ok(!challenge.is_expired_at(timestamp))
+    }
+
+    fn challenges(&self) -> Vec<AuthChallenge> {
+        self.get(KEY_AUTH_CHALLENGES).unwrap_or_default()
+    }
+}
+
+impl SerdeLocalStore for AuthChallengeStore {}
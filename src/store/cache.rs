@@ -0,0 +1,59 @@
+//! Provides the in-memory cache of the values parsed from local storage, so that repeated reads of the
+//! same item, e.g. the accounts or the latest message of a group, do not parse its JSON again.
+//!
+//! The cache keeps the most recently used items up to [CAPACITY]. An item is invalidated when it is
+//! written or removed through the stores, and all the items are invalidated when the storage is
+//! cleared, replaced or migrated. In browsers, the writes of other tabs invalidate the items through
+//! `storage` events. The writes which bypass this library, e.g. by the same page or a custom storage
+//! object, are not seen, and require a call of `invalidateCache`.
+//!
+//! The items holding secrets are not cached (see [get_secret](crate::store::SerdeLocalStore::get_secret)).
+
+use std::{any::Any, cell::RefCell, rc::Rc};
+
+/// The maximum number of cached items.
+const CAPACITY: usize = 256;
+
+thread_local! {
+    /// The cached items with their keys in local storage, ordered from the least recently used.
+    static ITEMS: RefCell<Vec<(String, Rc<dyn Any>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns the cached value of the key, or None if it is not cached as the type. The cached value is
+/// None if the item does not exist or cannot be parsed as the type.
+pub(super) fn get<T: Clone + 'static>(key: &str) -> Option<Option<T>> {
+    ITEMS.with(|cell| {
+        let mut items = cell.borrow_mut();
+        let idx = items.iter().position(|(k, _)| k == key)?;
+        let item = items.remove(idx);
+        let value = item.1.downcast_ref::<Option<T>>().cloned();
+        items.push(item);
+        value
+    })
+}
+
+/// Caches the value of the key, evicting the least recently used item if the cache is full.
+pub(super) fn insert<T: 'static>(key: &str, value: Option<T>) {
+    ITEMS.with(|cell| {
+        let mut items = cell.borrow_mut();
+        items.retain(|(k, _)| k != key);
+        if items.len() >= CAPACITY {
+            items.remove(0);
+        }
+        items.push((key.to_string(), Rc::new(value)));
+    })
+}
+
+/// Invalidates the cached value of the key. The key of a chunk invalidates the value split into it.
+pub(crate) fn invalidate(key: &str) {
+    let key = key
+        .rsplit_once("#chunk")
+        .filter(|(_, idx)| !idx.is_empty() && idx.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(key, |(key, _)| key);
+    ITEMS.with(|cell| cell.borrow_mut().retain(|(k, _)| k != key))
+}
+
+/// Invalidates all the cached values.
+pub(crate) fn clear() {
+    ITEMS.with(|cell| cell.borrow_mut().clear())
+}
@@ -0,0 +1,42 @@
+//! Provides a struct `ContactStore` for storing the contacts.
+
+use crate::{account::Identity, contact::Contact};
+
+use super::SerdeLocalStore;
+
+const KEY_CONTACTS: &str = "contacts";
+
+/// ContactStore is a store for contacts. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct ContactStore {}
+
+impl ContactStore {
+    /// Returns all the contacts.
+    pub(crate) fn contacts(&self) -> Vec<Contact> {
+        self.get(KEY_CONTACTS).unwrap_or_default()
+    }
+
+    /// Returns the contact of the given identity.
+    pub(crate) fn contact(&self, identity: &Identity) -> Option<Contact> {
+        self.contacts()
+            .into_iter()
+            .find(|contact| &contact.identity == identity)
+    }
+
+    /// Adds the contact. It replaces the contact of the same identity.
+    pub(crate) fn set_contact(&mut self, contact: Contact) -> Result<(), String> {
+        let mut contacts = self.contacts();
+        contacts.retain(|c| c.identity != contact.identity);
+        contacts.push(contact);
+        self.set(KEY_CONTACTS, contacts)
+    }
+
+    /// Removes the contact of the given identity.
+    pub(crate) fn remove_contact(&mut self, identity: &Identity) -> Result<(), String> {
+        let mut contacts = self.contacts();
+        contacts.retain(|c| &c.identity != identity);
+        self.set(KEY_CONTACTS, contacts)
+    }
+}
+
+impl SerdeLocalStore for ContactStore {}
@@ -0,0 +1,50 @@
+//! Provides a struct `DraftStore` for storing the drafts of groups.
+
+use crate::draft::Draft;
+
+use super::SerdeLocalStore;
+
+const KEY_DRAFTS: &str = "drafts";
+
+/// DraftStore is a store for the unsigned drafts of groups. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct DraftStore {}
+
+impl DraftStore {
+    /// Returns the drafts of the given group, in the order of creation.
+    pub(crate) fn drafts(&self, group_id: &str) -> Vec<Draft> {
+        self.get(format!("{KEY_DRAFTS}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+
+    /// Returns the draft with the given id in the given group.
+    pub(crate) fn draft(&self, group_id: &str, draft_id: &str) -> Option<Draft> {
+        self.drafts(group_id)
+            .into_iter()
+            .find(|draft| draft.id == draft_id)
+    }
+
+    /// Saves the draft in the given group. It replaces the draft of the same id.
+    pub(crate) fn set_draft(&mut self, group_id: &str, draft: Draft) -> Result<(), String> {
+        let mut drafts = self.drafts(group_id);
+        match drafts.iter_mut().find(|d| d.id == draft.id) {
+            Some(d) => *d = draft,
+            None => drafts.push(draft),
+        }
+        self.set(format!("{KEY_DRAFTS}_{group_id}").as_str(), drafts)
+    }
+
+    /// Removes the draft with the given id in the given group. It returns true if the draft existed.
+    pub(crate) fn remove_draft(&mut self, group_id: &str, draft_id: &str) -> Result<bool, String> {
+        let mut drafts = self.drafts(group_id);
+        let len = drafts.len();
+        drafts.retain(|draft| draft.id != draft_id);
+        if drafts.len() == len {
+            return Ok(false);
+        }
+        self.set(format!("{KEY_DRAFTS}_{group_id}").as_str(), drafts)?;
+        Ok(true)
+    }
+}
+
+impl SerdeLocalStore for DraftStore {}
@@ -0,0 +1,104 @@
+//! Provides the injection of faults into the in-memory storage of the native builds, to test how the
+//! library and the apps behave when the local storage of browsers fails: writes beyond the quota, writes
+//! which fail intermittently, and items changed by other parties, e.g. other tabs, while the library runs,
+//! whether or not the library has seen the changes.
+//!
+//! A failed write is reported to the [diagnostics](crate::diagnostics) and returns an error, as the writes
+//! of the library fail when the Web Storage throws, e.g. `QuotaExceededError`. In the web builds, the same faults
+//! are injected with the storage object of `js/fault-storage.js` (see `setStorage`).
+
+use std::cell::RefCell;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{cache, memory};
+
+/// OnWrite is the function called with the key after each write or removal of the library.
+pub type OnWrite = Box<dyn FnMut(&str)>;
+
+thread_local! {
+    static FAULTS: RefCell<Option<(Faults, StdRng)>> = const { RefCell::new(None) };
+    static ON_WRITE: RefCell<Option<OnWrite>> = const { RefCell::new(None) };
+}
+
+/// Faults are the failures injected into the writes of the storage.
+#[derive(Clone, Debug, Default)]
+pub struct Faults {
+    /// The maximum total length of the keys and the values of the items. A write beyond it fails as the
+    /// quota is exceeded.
+    pub quota: Option<usize>,
+    /// The probability, from 0 to 1, that a write or a removal fails intermittently.
+    pub failure_rate: f64,
+    /// The seed of the intermittent failures, so that they are reproducible.
+    pub seed: u64,
+}
+
+/// Injects the faults into the writes of the storage, or stops injecting them if it is None.
+pub fn set_faults(faults: Option<Faults>) {
+    FAULTS.with(|cell| {
+        *cell.borrow_mut() = faults.map(|faults| {
+            let rng = StdRng::seed_from_u64(faults.seed);
+            (faults, rng)
+        })
+    });
+}
+
+/// Sets the function called with the key after each write or removal of the library, e.g. to change the
+/// items with [external_set_item] as if other parties wrote them concurrently.
+pub fn set_on_write(on_write: Option<OnWrite>) {
+    ON_WRITE.with(|cell| *cell.borrow_mut() = on_write);
+}
+
+/// Sets the item as other parties do, e.g. other tabs. The cached value of the item is invalidated, as
+/// by the `storage` events of browsers. The faults are not injected.
+pub fn external_set_item(key: &str, value: &str) {
+    memory::insert_item(key, value);
+    cache::invalidate(key);
+}
+
+/// Sets the item as another tab does before its `storage` event is delivered, i.e. the cached value of the
+/// item is not invalidated, e.g. to test the writes over a stale head. The faults are not injected.
+pub fn external_set_item_unseen(key: &str, value: &str) {
+    memory::insert_item(key, value);
+}
+
+/// Removes the item as other parties do (see [external_set_item]).
+pub fn external_remove_item(key: &str) {
+    memory::delete_item(key);
+    cache::invalidate(key);
+}
+
+/// Returns the reason of the failure if a write of the value of the length to the key fails.
+pub(super) fn check_write(key: &str, value_len: Option<usize>) -> Result<(), String> {
+    FAULTS.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let Some((faults, rng)) = cell.as_mut() else {
+            return Ok(());
+        };
+        if faults.failure_rate > 0.0 && rng.gen_bool(faults.failure_rate.min(1.0)) {
+            return Err("intermittent failure".to_string());
+        }
+        if let (Some(quota), Some(value_len)) = (faults.quota, value_len) {
+            let used = memory::used_bytes() - memory::item_len(key).unwrap_or(0);
+            if used + key.len() + value_len > quota {
+                return Err("quota exceeded".to_string());
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Calls the function set with [set_on_write]. The function is taken while it runs, so that its own
+/// writes do not call it again.
+pub(super) fn after_write(key: &str) {
+    let Some(mut on_write) = ON_WRITE.with(|cell| cell.borrow_mut().take()) else {
+        return;
+    };
+    on_write(key);
+    ON_WRITE.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            *cell = Some(on_write);
+        }
+    });
+}
@@ -1,10 +1,14 @@
 //! Provides a struct `GroupStore` for storing group related data.
 
-use crate::core::group::Group;
+use crate::{
+    core::group::{Group, GroupMetadata},
+    state::GroupState,
+};
 
 use super::SerdeLocalStore;
 
 const KEY_GROUPS: &str = "groups";
+const KEY_GROUP_STATE: &str = "grpstate";
 
 /// GroupStore is a store for group related data. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
 #[derive(Default)]
@@ -17,12 +21,55 @@ impl GroupStore {
     }
 
     /// Adds a group to the list of groups.
-    pub(crate) fn add_group(&mut self, group: Group) {
+    pub(crate) fn add_group(&mut self, group: Group) -> Result<(), String> {
         let mut groups = self.groups();
         if !groups.contains(&group) {
             groups.push(group);
-            self.set(KEY_GROUPS, groups);
+            self.set(KEY_GROUPS, groups)?;
         }
+        Ok(())
+    }
+
+    /// Updates the metadata of the group with the given ID. It returns the updated group, or None if the
+    /// group is not found.
+    pub(crate) fn update_metadata(
+        &mut self,
+        group_id: &str,
+        update: impl FnOnce(&mut GroupMetadata),
+    ) -> Result<Option<Group>, String> {
+        let mut groups = self.groups();
+        let Some(group) = groups.iter_mut().find(|group| group.id == group_id) else {
+            return Ok(None);
+        };
+        update(&mut group.metadata);
+        let group = group.clone();
+        self.set(KEY_GROUPS, groups)?;
+        Ok(Some(group))
+    }
+
+    /// Sets the last activity of the group with the given ID to the given unix timestamp.
+    pub(crate) fn set_last_activity(
+        &mut self,
+        group_id: &str,
+        timestamp: u64,
+    ) -> Result<(), String> {
+        let mut groups = self.groups();
+        if let Some(group) = groups.iter_mut().find(|group| group.id == group_id) {
+            group.last_activity = Some(timestamp);
+            self.set(KEY_GROUPS, groups)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the state of the given group after its latest message.
+    pub(crate) fn state(&self, group_id: &str) -> GroupState {
+        self.get(format!("{KEY_GROUP_STATE}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+
+    /// Sets the state of the given group.
+    pub(crate) fn set_state(&mut self, group_id: &str, state: &GroupState) -> Result<(), String> {
+        self.set(format!("{KEY_GROUP_STATE}_{group_id}").as_str(), state)
     }
 }
 
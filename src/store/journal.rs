@@ -0,0 +1,171 @@
+//! Provides the write-ahead journal of local storage, which protects the chains against the writes which
+//! are interrupted, e.g. when the browser kills the tab in the middle of writing a message.
+//!
+//! The items written in [atomically] are kept in memory until it returns. They are then recorded in the
+//! journal item before they are applied to local storage, and the journal is removed once they are all
+//! applied. If the journal is found on the first access of the stores, or before the next atomic write,
+//! the interrupted writes are rolled forward by applying the recorded items again. A journal which is not
+//! completely written is discarded, i.e. the writes are rolled back, as none of its items is applied yet.
+//!
+//! Writing an item of local storage is atomic, and so is writing a chunked journal, whose marker is
+//! written after its chunks (see [set_to_localstorage]).
+
+use std::cell::RefCell;
+
+use crate::diagnostics::{self, Category};
+
+use super::{
+    get_from_localstorage, get_item, remove_from_localstorage, remove_item, set_to_localstorage,
+    storage_keys,
+};
+
+/// The item of the journal. It is shared by all namespaces, as the journaled keys are namespaced.
+const KEY_JOURNAL: &str = "\u{2}journal";
+
+/// JournalEntry is an item to write, with its key in local storage, or to remove if the value is None.
+type JournalEntry = (String, Option<String>);
+
+thread_local! {
+    /// The items written in the current atomic write, or None if there is none.
+    static PENDING: RefCell<Option<Vec<JournalEntry>>> = const { RefCell::new(None) };
+}
+
+/// Runs the function as an atomic write: the items it writes or removes are applied to local storage
+/// together after it returns, through the journal. The items are read back from memory until then. If the
+/// function panics, none of the items is written. An atomic write inside another joins the outer one.
+///
+/// It fails if the items cannot be written, e.g. as the quota is exceeded. If the journal cannot be
+/// written, none of the items is written. If an item cannot be applied after it, the journal is kept, so
+/// that the items are written when it is recovered.
+pub(crate) fn atomically<R>(f: impl FnOnce() -> R) -> Result<R, String> {
+    if PENDING.with(|cell| cell.borrow().is_some()) {
+        return Ok(f());
+    }
+    if get_item(KEY_JOURNAL).is_some() {
+        recover()?;
+    }
+
+    PENDING.with(|cell| *cell.borrow_mut() = Some(vec![]));
+    let guard = Guard;
+    let result = f();
+    let entries = PENDING
+        .with(|cell| cell.borrow_mut().take())
+        .unwrap_or_default();
+    std::mem::forget(guard);
+    commit(&entries)?;
+    Ok(result)
+}
+
+/// Records the write of the item in the current atomic write. It returns false if there is none, so that
+/// the item is written directly.
+pub(super) fn record(key: &str, value: Option<&str>) -> bool {
+    PENDING.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let Some(entries) = cell.as_mut() else {
+            return false;
+        };
+        entries.retain(|(k, _)| k != key);
+        entries.push((key.to_string(), value.map(str::to_string)));
+        true
+    })
+}
+
+/// Returns the value of the item written in the current atomic write, which is None if the item is
+/// removed, or None if the item is not written in it.
+pub(super) fn pending(key: &str) -> Option<Option<String>> {
+    PENDING.with(|cell| {
+        cell.borrow()
+            .as_ref()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.clone())
+    })
+}
+
+/// Returns the keys of the items written and removed in the current atomic write.
+pub(super) fn pending_keys() -> (Vec<String>, Vec<String>) {
+    PENDING.with(|cell| {
+        let cell = cell.borrow();
+        let entries = cell.as_deref().unwrap_or_default();
+        let keys = |written: bool| {
+            entries
+                .iter()
+                .filter(|(_, value)| value.is_some() == written)
+                .map(|(key, _)| key.clone())
+                .collect()
+        };
+        (keys(true), keys(false))
+    })
+}
+
+/// Rolls the interrupted writes forward if the journal is complete, or back otherwise, e.g. on the first
+/// access of the stores. The recovered writes are reported to the diagnostics. It fails if the items cannot
+/// be written, in which case the journal is recovered again later.
+pub(crate) fn recover() -> Result<(), String> {
+    match get_from_localstorage(KEY_JOURNAL) {
+        Some(journal) => match serde_json::from_str::<Vec<JournalEntry>>(&journal) {
+            Ok(entries) => {
+                diagnostics::warn(
+                    Category::Storage,
+                    format!(
+                        "rolled forward an interrupted write of {} items",
+                        entries.len()
+                    ),
+                );
+                commit(&entries)
+            }
+            Err(_) => {
+                diagnostics::warn(Category::Storage, "rolled back an interrupted write");
+                remove_from_localstorage(KEY_JOURNAL)
+            }
+        },
+        None => {
+            // the chunks of a journal whose marker is not written, or of a journal which is incompletely
+            // removed
+            let prefix = format!("{KEY_JOURNAL}#chunk");
+            let chunks: Vec<String> = storage_keys()
+                .into_iter()
+                .filter(|key| key.starts_with(&prefix))
+                .collect();
+            if !chunks.is_empty() {
+                diagnostics::warn(Category::Storage, "rolled back an interrupted write");
+            }
+            for key in chunks {
+                remove_item(&key)?;
+            }
+            if get_item(KEY_JOURNAL).is_some() {
+                // the marker of a journal whose chunks are missing
+                remove_item(KEY_JOURNAL)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Records the items in the journal, applies them to local storage and removes the journal.
+fn commit(entries: &[JournalEntry]) -> Result<(), String> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    set_to_localstorage(KEY_JOURNAL, &serde_json::to_string(entries).unwrap())?;
+    for (key, value) in entries {
+        match value {
+            Some(value) => set_to_localstorage(key, value)?,
+            None => remove_from_localstorage(key)?,
+        }
+    }
+    // the items are all written. A journal which is left is rolled forward again, which writes the same
+    // items.
+    let _ = remove_from_localstorage(KEY_JOURNAL);
+    Ok(())
+}
+
+/// Guard discards the items of the current atomic write if it is dropped, i.e. the function of the atomic
+/// write panics.
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        PENDING.with(|cell| *cell.borrow_mut() = None);
+    }
+}
@@ -0,0 +1,37 @@
+//! Provides a struct `GroupKeyStore` for storing the content keys of encrypted groups.
+
+use crate::encryption::GroupKey;
+
+use super::SerdeLocalStore;
+
+const KEY_GROUP_KEY: &str = "grpkey";
+
+/// GroupKeyStore is a store for group content keys. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+///
+/// Only the current key of a group is kept, so that a compromised device does not reveal the messages
+/// encrypted with the superseded keys. The stored messages are deciphered before a key is superseded
+/// (see `Writer::decipher_stored`), and their data is read from the
+/// [RatchetStore](super::ratchet::RatchetStore).
+#[derive(Default)]
+pub(crate) struct GroupKeyStore {}
+
+impl GroupKeyStore {
+    /// Returns the current content key of the given group, if the group is encrypted.
+    pub(crate) fn group_key(&self, group_id: &str) -> Option<GroupKey> {
+        // the keys were kept from the oldest to the current one by earlier versions
+        self.get::<Vec<GroupKey>>(format!("{KEY_GROUP_KEY}_{group_id}").as_str())?
+            .pop()
+    }
+
+    /// Sets the current content key of the given group, which replaces the previous key.
+    pub(crate) fn set_group_key(&mut self, group_id: &str, key: GroupKey) -> Result<(), String> {
+        self.set(format!("{KEY_GROUP_KEY}_{group_id}").as_str(), vec![key])
+    }
+
+    /// Removes the content key of the given group. Subsequent messages are not encrypted.
+    pub(crate) fn remove_group_key(&mut self, group_id: &str) -> Result<(), String> {
+        self.remove(format!("{KEY_GROUP_KEY}_{group_id}").as_str())
+    }
+}
+
+impl SerdeLocalStore for GroupKeyStore {}
@@ -0,0 +1,29 @@
+//! Provides a struct `LightStore` for storing the states of the light verification mode.
+
+use crate::light::LightState;
+
+use super::SerdeLocalStore;
+
+const KEY_LIGHT_STATE: &str = "light";
+
+/// LightStore is a store for the states of groups verified in the light verification mode. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct LightStore {}
+
+impl LightStore {
+    /// Returns the light state of the given group.
+    pub(crate) fn light_state(&self, group_id: &str) -> Option<LightState> {
+        self.get(format!("{KEY_LIGHT_STATE}_{group_id}").as_str())
+    }
+
+    /// Sets the light state of the given group.
+    pub(crate) fn set_light_state(
+        &mut self,
+        group_id: &str,
+        state: &LightState,
+    ) -> Result<(), String> {
+        self.set(format!("{KEY_LIGHT_STATE}_{group_id}").as_str(), state)
+    }
+}
+
+impl SerdeLocalStore for LightStore {}
@@ -0,0 +1,66 @@
+//! Provides a struct `LimitStore` for storing the write limits and the recent writes of the authors.
+
+use crate::{account::Identity, writer::WriteLimits};
+
+use super::SerdeLocalStore;
+
+const KEY_WRITE_LIMITS: &str = "write_limits";
+const KEY_RECENT_WRITES: &str = "recent_writes";
+
+/// LimitStore is a store for the [WriteLimits] and the unix timestamps of the recent writes of each
+/// author in each group. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct LimitStore {}
+
+impl LimitStore {
+    /// Returns the write limits. By default, there are no limits.
+    pub(crate) fn limits(&self) -> WriteLimits {
+        self.get(KEY_WRITE_LIMITS).unwrap_or_default()
+    }
+
+    /// Sets the write limits.
+    pub(crate) fn set_limits(&mut self, limits: &WriteLimits) -> Result<(), String> {
+        self.set(KEY_WRITE_LIMITS, limits)
+    }
+
+    /// Returns the number of messages of the author written in the given group since the given unix timestamp.
+    pub(crate) fn writes_since(&self, group_id: &str, author: &Identity, since: u64) -> usize {
+        self.recent_writes(group_id)
+            .iter()
+            .find(|(id, _)| id == author)
+            .map_or(0, |(_, timestamps)| {
+                timestamps.iter().filter(|t| **t >= since).count()
+            })
+    }
+
+    /// Records a write of the author in the given group at the given unix timestamp. The writes before
+    /// `since` are forgotten.
+    pub(crate) fn record_write(
+        &mut self,
+        group_id: &str,
+        author: &Identity,
+        timestamp: u64,
+        since: u64,
+    ) -> Result<(), String> {
+        let mut recent_writes = self.recent_writes(group_id);
+        recent_writes
+            .iter_mut()
+            .for_each(|(_, timestamps)| timestamps.retain(|t| *t >= since));
+        recent_writes.retain(|(_, timestamps)| !timestamps.is_empty());
+        match recent_writes.iter_mut().find(|(id, _)| id == author) {
+            Some((_, timestamps)) => timestamps.push(timestamp),
+            None => recent_writes.push((author.clone(), vec![timestamp])),
+        }
+        self.set(
+            format!("{KEY_RECENT_WRITES}_{group_id}").as_str(),
+            recent_writes,
+        )
+    }
+
+    fn recent_writes(&self, group_id: &str) -> Vec<(Identity, Vec<u64>)> {
+        self.get(format!("{KEY_RECENT_WRITES}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+}
+
+impl SerdeLocalStore for LimitStore {}
@@ -0,0 +1,149 @@
+//! Provides the access to the Web Storage which backs the stores in the web builds.
+//!
+//! The storage is the `localStorage` of the global scope, which is available in the windows of browsers.
+//! Where it is not available, e.g. in web workers and Node, the caller provides an object with the
+//! methods of the Web Storage API with [set_storage].
+
+use std::cell::{Cell, RefCell};
+
+use wasm_bindgen::{prelude::*, JsCast};
+
+use crate::diagnostics::{self, Category};
+
+#[wasm_bindgen]
+extern "C" {
+    /// StorageLike is an object with the methods of the Web Storage API, e.g. `localStorage`.
+    #[derive(Clone)]
+    pub type StorageLike;
+
+    #[wasm_bindgen(method, catch, js_name = getItem)]
+    pub(super) fn get_item(this: &StorageLike, key: &str) -> Result<Option<String>, JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = setItem)]
+    pub(super) fn set_item(this: &StorageLike, key: &str, value: &str) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = removeItem)]
+    pub(super) fn remove_item(this: &StorageLike, key: &str) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch)]
+    pub(super) fn key(this: &StorageLike, index: u32) -> Result<Option<String>, JsValue>;
+
+    #[wasm_bindgen(method, getter)]
+    pub(super) fn length(this: &StorageLike) -> u32;
+
+    #[wasm_bindgen(method, catch)]
+    fn clear(this: &StorageLike) -> Result<(), JsValue>;
+}
+
+thread_local! {
+    static STORAGE: RefCell<Option<StorageLike>> = const { RefCell::new(None) };
+    static LISTENING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets the storage provided by the caller. The `localStorage` of the global scope is used if it is None.
+pub(crate) fn set_storage(storage: Option<StorageLike>) {
+    STORAGE.with(|cell| *cell.borrow_mut() = storage);
+    super::cache::clear();
+}
+
+/// Returns the storage provided by the caller, or the `localStorage` of the global scope if it exists.
+fn storage() -> Option<StorageLike> {
+    if let Some(storage) = STORAGE.with(|cell| cell.borrow().clone()) {
+        return Some(storage);
+    }
+    // the global scope is the window, the worker or the global object of Node. Accessing
+    // `localStorage` throws if it is denied, e.g. in sandboxed frames.
+    let storage =
+        js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("localStorage")).ok()?;
+    if storage.is_undefined() || storage.is_null() {
+        return None;
+    }
+    if !LISTENING.replace(true) {
+        listen_storage_events();
+    }
+    Some(storage.unchecked_into())
+}
+
+/// Invalidates the cached values changed by other tabs, which are notified by the `storage` events of
+/// the window. The key of the event is None if the storage is cleared.
+fn listen_storage_events() {
+    let global = js_sys::global();
+    let Some(add_event_listener) =
+        js_sys::Reflect::get(&global, &JsValue::from_str("addEventListener"))
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+    else {
+        return;
+    };
+    let listener =
+        Closure::<dyn FnMut(JsValue)>::new(|event: JsValue| {
+            match js_sys::Reflect::get(&event, &JsValue::from_str("key"))
+                .ok()
+                .and_then(|key| key.as_string())
+            {
+                Some(key) => super::cache::invalidate(&key),
+                None => super::cache::clear(),
+            }
+        });
+    let _ = add_event_listener.call2(&global, &JsValue::from_str("storage"), listener.as_ref());
+    listener.forget();
+}
+
+pub(super) fn get_item(key: &str) -> Option<String> {
+    match storage()?.get_item(key) {
+        Ok(value) => value,
+        Err(err) => {
+            report("get", key, &err);
+            None
+        }
+    }
+}
+
+pub(super) fn set_item(key: &str, value: &str) -> Result<(), String> {
+    // e.g. the quota is exceeded. The error is reported before the write fails.
+    storage()
+        .ok_or("Fail to get storage".to_string())?
+        .set_item(key, value)
+        .map_err(|err| report("set", key, &err))
+}
+
+pub(super) fn remove_item(key: &str) -> Result<(), String> {
+    storage()
+        .ok_or("Fail to get storage".to_string())?
+        .remove_item(key)
+        .map_err(|err| report("remove", key, &err))
+}
+
+/// Reports the error of the storage to the diagnostics, and returns its message.
+fn report(operation: &str, key: &str, err: &JsValue) -> String {
+    let message = format!("fail to {operation} item {key}: {}", error_message(err));
+    diagnostics::error(Category::Storage, message.clone());
+    message
+}
+
+/// Returns the message of the error thrown by the storage, e.g. `QuotaExceededError`.
+pub(super) fn error_message(err: &JsValue) -> String {
+    js_sys::Reflect::get(err, &JsValue::from_str("message"))
+        .ok()
+        .and_then(|message| message.as_string())
+        .or_else(|| err.as_string())
+        .unwrap_or_default()
+}
+
+/// Returns the keys of all the items in the storage.
+pub(super) fn storage_keys() -> Vec<String> {
+    let Some(storage) = storage() else {
+        return vec![];
+    };
+    (0..storage.length())
+        .filter_map(|idx| storage.key(idx).ok()?)
+        .collect()
+}
+
+/// Removes all the items in the storage.
+pub(super) fn clear_items() -> Result<(), String> {
+    storage()
+        .ok_or("Fail to get storage".to_string())?
+        .clear()
+        .map_err(|_| "Fail to clear storage".to_string())
+}
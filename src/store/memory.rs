@@ -0,0 +1,118 @@
+//! Provides the in-memory storage which backs the stores in the native builds, in place of the local
+//! storage of browsers. The items can be kept in a JSON file with [open_file], e.g. for validators on
+//! servers and tools which audit exported chains. The items are kept per thread.
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::faults;
+use crate::diagnostics::{self, Category};
+
+thread_local! {
+    static ITEMS: RefCell<BTreeMap<String, String>> = const { RefCell::new(BTreeMap::new()) };
+    static FILE: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Backs the storage with the JSON file at the path. The items in the file, if it exists, replace the
+/// items in memory, and every change is written to the file.
+pub fn open_file(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref().to_path_buf();
+    let items = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+        Err(err) => return Err(err),
+    };
+    ITEMS.with(|cell| *cell.borrow_mut() = items);
+    super::cache::clear();
+    FILE.with(|cell| *cell.borrow_mut() = Some(path));
+    write_file()
+}
+
+/// Stops writing the changes to the file. The items are kept in memory.
+pub fn close_file() {
+    FILE.with(|cell| *cell.borrow_mut() = None);
+}
+
+/// Writes the items to the file, if the storage is backed by a file.
+fn write_file() -> io::Result<()> {
+    let Some(path) = FILE.with(|cell| cell.borrow().clone()) else {
+        return Ok(());
+    };
+    let content = ITEMS.with(|cell| serde_json::to_string(&*cell.borrow()))?;
+    fs::write(path, content)
+}
+
+pub(super) fn get_item(key: &str) -> Option<String> {
+    ITEMS.with(|cell| cell.borrow().get(key).cloned())
+}
+
+pub(super) fn set_item(key: &str, value: &str) -> Result<(), String> {
+    faults::check_write(key, Some(value.len())).map_err(|reason| fail("set", key, &reason))?;
+    insert_item(key, value);
+    faults::after_write(key);
+    Ok(())
+}
+
+pub(super) fn remove_item(key: &str) -> Result<(), String> {
+    faults::check_write(key, None).map_err(|reason| fail("remove", key, &reason))?;
+    delete_item(key);
+    faults::after_write(key);
+    Ok(())
+}
+
+pub(super) fn insert_item(key: &str, value: &str) {
+    ITEMS.with(|cell| cell.borrow_mut().insert(key.to_string(), value.to_string()));
+    report(write_file());
+}
+
+pub(super) fn delete_item(key: &str) {
+    ITEMS.with(|cell| cell.borrow_mut().remove(key));
+    report(write_file());
+}
+
+/// Returns the length of the key and the value of the item, if it exists.
+pub(super) fn item_len(key: &str) -> Option<usize> {
+    ITEMS.with(|cell| cell.borrow().get(key).map(|value| key.len() + value.len()))
+}
+
+/// Returns the total length of the keys and the values of the items.
+pub(super) fn used_bytes() -> usize {
+    ITEMS.with(|cell| {
+        cell.borrow()
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum()
+    })
+}
+
+/// Reports the injected failure of the write to the diagnostics, and returns the error of the write, as the
+/// Web Storage throws.
+fn fail(operation: &str, key: &str, reason: &str) -> String {
+    let message = format!("fail to {operation} item {key}: {reason}");
+    diagnostics::error(Category::Storage, message.clone());
+    message
+}
+
+/// Reports the error of writing the file to the diagnostics. The items are kept in memory, and the file is
+/// written again on the next change.
+fn report(result: io::Result<()>) {
+    if let Err(err) = result {
+        diagnostics::error(Category::Storage, format!("fail to write the file: {err}"));
+    }
+}
+
+/// Returns the keys of all the items.
+pub(super) fn storage_keys() -> Vec<String> {
+    ITEMS.with(|cell| cell.borrow().keys().cloned().collect())
+}
+
+/// Removes all the items.
+pub(super) fn clear_items() -> Result<(), String> {
+    ITEMS.with(|cell| cell.borrow_mut().clear());
+    write_file().map_err(|_| "Fail to write storage file".to_string())
+}
@@ -0,0 +1,30 @@
+//! Provides a struct `MerkleStore` for storing the Merkle Mountain Ranges of groups.
+
+use crate::mmr::MerkleMountainRange;
+
+use super::SerdeLocalStore;
+
+const KEY_MMR: &str = "mmr";
+
+/// MerkleStore is a store for the Merkle Mountain Ranges over message hashes. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct MerkleStore {}
+
+impl MerkleStore {
+    /// Returns the Merkle Mountain Range of the given group.
+    pub(crate) fn mmr(&self, group_id: &str) -> MerkleMountainRange {
+        self.get(format!("{KEY_MMR}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+
+    /// Sets the Merkle Mountain Range of the given group.
+    pub(crate) fn set_mmr(
+        &mut self,
+        group_id: &str,
+        mmr: &MerkleMountainRange,
+    ) -> Result<(), String> {
+        self.set(format!("{KEY_MMR}_{group_id}").as_str(), mmr)
+    }
+}
+
+impl SerdeLocalStore for MerkleStore {}
@@ -1,39 +1,77 @@
 //! Provides a struct `SignedMessageStore` for storing signed messages.
 
-use sha2::Digest;
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+};
+
+use sha2::{Digest, Sha256};
 
 use crate::{
     account::Identity,
-    core::message::{MessageHash, SignedMessage},
-    message::Signature,
+    core::{
+        compare::ct_eq,
+        group::{unix_timestamp, HashAlgorithm, RetentionPolicy},
+        message::{
+            hash_from_string, hash_to_string, MessageHash, MessageKind, SignedMessage, Tombstone,
+        },
+    },
+    diagnostics::{self, Category},
+    header::HeaderIndex,
+    index::MessageIndex,
+    message::{Signature, SIGNATURE_SCHEME},
+    settings::GroupSettings,
+    state::{Checkpoint, Redaction},
+    stats::{ChainPosition, ChainStats, GcReport, GroupInfo},
+    tamper::{TamperFinding, TamperKind},
+    validation::{ValidationCursor, ValidationPass, ValidationReport, ValidationWatermark},
 };
 
-use super::SerdeLocalStore;
+use super::{get_namespaced_item, journal, namespaced_keys, SerdeLocalStore};
 
 const KEY_MESSAGE: &str = "msg";
 const KEY_LATEST_MESSAGEHASH: &str = "latest_msghash";
+const KEY_MESSAGE_INDEX: &str = "msg_index";
+const KEY_GROUP_INFO: &str = "grpinfo";
+const KEY_MESSAGE_HEADERS: &str = "msg_headers";
+const KEY_COMPRESSION_THRESHOLD: &str = "compress_threshold";
+const KEY_VALIDATED_HEAD: &str = "validated_head";
+
+/// The default size (in bytes) of message data above which the data is compressed before storage.
+const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+/// The compression level used by deflate, from 0 (none) to 10 (best).
+const COMPRESSION_LEVEL: u8 = 6;
 
 /// SignedMessageStore is a store for signed messages. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
 #[derive(Default)]
 pub(crate) struct SignedMessageStore {}
 
 impl SignedMessageStore {
-    /// Returns the message with the given hash.
+    /// Returns the message with the given hash. The message is not cached, so that walking a chain does
+    /// not copy the messages into the cache.
     pub(crate) fn message(
         &self,
         group_id: &str,
         hash: &MessageHash,
     ) -> Option<SignedMessage<Identity, Signature>> {
-        self.get(format!("{KEY_MESSAGE}_{group_id}_{:x?}", hash).as_str())
+        decompressed(
+            self.get_uncached(
+                format!("{KEY_MESSAGE}_{group_id}_{}", hash_to_string(hash)).as_str(),
+            )?,
+        )
     }
 
-    /// Returns the latest message for the given group ID.
+    /// Returns the latest message for the given group ID. The latest message is read before every write,
+    /// so it is cached.
     pub(crate) fn latest_message(
         &self,
         group_id: &str,
     ) -> Option<(MessageHash, SignedMessage<Identity, Signature>)> {
-        self.latest_message_hash(group_id)
-            .and_then(|hash| self.message(group_id, &hash).map(|message| (hash, message)))
+        let hash = self.latest_message_hash(group_id)?;
+        let message =
+            self.get(format!("{KEY_MESSAGE}_{group_id}_{}", hash_to_string(&hash)).as_str())?;
+        Some((hash, decompressed(message)?))
     }
 
     /// Returns the latest message hash for the given group ID.
@@ -41,26 +79,194 @@ impl SignedMessageStore {
         self.get(format!("{KEY_LATEST_MESSAGEHASH}_{group_id}",).as_str())
     }
 
+    /// Returns the latest message hash for the given group ID read from the local storage instead of the
+    /// cache, which may be stale if another tab has just written the group.
+    pub(crate) fn stored_head(&self, group_id: &str) -> Option<MessageHash> {
+        self.get_uncached(format!("{KEY_LATEST_MESSAGEHASH}_{group_id}",).as_str())
+    }
+
     /// Saves a message to the store. It returns the hash of the message.
     /// This method does not validate the message.
     ///
     /// The steps involved:
     /// 1. Save the message.
     /// 2. Update the latest message hash.
-    /// 3. Return the hash of the message.
-    pub(crate) fn save_message<H: Digest>(
+    /// 3. Add the message to the [MessageIndex] and the [GroupInfo] of the group.
+    /// 4. Return the hash of the message.
+    ///
+    /// The hash is computed by the caller with the hash algorithm of the group, which may be migrated.
+    pub(crate) fn save_message(
         &mut self,
         group_id: &str,
+        hash: MessageHash,
         message: &SignedMessage<Identity, Signature>,
-    ) -> MessageHash {
+    ) -> Result<MessageHash, String> {
+        // the index and the info are read before saving so that they are rebuilt without the message
+        let mut index = self.index(group_id);
+        let mut info = self.group_info(group_id);
+        let mut headers = self.headers(group_id);
+
         // save message
-        let hash = message.hash::<H>();
-        self.set_message(group_id, &hash, message.clone());
+        let bytes = self.set_message(group_id, &hash, message)?;
 
         // update latest message
-        self.set_latest_message_hash(group_id, &hash);
+        self.set_latest_message_hash(group_id, &hash)?;
+
+        // update index
+        index.add(hash, message);
+        self.set(format!("{KEY_MESSAGE_INDEX}_{group_id}").as_str(), index)?;
+        info.add(hash, message, bytes);
+        self.set_group_info(group_id, &info)?;
+        headers.add(hash, message);
+        self.set_headers(group_id, &headers)?;
 
-        hash
+        Ok(hash)
+    }
+
+    /// Returns the [MessageIndex] of the given group. If the index is not stored, e.g. the messages were
+    /// saved before the index was maintained, it is rebuilt from the stored messages.
+    pub(crate) fn index(&self, group_id: &str) -> MessageIndex {
+        if let Some(index) = self.get(format!("{KEY_MESSAGE_INDEX}_{group_id}").as_str()) {
+            return index;
+        }
+        let mut messages = vec![];
+        let mut hash = self.latest_message_hash(group_id);
+        while let Some(message) = hash.and_then(|hash| self.message(group_id, &hash)) {
+            let previous_hash = message.message.previous_hash;
+            messages.extend(hash.map(|hash| (hash, message)));
+            hash = Some(previous_hash);
+        }
+
+        let mut index = MessageIndex::default();
+        for (hash, message) in messages.iter().rev() {
+            index.add(*hash, message);
+        }
+        index
+    }
+
+    /// Returns the [GroupInfo] of the given group. If the info is not stored, e.g. the messages were saved
+    /// before the info was maintained, it is rebuilt from the stored messages.
+    pub(crate) fn group_info(&self, group_id: &str) -> GroupInfo {
+        if let Some(info) = self.get(format!("{KEY_GROUP_INFO}_{group_id}").as_str()) {
+            return info;
+        }
+        let mut messages = vec![];
+        let mut hash = self.latest_message_hash(group_id);
+        while let Some((bytes, message)) =
+            hash.and_then(|hash| self.stored_message(group_id, &hash))
+        {
+            let previous_hash = message.message.previous_hash;
+            messages.extend(hash.map(|hash| (hash, bytes, message)));
+            hash = Some(previous_hash);
+        }
+
+        let mut info = GroupInfo::default();
+        for (hash, bytes, message) in messages.iter().rev() {
+            info.add(*hash, message, *bytes);
+        }
+        info
+    }
+
+    /// Returns the [HeaderIndex] of the given group. If the headers are not stored, e.g. the messages were
+    /// saved before the headers were maintained, they are rebuilt from the stored messages.
+    pub(crate) fn headers(&self, group_id: &str) -> HeaderIndex {
+        if let Some(headers) = self.get(format!("{KEY_MESSAGE_HEADERS}_{group_id}").as_str()) {
+            return headers;
+        }
+        let mut messages = vec![];
+        let mut hash = self.latest_message_hash(group_id);
+        while let Some(message) = hash.and_then(|hash| self.message(group_id, &hash)) {
+            let previous_hash = message.message.previous_hash;
+            messages.extend(hash.map(|hash| (hash, message)));
+            hash = Some(previous_hash);
+        }
+
+        let mut headers = HeaderIndex::default();
+        for (hash, message) in messages.iter().rev() {
+            headers.add(*hash, message);
+        }
+        headers
+    }
+
+    /// Returns the [ChainStats] of the given group by walking the chain from the head. The stored messages
+    /// which are not in the chain and the given received messages, e.g. quarantined, are counted as forks
+    /// if they branch off the chain before its head.
+    pub(crate) fn chain_stats(
+        &self,
+        group_id: &str,
+        received: &[SignedMessage<Identity, Signature>],
+    ) -> ChainStats {
+        let head = self.latest_message_hash(group_id);
+        let mut stats = ChainStats {
+            group_id: group_id.to_string(),
+            validated_head: self
+                .get::<ValidationWatermark>(format!("{KEY_VALIDATED_HEAD}_{group_id}").as_str())
+                .map(|watermark| watermark.hash),
+            ..Default::default()
+        };
+
+        let mut chain = HashSet::new();
+        let mut bytes = 0;
+        let mut hash = head;
+        while let Some(current) = hash.filter(|hash| chain.insert(*hash)) {
+            let Some((size, message)) = self
+                .stored_message(group_id, &current)
+                .and_then(|(size, message)| Some((size, decompressed(message)?)))
+            else {
+                break;
+            };
+            stats.message_count += 1;
+            bytes += size;
+            *stats
+                .signature_schemes
+                .entry(SIGNATURE_SCHEME.to_string())
+                .or_default() += 1;
+            if let Some(algorithm) = HashAlgorithm::of(&message, &current) {
+                *stats
+                    .hash_algorithms
+                    .entry(algorithm.name().to_string())
+                    .or_default() += 1;
+            }
+            if stats
+                .validated_head
+                .is_some_and(|validated| ct_eq(&validated, &current))
+            {
+                stats.validated_seq = Some(message.seq);
+            }
+            if message.message.kind == MessageKind::Checkpoint {
+                stats.checkpoints.push(ChainPosition {
+                    seq: message.seq,
+                    hash: current,
+                });
+            }
+            hash = (!message.is_first_message()).then_some(message.message.previous_hash);
+        }
+        stats.checkpoints.reverse();
+        stats.average_message_bytes = bytes.checked_div(stats.message_count).unwrap_or(0);
+
+        let prefix = format!("{KEY_MESSAGE}_{group_id}_");
+        let stored = namespaced_keys(&prefix)
+            .iter()
+            .filter_map(|key| hash_from_string(&key[prefix.len()..]))
+            .filter(|hash| !chain.contains(hash))
+            .filter_map(|hash| self.message(group_id, &hash))
+            .collect::<Vec<_>>();
+        let received = received.iter().filter(|message| {
+            !HashAlgorithm::ALL
+                .iter()
+                .any(|algorithm| chain.contains(&algorithm.hash(*message)))
+        });
+        stats.forks = stored
+            .iter()
+            .chain(received)
+            .filter(|message| {
+                let parent = &message.message.previous_hash;
+                !message.is_first_message()
+                    && chain.contains(parent)
+                    && !head.is_some_and(|head| ct_eq(&head, parent))
+            })
+            .count() as u64;
+        stats
     }
 
     /// Returns the stored messages for the given group ID.
@@ -72,52 +278,766 @@ impl SignedMessageStore {
             None => return messages,
         };
         while let Some(message) = self.message(group_id, &latest_hash) {
-            messages.push(message.clone());
             latest_hash = message.message.previous_hash;
+            messages.push(message);
         }
         messages
     }
 
-    /// Validates the stored messages for the given group ID.
-    pub(crate) fn validate_messages<H: Digest>(&self, group_id: &str) -> bool {
-        let mut latest_msg = match self.latest_message(group_id) {
-            Some((_, m)) => m,
-            None => return true,
+    /// Checks if any stored message of the given group is signed by the identity.
+    pub(crate) fn has_author(&self, group_id: &str, identity: &Identity) -> bool {
+        let mut hash = self.latest_message_hash(group_id);
+        while let Some(message) = hash.and_then(|hash| self.message(group_id, &hash)) {
+            if &message.id == identity {
+                return true;
+            }
+            hash = Some(message.message.previous_hash);
+        }
+        false
+    }
+
+    /// Returns the hashes of the stored messages for the given group ID, ordered from the latest.
+    pub(crate) fn message_hashes(&self, group_id: &str) -> Vec<MessageHash> {
+        let mut hashes = vec![];
+        let mut hash = self.latest_message_hash(group_id);
+        while let Some(message) = hash.and_then(|hash| self.message(group_id, &hash)) {
+            hashes.extend(hash);
+            hash = Some(message.message.previous_hash);
+        }
+        hashes
+    }
+
+    /// Validates the stored messages for the given group ID. The chain must start with a valid genesis
+    /// record or a checkpoint record, and each message must be valid against the state of the group replayed up to its parent.
+    /// The data of a purged message cannot be verified, so its signature is verified against the digest
+    /// kept in its tombstone, and it must be redacted by a later redaction record or expired by the
+    /// retention of the group policy, by the signed timestamps of the newer messages. If the settings check the
+    /// timestamps strictly, the timestamp of each message is checked against its parent.
+    pub(crate) fn validate_messages<H: Digest>(
+        &self,
+        group_id: &str,
+        settings: &GroupSettings,
+    ) -> bool {
+        self.validate_messages_with_progress::<H>(group_id, settings, usize::MAX, |_| {})
+    }
+
+    /// Validates the stored messages for the given group ID as [validate_messages] does, but only from the
+    /// watermark, which is the head of the previous successful validation, to the head of the chain. The
+    /// watermark is used only if it is still on the chain, i.e. the message at it is reached from the head
+    /// and still matches its hash and signature, otherwise the whole chain is validated. The watermark is
+    /// moved to the head after a successful validation.
+    ///
+    /// [validate_messages]: SignedMessageStore::validate_messages
+    pub(crate) fn validate_from_watermark<H: Digest>(
+        &mut self,
+        group_id: &str,
+        settings: &GroupSettings,
+    ) -> Result<ValidationReport, String> {
+        let key = format!("{KEY_VALIDATED_HEAD}_{group_id}");
+        let head = self
+            .latest_message(group_id)
+            .map(|(hash, message)| (hash, message.seq));
+        let watermark: Option<ValidationWatermark> = self.get(&key);
+        let at_watermark = head
+            .zip(watermark.as_ref())
+            .is_some_and(|((head, _), watermark)| ct_eq(&head, &watermark.hash));
+
+        let mut cursor = self.start_validation(group_id);
+        if let ValidationPass::Backward {
+            watermark: from, ..
+        } = &mut cursor.pass
+        {
+            *from = watermark.map(Box::new);
+        }
+        while cursor.result.is_none() {
+            self.resume_validation::<H>(&mut cursor, settings, usize::MAX);
+        }
+        let valid = cursor.result.unwrap_or(false);
+        if let (
+            Some((hash, seq)),
+            ValidationPass::Forward {
+                state,
+                previous_timestamp,
+                ..
+            },
+        ) = (head.filter(|_| valid), cursor.pass)
+        {
+            let watermark = ValidationWatermark {
+                hash,
+                seq,
+                state,
+                previous_timestamp,
+            };
+            self.set(&key, watermark)?;
+        }
+        Ok(ValidationReport {
+            group_id: group_id.to_string(),
+            valid,
+            checked: cursor.checked,
+            skipped: valid && at_watermark,
+        })
+    }
+
+    /// Validates the stored messages for the given group ID as [validate_messages] does, and reports the
+    /// cursor after each chunk of messages.
+    ///
+    /// [validate_messages]: SignedMessageStore::validate_messages
+    pub(crate) fn validate_messages_with_progress<H: Digest>(
+        &self,
+        group_id: &str,
+        settings: &GroupSettings,
+        chunk_size: usize,
+        mut on_progress: impl FnMut(&ValidationCursor),
+    ) -> bool {
+        let mut cursor = self.start_validation(group_id);
+        while cursor.result.is_none() {
+            self.resume_validation::<H>(&mut cursor, settings, chunk_size);
+            on_progress(&cursor);
+        }
+        cursor.result.unwrap_or(false)
+    }
+
+    /// Creates the cursor of the validation of the stored messages for the given group ID, at the head.
+    pub(crate) fn start_validation(&self, group_id: &str) -> ValidationCursor {
+        let head = self
+            .latest_message(group_id)
+            .map(|(hash, message)| (hash, message.seq));
+        let message_count = self.group_info(group_id).message_count;
+        ValidationCursor::new(group_id, head, message_count, unix_timestamp())
+    }
+
+    /// Resumes the validation of the cursor for at most `chunk_size` messages, as [validate_messages]
+    /// does for the entire chain. The result is set in the cursor once the validation is complete.
+    ///
+    /// [validate_messages]: SignedMessageStore::validate_messages
+    pub(crate) fn resume_validation<H: Digest>(
+        &self,
+        cursor: &mut ValidationCursor,
+        settings: &GroupSettings,
+        chunk_size: usize,
+    ) {
+        let mut checked = 0;
+        while cursor.result.is_none() && checked < chunk_size.max(1) {
+            cursor.result = self.validation_step::<H>(
+                &cursor.group_id,
+                &mut cursor.pass,
+                settings,
+                &mut checked,
+            );
+        }
+        cursor.checked += checked as u64;
+        if let Some(result) = cursor.result {
+            diagnostics::debug(
+                Category::Validation,
+                format!(
+                    "validated group {} with {} checks: {result}",
+                    cursor.group_id, cursor.checked
+                ),
+            );
+        }
+    }
+
+    /// Checks the next message of the pass, or moves to the forward pass after the oldest stored message.
+    /// It returns the result if the validation is complete.
+    fn validation_step<H: Digest>(
+        &self,
+        group_id: &str,
+        pass: &mut ValidationPass,
+        settings: &GroupSettings,
+        checked: &mut usize,
+    ) -> Option<bool> {
+        match pass {
+            ValidationPass::Backward {
+                next,
+                child_seq,
+                latest_seq,
+                now,
+                redacted,
+                chain,
+                newer_timestamp,
+                expiring,
+                watermark,
+            } => {
+                let Some(message) = self.message(group_id, next) else {
+                    // the parent of the oldest stored message is not stored. Replay the chain from the
+                    // genesis record, or from the checkpoint if the older messages are pruned.
+                    let Some(oldest) = chain.last() else {
+                        return Some(true);
+                    };
+                    let Some(oldest) = self.message(group_id, oldest) else {
+                        return Some(invalid(group_id, oldest, "message is not stored"));
+                    };
+                    let state = (oldest.message.kind == MessageKind::Checkpoint)
+                        .then(|| serde_json::from_slice::<Checkpoint>(&oldest.message.data).ok())
+                        .flatten()
+                        .map(|checkpoint| checkpoint.state)
+                        .unwrap_or_default();
+                    *pass = ValidationPass::Forward {
+                        state: Box::new(state),
+                        remaining: std::mem::take(chain),
+                        previous_timestamp: None,
+                        latest_seq: *latest_seq,
+                        now: *now,
+                        expiring: std::mem::take(expiring),
+                    };
+                    return None;
+                };
+                *checked += 1;
+                if child_seq.is_some_and(|child_seq| message.next_seq() != Some(child_seq)) {
+                    return Some(invalid(group_id, next, "sequence number is not continuous"));
+                }
+                let is_intact = if message.message.redacted {
+                    // the fields of the purged message are not signed, so its expiry is checked with the
+                    // signed timestamps of the newer messages, against the signed group policy
+                    if !redacted.contains(next) {
+                        expiring.push((*next, message.seq, *newer_timestamp));
+                    }
+                    message.verify_tombstone(next)
+                } else {
+                    if let Some(timestamp) = message.message.timestamp {
+                        *newer_timestamp =
+                            Some(newer_timestamp.map_or(timestamp, |newer| newer.min(timestamp)));
+                    }
+                    // the algorithm is checked in the forward pass, with the state after the message
+                    message.verify::<H>() && HashAlgorithm::of(&message, next).is_some()
+                };
+                if !is_intact {
+                    return Some(invalid(group_id, next, "hash or signature is invalid"));
+                }
+                if message.message.kind == MessageKind::Redaction {
+                    if let Ok(redaction) =
+                        serde_json::from_slice::<Redaction>(&message.message.data)
+                    {
+                        redacted.push(redaction.hash);
+                    }
+                }
+                match watermark.take() {
+                    // the chain up to the watermark is validated, so the newer messages are replayed from
+                    // the state after it
+                    Some(from) if from.seq == message.seq && ct_eq(&from.hash, next) => {
+                        *pass = ValidationPass::Forward {
+                            state: from.state,
+                            remaining: std::mem::take(chain),
+                            previous_timestamp: from.previous_timestamp,
+                            latest_seq: *latest_seq,
+                            now: *now,
+                            expiring: std::mem::take(expiring),
+                        };
+                        return None;
+                    }
+                    // the watermark is not reached yet. Otherwise, it is not on the chain, and the whole
+                    // chain is validated.
+                    Some(from) if from.seq < message.seq => *watermark = Some(from),
+                    _ => {}
+                }
+                chain.push(*next);
+                *child_seq = Some(message.seq);
+                *next = message.message.previous_hash;
+                None
+            }
+            ValidationPass::Forward {
+                state,
+                remaining,
+                previous_timestamp,
+                latest_seq,
+                now,
+                expiring,
+            } => {
+                let Some(hash) = remaining.pop() else {
+                    return Some(true);
+                };
+                *checked += 1;
+                let Some(message) = self.message(group_id, &hash) else {
+                    return Some(invalid(group_id, &hash, "message is not stored"));
+                };
+                let checked_state = state.check(group_id, &message).and_then(|_| {
+                    settings.check_timestamp(&message, *previous_timestamp, unix_timestamp())
+                });
+                state.update(&message);
+                if let Err(reason) = checked_state {
+                    return Some(invalid(group_id, &hash, reason));
+                }
+                if !message.message.redacted && !ct_eq(&state.hash_algorithm.hash(&message), &hash)
+                {
+                    return Some(invalid(
+                        group_id,
+                        &hash,
+                        "hash algorithm is not of the group",
+                    ));
+                }
+                *previous_timestamp = message.message.timestamp.or(*previous_timestamp);
+                if !remaining.is_empty() {
+                    return None;
+                }
+                let retention = &state.policy.retention;
+                match expiring.iter().find(|(_, seq, signed_at)| {
+                    !retention.is_expired_at(*seq, *signed_at, *latest_seq, *now)
+                }) {
+                    Some((hash, _, _)) => Some(invalid(
+                        group_id,
+                        hash,
+                        "purged message is not expired by the group policy",
+                    )),
+                    None => Some(true),
+                }
+            }
+        }
+    }
+
+    /// Removes the messages before the latest checkpoint record of the given group. It returns the number
+    /// of removed messages. The messages are removed atomically through the [journal].
+    pub(crate) fn prune(&mut self, group_id: &str) -> Result<usize, String> {
+        journal::atomically(|| {
+            let Some(mut hash) = self.latest_message_hash(group_id) else {
+                return Ok(0);
+            };
+            let checkpoint = loop {
+                let Some(message) = self.message(group_id, &hash) else {
+                    return Ok(0);
+                };
+                if message.message.kind == MessageKind::Checkpoint {
+                    break message;
+                }
+                hash = message.message.previous_hash;
+            };
+
+            let mut index = self.index(group_id);
+            let mut info = self.group_info(group_id);
+            let mut headers = self.headers(group_id);
+            let mut removed = 0;
+            let mut hash = checkpoint.message.previous_hash;
+            while let Some((bytes, message)) = self.stored_message(group_id, &hash) {
+                self.remove(
+                    format!("{KEY_MESSAGE}_{group_id}_{}", hash_to_string(&hash)).as_str(),
+                )?;
+                index.remove(&hash);
+                info.remove(bytes);
+                headers.remove(&hash);
+                removed += 1;
+                hash = message.message.previous_hash;
+            }
+            self.set(format!("{KEY_MESSAGE_INDEX}_{group_id}").as_str(), index)?;
+            self.set_group_info(group_id, &info)?;
+            self.set_headers(group_id, &headers)?;
+            Ok(removed)
+        })?
+    }
+
+    /// Purges the data of the messages which are expired by both the local retention policy and the
+    /// retention of the group policy. The age of each message is taken from the signed timestamps of the
+    /// newer messages which are not purged, as the validation of the chain does. It returns the number of
+    /// purged messages.
+    pub(crate) fn compact(
+        &mut self,
+        group_id: &str,
+        retention_policy: &RetentionPolicy,
+        group_retention: &RetentionPolicy,
+    ) -> Result<usize, String> {
+        let Some((mut hash, latest_msg)) = self.latest_message(group_id) else {
+            return Ok(0);
         };
+        let latest_seq = latest_msg.seq;
+        let now = unix_timestamp();
 
-        if !latest_msg.verify::<H>() {
-            return false;
+        let mut purged = 0;
+        let mut newer_timestamp: Option<u64> = None;
+        while let Some(message) = self.message(group_id, &hash) {
+            if message.message.redacted {
+                hash = message.message.previous_hash;
+                continue;
+            }
+            if retention_policy.is_expired(&message, latest_seq, now)
+                && group_retention.is_expired_at(message.seq, newer_timestamp, latest_seq, now)
+            {
+                self.purge_message(group_id, &hash)?;
+                purged += 1;
+            } else if let Some(timestamp) = message.message.timestamp {
+                newer_timestamp =
+                    Some(newer_timestamp.map_or(timestamp, |newer| newer.min(timestamp)));
+            }
+            hash = message.message.previous_hash;
         }
+        Ok(purged)
+    }
 
-        while let Some(message) = self.message(group_id, &latest_msg.message.previous_hash) {
-            if !message.is_valid_parent_of::<H>(&latest_msg) {
-                return false;
+    /// Purges the data of the message with the given hash, which must be redacted by a redaction record
+    /// or expired by the retention policy.
+    /// The message is kept with empty data and a [Tombstone] of its hash and signed digest, so that the chain
+    /// remains linked and the message is still verified. The message and its counters
+    /// are written atomically through the [journal].
+    pub(crate) fn purge_message(
+        &mut self,
+        group_id: &str,
+        hash: &MessageHash,
+    ) -> Result<(), String> {
+        journal::atomically(|| {
+            let Some((old_bytes, _)) = self.stored_message(group_id, hash) else {
+                return Ok(());
+            };
+            if let Some(mut message) = self.message(group_id, hash) {
+                if message.message.redacted {
+                    return Ok(());
+                }
+                message.message.tombstone = Some(Tombstone {
+                    hash: *hash,
+                    digest: message.message.to_hash::<Sha256>(),
+                });
+                message.message.data = vec![];
+                message.message.redacted = true;
+                let bytes = self.set_message(group_id, hash, &message)?;
+
+                let mut info = self.group_info(group_id);
+                info.storage_bytes = (info.storage_bytes + bytes).saturating_sub(old_bytes);
+                self.set_group_info(group_id, &info)?;
+                let mut headers = self.headers(group_id);
+                headers.redact(hash);
+                self.set_headers(group_id, &headers)?;
+                // the purged message may be below the watermark, so the next validation checks the
+                // whole chain
+                self.remove(format!("{KEY_VALIDATED_HEAD}_{group_id}").as_str())?;
+            }
+            Ok(())
+        })?
+    }
+
+    /// Detects the stored items of the given group which are modified outside the library. The chain is
+    /// walked from the head pointer, checking each message against the hash in its key, and the stored
+    /// messages which are not in the chain are reported as well.
+    pub(crate) fn detect_tampering<H: Digest>(&self, group_id: &str) -> Vec<TamperFinding> {
+        let finding = |key: String, kind, seq| TamperFinding {
+            group_id: group_id.to_string(),
+            key,
+            kind,
+            seq,
+        };
+        let message_key =
+            |hash: &MessageHash| format!("{KEY_MESSAGE}_{group_id}_{}", hash_to_string(hash));
+        let head_key = format!("{KEY_LATEST_MESSAGEHASH}_{group_id}");
+        let mut findings = vec![];
+
+        let head = self.latest_message_hash(group_id);
+        if head.is_none() && get_namespaced_item(&head_key).is_some() {
+            findings.push(finding(head_key.clone(), TamperKind::UnparsableItem, None));
+        }
+
+        // walk the chain from the head pointer
+        let mut reachable = HashSet::new();
+        let mut head_seq = None;
+        let mut child: Option<SignedMessage<Identity, Signature>> = None;
+        let mut hash = head;
+        while let Some(current) = hash.filter(|hash| reachable.insert(*hash)) {
+            let key = message_key(&current);
+            let parent_seq = child.as_ref().and_then(|child| child.seq.checked_sub(1));
+            let Some(message) = self.message(group_id, &current) else {
+                if get_namespaced_item(&key).is_some() {
+                    findings.push(finding(key, TamperKind::UnparsableItem, parent_seq));
+                } else if child.is_none() {
+                    findings.push(finding(head_key.clone(), TamperKind::DanglingHead, None));
+                } else if child
+                    .as_ref()
+                    .is_some_and(|child| child.message.kind != MessageKind::Checkpoint)
+                {
+                    findings.push(finding(key, TamperKind::MissingMessage, parent_seq));
+                }
+                break;
+            };
+            let is_modified = if message.message.redacted {
+                !message.verify_tombstone(&current)
+            } else {
+                HashAlgorithm::of(&message, &current).is_none() || !message.verify::<H>()
+            };
+            let is_discontinuous = child
+                .as_ref()
+                .is_some_and(|child| message.next_seq() != Some(child.seq));
+            if is_modified || is_discontinuous {
+                findings.push(finding(key, TamperKind::ModifiedMessage, Some(message.seq)));
             }
+            head_seq.get_or_insert(message.seq);
+            hash = (!message.is_first_message()).then_some(message.message.previous_hash);
+            child = Some(message);
+        }
+
+        // find the stored messages which are not in the chain
+        let prefix = format!("{KEY_MESSAGE}_{group_id}_");
+        for key in namespaced_keys(&prefix) {
+            let Some(hash) = hash_from_string(&key[prefix.len()..]) else {
+                continue;
+            };
+            if reachable.contains(&hash) {
+                continue;
+            }
+            let Some(message) = self.message(group_id, &hash) else {
+                findings.push(finding(key, TamperKind::UnparsableItem, None));
+                continue;
+            };
+            let follows_head = head
+                .is_some_and(|head| ct_eq(&head, &message.message.previous_hash))
+                && head_seq.is_some_and(|seq| seq.checked_add(1) == Some(message.seq));
+            if follows_head {
+                findings.push(finding(head_key.clone(), TamperKind::StaleHead, head_seq));
+            }
+            findings.push(finding(key, TamperKind::OrphanedMessage, Some(message.seq)));
+        }
+        findings
+    }
+
+    /// Rebuilds the head pointer of the given group from the stored messages, e.g. after it is lost or
+    /// corrupted. The messages which match the hashes in their keys are linked by their previous hashes,
+    /// and the latest message of the longest chain down to the genesis record, or to a checkpoint if the
+    /// older messages are pruned, becomes the head. The index and the info of the group are rebuilt from
+    /// the chain. It returns the hash of the head, or None if no chain is found.
+    pub(crate) fn rebuild_head<H: Digest>(
+        &mut self,
+        group_id: &str,
+    ) -> Result<Option<MessageHash>, String> {
+        let prefix = format!("{KEY_MESSAGE}_{group_id}_");
+        let messages: BTreeMap<MessageHash, SignedMessage<Identity, Signature>> =
+            namespaced_keys(&prefix)
+                .iter()
+                .filter_map(|key| hash_from_string(&key[prefix.len()..]))
+                .filter_map(|hash| self.message(group_id, &hash).map(|message| (hash, message)))
+                .filter(|(hash, message)| {
+                    if message.message.redacted {
+                        message.verify_tombstone(hash)
+                    } else {
+                        HashAlgorithm::of(message, hash).is_some() && message.verify::<H>()
+                    }
+                })
+                .collect();
+
+        // the heads are the messages which are not the parents of other messages
+        let parents: HashSet<MessageHash> = messages
+            .values()
+            .map(|message| message.message.previous_hash)
+            .collect();
+        let Some((head, _)) = messages
+            .iter()
+            .filter(|(hash, _)| !parents.contains(*hash))
+            .filter(|(hash, _)| is_rooted(&messages, hash))
+            .max_by_key(|(_, message)| message.seq)
+        else {
+            return Ok(None);
+        };
+        let head = *head;
+
+        self.set_latest_message_hash(group_id, &head)?;
+        self.remove(format!("{KEY_MESSAGE_INDEX}_{group_id}").as_str())?;
+        self.remove(format!("{KEY_GROUP_INFO}_{group_id}").as_str())?;
+        self.remove(format!("{KEY_MESSAGE_HEADERS}_{group_id}").as_str())?;
+        diagnostics::warn(
+            Category::Storage,
+            format!(
+                "rebuilt the head of group {group_id} at message {}",
+                hash_to_string(&head)
+            ),
+        );
+        Ok(Some(head))
+    }
+
+    /// Removes the stored messages which are not in the chain from the head of their group, e.g. the
+    /// leftovers of aborted writes or resolved forks. The groups without a head pointer are skipped, as
+    /// their chains may be recovered with [rebuild_head], and so are the groups whose chains are broken,
+    /// as the older messages are not reachable from the head.
+    ///
+    /// [rebuild_head]: SignedMessageStore::rebuild_head
+    pub(crate) fn collect_garbage(&mut self) -> Result<GcReport, String> {
+        let prefix = format!("{KEY_MESSAGE}_");
+        let mut stored: BTreeMap<String, Vec<(String, MessageHash)>> = BTreeMap::new();
+        for key in namespaced_keys(&prefix) {
+            let Some((group_id, hash)) = key[prefix.len()..].rsplit_once('_') else {
+                continue;
+            };
+            if let Some(hash) = hash_from_string(hash) {
+                stored
+                    .entry(group_id.to_string())
+                    .or_default()
+                    .push((key.clone(), hash));
+            }
+        }
+
+        let mut report = GcReport::default();
+        for (group_id, entries) in stored {
+            let Some(reachable) = self.reachable_messages(&group_id) else {
+                continue;
+            };
+            for (key, hash) in entries {
+                if reachable.contains(&hash) {
+                    continue;
+                }
+                let bytes = get_namespaced_item(&key).map_or(0, |value| value.len());
+                self.remove(&key)?;
+                report.removed += 1;
+                report.reclaimed_bytes += (key.len() + bytes) as u64;
+            }
+        }
+        if report.removed > 0 {
+            diagnostics::debug(
+                Category::Storage,
+                format!(
+                    "collected {} orphaned messages of {} bytes",
+                    report.removed, report.reclaimed_bytes
+                ),
+            );
+        }
+        Ok(report)
+    }
 
-            latest_msg = message.clone();
+    /// Returns the hashes of the messages in the chain from the head of the given group, or None if the
+    /// group has no head or the chain does not reach the first message or a pruned checkpoint.
+    fn reachable_messages(&self, group_id: &str) -> Option<HashSet<MessageHash>> {
+        let mut reachable = HashSet::new();
+        let mut hash = self.latest_message_hash(group_id)?;
+        loop {
+            let message = self.message(group_id, &hash)?;
+            if !reachable.insert(hash) {
+                return None;
+            }
+            if message.is_first_message() {
+                return Some(reachable);
+            }
+            hash = message.message.previous_hash;
+            if message.message.kind == MessageKind::Checkpoint
+                && get_namespaced_item(&format!(
+                    "{KEY_MESSAGE}_{group_id}_{}",
+                    hash_to_string(&hash)
+                ))
+                .is_none()
+            {
+                return Some(reachable);
+            }
         }
+    }
 
-        latest_msg.is_first_message()
+    /// Returns the size (in bytes) of message data above which the data is compressed before storage.
+    pub(crate) fn compression_threshold(&self) -> usize {
+        self.get(KEY_COMPRESSION_THRESHOLD)
+            .unwrap_or(DEFAULT_COMPRESSION_THRESHOLD)
     }
 
+    /// Sets the size (in bytes) of message data above which the data is compressed before storage.
+    pub(crate) fn set_compression_threshold(&mut self, threshold: usize) -> Result<(), String> {
+        self.set(KEY_COMPRESSION_THRESHOLD, threshold)
+    }
+
+    /// Returns the message with the given hash as it is stored, i.e. possibly compressed, with the number
+    /// of bytes it takes in the local storage.
+    fn stored_message(
+        &self,
+        group_id: &str,
+        hash: &MessageHash,
+    ) -> Option<(u64, SignedMessage<Identity, Signature>)> {
+        let message: SignedMessage<Identity, Signature> = self
+            .get_uncached(format!("{KEY_MESSAGE}_{group_id}_{}", hash_to_string(hash)).as_str())?;
+        Some((stored_size(&message), message))
+    }
+
+    /// Saves the message under the given hash. The message data is compressed if it is larger than
+    /// the compression threshold. The `compressed` flag of the given message is ignored because
+    /// the data passed in is always uncompressed. It returns the number of bytes the message takes in the
+    /// local storage.
     fn set_message(
         &mut self,
         group_id: &str,
         hash: &MessageHash,
-        message: SignedMessage<Identity, Signature>,
-    ) {
+        message: &SignedMessage<Identity, Signature>,
+    ) -> Result<u64, String> {
+        let deflated = (message.message.data.len() > self.compression_threshold())
+            .then(|| {
+                miniz_oxide::deflate::compress_to_vec(&message.message.data, COMPRESSION_LEVEL)
+            })
+            .filter(|deflated| deflated.len() < message.message.data.len());
+
+        // the message is copied without its data only if the stored data or flag differ
+        let key = format!("{KEY_MESSAGE}_{group_id}_{}", hash_to_string(hash));
+        match deflated {
+            Some(deflated) => self.set_stored_message(&key, message.with_data(deflated, true)),
+            None if message.message.compressed => self
+                .set_stored_message(&key, message.with_data(message.message.data.clone(), false)),
+            None => self.set_stored_message(&key, message),
+        }
+    }
+
+    /// Saves the message as it is stored, and returns the number of bytes it takes in the local storage.
+    fn set_stored_message<M: Borrow<SignedMessage<Identity, Signature>>>(
+        &mut self,
+        key: &str,
+        message: M,
+    ) -> Result<u64, String> {
+        let bytes = stored_size(message.borrow());
+        self.set(key, message.borrow())?;
+        Ok(bytes)
+    }
+
+    fn set_group_info(&mut self, group_id: &str, info: &GroupInfo) -> Result<(), String> {
+        self.set(format!("{KEY_GROUP_INFO}_{group_id}").as_str(), info)
+    }
+
+    fn set_headers(&mut self, group_id: &str, headers: &HeaderIndex) -> Result<(), String> {
         self.set(
-            format!("{KEY_MESSAGE}_{group_id}_{:x?}", hash).as_str(),
-            message,
-        );
+            format!("{KEY_MESSAGE_HEADERS}_{group_id}").as_str(),
+            headers,
+        )
     }
 
-    fn set_latest_message_hash(&mut self, group_id: &str, hash: &MessageHash) {
+    fn set_latest_message_hash(
+        &mut self,
+        group_id: &str,
+        hash: &MessageHash,
+    ) -> Result<(), String> {
         self.set(
             format!("{KEY_LATEST_MESSAGEHASH}_{group_id}",).as_str(),
             hash,
-        );
+        )
     }
 }
 
 impl SerdeLocalStore for SignedMessageStore {}
+
+/// Returns true if the chain from the message with the given hash is continuous down to the first message,
+/// or to a checkpoint whose parent is not stored.
+fn is_rooted(
+    messages: &BTreeMap<MessageHash, SignedMessage<Identity, Signature>>,
+    hash: &MessageHash,
+) -> bool {
+    let mut current = messages.get(hash);
+    while let Some(message) = current {
+        if message.is_first_message() {
+            return true;
+        }
+        match messages.get(&message.message.previous_hash) {
+            Some(parent) if parent.next_seq() == Some(message.seq) => current = Some(parent),
+            Some(_) => return false,
+            None => return message.message.kind == MessageKind::Checkpoint,
+        }
+    }
+    false
+}
+
+/// Reports the message which fails the validation to the diagnostics, and returns false.
+fn invalid(group_id: &str, hash: &MessageHash, reason: impl Display) -> bool {
+    diagnostics::warn(
+        Category::Validation,
+        format!(
+            "message {} of group {group_id} is invalid: {reason}",
+            hash_to_string(hash)
+        ),
+    );
+    false
+}
+
+/// Returns the message with its data inflated if it is stored compressed.
+fn decompressed(
+    mut message: SignedMessage<Identity, Signature>,
+) -> Option<SignedMessage<Identity, Signature>> {
+    if message.message.compressed {
+        message.message.data =
+            miniz_oxide::inflate::decompress_to_vec(&message.message.data).ok()?;
+        message.message.compressed = false;
+    }
+    Some(message)
+}
+
+/// Returns the number of bytes of the serialized message.
+fn stored_size(message: &SignedMessage<Identity, Signature>) -> u64 {
+    serde_json::to_vec(message).map_or(0, |bytes| bytes.len() as u64)
+}
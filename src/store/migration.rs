@@ -0,0 +1,145 @@
+//! Provides the versioning of the layout of local storage, e.g. the keys and the encodings of the items.
+//!
+//! The version of the layout is stored with the items, and the migrations from older layouts run on the
+//! first access of the stores, so that the chains stored by earlier versions of the library are kept
+//! when it is upgraded. Storage without the version item is at version 0.
+
+use std::cell::Cell;
+
+use serde_json::{Map, Value};
+
+use crate::core::message::to_hex;
+
+use super::{
+    cache, get_item, journal, namespace::NAMESPACE_SEPARATOR, remove_item, set_item,
+    settings::KEY_GROUP_SETTINGS, storage_keys,
+};
+
+/// The item of the version of the layout. It is shared by all namespaces.
+const KEY_SCHEMA_VERSION: &str = "\u{2}schema_version";
+
+/// The migrations in order. The migration at index `i` upgrades the layout from version `i` to `i + 1`.
+const MIGRATIONS: &[fn() -> Result<(), String>] = &[migrate_hash_keys, migrate_group_settings];
+
+/// The version of the layout written by this version of the library.
+pub(crate) const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+thread_local! {
+    /// Whether the migrations have run since the library was loaded. The storage of native builds is
+    /// per thread, so it is kept per thread too.
+    static MIGRATED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs the migrations, if they have not run since the library was loaded. The writes interrupted when the
+/// library was last run are recovered first (see [journal](super::journal)), as they are in the layout of
+/// that run.
+pub(crate) fn ensure_migrated() {
+    if !MIGRATED.replace(true) && journal::recover().and_then(|_| migrate()).is_err() {
+        // the failed writes are reported to the diagnostics, and run again on the next access
+        MIGRATED.set(false);
+    }
+}
+
+/// Runs the migrations again on the next access, e.g. after the storage is replaced.
+#[cfg(feature = "web")]
+pub(crate) fn reset() {
+    MIGRATED.set(false);
+}
+
+/// Returns the version of the layout of local storage.
+pub(crate) fn schema_version() -> u32 {
+    get_item(KEY_SCHEMA_VERSION)
+        .and_then(|version| version.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Runs the migrations from the stored version up to [SCHEMA_VERSION], and returns the resulting
+/// version. The version is stored after each migration, so that an interrupted run resumes from the
+/// migration which did not complete. Storage written by a newer version of the library is not changed.
+pub(crate) fn migrate() -> Result<u32, String> {
+    let mut version = schema_version();
+    while let Some(migration) = MIGRATIONS.get(version as usize) {
+        let result = migration();
+        cache::clear();
+        result?;
+        version += 1;
+        set_item(KEY_SCHEMA_VERSION, &version.to_string())?;
+    }
+    Ok(version)
+}
+
+/// Marks the layout of local storage as the current version, e.g. after it is cleared.
+pub(crate) fn set_current_version() -> Result<(), String> {
+    set_item(KEY_SCHEMA_VERSION, &SCHEMA_VERSION.to_string())
+}
+
+/// Version 1: renames the items whose keys contain hashes in the debug format of byte arrays, e.g.
+/// `[1a, 2b, ..]`, to the keys with the hashes in hex. It renames the items of all namespaces.
+fn migrate_hash_keys() -> Result<(), String> {
+    for key in storage_keys() {
+        let Some(new_key) = hex_hash_key(&key) else {
+            continue;
+        };
+        if let Some(value) = get_item(&key) {
+            set_item(&new_key, &value)?;
+            remove_item(&key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the key with the hash in the debug format replaced by its hex encoding, if the key has one.
+/// The hash is the last bracketed part, which is followed only by the suffix of a chunk key.
+fn hex_hash_key(key: &str) -> Option<String> {
+    let start = key.rfind('[')?;
+    let end = start + key[start..].find(']')?;
+    let bytes = key[start + 1..end]
+        .split(", ")
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    (bytes.len() == 32).then(|| format!("{}{}{}", &key[..start], to_hex(&bytes), &key[end + 1..]))
+}
+
+/// Version 2: moves the allowed signers and the retention policies of groups, which were separate items,
+/// into the settings of the groups. It moves the items of all namespaces.
+fn migrate_group_settings() -> Result<(), String> {
+    // the prefixes of the keys of version 1 and the fields of the settings to which they are moved
+    const FIELDS: [(&str, &str); 2] = [
+        ("grpsigners_", "allowed_signers"),
+        ("grpretention_", "retention"),
+    ];
+    for key in storage_keys() {
+        let (namespace, name) = split_namespace(&key);
+        let Some((field, group_id)) = FIELDS.iter().find_map(|(prefix, field)| {
+            name.strip_prefix(prefix).map(|group_id| (*field, group_id))
+        }) else {
+            continue;
+        };
+        let Some(value) =
+            get_item(&key).and_then(|value| serde_json::from_str::<Value>(&value).ok())
+        else {
+            continue;
+        };
+        let settings_key = format!("{namespace}{KEY_GROUP_SETTINGS}_{group_id}");
+        let mut settings = get_item(&settings_key)
+            .and_then(|settings| serde_json::from_str::<Map<String, Value>>(&settings).ok())
+            .unwrap_or_default();
+        settings.insert(field.to_string(), value);
+        set_item(&settings_key, &Value::Object(settings).to_string())?;
+        remove_item(&key)?;
+    }
+    Ok(())
+}
+
+/// Splits the key into the prefix of its namespace, which is empty in the default namespace, and the key
+/// in the namespace.
+fn split_namespace(key: &str) -> (&str, &str) {
+    let end = key
+        .strip_prefix(NAMESPACE_SEPARATOR)
+        .and_then(|rest| rest.find(NAMESPACE_SEPARATOR))
+        .map(|end| end + 2 * NAMESPACE_SEPARATOR.len_utf8());
+    match end {
+        Some(end) => key.split_at(end),
+        None => ("", key),
+    }
+}
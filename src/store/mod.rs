@@ -1,38 +1,276 @@
 //! Provides a local storage implementation for the store.
 
+use crate::{
+    diagnostics::{self, Category},
+    metrics::{self, Operation},
+};
+#[cfg(feature = "web")]
+use local_storage::{get_item, remove_item, set_item, storage_keys};
+#[cfg(not(feature = "web"))]
+use memory::{get_item, remove_item, set_item, storage_keys};
+use migration::ensure_migrated;
+use namespace::namespaced_key;
 use serde::{de::DeserializeOwned, Serialize};
+use zeroize::Zeroizing;
 
 pub(crate) mod account;
+pub(crate) mod acknowledgment;
+pub(crate) mod anchor;
+pub(crate) mod attestation;
+pub(crate) mod auth;
+pub(crate) mod cache;
+pub(crate) mod contact;
+pub(crate) mod draft;
+#[cfg(not(feature = "web"))]
+pub mod faults;
 pub(crate) mod group;
+pub(crate) mod journal;
+pub(crate) mod key;
+pub(crate) mod light;
+pub(crate) mod limit;
+#[cfg(feature = "web")]
+pub mod local_storage;
+#[cfg(not(feature = "web"))]
+pub mod memory;
+pub(crate) mod merkle;
 pub(crate) mod message;
+pub(crate) mod migration;
+pub(crate) mod namespace;
+pub(crate) mod outbox;
+pub(crate) mod pending;
+pub(crate) mod pin;
+pub(crate) mod profile;
+pub(crate) mod quarantine;
+pub(crate) mod ratchet;
+pub(crate) mod reaction;
+pub(crate) mod reference;
+pub(crate) mod search;
+pub(crate) mod settings;
+#[cfg(feature = "web")]
+pub(crate) mod transfer;
+pub(crate) mod transparency;
+pub(crate) mod unread;
 
 /// SerdeLocalStore is a trait that provides methods to get and set values from local storage.
-/// The item to store must be serializable and deserializable.
+/// The item to store must be serializable and deserializable. The keys are in the current namespace
+/// (see [NamespaceStore](crate::store::namespace::NamespaceStore)). The layout of local storage is
+/// migrated on the first access (see [migration](crate::store::migration)), and the parsed values are
+/// cached (see [cache](crate::store::cache)). The writes in an atomic write are journaled (see
+/// [journal](crate::store::journal)).
 pub(crate) trait SerdeLocalStore {
-    fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
-        get_from_localstorage(key).map(|str_value| serde_json::from_str(&str_value).ok())?
+    fn get<T: DeserializeOwned + Clone + 'static>(&self, key: &str) -> Option<T> {
+        ensure_migrated();
+        let key = namespaced_key(key);
+        if let Some(value) = cache::get(&key) {
+            return value;
+        }
+        let value: Option<T> =
+            get_from_localstorage(&key).and_then(|str_value| serde_json::from_str(&str_value).ok());
+        // the values written in the current atomic write are not cached until it is committed
+        if journal::pending(&key).is_none() {
+            cache::insert(&key, value.clone());
+        }
+        value
+    }
+
+    /// Gets a value without the cache, for the large values which are rarely read twice, e.g. the
+    /// messages read when walking a chain. The value is not cloned into the cache.
+    fn get_uncached<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        ensure_migrated();
+        let key = namespaced_key(key);
+        get_from_localstorage(&key).and_then(|str_value| parse(&key, &str_value))
     }
 
-    fn set<T: Serialize>(&mut self, key: &str, value: T) {
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), String> {
+        ensure_migrated();
         if let Ok(str_value) = serde_json::to_string(&value) {
-            set_to_localstorage(key, &str_value)
+            set_to_localstorage(&namespaced_key(key), &str_value)?;
+        }
+        Ok(())
+    }
+
+    /// Gets a value which holds secrets. The JSON read from local storage is wiped after it is parsed.
+    fn get_secret<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        ensure_migrated();
+        let key = namespaced_key(key);
+        let str_value = Zeroizing::new(get_from_localstorage(&key)?);
+        parse(&key, &str_value)
+    }
+
+    /// Sets a value which holds secrets. The serialized JSON is wiped after it is written to local storage.
+    fn set_secret<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), String> {
+        ensure_migrated();
+        if let Ok(str_value) = serde_json::to_string(&value).map(Zeroizing::new) {
+            set_to_localstorage(&namespaced_key(key), &str_value)?;
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), String> {
+        ensure_migrated();
+        remove_from_localstorage(&namespaced_key(key))
+    }
+}
+
+/// Gets the raw item with the key in the current namespace, e.g. for the [StorageBackend](crate::chain::StorageBackend).
+pub(crate) fn get_namespaced_item(key: &str) -> Option<String> {
+    ensure_migrated();
+    get_from_localstorage(&namespaced_key(key))
+}
+
+/// Sets the raw item with the key in the current namespace.
+pub(crate) fn set_namespaced_item(key: &str, value: &str) -> Result<(), String> {
+    ensure_migrated();
+    set_to_localstorage(&namespaced_key(key), value)
+}
+
+/// Removes the raw item with the key in the current namespace.
+pub(crate) fn remove_namespaced_item(key: &str) -> Result<(), String> {
+    ensure_migrated();
+    remove_from_localstorage(&namespaced_key(key))
+}
+
+/// Returns the keys of the items in the current namespace which start with the prefix, without the
+/// prefix of the namespace.
+pub(crate) fn namespaced_keys(prefix: &str) -> Vec<String> {
+    ensure_migrated();
+    let namespaced_prefix = namespaced_key(prefix);
+    let namespace_len = namespaced_prefix.len() - prefix.len();
+    // the items written in the current atomic write are not in the storage yet
+    let (written, removed) = journal::pending_keys();
+    let mut keys: Vec<String> = storage_keys()
+        .into_iter()
+        .filter(|key| !removed.contains(key) && !written.contains(key))
+        .collect();
+    keys.extend(written);
+    keys.into_iter()
+        .filter(|key| key.starts_with(&namespaced_prefix))
+        .map(|key| key[namespace_len..].to_string())
+        .collect()
+}
+
+/// Parses the item of local storage. The items which cannot be parsed are reported to the diagnostics.
+fn parse<T: DeserializeOwned>(key: &str, str_value: &str) -> Option<T> {
+    match serde_json::from_str(str_value) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            diagnostics::warn(
+                Category::Storage,
+                format!("fail to parse item {key}: {err}"),
+            );
+            None
         }
     }
 }
 
+/// The maximum length of a single item written to local storage. Longer values are split into
+/// chunks stored under separate keys.
+const MAX_ITEM_LENGTH: usize = 256 * 1024;
+/// The prefix of the item which records the number of chunks of a chunked value. Serialized JSON
+/// never starts with a control character, so it cannot be confused with an ordinary value.
+const CHUNK_MARKER: &str = "\u{1}chunks:";
+
+fn chunk_key(key: &str, idx: usize) -> String {
+    format!("{key}#chunk{idx}")
+}
+
+/// Returns the number of chunks if the raw item is a chunk marker.
+fn chunk_count(raw_value: &str) -> Option<usize> {
+    raw_value.strip_prefix(CHUNK_MARKER)?.parse().ok()
+}
+
+/// Gets the value from local storage, reassembling it if it was split into chunks. The value written in
+/// the current atomic write is read from memory.
 fn get_from_localstorage(key: &str) -> Option<String> {
-    web_sys::window()?
-        .local_storage()
-        .ok()??
-        .get_item(key)
-        .ok()?
-}
-fn set_to_localstorage(key: &str, value: &str) {
-    web_sys::window()
-        .unwrap()
-        .local_storage()
-        .unwrap()
-        .unwrap()
-        .set_item(key, value)
-        .unwrap();
+    if let Some(value) = journal::pending(key) {
+        return value;
+    }
+    metrics::measure(Operation::StorageRead, || {
+        let value = get_item(key)?;
+        match chunk_count(&value) {
+            Some(count) => (0..count)
+                .map(|idx| get_item(&chunk_key(key, idx)))
+                .collect(),
+            None => Some(value),
+        }
+    })
+}
+
+/// Sets the value to local storage. Values longer than [MAX_ITEM_LENGTH] are split into chunks,
+/// and the chunks left from a previous longer value are removed. The marker of the chunks is written after
+/// them, so that the value is either completely written or not found. In an atomic write, the value is
+/// kept in memory until the write is committed. It fails if an item cannot be written, e.g. as the quota
+/// is exceeded.
+fn set_to_localstorage(key: &str, value: &str) -> Result<(), String> {
+    if journal::record(key, Some(value)) {
+        cache::invalidate(key);
+        return Ok(());
+    }
+    metrics::measure(Operation::StorageWrite, || {
+        cache::invalidate(key);
+        let previous_count = get_item(key)
+            .and_then(|value| chunk_count(&value))
+            .unwrap_or(0);
+
+        let count = if value.len() > MAX_ITEM_LENGTH {
+            let chunks = split_at_char_boundaries(value, MAX_ITEM_LENGTH);
+            for (idx, chunk) in chunks.iter().enumerate() {
+                set_item(&chunk_key(key, idx), chunk)?;
+            }
+            set_item(key, &format!("{CHUNK_MARKER}{}", chunks.len()))?;
+            chunks.len()
+        } else {
+            set_item(key, value)?;
+            0
+        };
+
+        for idx in count..previous_count {
+            remove_item(&chunk_key(key, idx))?;
+        }
+        Ok(())
+    })
+}
+
+/// Removes the value from local storage, including its chunks if it was split. In an atomic write, the
+/// removal is kept in memory until the write is committed.
+fn remove_from_localstorage(key: &str) -> Result<(), String> {
+    if journal::record(key, None) {
+        cache::invalidate(key);
+        return Ok(());
+    }
+    metrics::measure(Operation::StorageWrite, || {
+        cache::invalidate(key);
+        if let Some(count) = get_item(key).and_then(|value| chunk_count(&value)) {
+            for idx in 0..count {
+                remove_item(&chunk_key(key, idx))?;
+            }
+        }
+        remove_item(key)
+    })
+}
+
+/// Splits the string into pieces of at most `max_len` bytes without breaking UTF-8 characters.
+fn split_at_char_boundaries(value: &str, max_len: usize) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = value;
+    while !rest.is_empty() {
+        let mut end = max_len.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, remaining) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = remaining;
+    }
+    chunks
+}
+
+/// Removes all the items of the storage, and marks it as the current layout.
+pub(crate) fn clear_storage() -> Result<(), String> {
+    #[cfg(feature = "web")]
+    local_storage::clear_items()?;
+    #[cfg(not(feature = "web"))]
+    memory::clear_items()?;
+    cache::clear();
+    migration::set_current_version()
 }
@@ -0,0 +1,106 @@
+//! Provides a struct `NamespaceStore` for the isolated storage namespaces, e.g. "work" and "personal".
+//!
+//! Each namespace has its own accounts, groups and chains. The items of the stores are written under
+//! keys prefixed with the current namespace, except in the default namespace whose keys are not prefixed,
+//! so that the data written before namespaces were introduced belongs to it. The items of this store
+//! are shared by all namespaces.
+
+use super::{get_item, remove_from_localstorage, set_item, storage_keys};
+
+/// The name of the namespace whose keys are not prefixed.
+pub(crate) const DEFAULT_NAMESPACE: &str = "default";
+
+const KEY_CURRENT_NAMESPACE: &str = "\u{2}namespace";
+const KEY_NAMESPACE_LIST: &str = "\u{2}namespaces";
+/// The separator around the namespace in the prefix of keys. Keys of the stores never start with a
+/// control character, so prefixed keys cannot be confused with keys in the default namespace.
+pub(super) const NAMESPACE_SEPARATOR: char = '\u{2}';
+
+/// NamespaceStore is a store for the storage namespaces.
+#[derive(Default)]
+pub(crate) struct NamespaceStore {}
+
+impl NamespaceStore {
+    /// Returns the current namespace.
+    pub(crate) fn current_namespace(&self) -> String {
+        current_namespace()
+    }
+
+    /// Returns the namespaces, starting with the default namespace.
+    pub(crate) fn namespaces(&self) -> Vec<String> {
+        let mut namespaces = vec![DEFAULT_NAMESPACE.to_string()];
+        namespaces.extend(
+            get_item(KEY_NAMESPACE_LIST)
+                .and_then(|value| serde_json::from_str::<Vec<String>>(&value).ok())
+                .unwrap_or_default(),
+        );
+        namespaces
+    }
+
+    /// Switches to the namespace with the given name, creating it if it does not exist. It returns
+    /// false if the name is empty or contains control characters, and fails if the storage cannot be
+    /// written.
+    pub(crate) fn switch_namespace(&mut self, name: &str) -> Result<bool, String> {
+        if name.is_empty() || name.chars().any(char::is_control) {
+            return Ok(false);
+        }
+        let mut namespaces = self.namespaces();
+        if !namespaces.iter().any(|namespace| namespace == name) {
+            namespaces.push(name.to_string());
+            self.set_namespaces(namespaces)?;
+        }
+        set_item(KEY_CURRENT_NAMESPACE, name)?;
+        Ok(true)
+    }
+
+    /// Deletes the namespace with the given name and all of its items. If it is the current namespace,
+    /// the default namespace becomes current. The default namespace cannot be deleted. It fails if the
+    /// storage cannot be written.
+    pub(crate) fn delete_namespace(&mut self, name: &str) -> Result<bool, String> {
+        let mut namespaces = self.namespaces();
+        if name == DEFAULT_NAMESPACE || !namespaces.iter().any(|namespace| namespace == name) {
+            return Ok(false);
+        }
+        let prefix = namespace_prefix(name);
+        storage_keys()
+            .iter()
+            .filter(|key| key.starts_with(&prefix))
+            .try_for_each(|key| remove_from_localstorage(key))?;
+
+        namespaces.retain(|namespace| namespace != name);
+        self.set_namespaces(namespaces)?;
+        if current_namespace() == name {
+            set_item(KEY_CURRENT_NAMESPACE, DEFAULT_NAMESPACE)?;
+        }
+        Ok(true)
+    }
+
+    fn set_namespaces(&mut self, namespaces: Vec<String>) -> Result<(), String> {
+        let namespaces: Vec<String> = namespaces
+            .into_iter()
+            .filter(|namespace| namespace != DEFAULT_NAMESPACE)
+            .collect();
+        set_item(
+            KEY_NAMESPACE_LIST,
+            &serde_json::to_string(&namespaces).unwrap(),
+        )
+    }
+}
+
+/// Returns the key of the item in the current namespace.
+pub(crate) fn namespaced_key(key: &str) -> String {
+    let namespace = current_namespace();
+    if namespace == DEFAULT_NAMESPACE {
+        key.to_string()
+    } else {
+        format!("{}{key}", namespace_prefix(&namespace))
+    }
+}
+
+fn current_namespace() -> String {
+    get_item(KEY_CURRENT_NAMESPACE).unwrap_or(DEFAULT_NAMESPACE.to_string())
+}
+
+fn namespace_prefix(namespace: &str) -> String {
+    format!("{NAMESPACE_SEPARATOR}{namespace}{NAMESPACE_SEPARATOR}")
+}
@@ -0,0 +1,40 @@
+//! Provides a struct `OutboxStore` for storing the messages queued in the outbox.
+
+use crate::outbox::OutboxEntry;
+
+use super::SerdeLocalStore;
+
+const KEY_OUTBOX: &str = "outbox";
+
+/// OutboxStore is a store for the messages queued in the outbox, in the order they are queued. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct OutboxStore {}
+
+impl OutboxStore {
+    /// Returns the queued messages, in the order they are queued.
+    pub(crate) fn entries(&self) -> Vec<OutboxEntry> {
+        self.get(KEY_OUTBOX).unwrap_or_default()
+    }
+
+    /// Returns the last queued message of the given group.
+    pub(crate) fn last_entry(&self, group_id: &str) -> Option<OutboxEntry> {
+        self.entries()
+            .into_iter()
+            .rev()
+            .find(|entry| entry.group_id == group_id)
+    }
+
+    /// Queues the message at the end of the outbox.
+    pub(crate) fn push_entry(&mut self, entry: OutboxEntry) -> Result<(), String> {
+        let mut entries = self.entries();
+        entries.push(entry);
+        self.set(KEY_OUTBOX, entries)
+    }
+
+    /// Replaces the queued messages.
+    pub(crate) fn set_entries(&mut self, entries: Vec<OutboxEntry>) -> Result<(), String> {
+        self.set(KEY_OUTBOX, entries)
+    }
+}
+
+impl SerdeLocalStore for OutboxStore {}
@@ -0,0 +1,75 @@
+//! Provides a struct `PendingStore` for storing the messages which arrived before their ancestors.
+
+use sha2::Sha256;
+
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, SignedMessage},
+    message::Signature,
+};
+
+use super::SerdeLocalStore;
+
+const KEY_PENDING: &str = "pending";
+
+/// PendingStore is the reordering buffer of the messages which arrived before their ancestors, keyed by
+/// their missing parent hashes. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct PendingStore {}
+
+impl PendingStore {
+    /// Returns the pending messages of the given group, ordered by their sequence numbers.
+    pub(crate) fn pending(&self, group_id: &str) -> Vec<SignedMessage<Identity, Signature>> {
+        let mut pending: Vec<_> = self
+            .all_pending(group_id)
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect();
+        pending.sort_by_key(|msg| msg.seq);
+        pending
+    }
+
+    /// Buffers the message until its parent is written, if it is not buffered yet.
+    pub(crate) fn add_pending(
+        &mut self,
+        group_id: &str,
+        message: SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let mut all_pending = self.all_pending(group_id);
+        let hash = message.hash::<Sha256>();
+        if all_pending
+            .iter()
+            .any(|(_, msg)| msg.hash::<Sha256>() == hash)
+        {
+            return Ok(());
+        }
+        all_pending.push((message.message.previous_hash, message));
+        self.set(format!("{KEY_PENDING}_{group_id}").as_str(), all_pending)
+    }
+
+    /// Removes and returns the pending messages whose parent is the message with the given hash.
+    pub(crate) fn take_children(
+        &mut self,
+        group_id: &str,
+        parent: &MessageHash,
+    ) -> Result<Vec<SignedMessage<Identity, Signature>>, String> {
+        let (children, others): (Vec<_>, Vec<_>) = self
+            .all_pending(group_id)
+            .into_iter()
+            .partition(|(previous_hash, _)| previous_hash == parent);
+        if !children.is_empty() {
+            self.set(format!("{KEY_PENDING}_{group_id}").as_str(), others)?;
+        }
+        Ok(children.into_iter().map(|(_, msg)| msg).collect())
+    }
+
+    fn all_pending(
+        &self,
+        group_id: &str,
+    ) -> Vec<(MessageHash, SignedMessage<Identity, Signature>)> {
+        self.get(format!("{KEY_PENDING}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+}
+
+impl SerdeLocalStore for PendingStore {}
@@ -0,0 +1,57 @@
+//! Provides a struct `PinStore` for storing the agreement keys pinned on first use.
+
+use crate::account::Identity;
+
+use super::SerdeLocalStore;
+
+const KEY_PINNED_KEYS: &str = "pins";
+
+/// PinStore is a store for the agreement keys of signers which are pinned when they are first seen in
+/// a group. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct PinStore {}
+
+impl PinStore {
+    /// Returns the pinned agreement key of the signer in the given group.
+    pub(crate) fn pinned_key(&self, group_id: &str, signer: &Identity) -> Option<Vec<u8>> {
+        self.pinned_keys(group_id)
+            .into_iter()
+            .find(|(id, _)| id == signer)
+            .map(|(_, key)| key)
+    }
+
+    /// Pins the agreement key of the signer in the given group, if no key is pinned yet.
+    pub(crate) fn pin_key(
+        &mut self,
+        group_id: &str,
+        signer: &Identity,
+        key: Vec<u8>,
+    ) -> Result<(), String> {
+        let mut pinned_keys = self.pinned_keys(group_id);
+        if pinned_keys.iter().any(|(id, _)| id == signer) {
+            return Ok(());
+        }
+        pinned_keys.push((signer.clone(), key));
+        self.set(
+            format!("{KEY_PINNED_KEYS}_{group_id}").as_str(),
+            pinned_keys,
+        )
+    }
+
+    /// Removes the pinned agreement key of the signer in the given group, so that the next key is pinned.
+    pub(crate) fn unpin_key(&mut self, group_id: &str, signer: &Identity) -> Result<(), String> {
+        let mut pinned_keys = self.pinned_keys(group_id);
+        pinned_keys.retain(|(id, _)| id != signer);
+        self.set(
+            format!("{KEY_PINNED_KEYS}_{group_id}").as_str(),
+            pinned_keys,
+        )
+    }
+
+    fn pinned_keys(&self, group_id: &str) -> Vec<(Identity, Vec<u8>)> {
+        self.get(format!("{KEY_PINNED_KEYS}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+}
+
+impl SerdeLocalStore for PinStore {}
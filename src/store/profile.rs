@@ -0,0 +1,45 @@
+//! Provides a struct `ProfileStore` for storing the profile chains of identities.
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    account::Identity,
+    core::message::{to_hex, SignedMessage},
+    message::Signature,
+};
+
+use super::SerdeLocalStore;
+
+const KEY_PROFILE: &str = "profile";
+
+/// ProfileStore is a store for the profile chains of identities. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct ProfileStore {}
+
+impl ProfileStore {
+    /// Returns the profile updates of the identity, from the oldest.
+    pub(crate) fn profile_updates(
+        &self,
+        identity: &Identity,
+    ) -> Vec<SignedMessage<Identity, Signature>> {
+        self.get(profile_key(identity).as_str()).unwrap_or_default()
+    }
+
+    /// Appends the update to the profile chain of its signer. The update must be validated with
+    /// [is_valid_update](crate::profile::is_valid_update).
+    pub(crate) fn add_profile_update(
+        &mut self,
+        update: SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let key = profile_key(&update.id);
+        let mut updates = self.profile_updates(&update.id);
+        updates.push(update);
+        self.set(key.as_str(), updates)
+    }
+}
+
+impl SerdeLocalStore for ProfileStore {}
+
+fn profile_key(identity: &Identity) -> String {
+    format!("{KEY_PROFILE}_{}", to_hex(&Sha256::digest(identity)))
+}
@@ -0,0 +1,71 @@
+//! Provides a struct `QuarantineStore` for storing the quarantined messages of groups.
+
+use sha2::Sha256;
+
+use crate::{core::message::MessageHash, quarantine::QuarantineEntry};
+
+use super::SerdeLocalStore;
+
+const KEY_QUARANTINE: &str = "quarantine";
+
+/// QuarantineStore is a store for the inbound messages rejected for their positions in the chain. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct QuarantineStore {}
+
+impl QuarantineStore {
+    /// Returns the quarantined messages of the given group, in the order they are received.
+    pub(crate) fn entries(&self, group_id: &str) -> Vec<QuarantineEntry> {
+        self.get(format!("{KEY_QUARANTINE}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+
+    /// Quarantines the message in the given group. If the message is already quarantined, only the
+    /// reason is updated.
+    pub(crate) fn add_entry(
+        &mut self,
+        group_id: &str,
+        entry: QuarantineEntry,
+    ) -> Result<(), String> {
+        let mut entries = self.entries(group_id);
+        let hash = entry.signed_message.hash::<Sha256>();
+        match entries
+            .iter_mut()
+            .find(|e| e.signed_message.hash::<Sha256>() == hash)
+        {
+            Some(e) => e.reason = entry.reason,
+            None => entries.push(entry),
+        }
+        self.set_entries(group_id, entries)
+    }
+
+    /// Removes the quarantined message with the given hash. It returns true if the message was quarantined.
+    pub(crate) fn remove_entry(
+        &mut self,
+        group_id: &str,
+        hash: &MessageHash,
+    ) -> Result<bool, String> {
+        let mut entries = self.entries(group_id);
+        let len = entries.len();
+        entries.retain(|entry| &entry.signed_message.hash::<Sha256>() != hash);
+        if entries.len() == len {
+            return Ok(false);
+        }
+        self.set_entries(group_id, entries)?;
+        Ok(true)
+    }
+
+    /// Replaces the quarantined messages of the given group.
+    pub(crate) fn set_entries(
+        &mut self,
+        group_id: &str,
+        entries: Vec<QuarantineEntry>,
+    ) -> Result<(), String> {
+        if entries.is_empty() {
+            self.remove(format!("{KEY_QUARANTINE}_{group_id}").as_str())
+        } else {
+            self.set(format!("{KEY_QUARANTINE}_{group_id}").as_str(), entries)
+        }
+    }
+}
+
+impl SerdeLocalStore for QuarantineStore {}
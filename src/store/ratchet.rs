@@ -0,0 +1,133 @@
+//! Provides a struct `RatchetStore` for storing the ratchet states of encrypted groups, and the data of the
+//! encrypted messages deciphered on this device.
+//!
+//! The message keys of the ratchet are deleted once they are used, and the superseded group content keys
+//! are not kept (see [GroupKeyStore](super::key::GroupKeyStore)), so the messages cannot be decrypted
+//! again. Their data is kept instead, when the messages are signed or first read, so that they are read
+//! again without their keys.
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    encryption::{self, GroupKey},
+    ratchet::RatchetState,
+};
+
+use super::SerdeLocalStore;
+
+const KEY_RATCHET: &str = "ratchet";
+const KEY_RATCHET_DATA: &str = "ratchetdata";
+const KEY_RATCHET_STORAGE_KEY: &str = "ratchet_storekey";
+
+/// DecipheredData is the data of an encrypted message by the digest of its ciphertext.
+type DecipheredData = Vec<([u8; 32], Vec<u8>)>;
+
+/// RatchetStore is a store for ratchet states. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+///
+/// The states and the deciphered data are wrapped with a storage key of the device before they are
+/// saved. The storage key is read and written as a secret, i.e. it is not cached in memory.
+#[derive(Default)]
+pub(crate) struct RatchetStore {}
+
+impl RatchetStore {
+    /// Returns the ratchet state of the given group, if the ratchet is started.
+    pub(crate) fn ratchet(&self, group_id: &str) -> Option<RatchetState> {
+        self.unwrap(format!("{KEY_RATCHET}_{group_id}").as_str())
+    }
+
+    /// Sets the ratchet state of the given group.
+    pub(crate) fn set_ratchet(
+        &mut self,
+        group_id: &str,
+        state: &RatchetState,
+    ) -> Result<(), String> {
+        self.wrap(format!("{KEY_RATCHET}_{group_id}").as_str(), state)
+    }
+
+    /// Returns the deciphered data of the message of the given group with the ciphertext.
+    pub(crate) fn deciphered(&self, group_id: &str, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        let digest: [u8; 32] = Sha256::digest(ciphertext).into();
+        self.deciphered_data(group_id)
+            .into_iter()
+            .find_map(|(d, data)| (d == digest).then_some(data))
+    }
+
+    /// Keeps the deciphered data of the message of the given group with the ciphertext.
+    pub(crate) fn set_deciphered(
+        &mut self,
+        group_id: &str,
+        ciphertext: &[u8],
+        data: &[u8],
+    ) -> Result<(), String> {
+        let digest: [u8; 32] = Sha256::digest(ciphertext).into();
+        let mut deciphered = self.deciphered_data(group_id);
+        if deciphered.iter().any(|(d, _)| d == &digest) {
+            return Ok(());
+        }
+        deciphered.push((digest, data.to_vec()));
+        self.wrap(
+            format!("{KEY_RATCHET_DATA}_{group_id}").as_str(),
+            &deciphered,
+        )
+    }
+
+    /// Removes the deciphered data of the messages of the given group whose ciphertexts are not in the
+    /// given ciphertexts, e.g. after the messages are purged or pruned.
+    pub(crate) fn retain_deciphered<'a>(
+        &mut self,
+        group_id: &str,
+        ciphertexts: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Result<(), String> {
+        let digests: Vec<[u8; 32]> = ciphertexts
+            .into_iter()
+            .map(|ciphertext| Sha256::digest(ciphertext).into())
+            .collect();
+        let mut deciphered = self.deciphered_data(group_id);
+        let len = deciphered.len();
+        deciphered.retain(|(digest, _)| digests.contains(digest));
+        if deciphered.len() != len {
+            self.wrap(
+                format!("{KEY_RATCHET_DATA}_{group_id}").as_str(),
+                &deciphered,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Removes the deciphered data of all the messages of the given group, e.g. when its key is removed.
+    pub(crate) fn remove_deciphered(&mut self, group_id: &str) -> Result<(), String> {
+        self.remove(format!("{KEY_RATCHET_DATA}_{group_id}").as_str())
+    }
+
+    fn deciphered_data(&self, group_id: &str) -> DecipheredData {
+        self.unwrap(format!("{KEY_RATCHET_DATA}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+
+    /// Reads the value wrapped with the storage key.
+    fn unwrap<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let ciphertext: Vec<u8> = self.get_uncached(key)?;
+        let storage_key: GroupKey = self.get_secret(KEY_RATCHET_STORAGE_KEY)?;
+        let plaintext = zeroize::Zeroizing::new(encryption::decrypt(&storage_key, &ciphertext)?);
+        serde_json::from_slice(&plaintext).ok()
+    }
+
+    /// Writes the value wrapped with the storage key.
+    fn wrap<T: serde::Serialize>(&mut self, key: &str, value: &T) -> Result<(), String> {
+        let storage_key = self.storage_key()?;
+        let plaintext = zeroize::Zeroizing::new(serde_json::to_vec(value).unwrap());
+        self.set(key, encryption::encrypt(&storage_key, &plaintext))
+    }
+
+    /// Returns the storage key of the device, generating it on first use.
+    fn storage_key(&mut self) -> Result<GroupKey, String> {
+        if let Some(key) = self.get_secret(KEY_RATCHET_STORAGE_KEY) {
+            return Ok(key);
+        }
+        let key = encryption::generate_group_key();
+        self.set_secret(KEY_RATCHET_STORAGE_KEY, key)?;
+        Ok(key)
+    }
+}
+
+impl SerdeLocalStore for RatchetStore {}
@@ -0,0 +1,33 @@
+//! Provides a struct `ReactionStore` for storing the reactions to messages.
+
+use crate::{core::message::MessageHash, reaction::Reaction};
+
+use super::SerdeLocalStore;
+
+const KEY_REACTIONS: &str = "reactions";
+
+/// ReactionStore is a store for reactions. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct ReactionStore {}
+
+impl ReactionStore {
+    /// Returns the reactions to the message with the given hash.
+    pub(crate) fn reactions(&self, group_id: &str, hash: &MessageHash) -> Vec<Reaction> {
+        self.get::<Vec<Reaction>>(format!("{KEY_REACTIONS}_{group_id}").as_str())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|reaction| &reaction.body.hash == hash)
+            .collect()
+    }
+
+    /// Adds the reaction. It replaces the reaction with the same content.
+    pub(crate) fn add_reaction(&mut self, reaction: Reaction) -> Result<(), String> {
+        let key = format!("{KEY_REACTIONS}_{}", reaction.body.group_id);
+        let mut reactions: Vec<Reaction> = self.get(key.as_str()).unwrap_or_default();
+        reactions.retain(|r| r.body != reaction.body);
+        reactions.push(reaction);
+        self.set(key.as_str(), reactions)
+    }
+}
+
+impl SerdeLocalStore for ReactionStore {}
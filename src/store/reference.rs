@@ -0,0 +1,44 @@
+//! Provides a struct `ReferenceStore` for storing the reverse lookup of cross-group message references.
+
+use crate::core::message::{hash_to_string, MessageHash, MessageRef};
+
+use super::SerdeLocalStore;
+
+const KEY_REFERENCES: &str = "refs";
+
+/// ReferenceStore is a store for the messages which refer to each message, so that the references to a
+/// message are found without reading the chains of other groups. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct ReferenceStore {}
+
+impl ReferenceStore {
+    /// Returns the messages which refer to the message with the given hash in the given group.
+    pub(crate) fn references_to(&self, group_id: &str, hash: &MessageHash) -> Vec<MessageRef> {
+        self.get(format!("{KEY_REFERENCES}_{group_id}_{}", hash_to_string(hash)).as_str())
+            .unwrap_or_default()
+    }
+
+    /// Adds the referring message to the references of the referred message.
+    pub(crate) fn add_reference(
+        &mut self,
+        referred: &MessageRef,
+        referring: MessageRef,
+    ) -> Result<(), String> {
+        let mut references = self.references_to(&referred.group_id, &referred.hash);
+        if !references.contains(&referring) {
+            references.push(referring);
+            self.set(
+                format!(
+                    "{KEY_REFERENCES}_{}_{}",
+                    referred.group_id,
+                    hash_to_string(&referred.hash)
+                )
+                .as_str(),
+                references,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl SerdeLocalStore for ReferenceStore {}
@@ -0,0 +1,34 @@
+//! Provides a struct `SearchStore` for storing the full-text search indexes of groups.
+
+use crate::search::SearchIndex;
+
+use super::SerdeLocalStore;
+
+const KEY_SEARCH_INDEX: &str = "search";
+
+/// SearchStore is a store for the optional full-text search indexes of groups. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct SearchStore {}
+
+impl SearchStore {
+    /// Returns the search index of the given group, if it is built.
+    pub(crate) fn search_index(&self, group_id: &str) -> Option<SearchIndex> {
+        self.get(format!("{KEY_SEARCH_INDEX}_{group_id}").as_str())
+    }
+
+    /// Sets the search index of the given group.
+    pub(crate) fn set_search_index(
+        &mut self,
+        group_id: &str,
+        index: &SearchIndex,
+    ) -> Result<(), String> {
+        self.set(format!("{KEY_SEARCH_INDEX}_{group_id}").as_str(), index)
+    }
+
+    /// Removes the search index of the given group.
+    pub(crate) fn remove_search_index(&mut self, group_id: &str) -> Result<(), String> {
+        self.remove(format!("{KEY_SEARCH_INDEX}_{group_id}").as_str())
+    }
+}
+
+impl SerdeLocalStore for SearchStore {}
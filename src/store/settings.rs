@@ -0,0 +1,44 @@
+//! Provides a struct `GroupSettingsStore` for storing the local settings of groups.
+
+use crate::settings::GroupSettings;
+
+use super::SerdeLocalStore;
+
+pub(super) const KEY_GROUP_SETTINGS: &str = "grpsettings";
+
+/// GroupSettingsStore is a store for the local settings of groups. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct GroupSettingsStore {}
+
+impl GroupSettingsStore {
+    /// Returns the settings of the given group, or the default settings if they are not set.
+    pub(crate) fn settings(&self, group_id: &str) -> GroupSettings {
+        self.get(format!("{KEY_GROUP_SETTINGS}_{group_id}").as_str())
+            .unwrap_or_default()
+    }
+
+    /// Sets the settings of the given group.
+    pub(crate) fn set_settings(
+        &mut self,
+        group_id: &str,
+        settings: &GroupSettings,
+    ) -> Result<(), String> {
+        self.set(
+            format!("{KEY_GROUP_SETTINGS}_{group_id}").as_str(),
+            settings,
+        )
+    }
+
+    /// Updates the settings of the given group.
+    pub(crate) fn update_settings(
+        &mut self,
+        group_id: &str,
+        update: impl FnOnce(&mut GroupSettings),
+    ) -> Result<(), String> {
+        let mut settings = self.settings(group_id);
+        update(&mut settings);
+        self.set_settings(group_id, &settings)
+    }
+}
+
+impl SerdeLocalStore for GroupSettingsStore {}
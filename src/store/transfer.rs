@@ -0,0 +1,119 @@
+//! Provides the transfer of the items between storage backends, e.g. from the legacy `localStorage` to a
+//! storage object backed by IndexedDB, so that the accounts, groups and chains are moved at once.
+//!
+//! All the items of the source are copied as they are, in all namespaces, and read back from the target
+//! to verify them. If a write or a verification fails, the items written to the target are restored to
+//! their previous values, so that the target is left as it was. The source is never changed. The layout
+//! of the copied items is migrated on the first access of the target (see [migration](super::migration)).
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use super::{
+    cache,
+    local_storage::{error_message, StorageLike},
+    migration,
+};
+
+/// TransferStage is the stage of a transfer reported to the progress function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStage {
+    Copy,
+    Verify,
+}
+
+/// TransferReport is the result of a completed transfer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransferReport {
+    /// The number of items copied.
+    pub items: usize,
+    /// The total length of the keys and the values copied.
+    pub bytes: usize,
+}
+
+/// Copies all the items from the storage `from` to the storage `to` and verifies them. The progress is
+/// reported with the stage, the number of items done and the total number of items. On failure, the
+/// target is rolled back and the error is returned.
+pub(crate) fn transfer(
+    from: &StorageLike,
+    to: &StorageLike,
+    mut progress: impl FnMut(TransferStage, usize, usize),
+) -> Result<TransferReport, String> {
+    let items = read_items(from)?;
+    let total = items.len();
+
+    // the previous values of the items of the target, to restore them on failure
+    let mut previous: Vec<(&str, Option<String>)> = Vec::with_capacity(total);
+    let result = (|| {
+        for (idx, (key, value)) in items.iter().enumerate() {
+            let old_value = to
+                .get_item(key)
+                .map_err(|err| format!("Fail to read item {key}: {}", error_message(&err)))?;
+            previous.push((key, old_value));
+            to.set_item(key, value)
+                .map_err(|err| format!("Fail to write item {key}: {}", error_message(&err)))?;
+            progress(TransferStage::Copy, idx + 1, total);
+        }
+        for (idx, (key, value)) in items.iter().enumerate() {
+            match to.get_item(key) {
+                Ok(Some(copied)) if &copied == value => {}
+                _ => return Err(format!("Fail to verify item {key}")),
+            }
+            progress(TransferStage::Verify, idx + 1, total);
+        }
+        Ok(())
+    })();
+
+    // the target may be the storage of the library, e.g. when the items are restored into it
+    cache::clear();
+    migration::reset();
+    if let Err(err) = result {
+        rollback(to, &previous).map_err(|rollback_err| format!("{err}, and {rollback_err}"))?;
+        return Err(err);
+    }
+    Ok(TransferReport {
+        items: total,
+        bytes: items
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum(),
+    })
+}
+
+/// Reads all the items of the storage.
+fn read_items(storage: &StorageLike) -> Result<Vec<(String, String)>, String> {
+    (0..storage.length())
+        .map(|idx| {
+            let key = storage
+                .key(idx)
+                .ok()
+                .flatten()
+                .ok_or(format!("Fail to read the key of item {idx}"))?;
+            let value = storage
+                .get_item(&key)
+                .ok()
+                .flatten()
+                .ok_or(format!("Fail to read item {key}"))?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Restores the items of the target to their previous values, in the reverse order of the writes.
+fn rollback(to: &StorageLike, previous: &[(&str, Option<String>)]) -> Result<(), String> {
+    let mut failed: Option<JsValue> = None;
+    for (key, value) in previous.iter().rev() {
+        let result = match value {
+            Some(value) => to.set_item(key, value),
+            None => to.remove_item(key),
+        };
+        if let Err(err) = result {
+            failed.get_or_insert(err);
+        }
+    }
+    match failed {
+        Some(err) => Err(format!("fail to roll back: {}", error_message(&err))),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,41 @@
+//! Provides a struct `KeyLogStore` for storing the key transparency logs of accounts.
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    account::Identity,
+    core::message::{to_hex, SignedMessage},
+    message::Signature,
+};
+
+use super::SerdeLocalStore;
+
+const KEY_KEY_LOG: &str = "keylog";
+
+/// KeyLogStore is a store for the key transparency logs of accounts. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct KeyLogStore {}
+
+impl KeyLogStore {
+    /// Returns the entries of the key transparency log of the account, from the oldest.
+    pub(crate) fn key_log(&self, identity: &Identity) -> Vec<SignedMessage<Identity, Signature>> {
+        self.get(key_log_key(identity).as_str()).unwrap_or_default()
+    }
+
+    /// Appends the entry to the key transparency log of its signer.
+    pub(crate) fn append_key_log(
+        &mut self,
+        entry: SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let key = key_log_key(&entry.id);
+        let mut entries = self.key_log(&entry.id);
+        entries.push(entry);
+        self.set(key.as_str(), entries)
+    }
+}
+
+impl SerdeLocalStore for KeyLogStore {}
+
+fn key_log_key(identity: &Identity) -> String {
+    format!("{KEY_KEY_LOG}_{}", to_hex(&Sha256::digest(identity)))
+}
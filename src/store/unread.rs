@@ -0,0 +1,31 @@
+//! Provides a struct `SeenStore` for storing the latest messages seen by the local accounts.
+
+use crate::{account::Identity, unread::SeenMarker};
+
+use super::SerdeLocalStore;
+
+const KEY_SEEN: &str = "seen";
+
+/// SeenStore is a store for the seen markers of groups, one per local account. It implements the trait [SerdeLocalStore](crate::store::SerdeLocalStore).
+#[derive(Default)]
+pub(crate) struct SeenStore {}
+
+impl SeenStore {
+    /// Returns the seen marker of the account in the given group.
+    pub(crate) fn marker(&self, group_id: &str, identity: &Identity) -> Option<SeenMarker> {
+        self.get::<Vec<SeenMarker>>(format!("{KEY_SEEN}_{group_id}").as_str())?
+            .into_iter()
+            .find(|marker| &marker.identity == identity)
+    }
+
+    /// Sets the seen marker of its account in the given group. It replaces the previous marker.
+    pub(crate) fn set_marker(&mut self, group_id: &str, marker: SeenMarker) -> Result<(), String> {
+        let key = format!("{KEY_SEEN}_{group_id}");
+        let mut markers: Vec<SeenMarker> = self.get(key.as_str()).unwrap_or_default();
+        markers.retain(|m| m.identity != marker.identity);
+        markers.push(marker);
+        self.set(key.as_str(), markers)
+    }
+}
+
+impl SerdeLocalStore for SeenStore {}
@@ -0,0 +1,39 @@
+//! Provides the tamper detection of the stored chains, which finds the items of the local storage which
+//! are modified outside the library, e.g. with the developer tools or by another script.
+//!
+//! Each message is stored under a key with its hash, and the head of each chain under a pointer. The
+//! detection walks the chain from the pointer, checking each message against the hash in its key, and
+//! looks for the stored messages which the walk does not reach. The purged messages are checked against the
+//! hash and the signed digest kept in their tombstones, as their data is removed.
+
+use serde::{Deserialize, Serialize};
+
+/// TamperKind tells how a stored item is modified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TamperKind {
+    /// The message does not match the hash in its key, or its signature is invalid.
+    ModifiedMessage,
+    /// The item cannot be parsed.
+    UnparsableItem,
+    /// The message is referenced by its child but is not stored. The parents of checkpoints are not
+    /// reported, as they may be pruned.
+    MissingMessage,
+    /// The head pointer refers to a message which is not stored.
+    DanglingHead,
+    /// The head pointer is rolled back, as a stored message follows the message it refers to.
+    StaleHead,
+    /// The message is stored but is not in the chain from the head pointer.
+    OrphanedMessage,
+}
+
+/// TamperFinding is a stored item which is modified outside the library.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TamperFinding {
+    pub group_id: String,
+    /// The key of the item in the local storage, without the prefix of the namespace.
+    pub key: String,
+    pub kind: TamperKind,
+    /// The sequence number of the message in the chain, if it is known.
+    pub seq: Option<u64>,
+}
@@ -0,0 +1,46 @@
+//! Provides the thread tree of the replies to a message.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::Identity,
+    core::message::{MessageHash, SignedMessage},
+    message::Signature,
+};
+
+/// ThreadNode is a message with the replies to it, ordered from the oldest.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ThreadNode {
+    /// The hash of the message in the chain.
+    pub hash: MessageHash,
+    pub message: SignedMessage<Identity, Signature>,
+    pub replies: Vec<ThreadNode>,
+}
+
+/// Builds the thread tree rooted at the message with the given hash. The messages are paired with
+/// their hashes and ordered from the latest.
+pub(crate) fn thread(
+    messages: &[(MessageHash, SignedMessage<Identity, Signature>)],
+    root: &MessageHash,
+) -> Option<ThreadNode> {
+    let (hash, message) = messages.iter().find(|(hash, _)| hash == root)?;
+    Some(build(messages, *hash, message.clone()))
+}
+
+fn build(
+    messages: &[(MessageHash, SignedMessage<Identity, Signature>)],
+    hash: MessageHash,
+    message: SignedMessage<Identity, Signature>,
+) -> ThreadNode {
+    let replies = messages
+        .iter()
+        .rev()
+        .filter(|(_, msg)| msg.message.reply_to == Some(hash))
+        .map(|(reply_hash, reply)| build(messages, *reply_hash, reply.clone()))
+        .collect();
+    ThreadNode {
+        hash,
+        message,
+        replies,
+    }
+}
@@ -0,0 +1,181 @@
+//! Provides the transcripts of groups, which are the data messages rendered in human-readable formats,
+//! e.g. to share or archive a conversation outside the app.
+//!
+//! A transcript lists the messages from the oldest with their timestamps and the aliases of their signers
+//! if they are contacts. The hash of each message is given in a footnote, so that the messages can be
+//! verified against the chain later.
+
+use std::fmt::Write as _;
+
+use crate::{
+    account::Identity,
+    core::message::{hash_to_string, MessageHash, SignedMessage},
+    message::Signature,
+};
+
+/// TranscriptFormat is the format of a transcript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    Markdown,
+    Html,
+    Csv,
+}
+
+impl TryFrom<&str> for TranscriptFormat {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "markdown" | "md" => Ok(TranscriptFormat::Markdown),
+            "html" => Ok(TranscriptFormat::Html),
+            "csv" => Ok(TranscriptFormat::Csv),
+            _ => Err("Invalid transcript format".to_string()),
+        }
+    }
+}
+
+/// TranscriptEntry is a message of a transcript.
+#[derive(Clone)]
+pub struct TranscriptEntry {
+    pub hash: MessageHash,
+    /// The alias of the signer if it is a contact, or else its identity.
+    pub author: String,
+    pub timestamp: Option<u64>,
+    /// The data of the message as text, or a placeholder if it is redacted or encrypted.
+    pub text: String,
+}
+
+impl TranscriptEntry {
+    /// Creates the entry of the signed message with the alias of its signer.
+    pub fn new(
+        hash: MessageHash,
+        signed_msg: &SignedMessage<Identity, Signature>,
+        alias: Option<String>,
+    ) -> Self {
+        let message = &signed_msg.message;
+        let text = if message.redacted {
+            "[redacted]".to_string()
+        } else if message.encrypted {
+            "[encrypted]".to_string()
+        } else {
+            String::from_utf8_lossy(&message.data).into_owned()
+        };
+        Self {
+            hash,
+            author: alias.unwrap_or_else(|| signed_msg.id.to_string()),
+            timestamp: message.timestamp,
+            text,
+        }
+    }
+}
+
+/// Renders the transcript of the group in the format. The entries must be ordered from the oldest.
+pub fn render(group_id: &str, entries: &[TranscriptEntry], format: TranscriptFormat) -> String {
+    let mut out = String::new();
+    match format {
+        TranscriptFormat::Markdown => {
+            let _ = writeln!(out, "# {}\n", escape_markdown(group_id));
+            for (i, entry) in entries.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "**{}** ({}): {}[^{}]\n",
+                    escape_markdown(&entry.author),
+                    format_timestamp(entry.timestamp),
+                    escape_markdown(&entry.text).replace('\n', "  \n"),
+                    i + 1
+                );
+            }
+            for (i, entry) in entries.iter().enumerate() {
+                let _ = writeln!(out, "[^{}]: `{}`", i + 1, hash_to_string(&entry.hash));
+            }
+        }
+        TranscriptFormat::Html => {
+            let _ = writeln!(out, "<h1>{}</h1>", escape_html(group_id));
+            out.push_str("<ol class=\"transcript\">\n");
+            for (i, entry) in entries.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "<li><strong>{}</strong> <time>{}</time>: {}<sup><a href=\"#hash-{n}\">{n}</a></sup></li>",
+                    escape_html(&entry.author),
+                    format_timestamp(entry.timestamp),
+                    escape_html(&entry.text).replace('\n', "<br>"),
+                    n = i + 1
+                );
+            }
+            out.push_str("</ol>\n<ol class=\"hashes\">\n");
+            for (i, entry) in entries.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "<li id=\"hash-{}\"><code>{}</code></li>",
+                    i + 1,
+                    hash_to_string(&entry.hash)
+                );
+            }
+            out.push_str("</ol>\n");
+        }
+        TranscriptFormat::Csv => {
+            out.push_str("timestamp,author,text,hash\r\n");
+            for entry in entries {
+                let _ = write!(
+                    out,
+                    "{},{},{},{}\r\n",
+                    escape_csv(&format_timestamp(entry.timestamp)),
+                    escape_csv(&entry.author),
+                    escape_csv(&entry.text),
+                    hash_to_string(&entry.hash)
+                );
+            }
+        }
+    }
+    out
+}
+
+/// Formats the unix timestamp as "YYYY-MM-DD HH:MM:SS UTC", or returns an empty string if it is None.
+pub fn format_timestamp(timestamp: Option<u64>) -> String {
+    let Some(timestamp) = timestamp else {
+        return String::new();
+    };
+    let (days, secs) = ((timestamp / 86400) as i64, timestamp % 86400);
+    // the civil date of the days since 1970-01-01, in the proleptic Gregorian calendar
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>#|~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn escape_csv(text: &str) -> String {
+    if text.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
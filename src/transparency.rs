@@ -0,0 +1,67 @@
+//! Provides the key transparency log of an account, which is an append-only chain of the key events of
+//! the account signed by the account itself.
+//!
+//! The log is exported with `keyLog` and verified by peers with [verify_log], so that they can audit
+//! the devices which the account has certified and revoked.
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    account::Identity, core::message::SignedMessage, device::DeviceCertificate, message::Signature,
+};
+
+/// KeyEvent is the data of an entry in the key transparency log.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyEvent {
+    /// The account is created. It is the first entry of the log.
+    Created { timestamp: u64 },
+    /// A device is certified by the account.
+    DeviceAdded {
+        certificate: DeviceCertificate,
+        timestamp: u64,
+    },
+    /// A device of the account is revoked.
+    DeviceRevoked { device: Identity, timestamp: u64 },
+}
+
+/// Verifies the key transparency log, ordered from the oldest. The entries must be signed by the
+/// account and linked in order. The first entry must be the creation of the account, the certificates
+/// must be valid and issued by the account, and only the certified devices may be revoked.
+pub fn verify_log(entries: &[SignedMessage<Identity, Signature>]) -> bool {
+    let Some(first) = entries.first() else {
+        return false;
+    };
+    let mut devices: Vec<Identity> = vec![];
+    let mut previous: Option<&SignedMessage<Identity, Signature>> = None;
+    for entry in entries {
+        let is_linked = match previous {
+            Some(previous) => {
+                previous.id == entry.id && previous.is_valid_parent_of::<Sha256>(entry)
+            }
+            None => entry.is_first_message() && entry.verify::<Sha256>(),
+        };
+        if !is_linked {
+            return false;
+        }
+        let Ok(event) = serde_json::from_slice::<KeyEvent>(&entry.message.data) else {
+            return false;
+        };
+        let is_valid = match (event, previous) {
+            (KeyEvent::Created { .. }, None) => true,
+            (_, None) | (KeyEvent::Created { .. }, Some(_)) => false,
+            (KeyEvent::DeviceAdded { certificate, .. }, Some(_)) => {
+                let is_valid = certificate.body.master == first.id && certificate.verify();
+                devices.push(certificate.body.device);
+                is_valid
+            }
+            (KeyEvent::DeviceRevoked { device, .. }, Some(_)) => devices.contains(&device),
+        };
+        if !is_valid {
+            return false;
+        }
+        previous = Some(entry);
+    }
+    true
+}
@@ -0,0 +1,37 @@
+//! Provides the unread tracking of groups, which is the last message seen by each local account.
+//!
+//! The unread messages are counted from the counters of the group (see [GroupInfo]) instead of walking
+//! the chain, so the count is cheap to read after every write.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{account::Identity, core::message::MessageHash, stats::GroupInfo};
+
+/// SeenMarker is the latest message of a group seen by a local account.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SeenMarker {
+    /// The account which has seen the message.
+    pub identity: Identity,
+    /// The hash of the seen message.
+    pub hash: MessageHash,
+    /// The sequence number of the seen message.
+    pub seq: u64,
+}
+
+impl SeenMarker {
+    /// Returns the number of messages of the group after the seen message. It is at most the number of
+    /// stored messages, as the pruned messages are not counted.
+    pub fn unread_count(&self, info: &GroupInfo) -> u64 {
+        let after = info.seq.map_or(0, |seq| seq.saturating_sub(self.seq));
+        after.min(info.message_count)
+    }
+}
+
+/// Returns the number of unread messages of the group for the marker, which are all the stored messages
+/// if nothing is seen.
+pub fn unread_count(info: &GroupInfo, marker: Option<&SeenMarker>) -> u64 {
+    match marker {
+        Some(marker) => marker.unread_count(info),
+        None => info.message_count,
+    }
+}
@@ -0,0 +1,160 @@
+//! Provides the validation of a chain in chunks, so that long chains are validated without blocking the
+//! thread, e.g. the main thread of a web app. The progress is kept in a cursor, which is resumed until
+//! the result is known.
+//!
+//! The validation runs in two passes. The backward pass walks from the head to the oldest stored message,
+//! checking the hashes and the signatures, and collecting the redactions which apply to older messages.
+//! The forward pass replays the state of the group from the oldest message, checking each record, and
+//! finally checks that the purged messages which are not redacted are expired by the group policy.
+//!
+//! A validation may start from the [ValidationWatermark] of the previous successful validation, in which
+//! case the backward pass stops at the watermark if it is still on the chain, and the forward pass replays
+//! the newer messages from the state kept in the watermark.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{core::message::MessageHash, state::GroupState};
+
+/// ValidationCursor is the progress of the validation of the chain of a group.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ValidationCursor {
+    /// The group of the chain.
+    pub group_id: String,
+    /// The number of checks done in both passes. The chain of n messages takes 2n checks.
+    pub checked: u64,
+    /// The number of checks of the chain, as counted when the validation started.
+    pub total: u64,
+    /// The result of the validation, or None if it is not complete.
+    pub result: Option<bool>,
+    pub(crate) pass: ValidationPass,
+}
+
+/// ValidationReport is the result of the validation of the chain of a group, e.g. by `validateAll`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub group_id: String,
+    pub valid: bool,
+    /// The number of checks done, which is 1 if the validation is skipped, for the check of the head.
+    pub checked: u64,
+    /// True if the validation is skipped, because the head of the chain is the watermark, i.e. the head of
+    /// the previous successful validation, and it is unchanged.
+    pub skipped: bool,
+}
+
+/// ValidationWatermark is the head of the previous successful validation of the chain of a group, from
+/// which the next validation continues.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ValidationWatermark {
+    /// The hash of the validated head, which must still be on the chain to be used.
+    pub(crate) hash: MessageHash,
+    /// The sequence number of the validated head.
+    pub(crate) seq: u64,
+    /// The state of the group after the validated head.
+    pub(crate) state: Box<GroupState>,
+    /// The timestamp of the validated head, or of the latest validated message with a timestamp, against
+    /// which the timestamp of the next message is checked if the timestamps are checked strictly.
+    pub(crate) previous_timestamp: Option<u64>,
+}
+
+/// ValidationPass is the pass of the validation and its progress.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) enum ValidationPass {
+    /// Walks the chain from the head back to the oldest stored message.
+    Backward {
+        /// The hash of the message to check next.
+        next: MessageHash,
+        /// The sequence number of the message checked last, which is the child of the next message.
+        child_seq: Option<u64>,
+        /// The sequence number of the head, against which the retention policy applies.
+        latest_seq: u64,
+        /// The unix timestamp when the validation started.
+        now: u64,
+        /// The hashes of the messages redacted by the checked redaction records.
+        redacted: Vec<MessageHash>,
+        /// The hashes of the checked messages, from the head.
+        chain: Vec<MessageHash>,
+        /// The earliest signed timestamp of the checked messages which are not purged, before which the
+        /// next message is signed if the timestamps are in order.
+        #[serde(default)]
+        newer_timestamp: Option<u64>,
+        /// The purged messages which are not redacted, with their sequence numbers and the timestamps
+        /// before which they are signed, to be checked against the retention of the group policy.
+        #[serde(default)]
+        expiring: Vec<(MessageHash, u64, Option<u64>)>,
+        /// The watermark of the previous successful validation, at which the pass stops if it is on the
+        /// chain.
+        #[serde(default)]
+        watermark: Option<Box<ValidationWatermark>>,
+    },
+    /// Replays the state of the group from the oldest stored message.
+    Forward {
+        /// The state of the group up to the message checked last.
+        state: Box<GroupState>,
+        /// The hashes of the messages to replay, from the head, so that the next one is the last.
+        remaining: Vec<MessageHash>,
+        /// The timestamp of the message checked last, against which the timestamp of the next message is
+        /// checked if the timestamps are checked strictly.
+        #[serde(default)]
+        previous_timestamp: Option<u64>,
+        /// The sequence number of the head, against which the retention policy applies.
+        #[serde(default)]
+        latest_seq: u64,
+        /// The unix timestamp when the validation started.
+        #[serde(default)]
+        now: u64,
+        /// The purged messages which must be expired by the retention of the group policy at the head.
+        #[serde(default)]
+        expiring: Vec<(MessageHash, u64, Option<u64>)>,
+    },
+}
+
+impl ValidationCursor {
+    /// Creates the cursor at the head of the chain of the group, which has the given number of stored
+    /// messages. The chain without messages is valid.
+    pub(crate) fn new(
+        group_id: &str,
+        head: Option<(MessageHash, u64)>,
+        message_count: u64,
+        now: u64,
+    ) -> Self {
+        let (pass, result) = match head {
+            Some((next, latest_seq)) => (
+                ValidationPass::Backward {
+                    next,
+                    child_seq: None,
+                    latest_seq,
+                    now,
+                    redacted: vec![],
+                    chain: vec![],
+                    newer_timestamp: None,
+                    expiring: vec![],
+                    watermark: None,
+                },
+                None,
+            ),
+            None => (
+                ValidationPass::Forward {
+                    state: Box::default(),
+                    remaining: vec![],
+                    previous_timestamp: None,
+                    latest_seq: 0,
+                    now,
+                    expiring: vec![],
+                },
+                Some(true),
+            ),
+        };
+        Self {
+            group_id: group_id.to_string(),
+            checked: 0,
+            total: message_count * 2,
+            result,
+            pass,
+        }
+    }
+
+    /// Returns true if the validation is complete.
+    pub fn is_complete(&self) -> bool {
+        self.result.is_some()
+    }
+}
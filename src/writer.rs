@@ -1,68 +1,724 @@
 //! Writer module provides a writer struct to write messages to the store.
 
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 
 use crate::{
     account::Identity,
+    agreement::{self, KeyAnnouncement, KeyDistribution},
+    capability::Capability,
     core::{
-        group::Group,
-        message::{MessageHash, SignedMessage},
+        compare::ct_eq,
+        group::{unix_timestamp, HashAlgorithm},
+        message::{MessageHash, MessageKind, MessageRef, SignedMessage, MAX_SEQ},
     },
+    encryption::GroupKey,
+    invite::JoinRequest,
     message::Signature,
-    store::{group::GroupStore, message::SignedMessageStore},
+    mmr::MerkleMountainRange,
+    ratchet::RatchetState,
+    reader::Reader,
+    state::{Checkpoint, PinChange, Redaction, Role},
+    store::{
+        account::AccountStore, cache, group::GroupStore, journal, key::GroupKeyStore,
+        limit::LimitStore, merkle::MerkleStore, message::SignedMessageStore, pending::PendingStore,
+        pin::PinStore, ratchet::RatchetStore, reference::ReferenceStore, search::SearchStore,
+        settings::GroupSettingsStore,
+    },
 };
 
+/// WriteError is the error returned when a signed message is rejected by [Writer::write_with_validation].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WriteError {
+    /// The signature of the message is invalid.
+    InvalidSignature,
+    /// The sequence number is not the next one in the group.
+    WrongSequence,
+    /// The previous hash is not the hash of the latest message in the group.
+    WrongPreviousHash,
+    /// The signer is not in the allowed signers of the group.
+    UnknownSigner,
+    /// The timestamp of the message is missing, in the future, or before the latest message of the group,
+    /// and the settings of the group check the timestamps strictly. It contains the reason.
+    InvalidTimestamp(String),
+    /// The message is rejected by the group state, e.g. the group policy or the permission of the signer.
+    Rejected(String),
+    /// The signer announces an agreement key other than the key pinned when the signer was first seen.
+    /// It contains the signer.
+    KeyChanged(String),
+    /// The message is already stored in the group, e.g. it is submitted again.
+    AlreadyExists,
+    /// The data of the message is larger than the maximum message size of the [WriteLimits].
+    MessageTooLarge,
+    /// The group has the maximum number of messages of the [WriteLimits].
+    TooManyMessages,
+    /// The signer wrote the maximum number of messages in the time window of the [WriteLimits].
+    RateLimited,
+    /// The group has reached its maximum length, i.e. the sequence number of the next message would exceed
+    /// [MAX_SEQ].
+    ChainFull,
+    /// The head of the group in the local storage is changed, e.g. by another tab, since the message was
+    /// validated or signed over it. The write may be retried, as the cached values are dropped.
+    HeadChanged,
+    /// The message is ahead of the chain and cannot be held, as the pending buffer of the group is full or
+    /// the message is too far ahead of the next sequence number (see [MAX_PENDING_MESSAGES]).
+    PendingFull,
+    /// The storage fails to write the message, e.g. as its quota is exceeded. It contains the reason.
+    Storage(String),
+}
+
+/// The number of attempts of a write when the head of the group is changed by another tab in the meantime,
+/// i.e. the write fails with [WriteError::HeadChanged].
+pub(crate) const WRITE_ATTEMPTS: usize = 3;
+
+/// The maximum number of messages held in the pending buffer of a group by [Writer::write_in_order], which
+/// is also the farthest a held message may be ahead of the next sequence number.
+pub const MAX_PENDING_MESSAGES: usize = 256;
+
+/// WriteLimits are the limits enforced by [Writer::write_with_validation], which protect the local
+/// storage from hostile or buggy peers. By default, there are no limits.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteLimits {
+    /// The maximum size (in bytes) of the data of a message.
+    #[serde(default)]
+    pub max_message_size: Option<usize>,
+    /// The maximum number of stored messages in a group.
+    #[serde(default)]
+    pub max_messages_per_group: Option<u64>,
+    /// The maximum number of messages of a signer written in a group within the time window.
+    #[serde(default)]
+    pub max_messages_per_author: Option<usize>,
+    /// The time window (in seconds) of `max_messages_per_author`.
+    #[serde(default)]
+    pub window: u64,
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::InvalidSignature => write!(f, "fail to validate message"),
+            WriteError::WrongSequence => write!(f, "wrong message sequence"),
+            WriteError::WrongPreviousHash => write!(f, "wrong previous hash"),
+            WriteError::UnknownSigner => write!(f, "unknown signer"),
+            WriteError::InvalidTimestamp(reason) => write!(f, "{reason}"),
+            WriteError::Rejected(reason) => write!(f, "{reason}"),
+            WriteError::KeyChanged(signer) => write!(f, "key changed: {signer}"),
+            WriteError::AlreadyExists => write!(f, "message already exists"),
+            WriteError::MessageTooLarge => write!(f, "message too large"),
+            WriteError::TooManyMessages => write!(f, "too many messages in group"),
+            WriteError::RateLimited => write!(f, "too many messages from signer"),
+            WriteError::ChainFull => write!(f, "group reached its maximum length"),
+            WriteError::HeadChanged => write!(f, "head of group changed"),
+            WriteError::PendingFull => write!(f, "too many pending messages"),
+            WriteError::Storage(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl From<WriteError> for String {
+    fn from(error: WriteError) -> Self {
+        error.to_string()
+    }
+}
+
 /// Writer is a struct that defines the writing process involved with the stores such as `GroupStore` and `SignedMessageStore`.
 #[derive(Default)]
 pub(crate) struct Writer {
     pub(crate) message_store: SignedMessageStore,
     pub(crate) group_store: GroupStore,
+    pub(crate) account_store: AccountStore,
+    pub(crate) key_store: GroupKeyStore,
+    pub(crate) ratchet_store: RatchetStore,
+    pub(crate) merkle_store: MerkleStore,
+    pub(crate) pin_store: PinStore,
+    pub(crate) search_store: SearchStore,
+    pub(crate) reference_store: ReferenceStore,
+    pub(crate) pending_store: PendingStore,
+    pub(crate) limit_store: LimitStore,
+    pub(crate) settings_store: GroupSettingsStore,
 }
 
 impl Writer {
-    /// Writes a signed message to the store. It saves the message to the `SignedMessageStore` an
-    /// adds the group to the `GroupStore`. It returns the message hash and the signed message.
+    /// Writes a signed message to the store. It saves the message to the `SignedMessageStore` and appends
+    /// its hash to the Merkle Mountain Range in the `MerkleStore`. If the message is the genesis record,
+    /// it adds the group to the `GroupStore`. The last activity and the group state in the
+    /// `GroupStore` are updated with the message, and the agreement key announced by the signer is pinned
+    /// in the `PinStore` if no key is pinned yet. If the message distributes or rotates the group content key to a
+    /// local account, and its signer may manage the key in the group state, the key is saved to the
+    /// `GroupKeyStore`, after the messages encrypted with the previous keys are deciphered. If the group has a search index in the
+    /// `SearchStore`, the text of the message is added to it, or the redacted message is removed from it.
+    /// If the message refers to another message, it is added to the references in the `ReferenceStore`.
+    /// If the message is signed by a local account, it is counted in the usage of the account in the
+    /// `AccountStore`. It returns the message hash and the signed message.
+    ///
+    /// The items are written atomically through the [journal], so that an interrupted write, e.g. as the
+    /// tab is closed, is either completed or undone when the library is loaded again. Right before, the
+    /// head of the group is compared with the parent of the message in the local storage, bypassing the
+    /// cache, so that a message signed or validated over a head which another tab has moved does not fork
+    /// the chain. It fails with [WriteError::HeadChanged] if they differ, and with [WriteError::Storage] if
+    /// the items cannot be written, e.g. as the quota is exceeded (see [journal::atomically]).
     pub(crate) fn write(
         &mut self,
         group_id: &str,
         signed_msg: SignedMessage<Identity, Signature>,
-    ) -> (MessageHash, SignedMessage<Identity, Signature>) {
-        let msg_hash = self
-            .message_store
-            .save_message::<Sha256>(group_id, &signed_msg);
+    ) -> Result<(MessageHash, SignedMessage<Identity, Signature>), WriteError> {
+        journal::atomically(|| {
+            self.check_head(group_id, &signed_msg)?;
+            self.apply(group_id, signed_msg)
+                .map_err(WriteError::Storage)
+        })
+        .map_err(WriteError::Storage)?
+    }
+
+    /// Checks that the message follows the head of the group in the local storage, or that the group has no
+    /// head if it is the first message. If not, the cached values are dropped, as the other tab may have
+    /// changed any of them, so that the write is retried over the new head.
+    fn check_head(
+        &self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), WriteError> {
+        let head = self.message_store.stored_head(group_id);
+        let parent = (!signed_msg.is_first_message()).then_some(signed_msg.message.previous_hash);
+        let follows_head = match (head, parent) {
+            (Some(head), Some(parent)) => ct_eq(&head, &parent),
+            (head, parent) => head.is_none() && parent.is_none(),
+        };
+        if !follows_head {
+            cache::clear();
+            return Err(WriteError::HeadChanged);
+        }
+        Ok(())
+    }
+
+    /// Applies the writes of [Writer::write] to the stores.
+    fn apply(
+        &mut self,
+        group_id: &str,
+        signed_msg: SignedMessage<Identity, Signature>,
+    ) -> Result<(MessageHash, SignedMessage<Identity, Signature>), String> {
+        // the message is hashed with the algorithm of the state after it, so that a `HashMigration`
+        // record is linked to its successor with the new algorithm
+        let mut state = self.group_store.state(group_id);
+        // the group key is taken only from the records which the state accepts, e.g. from the admins and
+        // the key holders, even if the message is written without validation
+        let manages_key = matches!(
+            signed_msg.message.kind,
+            MessageKind::KeyDistribution | MessageKind::Rekey | MessageKind::RatchetStart
+        ) && state.check(group_id, &signed_msg).is_ok();
+        state.update(&signed_msg);
+        let msg_hash = self.message_store.save_message(
+            group_id,
+            state.hash_algorithm.hash(&signed_msg),
+            &signed_msg,
+        )?;
+        self.append_to_mmr(group_id, msg_hash, signed_msg.seq)?;
+        self.account_store
+            .add_usage(&signed_msg.id, group_id, signed_msg.message.timestamp)?;
+        self.update_search_index(group_id, msg_hash, &signed_msg)?;
+        if let Some(reference) = &signed_msg.message.reference {
+            self.reference_store.add_reference(
+                reference,
+                MessageRef {
+                    group_id: group_id.to_string(),
+                    hash: msg_hash,
+                },
+            )?;
+        }
+
+        if let Some(genesis) = signed_msg.genesis() {
+            self.group_store.add_group(genesis.to_group())?;
+        }
+        self.group_store
+            .set_last_activity(group_id, unix_timestamp())?;
+        self.group_store.set_state(group_id, &state)?;
+
+        if let Some(announcement) = key_announcement(&signed_msg) {
+            self.pin_store
+                .pin_key(group_id, &signed_msg.id, announcement.agreement_key)?;
+        }
+        if manages_key
+            && matches!(
+                signed_msg.message.kind,
+                MessageKind::RatchetStart | MessageKind::Rekey
+            )
+        {
+            self.decipher_stored(group_id);
+        }
+        if manages_key
+            && matches!(
+                signed_msg.message.kind,
+                MessageKind::KeyDistribution | MessageKind::Rekey
+            )
+        {
+            self.accept_group_key(group_id, &signed_msg)?;
+        }
+        if manages_key
+            && matches!(
+                signed_msg.message.kind,
+                MessageKind::RatchetStart | MessageKind::Rekey
+            )
+        {
+            self.restart_ratchet(group_id, &signed_msg)?;
+        }
+        if signed_msg.message.encrypted {
+            self.discard_message_key(group_id, &signed_msg)?;
+        }
+
+        Ok((msg_hash, signed_msg))
+    }
+
+    /// Appends the message hash to the Merkle Mountain Range of the group. If the range does not end
+    /// right before the message, e.g. the messages were written before the range was maintained, it is
+    /// rebuilt from the stored messages.
+    fn append_to_mmr(
+        &mut self,
+        group_id: &str,
+        msg_hash: MessageHash,
+        seq: u64,
+    ) -> Result<(), String> {
+        let mut mmr = self.merkle_store.mmr(group_id);
+        if mmr.leaf_count() == seq {
+            mmr.append(msg_hash);
+        } else {
+            let hashes = self.message_store.message_hashes(group_id);
+            if hashes.len() != seq as usize + 1 {
+                // the older messages are pruned
+                return Ok(());
+            }
+            mmr = MerkleMountainRange::default();
+            hashes.into_iter().rev().for_each(|hash| mmr.append(hash));
+        }
+        self.merkle_store.set_mmr(group_id, &mmr)
+    }
+
+    /// Updates the search index of the group, if it is built. Only the data of unencrypted messages is
+    /// readable on write, so encrypted messages are indexed when the index is rebuilt with the content keys.
+    fn update_search_index(
+        &mut self,
+        group_id: &str,
+        msg_hash: MessageHash,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let Some(mut index) = self.search_store.search_index(group_id) else {
+            return Ok(());
+        };
+        match signed_msg.message.kind {
+            MessageKind::Data if !signed_msg.message.encrypted => {
+                if let Ok(text) = std::str::from_utf8(&signed_msg.message.data) {
+                    index.add(msg_hash, text);
+                }
+            }
+            MessageKind::Redaction => {
+                if let Ok(redaction) = serde_json::from_slice::<Redaction>(&signed_msg.message.data)
+                {
+                    index.remove(&redaction.hash);
+                }
+            }
+            _ => return Ok(()),
+        }
+        self.search_store.set_search_index(group_id, &index)
+    }
+
+    /// Starts the ratchet of the group on a `RatchetStart` record, or restarts it with the new group
+    /// content key on a `Rekey` record if it was started. The ratchet starts from the next message.
+    fn restart_ratchet(
+        &mut self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let Some(group_key) = self.key_store.group_key(group_id) else {
+            return Ok(());
+        };
+        let start = signed_msg.seq.saturating_add(1);
+        let ratchet = match self.ratchet_store.ratchet(group_id) {
+            Some(mut ratchet) => {
+                ratchet.restart(&group_key, start);
+                ratchet
+            }
+            None if signed_msg.message.kind == MessageKind::RatchetStart => {
+                RatchetState::new(&group_key, start)
+            }
+            None => return Ok(()),
+        };
+        self.ratchet_store.set_ratchet(group_id, &ratchet)
+    }
+
+    /// Deciphers the stored messages of the group with the current group key and ratchet, and keeps their
+    /// data, before a `Rekey` or `RatchetStart` record replaces them, as the superseded keys are not kept.
+    /// Otherwise the messages which are received but not read yet could not be decrypted anymore.
+    fn decipher_stored(&self, group_id: &str) {
+        Reader::default().messages(group_id);
+    }
+
+    /// Deletes the ratchet message key of the encrypted message if its data is kept, e.g. as it is signed on
+    /// this device, so that the key is not kept after the message is written.
+    fn discard_message_key(
+        &mut self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let Some(mut ratchet) = self.ratchet_store.ratchet(group_id) else {
+            return Ok(());
+        };
+        if self
+            .ratchet_store
+            .deciphered(group_id, &signed_msg.message.data)
+            .is_some()
+        {
+            ratchet.discard(signed_msg.seq);
+            self.ratchet_store.set_ratchet(group_id, &ratchet)?;
+        }
+        Ok(())
+    }
 
-        self.group_store.add_group(Group::new(group_id.to_string()));
+    /// Unwraps the group content key from a `KeyDistribution` or `Rekey` record if it is granted to a local account.
+    fn accept_group_key(
+        &mut self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+    ) -> Result<(), String> {
+        let Ok(distribution) = serde_json::from_slice::<KeyDistribution>(&signed_msg.message.data)
+        else {
+            return Ok(());
+        };
+        let accounts = self.account_store.accounts();
+        let group_key = distribution.grants.iter().find_map(|grant| {
+            accounts
+                .iter()
+                .find(|(id, _)| id == &grant.recipient)
+                .and_then(|(_, secret)| agreement::unwrap_key(&grant.wrapped_key, secret))
+        });
+        if let Some(group_key) = group_key {
+            self.key_store.set_group_key(group_id, group_key)?;
+        }
+        Ok(())
+    }
 
-        (msg_hash, signed_msg)
+    /// Saves the group content key of the `Rekey` record signed on this device, which is not granted to its
+    /// signer, once the record is written, and restarts the ratchet with it.
+    pub(crate) fn adopt_group_key(
+        &mut self,
+        group_id: &str,
+        signed_msg: &SignedMessage<Identity, Signature>,
+        group_key: GroupKey,
+    ) -> Result<(), String> {
+        self.key_store.set_group_key(group_id, group_key)?;
+        self.restart_ratchet(group_id, signed_msg)
     }
 
-    /// Writes a signed message to the store with validation. It validates the message signature, sequence, and previous hash.
+    /// Writes a signed message to the store with validation. A message which is already stored is rejected
+    /// with [WriteError::AlreadyExists]. It validates the message signature, sequence, and previous hash.
+    /// The first message must be the genesis record of the group, and the messages must follow the group
+    /// policy and membership (see [GroupState::check](crate::state::GroupState::check)). If the settings of
+    /// the group in the `GroupSettingsStore` have allowed signers, the signer must be one of them, and the
+    /// timestamp is checked if they check the timestamps strictly. A `KeyAnnouncement` record must announce the key
+    /// pinned for the signer, if any. The message must be within the [WriteLimits] in the `LimitStore`.
     /// It saves the message to the `SignedMessageStore` and adds the group to the `GroupStore`.
-    /// It returns the message hash and the signed message if successful, otherwise it returns a validation error message.
+    /// It returns the message hash and the signed message if successful, otherwise it returns a [WriteError].
+    /// If the head of the group is changed by another tab before the message is written, the message is
+    /// validated again against the new head, up to [WRITE_ATTEMPTS] times.
     pub(crate) fn write_with_validation(
         &mut self,
         group_id: &str,
         message: SignedMessage<Identity, Signature>,
-    ) -> Result<(MessageHash, SignedMessage<Identity, Signature>), String> {
+    ) -> Result<(MessageHash, SignedMessage<Identity, Signature>), WriteError> {
+        let mut attempts = 1;
+        loop {
+            match self.validate_and_write(group_id, &message) {
+                Err(WriteError::HeadChanged) if attempts < WRITE_ATTEMPTS => attempts += 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Validates the message and writes it, as [Writer::write_with_validation] does once.
+    fn validate_and_write(
+        &mut self,
+        group_id: &str,
+        message: &SignedMessage<Identity, Signature>,
+    ) -> Result<(MessageHash, SignedMessage<Identity, Signature>), WriteError> {
+        // detect duplicate
+        if HashAlgorithm::ALL.iter().any(|algorithm| {
+            self.message_store
+                .message(group_id, &algorithm.hash(message))
+                .is_some()
+        }) {
+            return Err(WriteError::AlreadyExists);
+        }
+
         // validate message signature
         if !message.verify::<Sha256>() {
-            return Err("fail to validate message".to_string());
+            return Err(WriteError::InvalidSignature);
+        }
+
+        // validate the write limits
+        let limits = self.limit_store.limits();
+        let now = unix_timestamp();
+        let since = now.saturating_sub(limits.window);
+        if limits
+            .max_message_size
+            .is_some_and(|max| message.message.data.len() > max)
+        {
+            return Err(WriteError::MessageTooLarge);
+        }
+        if limits
+            .max_messages_per_group
+            .is_some_and(|max| self.message_store.group_info(group_id).message_count >= max)
+        {
+            return Err(WriteError::TooManyMessages);
+        }
+        if limits
+            .max_messages_per_author
+            .is_some_and(|max| self.limit_store.writes_since(group_id, &message.id, since) >= max)
+        {
+            return Err(WriteError::RateLimited);
+        }
+
+        // validate signer
+        let settings = self.settings_store.settings(group_id);
+        if !settings.is_allowed_signer(&message.id) {
+            return Err(WriteError::UnknownSigner);
         }
 
         // validate sequence and previous hash
-        let (expect_prev_hash, expect_seq) = self
-            .message_store
-            .latest_message(group_id)
-            .map(|(hash, msg)| (hash, msg.seq + 1))
-            .unwrap_or(([0u8; 32], 0));
+        let latest = self.message_store.latest_message(group_id);
+        let (expect_prev_hash, expect_seq) = match &latest {
+            Some((hash, msg)) => (*hash, msg.next_seq().ok_or(WriteError::ChainFull)?),
+            None => ([0u8; 32], 0),
+        };
 
         if message.seq != expect_seq {
-            return Err("wrong message sequence".to_string());
+            return Err(WriteError::WrongSequence);
+        }
+        if !ct_eq(&message.message.previous_hash, &expect_prev_hash) {
+            return Err(WriteError::WrongPreviousHash);
+        }
+
+        // validate timestamp
+        let latest_timestamp = latest.and_then(|(_, msg)| msg.message.timestamp);
+        settings
+            .check_timestamp(message, latest_timestamp, now)
+            .map_err(WriteError::InvalidTimestamp)?;
+
+        // validate invitation expiry
+        let kind = message.message.kind;
+        if kind == MessageKind::Join {
+            let request = serde_json::from_slice::<JoinRequest>(&message.message.data)
+                .map_err(|_| WriteError::Rejected("invalid join record".to_string()))?;
+            if request.invite.is_expired_at(unix_timestamp()) {
+                return Err(WriteError::Rejected("invitation is expired".to_string()));
+            }
+        }
+
+        // validate capability expiry
+        let state = self.group_store.state(group_id);
+        let capability = match kind {
+            MessageKind::Delegation => serde_json::from_slice::<Capability>(&message.message.data)
+                .map(Some)
+                .map_err(|_| WriteError::Rejected("invalid delegation record".to_string()))?,
+            _ if !state.is_member(&message.id) => state.delegation_for(&message.id, kind).cloned(),
+            _ => None,
+        };
+        if let Some(capability) = capability {
+            if capability.is_expired_at(unix_timestamp()) {
+                return Err(WriteError::Rejected("capability is expired".to_string()));
+            }
         }
-        if message.message.previous_hash != expect_prev_hash {
-            return Err("wrong previous hash".to_string());
+
+        // validate edit
+        if let Some(supersedes) = &message.message.supersedes {
+            let original = self
+                .message_store
+                .message(group_id, supersedes)
+                .ok_or(WriteError::Rejected("edited message not found".to_string()))?;
+            if original.id != message.id {
+                return Err(WriteError::Rejected(
+                    "edited message is signed by another signer".to_string(),
+                ));
+            }
+        }
+
+        // validate redaction
+        if kind == MessageKind::Redaction {
+            let redaction = serde_json::from_slice::<Redaction>(&message.message.data)
+                .map_err(|_| WriteError::Rejected("invalid redaction record".to_string()))?;
+            let original = self
+                .message_store
+                .message(group_id, &redaction.hash)
+                .ok_or(WriteError::Rejected(
+                    "redacted message not found".to_string(),
+                ))?;
+            if !original.message.kind.is_data() {
+                return Err(WriteError::Rejected(
+                    "only data may be redacted".to_string(),
+                ));
+            }
+            if original.id != message.id && state.role_of(&message.id) != Some(Role::Admin) {
+                return Err(WriteError::Rejected(
+                    "redacted message is signed by another signer".to_string(),
+                ));
+            }
         }
 
-        Ok(self.write(group_id, message))
+        // validate pin
+        if matches!(kind, MessageKind::Pin | MessageKind::Unpin) {
+            let change = serde_json::from_slice::<PinChange>(&message.message.data)
+                .map_err(|_| WriteError::Rejected("invalid pin record".to_string()))?;
+            let pinned = self
+                .message_store
+                .message(group_id, &change.hash)
+                .ok_or(WriteError::Rejected("pinned message not found".to_string()))?;
+            if !pinned.message.kind.is_data() {
+                return Err(WriteError::Rejected("only data may be pinned".to_string()));
+            }
+        }
+
+        // validate checkpoint merkle root
+        if kind == MessageKind::Checkpoint {
+            let checkpoint = serde_json::from_slice::<Checkpoint>(&message.message.data)
+                .map_err(|_| WriteError::Rejected("invalid checkpoint record".to_string()))?;
+            let mmr = self.merkle_store.mmr(group_id);
+            if checkpoint.merkle_root.is_some_and(|root| {
+                checkpoint.seq.checked_add(1) == Some(mmr.leaf_count()) && root != mmr.root()
+            }) {
+                return Err(WriteError::Rejected(
+                    "checkpoint merkle root does not match".to_string(),
+                ));
+            }
+        }
+
+        // validate the announced key against the key pinned on first use
+        if let Some(announcement) = key_announcement(message) {
+            if self
+                .pin_store
+                .pinned_key(group_id, &message.id)
+                .is_some_and(|key| key != announcement.agreement_key)
+            {
+                return Err(WriteError::KeyChanged(message.id.to_string()));
+            }
+        }
+
+        // validate genesis, group policy and membership
+        state
+            .check(group_id, message)
+            .map_err(WriteError::Rejected)?;
+
+        // the write is counted in the same atomic write as the message
+        journal::atomically(|| {
+            let written = self.write(group_id, message.clone())?;
+            if limits.max_messages_per_author.is_some() {
+                self.limit_store
+                    .record_write(group_id, &message.id, now, since)
+                    .map_err(WriteError::Storage)?;
+            }
+            Ok(written)
+        })
+        .map_err(WriteError::Storage)?
+    }
+
+    /// Writes a signed message to the store with validation as [Writer::write_with_validation] does, but a
+    /// message whose ancestors have not arrived yet, i.e. ahead of the next sequence number, is held in
+    /// the `PendingStore` if its signature is valid and it is within the [WriteLimits] and the capacity of
+    /// the buffer (see [check_pending](Writer::check_pending)). After a message is written, the pending
+    /// messages which follow it are written in order with validation, and the rejected ones are dropped. It
+    /// returns the hashes of the written messages, which are empty if the message is held or already stored,
+    /// so that a message may be submitted again idempotently.
+    pub(crate) fn write_in_order(
+        &mut self,
+        group_id: &str,
+        message: SignedMessage<Identity, Signature>,
+    ) -> Result<Vec<MessageHash>, WriteError> {
+        let expect_seq = match self.message_store.latest_message(group_id) {
+            Some((_, msg)) => msg.next_seq().ok_or(WriteError::ChainFull)?,
+            None => 0,
+        };
+        if message.seq > MAX_SEQ {
+            return Err(WriteError::ChainFull);
+        }
+        if message.seq > expect_seq {
+            if !message.verify::<Sha256>() {
+                return Err(WriteError::InvalidSignature);
+            }
+            self.check_pending(group_id, &message, expect_seq)?;
+            self.pending_store
+                .add_pending(group_id, message)
+                .map_err(WriteError::Storage)?;
+            return Ok(vec![]);
+        }
+
+        let (hash, _) = match self.write_with_validation(group_id, message) {
+            Ok(written) => written,
+            Err(WriteError::AlreadyExists) => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+        let mut written = vec![hash];
+        while let Some(parent) = written.last() {
+            let children = self
+                .pending_store
+                .take_children(group_id, parent)
+                .map_err(WriteError::Storage)?;
+            let Some((hash, _)) = children
+                .into_iter()
+                .find_map(|child| self.write_with_validation(group_id, child).ok())
+            else {
+                break;
+            };
+            written.push(hash);
+        }
+        Ok(written)
+    }
+
+    /// Checks that the message ahead of the chain may be held in the pending buffer: its data is within the
+    /// maximum message size, the stored and the pending messages of the group are within the maximum number
+    /// of messages per group, and the pending messages of its signer are within the maximum number of
+    /// messages per author of the [WriteLimits]. The buffer holds at most [MAX_PENDING_MESSAGES] messages,
+    /// which are at most as far ahead of the next sequence number. A message which is held already passes.
+    fn check_pending(
+        &self,
+        group_id: &str,
+        message: &SignedMessage<Identity, Signature>,
+        expect_seq: u64,
+    ) -> Result<(), WriteError> {
+        let pending = self.pending_store.pending(group_id);
+        let hash = message.hash::<Sha256>();
+        if pending.iter().any(|msg| msg.hash::<Sha256>() == hash) {
+            return Ok(());
+        }
+
+        let limits = self.limit_store.limits();
+        if limits
+            .max_message_size
+            .is_some_and(|max| message.message.data.len() > max)
+        {
+            return Err(WriteError::MessageTooLarge);
+        }
+        if limits.max_messages_per_group.is_some_and(|max| {
+            self.message_store.group_info(group_id).message_count + pending.len() as u64 >= max
+        }) {
+            return Err(WriteError::TooManyMessages);
+        }
+        if limits
+            .max_messages_per_author
+            .is_some_and(|max| pending.iter().filter(|msg| msg.id == message.id).count() >= max)
+        {
+            return Err(WriteError::RateLimited);
+        }
+        if pending.len() >= MAX_PENDING_MESSAGES
+            || message.seq - expect_seq > MAX_PENDING_MESSAGES as u64
+        {
+            return Err(WriteError::PendingFull);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the announcement if the message is a `KeyAnnouncement` record.
+fn key_announcement(signed_msg: &SignedMessage<Identity, Signature>) -> Option<KeyAnnouncement> {
+    if signed_msg.message.kind != MessageKind::KeyAnnouncement {
+        return None;
     }
+    serde_json::from_slice(&signed_msg.message.data).ok()
 }
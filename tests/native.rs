@@ -0,0 +1,622 @@
+#![cfg(not(feature = "web"))]
+
+use webmessage::{initAccount, messages, signMessage, store::memory, validateMessages};
+
+#[test]
+fn test_native_storage() {
+    let path = std::env::temp_dir().join(format!("webmessage_{}.json", std::process::id()));
+    memory::open_file(&path).expect("it should open the storage file");
+
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    signMessage("group1", "data 2").expect("it should sign the message");
+    assert_eq!(messages("group1").len(), 3);
+    assert!(validateMessages("group1"));
+
+    // the items are loaded from the file
+    memory::close_file();
+    webmessage::clear().expect("it should clear the storage");
+    assert!(messages("group1").is_empty());
+    memory::open_file(&path).expect("it should open the storage file");
+    assert_eq!(messages("group1").len(), 3);
+    assert!(validateMessages("group1"));
+
+    webmessage::clear().expect("it should clear the storage");
+    memory::close_file();
+    std::fs::remove_file(&path).expect("it should remove the storage file");
+}
+
+#[test]
+fn test_fault_injection() {
+    use webmessage::store::faults::{self, Faults};
+
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    webmessage::clearDiagnostics();
+
+    // the writes beyond the quota fail, the failures are returned and reported, and the chain is unchanged
+    let before = messages("group1");
+    faults::set_faults(Some(Faults {
+        quota: Some(0),
+        ..Default::default()
+    }));
+    signMessage("group1", "data 2").expect_err("it should fail to write the message");
+    faults::set_faults(None);
+    assert!(!webmessage::diagnostics().is_empty());
+    assert_eq!(messages("group1"), before);
+    assert!(validateMessages("group1"));
+
+    // the queued messages stay in the outbox if they cannot be written, so that they are written once
+    webmessage::queueMessage("group1", "queued").expect("it should queue the message");
+    faults::set_faults(Some(Faults {
+        quota: Some(0),
+        ..Default::default()
+    }));
+    webmessage::flushOutbox().expect_err("it should fail to write the messages");
+    faults::set_faults(None);
+    assert_eq!(messages("group1"), before);
+    assert_eq!(webmessage::outbox().len(), 1);
+    assert_eq!(
+        webmessage::flushOutbox()
+            .expect("it should flush the outbox")
+            .len(),
+        1
+    );
+    assert!(webmessage::outbox().is_empty());
+    assert_eq!(messages("group1").len(), before.len() + 1);
+
+    // the intermittent failures are reproducible with the seed
+    let failures = |seed| {
+        faults::set_faults(Some(Faults {
+            failure_rate: 0.5,
+            seed,
+            ..Default::default()
+        }));
+        (0..10)
+            .map(|idx| webmessage::saveDraft("group1", &format!("draft {idx}"), None).is_err())
+            .collect::<Vec<_>>()
+    };
+    let first = failures(1);
+    assert!(first.contains(&true) && first.contains(&false));
+    assert_eq!(first, failures(1));
+    faults::set_faults(None);
+
+    // the items changed by other parties are seen
+    assert_eq!(webmessage::groups().len(), 1);
+    faults::set_on_write(Some(Box::new(|_| faults::external_remove_item("groups"))));
+    signMessage("group2", "data 1").expect("it should sign the message");
+    faults::set_on_write(None);
+    assert!(webmessage::groups().is_empty());
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_validation_watermark() {
+    use sha2::Sha256;
+    use webmessage::{
+        account::Identity, hash_to_string, message::Signature, store::faults, SignedMessage,
+    };
+
+    let report = || -> (bool, bool) {
+        let report: serde_json::Value = serde_json::from_str(
+            &webmessage::validateAll().expect("it should validate the groups")[0],
+        )
+        .expect("it should parse the report");
+        (
+            report["valid"].as_bool().unwrap(),
+            report["skipped"].as_bool().unwrap(),
+        )
+    };
+
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    let head = signMessage("group1", "data 2").expect("it should sign the message");
+    assert_eq!(report(), (true, false));
+    assert_eq!(report(), (true, true));
+
+    // the message at the watermark is checked, so that its modification is not skipped
+    let msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&head).expect("it should parse the signed message");
+    let mut modified: serde_json::Value = serde_json::from_str(&head).unwrap();
+    modified["message"]["data"] = serde_json::json!(b"modified".to_vec());
+    let key = format!("msg_group1_{}", hash_to_string(&msg.hash::<Sha256>()));
+    faults::external_set_item(&key, &modified.to_string());
+    assert_eq!(report(), (false, false));
+    faults::external_set_item(&key, &head);
+    assert_eq!(report(), (true, true));
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_chain_full() {
+    use sha2::Sha256;
+    use webmessage::{
+        account::{GenKeysAlgorithm, Identity, Secret},
+        hash_to_string,
+        message::{MessageSigner, Signature},
+        store::faults,
+        GenerateKeys, Message, SignedMessage, MAX_SEQ,
+    };
+
+    // the head of the group is at the maximum sequence number
+    initAccount().expect("it should create the account");
+    let (secret, identity) = GenKeysAlgorithm::generate_keys();
+    let last = SignedMessage::<Identity, Signature>::sign::<Secret, MessageSigner>(
+        identity,
+        &secret,
+        Message::root(b"last".to_vec()),
+        MAX_SEQ,
+    );
+    let hash = last.hash::<Sha256>();
+    faults::external_set_item(
+        &format!("msg_group1_{}", hash_to_string(&hash)),
+        &serde_json::to_string(&last).unwrap(),
+    );
+    faults::external_set_item(
+        "latest_msghash_group1",
+        &serde_json::to_string(&hash).unwrap(),
+    );
+
+    // the next message is not signed at the same position
+    assert_eq!(
+        signMessage("group1", "data").expect_err("the group is full"),
+        "group reached its maximum length"
+    );
+    webmessage::queueMessage("group1", "data").expect_err("the group is full");
+    assert_eq!(messages("group1").len(), 1);
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_head_contention() {
+    use webmessage::{store::faults, writer::WriteError};
+
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+
+    // the head is moved by another tab on every attempt, e.g. to a message which is not synced yet
+    faults::external_set_item_unseen(
+        "latest_msghash_group1",
+        &serde_json::to_string(&[7u8; 32]).unwrap(),
+    );
+    assert_eq!(
+        signMessage("group1", "data 2"),
+        Err(WriteError::HeadChanged.to_string())
+    );
+    assert_eq!(
+        webmessage::signTaggedMessage("group1", "data 2", vec![]),
+        Err(WriteError::HeadChanged.to_string())
+    );
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_group_settings_migration() {
+    use webmessage::{account::GenKeysAlgorithm, store::faults, GenerateKeys};
+
+    // the items of version 1 are written before the first access, which runs the migrations
+    let (_, identity) = GenKeysAlgorithm::generate_keys();
+    faults::external_set_item("\u{2}schema_version", "1");
+    faults::external_set_item(
+        "grpsigners_group1",
+        &serde_json::to_string(&[&identity]).unwrap(),
+    );
+    faults::external_set_item("grpretention_group1", r#"{"max_age":null,"max_count":2}"#);
+    faults::external_set_item(
+        "\u{2}work\u{2}grpretention_group1",
+        r#"{"max_age":60,"max_count":null}"#,
+    );
+
+    // the allowed signers and the retention policies are moved into the settings of the groups
+    assert_eq!(webmessage::schemaVersion(), 2);
+    assert_eq!(
+        webmessage::allowedSigners("group1"),
+        vec![identity.to_string()]
+    );
+    assert_eq!(
+        webmessage::retentionPolicy("group1"),
+        r#"{"max_age":null,"max_count":2}"#
+    );
+    webmessage::switchProfile("work").expect("it should switch the profile");
+    assert!(webmessage::allowedSigners("group1").is_empty());
+    assert_eq!(
+        webmessage::retentionPolicy("group1"),
+        r#"{"max_age":60,"max_count":null}"#
+    );
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_detect_tampering() {
+    use sha2::Sha256;
+    use webmessage::{
+        account::Identity, hash_to_string, message::Signature, store::faults, SignedMessage,
+    };
+
+    let hash_of = |msg_str: &str| {
+        let msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(msg_str).expect("it should parse the signed message");
+        hash_to_string(&msg.hash::<Sha256>())
+    };
+    let kinds = || -> Vec<(String, String, String, Option<u64>)> {
+        webmessage::detectTampering()
+            .iter()
+            .map(|finding| {
+                let finding: serde_json::Value =
+                    serde_json::from_str(finding).expect("it should parse the finding");
+                (
+                    finding["group_id"].as_str().unwrap().to_string(),
+                    finding["key"].as_str().unwrap().to_string(),
+                    finding["kind"].as_str().unwrap().to_string(),
+                    finding["seq"].as_u64(),
+                )
+            })
+            .collect()
+    };
+
+    initAccount().expect("it should create the account");
+    let first = signMessage("group1", "data 1").expect("it should sign the message");
+    signMessage("group1", "data 2").expect("it should sign the message");
+    let second = signMessage("group2", "data 1").expect("it should sign the message");
+    let third = signMessage("group2", "data 2").expect("it should sign the message");
+    assert!(kinds().is_empty());
+
+    // the data of a message is modified
+    let mut modified: serde_json::Value = serde_json::from_str(&first).unwrap();
+    modified["message"]["data"] = serde_json::json!(b"modified".to_vec());
+    let key = format!("msg_group1_{}", hash_of(&first));
+    faults::external_set_item(&key, &modified.to_string());
+    assert_eq!(
+        kinds(),
+        vec![(
+            "group1".to_string(),
+            key.clone(),
+            "modified_message".to_string(),
+            Some(1)
+        )]
+    );
+    faults::external_set_item(&key, &first);
+
+    // the head pointer is rolled back
+    faults::external_set_item(
+        "latest_msghash_group2",
+        &serde_json::to_string(
+            &serde_json::from_str::<SignedMessage<Identity, Signature>>(&second)
+                .unwrap()
+                .hash::<Sha256>(),
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        kinds(),
+        vec![
+            (
+                "group2".to_string(),
+                "latest_msghash_group2".to_string(),
+                "stale_head".to_string(),
+                Some(1)
+            ),
+            (
+                "group2".to_string(),
+                format!("msg_group2_{}", hash_of(&third)),
+                "orphaned_message".to_string(),
+                Some(2)
+            ),
+        ]
+    );
+
+    // the head pointer refers to a message which is not stored
+    faults::external_set_item(
+        "latest_msghash_group2",
+        &serde_json::to_string(&[7u8; 32]).unwrap(),
+    );
+    assert!(kinds().contains(&(
+        "group2".to_string(),
+        "latest_msghash_group2".to_string(),
+        "dangling_head".to_string(),
+        None
+    )));
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_tombstone_tampering() {
+    use sha2::Sha256;
+    use webmessage::{
+        account::Identity, hash_to_string, message::Signature, store::faults, SignedMessage,
+    };
+
+    initAccount().expect("it should create the account");
+    let purged: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "purged").expect("it should sign the message"))
+            .expect("it should parse the signed message");
+    let other: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "other").expect("it should sign the message"))
+            .expect("it should parse the signed message");
+    let hash = purged.hash::<Sha256>();
+    webmessage::redactMessage("group1", &serde_json::to_string(&hash).unwrap(), true)
+        .expect("it should redact and purge");
+    let tombstone = messages("group1")
+        .into_iter()
+        .find(|msg| msg.contains(r#""redacted":true"#))
+        .expect("it should keep the tombstone");
+    assert!(validateMessages("group1"));
+    assert!(webmessage::detectTampering().is_empty());
+
+    // the tombstone is modified, moved or removed
+    let key = format!("msg_group1_{}", hash_to_string(&hash));
+    let mut digest: serde_json::Value = serde_json::from_str(&tombstone).unwrap();
+    digest["message"]["tombstone"]["digest"] = serde_json::to_value([7u8; 32]).unwrap();
+    let mut moved: serde_json::Value = serde_json::from_str(&tombstone).unwrap();
+    moved["message"]["tombstone"]["hash"] = serde_json::json!(other.hash::<Sha256>());
+    let mut removed: serde_json::Value = serde_json::from_str(&tombstone).unwrap();
+    removed["message"]
+        .as_object_mut()
+        .unwrap()
+        .remove("tombstone");
+    let mut kind: serde_json::Value = serde_json::from_str(&tombstone).unwrap();
+    kind["message"]["kind"] = serde_json::json!("add_member");
+    for tampered in [digest, moved, removed] {
+        faults::external_set_item(&key, &tampered.to_string());
+        assert!(!validateMessages("group1"));
+        let finding: serde_json::Value =
+            serde_json::from_str(&webmessage::detectTampering()[0]).unwrap();
+        assert_eq!(finding["key"], key.as_str());
+        assert_eq!(finding["kind"], "modified_message");
+    }
+
+    // the kind of the tombstone is changed to a record, which is checked against the group state
+    faults::external_set_item(&key, &kind.to_string());
+    assert!(!validateMessages("group1"));
+
+    faults::external_set_item(&key, &tombstone);
+    assert!(validateMessages("group1"));
+    assert!(webmessage::detectTampering().is_empty());
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_rebuild_head() {
+    use sha2::Sha256;
+    use webmessage::{account::Identity, message::Signature, store::faults, SignedMessage};
+
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    let latest = signMessage("group1", "data 2").expect("it should sign the message");
+    let latest: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&latest).expect("it should parse the signed message");
+    let latest_hash = serde_json::to_string(&latest.hash::<Sha256>()).unwrap();
+    webmessage::rebuildHead("group2").expect_err("the group has no messages");
+
+    // the chain is not visible without the pointer
+    faults::external_remove_item("latest_msghash_group1");
+    assert!(messages("group1").is_empty());
+    assert_eq!(
+        webmessage::rebuildHead("group1").expect("it should rebuild the head"),
+        latest_hash
+    );
+    assert_eq!(messages("group1").len(), 3);
+    assert!(validateMessages("group1"));
+
+    // the corrupted pointer is replaced
+    faults::external_set_item("latest_msghash_group1", "corrupted");
+    assert_eq!(
+        webmessage::rebuildHead("group1").expect("it should rebuild the head"),
+        latest_hash
+    );
+    assert_eq!(messages("group1").len(), 3);
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_gc() {
+    use webmessage::store::faults;
+
+    initAccount().expect("it should create the account");
+    let orphan = signMessage("group1", "data 1").expect("it should sign the message");
+    signMessage("group2", "data 2").expect("it should sign the message");
+    assert_eq!(
+        webmessage::gc().expect("it should collect the garbage"),
+        r#"{"removed":0,"reclaimed_bytes":0}"#
+    );
+
+    // the leftover of an aborted write is removed
+    let key = format!("msg_group1_{}", "ab".repeat(32));
+    faults::external_set_item(&key, &orphan);
+    assert_eq!(
+        webmessage::gc().expect("it should collect the garbage"),
+        format!(
+            r#"{{"removed":1,"reclaimed_bytes":{}}}"#,
+            key.len() + orphan.len()
+        )
+    );
+    assert_eq!(messages("group1").len(), 2);
+    assert!(validateMessages("group1"));
+
+    // the chain without a head pointer is kept, so that it can be recovered
+    faults::external_remove_item("latest_msghash_group2");
+    assert_eq!(
+        webmessage::gc().expect("it should collect the garbage"),
+        r#"{"removed":0,"reclaimed_bytes":0}"#
+    );
+    webmessage::rebuildHead("group2").expect("it should rebuild the head");
+    assert_eq!(messages("group2").len(), 2);
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_write_ahead_journal() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use webmessage::store::faults;
+
+    let path = std::env::temp_dir().join(format!("webmessage_journal_{}.json", std::process::id()));
+    memory::open_file(&path).expect("it should open the storage file");
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+
+    // the tab is killed after the message is written, before the head of the group is updated
+    faults::set_on_write(Some(Box::new(|key| {
+        if key.starts_with("msg_group1_") {
+            panic!("the tab is killed");
+        }
+    })));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        signMessage("group1", "data 2").expect("it should sign the message")
+    }));
+    assert!(result.is_err());
+    faults::set_on_write(None);
+    memory::close_file();
+
+    // the write is rolled forward when the library is loaded again
+    std::thread::spawn(move || {
+        memory::open_file(&path).expect("it should open the storage file");
+        assert_eq!(messages("group1").len(), 3);
+        assert!(validateMessages("group1"));
+        assert!(webmessage::diagnostics()
+            .iter()
+            .any(|diagnostic| diagnostic.contains("rolled forward")));
+        signMessage("group1", "data 3").expect("it should sign the message");
+        assert_eq!(messages("group1").len(), 4);
+
+        webmessage::clear().expect("it should clear the storage");
+        memory::close_file();
+        std::fs::remove_file(&path).expect("it should remove the storage file");
+    })
+    .join()
+    .expect("it should recover the write");
+
+    webmessage::clear().expect("it should clear the storage");
+
+    // a journal whose marker is not written is rolled back
+    std::thread::spawn(|| {
+        faults::external_set_item(
+            "\u{2}journal#chunk0",
+            r#"[["drafts_group1","[{\"id\":\"1\",\"data\":\"draft\",\"created_at\":0,"#,
+        );
+        assert!(webmessage::listDrafts("group1").is_empty());
+        assert!(webmessage::diagnostics()
+            .iter()
+            .any(|diagnostic| diagnostic.contains("rolled back")));
+    })
+    .join()
+    .expect("it should discard the journal");
+}
+
+#[test]
+fn test_head_compare_and_swap() {
+    use sha2::Sha256;
+    use webmessage::{
+        account::{EncryptedSecret, Identity, Secret},
+        hash_to_string,
+        message::{MessageSigner, Signature},
+        store::faults,
+        SignedMessage,
+    };
+
+    let identity = initAccount().expect("it should create the account");
+    let parse = |msg_str: &str| -> SignedMessage<Identity, Signature> {
+        serde_json::from_str(msg_str).expect("it should parse the signed message")
+    };
+    let head = parse(&signMessage("group1", "data 1").expect("it should sign the message"));
+    let exported = webmessage::exportSecret(&identity, "passphrase", true).unwrap();
+    let secret: Secret = serde_json::from_str::<EncryptedSecret>(&exported)
+        .unwrap()
+        .decrypt("passphrase")
+        .expect("it should decrypt the secret");
+
+    // another tab appends a message, and its `storage` event is not delivered yet
+    let other = SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+        head.id.clone(),
+        &secret,
+        b"data from another tab".to_vec(),
+        head.hash::<Sha256>(),
+        head.clone(),
+    );
+    let other_hash = other.hash::<Sha256>();
+    faults::external_set_item_unseen(
+        &format!("msg_group1_{}", hash_to_string(&other_hash)),
+        &serde_json::to_string(&other).unwrap(),
+    );
+    faults::external_set_item_unseen(
+        "latest_msghash_group1",
+        &serde_json::to_string(&other_hash).unwrap(),
+    );
+
+    // the message signed over the stale head is signed again over the new head instead of forking
+    let mine = parse(&signMessage("group1", "data 2").expect("it should sign the message"));
+    assert_eq!(mine.message.previous_hash, other_hash);
+    assert_eq!(mine.seq, other.seq + 1);
+    assert_eq!(messages("group1").len(), 4);
+    assert!(validateMessages("group1"));
+
+    webmessage::clear().expect("it should clear the storage");
+}
+
+#[test]
+fn test_prune_index() {
+    let path = std::env::temp_dir().join(format!("webmessage_prune_{}.json", std::process::id()));
+    memory::open_file(&path).expect("it should open the storage file");
+    initAccount().expect("it should create the account");
+    for data in ["data 1", "data 2"] {
+        webmessage::signTaggedMessage("group1", data, vec!["tag".to_string()])
+            .expect("it should sign the message");
+    }
+    webmessage::checkpoint("group1").expect("it should sign the checkpoint");
+    signMessage("group1", "data 3").expect("it should sign the message");
+
+    // the pruned messages are removed from the index
+    assert_eq!(
+        webmessage::prune("group1").expect("it should prune the group"),
+        3
+    );
+    let items: std::collections::BTreeMap<String, String> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap())
+            .expect("it should parse the storage file");
+    let index: webmessage::index::MessageIndex =
+        serde_json::from_str(&items["msg_index_group1"]).expect("it should parse the index");
+    let indexed: usize = index.authors.iter().map(|(_, hashes)| hashes.len()).sum();
+    assert_eq!(indexed, messages("group1").len());
+    assert!(index.tagged("tag").is_empty());
+    assert!(webmessage::messagesByTag("group1", "tag").is_empty());
+
+    webmessage::clear().expect("it should clear the storage");
+    memory::close_file();
+    std::fs::remove_file(&path).expect("it should remove the storage file");
+}
+
+#[test]
+fn test_import_accounts_atomically() {
+    use webmessage::store::faults::{self, Faults};
+
+    initAccount().expect("it should create the account");
+    webmessage::newAccount().expect("it should create the account");
+    let exported = webmessage::exportAccounts("passphrase").expect("it should export");
+    webmessage::clear().expect("it should clear the storage");
+
+    // the writes fail after the first one, and none of the accounts is imported
+    faults::set_on_write(Some(Box::new(|_| {
+        faults::set_faults(Some(Faults {
+            quota: Some(0),
+            ..Default::default()
+        }))
+    })));
+    webmessage::importAccounts(&exported, "passphrase").expect_err("it should fail to write");
+    faults::set_on_write(None);
+    faults::set_faults(None);
+    assert!(webmessage::allAccounts().is_empty());
+
+    // the accounts are all imported once they can be written
+    webmessage::importAccounts(&exported, "passphrase").expect("it should import");
+    assert_eq!(webmessage::allAccounts().len(), 2);
+
+    webmessage::clear().expect("it should clear the storage");
+}
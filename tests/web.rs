@@ -1,10 +1,13 @@
+#![cfg(feature = "web")]
+
 use sha2::Sha256;
 use wasm_bindgen_test::*;
 use webmessage::{
     account::{GenKeysAlgorithm, Identity, Secret},
     groups, initAccount,
     message::{MessageSigner, Signature},
-    messages, signMessage, validateMessages, GenerateKeys, Group, SignedMessage,
+    messages, signMessage, validateMessages, GenerateKeys, Group, GroupGenesis, GroupPolicy,
+    Message, MessageRef, SignedMessage,
 };
 
 wasm_bindgen_test_configure!(run_in_browser);
@@ -16,9 +19,8 @@ fn test_accounts() {
     assert!(accounts.is_empty());
 
     // initialize an account
-    let id_and_secret = initAccount();
-    assert_eq!(id_and_secret.len(), 2);
-    let id = Identity::try_from(id_and_secret[0].as_str()).expect("it should parse the identity");
+    let identity = initAccount().expect("it should create the account");
+    let id = Identity::try_from(identity.as_str()).expect("it should parse the identity");
 
     // accounts should have one account
     let accounts = webmessage::allAccounts();
@@ -27,10 +29,9 @@ fn test_accounts() {
     assert_eq!(accounts[0], id.to_string());
 
     // add another account
-    let id_and_secret2 = webmessage::newAccount();
-    assert_eq!(id_and_secret2.len(), 2);
+    let identity2 = webmessage::newAccount().expect("it should create the account");
 
-    let id2 = Identity::try_from(id_and_secret2[0].as_str()).expect("it should parse the identity");
+    let id2 = Identity::try_from(identity2.as_str()).expect("it should parse the identity");
 
     // accounts should have two accounts
     let accounts = webmessage::allAccounts();
@@ -40,22 +41,21 @@ fn test_accounts() {
     assert_eq!(accounts[1], id2.to_string());
 
     // check if current account is the newly added account
-    let check_id_and_secret = initAccount();
-    assert_eq!(check_id_and_secret.len(), 2);
+    let check_identity = initAccount().expect("it should create the account");
     let check_id =
-        Identity::try_from(check_id_and_secret[0].as_str()).expect("it should parse the identity");
+        Identity::try_from(check_identity.as_str()).expect("it should parse the identity");
     assert!(check_id == id2);
 
     // set the current account to the first account
-    webmessage::setCurrentAccount(&id.to_string());
-    let check_id_and_secret = initAccount();
-    assert_eq!(check_id_and_secret.len(), 2);
+    webmessage::setCurrentAccount(&id.to_string()).expect("it should set the current account");
+    let check_identity = initAccount().expect("it should create the account");
     let check_id =
-        Identity::try_from(check_id_and_secret[0].as_str()).expect("it should parse the identity");
+        Identity::try_from(check_identity.as_str()).expect("it should parse the identity");
     assert!(check_id == id);
+    webmessage::setCurrentAccount("not an identity").expect_err("it is not an identity");
 
     // delete the first account
-    webmessage::deleteAccount(&id.to_string());
+    webmessage::deleteAccount(&id.to_string(), false).expect("it should delete the account");
     // accounts should have one account
     let accounts = webmessage::allAccounts();
     assert_eq!(accounts.len(), 1);
@@ -63,10 +63,9 @@ fn test_accounts() {
     assert_eq!(accounts[0], id2.to_string());
 
     // check if current account is the second account
-    let check_id_and_secret = initAccount();
-    assert_eq!(check_id_and_secret.len(), 2);
+    let check_identity = initAccount().expect("it should create the account");
     let check_id =
-        Identity::try_from(check_id_and_secret[0].as_str()).expect("it should parse the identity");
+        Identity::try_from(check_identity.as_str()).expect("it should parse the identity");
     assert!(check_id == id2);
 
     // clear the local storage
@@ -76,31 +75,39 @@ fn test_accounts() {
 #[wasm_bindgen_test]
 fn test_sign_message() {
     // test initial setup
-    let items = initAccount();
-    assert_eq!(items.len(), 2);
+    let identity = initAccount().expect("it should create the account");
 
-    let id = Identity::try_from(items[0].as_str()).expect("it should parse the identity");
+    let id = Identity::try_from(identity.as_str()).expect("it should parse the identity");
 
     assert!(messages("group1").is_empty());
     assert!(groups().is_empty());
 
     // test signing a new message
-    assert!(!signMessage("group1", "some data").is_empty());
+    assert!(!signMessage("group1", "some data")
+        .expect("it should sign the message")
+        .is_empty());
 
     let msgs = messages("group1");
     assert!(!msgs.is_empty());
     let signed_msg: SignedMessage<Identity, Signature> =
         serde_json::from_str(msgs[0].as_str()).expect("it should parse the signed message");
 
-    // validate the signed message
+    // validate the signed message, which follows the genesis record
     assert!(signed_msg.id == id);
-    assert_eq!(signed_msg.seq, 0);
+    assert_eq!(signed_msg.seq, 1);
     assert_eq!(signed_msg.message.data, "some data".as_bytes());
     assert!(!groups().is_empty());
 
+    // validate the genesis record
+    let genesis_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[1].as_str()).expect("it should parse the signed message");
+    assert!(genesis_msg.is_genesis_of("group1"));
+
     // test signing another message
-    assert!(!signMessage("group1", "some data again").is_empty());
-    assert!(messages("group1").len() == 2);
+    assert!(!signMessage("group1", "some data again")
+        .expect("it should sign the message")
+        .is_empty());
+    assert!(messages("group1").len() == 3);
     assert!(groups().len() == 1);
 
     // validate all the messages
@@ -112,16 +119,12 @@ fn test_sign_message() {
 
 #[wasm_bindgen_test]
 fn test_add_message() {
-    initAccount();
+    initAccount().expect("it should create the account");
 
     // create a new identity for signing a message
     let (other_msg, other_msg2) = {
         let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
-        let msg1 = SignedMessage::new_first_message::<Secret, MessageSigner>(
-            other_id.clone(),
-            &other_secret,
-            "other data".as_bytes().to_vec(),
-        );
+        let msg1 = other_genesis(other_id.clone(), &other_secret, "group1");
         let msg2 = SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
             other_id.clone(),
             &other_secret,
@@ -149,16 +152,26 @@ fn test_add_message() {
 
     assert!(validateMessages("group1"));
 
+    // a first message which is not a genesis record is rejected
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let first_msg = SignedMessage::new_first_message::<Secret, MessageSigner>(
+        other_id,
+        &other_secret,
+        "other data".as_bytes().to_vec(),
+    );
+    webmessage::addSignedMessage("group2", &serde_json::to_string(&first_msg).unwrap())
+        .expect_err("the first message must be a genesis record");
+
     // clear the local storage
     webmessage::clear().expect("it should clear the local storage");
 }
 
 #[wasm_bindgen_test]
 fn test_sign_and_then_add_other_message() {
-    initAccount();
+    initAccount().expect("it should create the account");
 
     // test signing a new message
-    let msg_str = signMessage("group1", "some data");
+    let msg_str = signMessage("group1", "some data").expect("it should sign the message");
     let signed_msg: SignedMessage<Identity, Signature> =
         serde_json::from_str(&msg_str).expect("it should parse the signed message");
     assert!(signed_msg.verify::<Sha256>());
@@ -182,7 +195,7 @@ fn test_sign_and_then_add_other_message() {
     webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
         .expect("it should add the signed message");
 
-    assert!(messages("group1").len() == 2);
+    assert!(messages("group1").len() == 3);
     assert!(groups().len() == 1);
     assert!(validateMessages("group1"));
 
@@ -192,16 +205,12 @@ fn test_sign_and_then_add_other_message() {
 
 #[wasm_bindgen_test]
 fn test_add_other_message_and_then_sign() {
-    initAccount();
+    initAccount().expect("it should create the account");
 
-    // create a new identity for signing a message
+    // create a new identity for creating the group
     let other_msg = {
         let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
-        SignedMessage::new_first_message::<Secret, MessageSigner>(
-            other_id.clone(),
-            &other_secret,
-            "other data".as_bytes().to_vec(),
-        )
+        other_genesis(other_id, &other_secret, "group1")
     };
     assert!(other_msg.verify::<Sha256>());
 
@@ -210,7 +219,7 @@ fn test_add_other_message_and_then_sign() {
         .expect("it should add the signed message");
 
     // test signing a new message
-    let msg_str = signMessage("group1", "some data");
+    let msg_str = signMessage("group1", "some data").expect("it should sign the message");
     let signed_msg: SignedMessage<Identity, Signature> =
         serde_json::from_str(&msg_str).expect("it should parse the signed message");
     assert!(signed_msg.verify::<Sha256>());
@@ -225,13 +234,13 @@ fn test_add_other_message_and_then_sign() {
 
 #[wasm_bindgen_test]
 fn test_groups() {
-    initAccount();
+    initAccount().expect("it should create the account");
 
-    signMessage("group1", "some data");
-    signMessage("group2", "some data");
+    signMessage("group1", "some data").expect("it should sign the message");
+    signMessage("group2", "some data").expect("it should sign the message");
 
-    assert!(messages("group1").len() == 1);
-    assert!(messages("group2").len() == 1);
+    assert!(messages("group1").len() == 2);
+    assert!(messages("group2").len() == 2);
     assert!(validateMessages("group1"));
     assert!(validateMessages("group2"));
 
@@ -248,7 +257,7 @@ fn test_groups() {
 
 #[wasm_bindgen_test]
 fn test_invalid_message() {
-    initAccount();
+    initAccount().expect("it should create the account");
 
     // create a new identity for signing a message
     let mut msg = {
@@ -274,3 +283,3999 @@ fn test_invalid_message() {
     // clear the local storage
     webmessage::clear().expect("it should clear the local storage");
 }
+
+#[wasm_bindgen_test]
+fn test_compressed_message() {
+    initAccount().expect("it should create the account");
+    webmessage::setCompressionThreshold(16).expect("it should set the threshold");
+
+    // sign a message that is larger than the compression threshold
+    let data = "some repetitive data ".repeat(100);
+    signMessage("group1", &data).expect("it should sign the message");
+
+    // the message should be decompressed on read
+    let msgs = messages("group1");
+    assert_eq!(msgs.len(), 2);
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[0].as_str()).expect("it should parse the signed message");
+    assert_eq!(signed_msg.message.data, data.as_bytes());
+    assert!(!signed_msg.message.compressed);
+
+    // sign another message and validate the chain
+    signMessage("group1", &data).expect("it should sign the message");
+    assert!(messages("group1").len() == 3);
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_chunked_message() {
+    initAccount().expect("it should create the account");
+    // disable compression so that the serialized message exceeds the maximum item length
+    webmessage::setCompressionThreshold(usize::MAX).expect("it should set the threshold");
+
+    let data = "x".repeat(200 * 1024);
+    signMessage("group1", &data).expect("it should sign the message");
+    signMessage("group1", "small data").expect("it should sign the message");
+
+    // the chunked message should be reassembled on read
+    let msgs = messages("group1");
+    assert_eq!(msgs.len(), 3);
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[1].as_str()).expect("it should parse the signed message");
+    assert_eq!(signed_msg.message.data, data.as_bytes());
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_encrypted_group() {
+    initAccount().expect("it should create the account");
+    let key = webmessage::newGroupKey("group1").expect("it should create the group key");
+
+    // the signed message carries the ciphertext
+    let msg_str = signMessage("group1", "secret data").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    assert!(signed_msg.message.encrypted);
+    assert_ne!(signed_msg.message.data, "secret data".as_bytes());
+    assert!(signed_msg.verify::<Sha256>());
+
+    // members holding the key read the plaintext
+    let msgs = messages("group1");
+    let read_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[0].as_str()).expect("it should parse the signed message");
+    assert_eq!(read_msg.message.data, "secret data".as_bytes());
+
+    // validation works without the key
+    webmessage::removeGroupKey("group1").expect("it should remove the group key");
+    assert!(validateMessages("group1"));
+    let msgs = messages("group1");
+    let read_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[0].as_str()).expect("it should parse the signed message");
+    assert_ne!(read_msg.message.data, "secret data".as_bytes());
+
+    // the shared key decrypts the messages again
+    webmessage::setGroupKey("group1", &key).expect("it should set the group key");
+    let msgs = messages("group1");
+    let read_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[0].as_str()).expect("it should parse the signed message");
+    assert_eq!(read_msg.message.data, "secret data".as_bytes());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_group_key_agreement() {
+    // wrap and unwrap the group key with the agreement key of another identity
+    let (other_secret, _) = GenKeysAlgorithm::generate_keys();
+    let group_key = webmessage::encryption::generate_group_key();
+    let wrapped_key = webmessage::agreement::wrap_key(
+        &group_key,
+        &webmessage::agreement::agreement_public_key(&other_secret),
+    )
+    .expect("it should wrap the group key");
+    assert_eq!(
+        webmessage::agreement::unwrap_key(&wrapped_key, &other_secret),
+        Some(group_key)
+    );
+    let (another_secret, _) = GenKeysAlgorithm::generate_keys();
+    assert!(webmessage::agreement::unwrap_key(&wrapped_key, &another_secret).is_none());
+
+    // grant the group key to a member who announced the agreement key
+    let identity = initAccount().expect("it should create the account");
+    webmessage::newGroupKey("group1").expect("it should create the group key");
+    signMessage("group1", "secret data").expect("it should sign the message");
+
+    let member = webmessage::newAccount().expect("it should create the account");
+    webmessage::grantGroupKey("group1", vec![member.clone()])
+        .expect_err("the member has not announced the agreement key");
+    webmessage::announceAgreementKey("group1").expect("it should announce the key");
+
+    webmessage::setCurrentAccount(&identity).expect("it should set the current account");
+    webmessage::grantGroupKey("group1", vec![member.clone()])
+        .expect("it should grant the group key");
+
+    assert!(messages("group1").len() == 4);
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_group_key_rotation() {
+    let owner = initAccount().expect("it should create the account");
+    webmessage::newGroupKey("group1").expect("it should create the group key");
+    signMessage("group1", "secret data").expect("it should sign the message");
+
+    // two members announce their agreement keys and are granted the group key
+    let member1 = webmessage::newAccount().expect("it should create the account");
+    webmessage::announceAgreementKey("group1").expect("it should announce the key");
+    let member2 = webmessage::newAccount().expect("it should create the account");
+    webmessage::announceAgreementKey("group1").expect("it should announce the key");
+    webmessage::setCurrentAccount(&owner).expect("it should set the current account");
+    webmessage::grantGroupKey("group1", vec![member1.clone(), member2.clone()])
+        .expect("it should grant the group key");
+
+    // remove the second member
+    let rekey_str = webmessage::rotateGroupKey("group1", vec![member2.clone()])
+        .expect("it should rotate the group key");
+    let rekey: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&rekey_str).expect("it should parse the signed message");
+    assert_eq!(rekey.message.kind, webmessage::MessageKind::Rekey);
+    let distribution: webmessage::agreement::KeyDistribution =
+        serde_json::from_slice(&rekey.message.data).expect("it should parse the key distribution");
+    assert_eq!(distribution.grants.len(), 1);
+    assert_eq!(distribution.grants[0].recipient.to_string(), member1);
+
+    // messages encrypted with the old and the new keys are readable
+    signMessage("group1", "more secret data").expect("it should sign the message");
+    let msgs = messages("group1");
+    let latest: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[0].as_str()).expect("it should parse the signed message");
+    assert_eq!(latest.message.data, "more secret data".as_bytes());
+    let first: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[msgs.len() - 2].as_str())
+            .expect("it should parse the signed message");
+    assert_eq!(first.message.data, "secret data".as_bytes());
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_ratchet() {
+    initAccount().expect("it should create the account");
+    let key = webmessage::newGroupKey("group1").expect("it should create the group key");
+    signMessage("group1", "before ratchet").expect("it should sign the message");
+    webmessage::startRatchet("group1").expect("it should start the ratchet");
+
+    // messages after the start of the ratchet are not encrypted with the group key
+    let msg_str = signMessage("group1", "after ratchet").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    assert!(signed_msg.message.encrypted);
+    let group_key = serde_json::from_str(&key).expect("it should parse the group key");
+    assert!(webmessage::encryption::decrypt(&group_key, &signed_msg.message.data).is_none());
+
+    // the message keys derived by the ratchet decrypt the messages
+    let mut ratchet = webmessage::ratchet::RatchetState::new(&group_key, signed_msg.seq);
+    let message_key = ratchet
+        .message_key(signed_msg.seq)
+        .expect("it should derive the message key");
+    assert_eq!(
+        webmessage::encryption::decrypt(&message_key, &signed_msg.message.data),
+        Some("after ratchet".as_bytes().to_vec())
+    );
+    assert!(ratchet.message_key(signed_msg.seq + 1).is_some());
+
+    // the message keys are deleted once they are used, and the keys of the skipped messages are kept
+    assert!(ratchet.message_key(signed_msg.seq).is_none());
+    assert!(ratchet.message_key(signed_msg.seq + 3).is_some());
+    assert!(ratchet.message_key(signed_msg.seq + 2).is_some());
+    assert!(ratchet.message_key(signed_msg.seq + 2).is_none());
+    let max_skipped = webmessage::ratchet::MAX_SKIPPED_KEYS as u64;
+    assert!(ratchet
+        .message_key(signed_msg.seq + 5 + max_skipped)
+        .is_none());
+
+    let msgs = messages("group1");
+    assert_eq!(msgs.len(), 4);
+    let latest: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[0].as_str()).expect("it should parse the signed message");
+    assert_eq!(latest.message.data, "after ratchet".as_bytes());
+    let first: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[2].as_str()).expect("it should parse the signed message");
+    assert_eq!(first.message.data, "before ratchet".as_bytes());
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_sealed_sender() {
+    let identity = initAccount().expect("it should create the account");
+    webmessage::signSealedMessage("group1", "sealed data")
+        .expect_err("sealed messages require a group key");
+    webmessage::newGroupKey("group1").expect("it should create the group key");
+
+    // the sealed message is signed by an ephemeral identity
+    let msg_str =
+        webmessage::signSealedMessage("group1", "sealed data").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    assert!(signed_msg.message.sealed);
+    assert!(signed_msg.id.to_string() != identity);
+    assert!(signed_msg.verify::<Sha256>());
+    assert!(validateMessages("group1"));
+
+    // members read the sender and the data
+    let msgs = messages("group1");
+    let read_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(msgs[0].as_str()).expect("it should parse the signed message");
+    assert_eq!(read_msg.id.to_string(), identity);
+    assert_eq!(read_msg.message.data, "sealed data".as_bytes());
+
+    // reveal the sender with a verifiable proof
+    let hash = serde_json::to_string(&signed_msg.hash::<Sha256>()).unwrap();
+    let proof = webmessage::revealSender("group1", &hash).expect("it should reveal the sender");
+    assert!(webmessage::verifySenderProof(&proof));
+    let mut sender_proof: webmessage::sealed::SenderProof =
+        serde_json::from_str(&proof).expect("it should parse the proof");
+    assert_eq!(sender_proof.content.sender.to_string(), identity);
+    sender_proof.content.data = "other data".as_bytes().to_vec();
+    assert!(!sender_proof.verify());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_create_group() {
+    let identity = initAccount().expect("it should create the account");
+
+    let genesis_str = webmessage::createGroup("group1", "Group 1", r#"{"encrypted":true}"#)
+        .expect("it should create the group");
+    webmessage::createGroup("group1", "Group 1", r#"{"encrypted":true}"#)
+        .expect_err("the group already exists");
+    let genesis_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&genesis_str).expect("it should parse the signed message");
+    let genesis = genesis_msg
+        .genesis()
+        .expect("it should be a genesis record");
+    assert_eq!(genesis.name, "Group 1");
+    assert_eq!(genesis.creator.to_string(), identity);
+    assert!(genesis.policy.encrypted);
+
+    // the group is added with the genesis record
+    let grps = groups();
+    assert!(grps.len() == 1);
+    let gp: Group = serde_json::from_str(grps[0].as_str()).expect("it should parse the group");
+    assert_eq!(gp.id, "group1");
+    assert_eq!(gp.timestamp, genesis.timestamp);
+
+    // the messages of the encrypted group are encrypted
+    let msg_str = signMessage("group1", "some data").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    assert!(signed_msg.message.encrypted);
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_members() {
+    let owner = initAccount().expect("it should create the account");
+    webmessage::createGroup("group1", "Group 1", r#"{"members_only":true}"#)
+        .expect("it should create the group");
+    assert_eq!(webmessage::members("group1"), vec![owner.clone()]);
+
+    // add a member
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    webmessage::addMember("group1", &other_id.to_string()).expect("it should add the member");
+    assert_eq!(
+        webmessage::members("group1"),
+        vec![owner.clone(), other_id.to_string()]
+    );
+
+    // the member can add messages
+    let msg_str = signMessage("group1", "some data").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    let other_msg = SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+        other_id.clone(),
+        &other_secret,
+        "other data".as_bytes().to_vec(),
+        signed_msg.hash::<Sha256>(),
+        signed_msg,
+    );
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
+        .expect("it should add the signed message");
+
+    // remove the member, whose messages are rejected afterwards
+    let remove_str =
+        webmessage::removeMember("group1", &other_id.to_string()).expect("it should remove");
+    let remove_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&remove_str).expect("it should parse the signed message");
+    assert_eq!(webmessage::members("group1"), vec![owner.clone()]);
+    let other_msg = SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+        other_id.clone(),
+        &other_secret,
+        "other data".as_bytes().to_vec(),
+        remove_msg.hash::<Sha256>(),
+        remove_msg,
+    );
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
+        .expect_err("the signer is not a member");
+
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_roles() {
+    let owner = initAccount().expect("it should create the account");
+    webmessage::createGroup("group1", "Group 1", r#"{"members_only":true}"#)
+        .expect("it should create the group");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    webmessage::addMember("group1", &other_id.to_string()).expect("it should add the member");
+
+    let roles = webmessage::memberRoles("group1");
+    let owner_role: webmessage::state::Member =
+        serde_json::from_str(&roles[0]).expect("it should parse the member");
+    assert_eq!(owner_role.identity.to_string(), owner);
+    assert_eq!(owner_role.role, webmessage::state::Role::Admin);
+
+    // a member may not change the membership
+    let latest = |group_id: &str| -> SignedMessage<Identity, Signature> {
+        serde_json::from_str(messages(group_id)[0].as_str()).expect("it should parse")
+    };
+    let prev = latest("group1");
+    let mut change = Message::new(
+        prev.hash::<Sha256>(),
+        serde_json::to_vec(&webmessage::state::MembershipChange {
+            member: other_id.clone(),
+            role: webmessage::state::Role::Admin,
+        })
+        .unwrap(),
+    );
+    change.kind = webmessage::MessageKind::AddMember;
+    let change_msg = SignedMessage::sign::<Secret, MessageSigner>(
+        other_id.clone(),
+        &other_secret,
+        change,
+        prev.seq + 1,
+    );
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&change_msg).unwrap())
+        .expect_err("the signer is not an admin");
+
+    // a read-only member may not append messages
+    webmessage::setRole("group1", &other_id.to_string(), "read_only")
+        .expect("it should set the role");
+    let prev = latest("group1");
+    let other_msg = SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+        other_id.clone(),
+        &other_secret,
+        "other data".as_bytes().to_vec(),
+        prev.hash::<Sha256>(),
+        prev,
+    );
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
+        .expect_err("the signer has no write permission");
+
+    // the admin may update the policy
+    webmessage::updatePolicy("group1", "{}").expect("it should update the policy");
+    webmessage::setRole("group1", &other_id.to_string(), "unknown")
+        .expect_err("the role is unknown");
+    assert!(validateMessages("group1"));
+
+    // the binding refuses the record which a member may not write, without writing it
+    let member = webmessage::newAccount().expect("it should create the account");
+    webmessage::setCurrentAccount(&owner).expect("it should set the current account");
+    webmessage::addMember("group1", &member).expect("it should add the member");
+    webmessage::setCurrentAccount(&member).expect("it should set the current account");
+    let count = messages("group1").len();
+    webmessage::addMember("group1", &other_id.to_string()).expect_err("the signer is not an admin");
+    webmessage::setRole("group1", &member, "admin").expect_err("the signer is not an admin");
+    webmessage::updatePolicy("group1", "{}").expect_err("the signer is not an admin");
+    assert_eq!(messages("group1").len(), count);
+    assert!(validateMessages("group1"));
+    webmessage::setCurrentAccount(&owner).expect("it should set the current account");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_allowed_signers() {
+    let owner = initAccount().expect("it should create the account");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    webmessage::setAllowedSigners("group1", vec![owner.clone()])
+        .expect("it should set the allowed signers");
+    assert_eq!(webmessage::allowedSigners("group1"), vec![owner.clone()]);
+
+    // the message from an unknown signer is rejected
+    let other_msg = other_genesis(other_id.clone(), &other_secret, "group1");
+    let err = webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
+        .expect_err("the signer is not allowed");
+    assert_eq!(
+        err,
+        webmessage::writer::WriteError::UnknownSigner.to_string()
+    );
+
+    // any signer is allowed after clearing the allowed signers
+    webmessage::clearAllowedSigners("group1").expect("it should clear the allowed signers");
+    assert!(webmessage::allowedSigners("group1").is_empty());
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
+        .expect("it should add the signed message");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+/// Creates the genesis record of the group signed by the given identity.
+#[wasm_bindgen_test]
+fn test_invites() {
+    let owner = initAccount().expect("it should create the account");
+    webmessage::createGroup("group1", "Group 1", r#"{"members_only":true}"#)
+        .expect("it should create the group");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let latest = |group_id: &str| -> SignedMessage<Identity, Signature> {
+        serde_json::from_str(messages(group_id)[0].as_str()).expect("it should parse")
+    };
+    let join = |invite: webmessage::invite::Invite, timestamp: u64| {
+        let prev = latest("group1");
+        let mut message = Message::new(
+            prev.hash::<Sha256>(),
+            serde_json::to_vec(&webmessage::invite::JoinRequest { invite, timestamp }).unwrap(),
+        );
+        message.kind = webmessage::MessageKind::Join;
+        SignedMessage::sign::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            message,
+            prev.seq + 1,
+        )
+    };
+
+    // the invitation is rejected after the expiry
+    let invite_str = webmessage::createInvite("group1", &other_id.to_string(), 1)
+        .expect("it should create the invitation");
+    let invite: webmessage::invite::Invite =
+        serde_json::from_str(&invite_str).expect("it should parse the invitation");
+    assert!(invite.verify());
+    let join_msg = join(invite, 1);
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&join_msg).unwrap())
+        .expect_err("the invitation is expired");
+
+    // the invitee joins with a valid invitation
+    let invite_str = webmessage::createInvite("group1", &other_id.to_string(), u64::MAX)
+        .expect("it should create the invitation");
+    let invite: webmessage::invite::Invite =
+        serde_json::from_str(&invite_str).expect("it should parse the invitation");
+    let join_msg = join(invite.clone(), 1);
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&join_msg).unwrap())
+        .expect("it should add the join record");
+    assert_eq!(
+        webmessage::members("group1"),
+        vec![owner.clone(), other_id.to_string()]
+    );
+
+    // the invitation is only valid for the invitee
+    let (_, another_id) = GenKeysAlgorithm::generate_keys();
+    webmessage::setCurrentAccount(&webmessage::newAccount().expect("it should create the account"))
+        .expect("it should set the current account");
+    webmessage::joinGroup("group1", &invite_str).expect_err("it is not the invitee");
+    assert!(!webmessage::members("group1").contains(&another_id.to_string()));
+    assert!(validateMessages("group1"));
+
+    // the signature of the invitation is not a signature of a message with its body
+    let replayed = SignedMessage {
+        message: Message::new([0u8; 32], serde_json::to_vec(&invite.body).unwrap()),
+        id: invite.body.inviter.clone(),
+        seq: 0,
+        signature: invite.signature.clone(),
+        version: 0,
+    };
+    assert!(!replayed.verify::<Sha256>());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_capabilities() {
+    initAccount().expect("it should create the account");
+    webmessage::createGroup("group1", "Group 1", r#"{"members_only":true}"#)
+        .expect("it should create the group");
+    let (bot_secret, bot_id) = GenKeysAlgorithm::generate_keys();
+    let latest = |group_id: &str| -> SignedMessage<Identity, Signature> {
+        serde_json::from_str(messages(group_id)[0].as_str()).expect("it should parse")
+    };
+    let bot_record = |kind: webmessage::MessageKind, data: Vec<u8>| {
+        let prev = latest("group1");
+        let mut message = Message::new(prev.hash::<Sha256>(), data);
+        message.kind = kind;
+        let signed_msg = SignedMessage::sign::<Secret, MessageSigner>(
+            bot_id.clone(),
+            &bot_secret,
+            message,
+            prev.seq + 1,
+        );
+        webmessage::addSignedMessage("group1", &serde_json::to_string(&signed_msg).unwrap())
+    };
+
+    // the expired capability is rejected
+    let capability =
+        webmessage::delegateCapability("group1", &bot_id.to_string(), vec!["data".to_string()], 1)
+            .expect("it should create the capability");
+    bot_record(webmessage::MessageKind::Delegation, capability.into_bytes())
+        .expect_err("the capability is expired");
+
+    // the delegate may post data after presenting the capability
+    let capability = webmessage::delegateCapability(
+        "group1",
+        &bot_id.to_string(),
+        vec!["data".to_string()],
+        u64::MAX,
+    )
+    .expect("it should create the capability");
+
+    // the signature of the capability is not a signature of a message with its body
+    let parsed: webmessage::capability::Capability =
+        serde_json::from_str(&capability).expect("it should parse the capability");
+    let replayed = SignedMessage {
+        message: Message::new([0u8; 32], serde_json::to_vec(&parsed.body).unwrap()),
+        id: parsed.body.issuer.clone(),
+        seq: 0,
+        signature: parsed.signature.clone(),
+        version: 0,
+    };
+    assert!(!replayed.verify::<Sha256>());
+
+    bot_record(webmessage::MessageKind::Data, b"bot data".to_vec())
+        .expect_err("the capability is not presented");
+    bot_record(webmessage::MessageKind::Delegation, capability.into_bytes())
+        .expect("it should add the delegation record");
+    bot_record(webmessage::MessageKind::Data, b"bot data".to_vec())
+        .expect("it should add the delegated message");
+
+    // the delegate may not sign the kinds which are not granted
+    let (_, other_id) = GenKeysAlgorithm::generate_keys();
+    let change = serde_json::to_vec(&webmessage::state::MembershipChange {
+        member: other_id,
+        role: webmessage::state::Role::Member,
+    })
+    .unwrap();
+    bot_record(webmessage::MessageKind::AddMember, change).expect_err("the kind is not granted");
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_devices() {
+    let owner = initAccount().expect("it should create the account");
+    webmessage::createGroup("group1", "Group 1", r#"{"members_only":true}"#)
+        .expect("it should create the group");
+    let device = webmessage::addDevice("phone").expect("it should add the device");
+    assert_eq!(webmessage::devices(), vec![device[2].clone()]);
+    let device_id = Identity::try_from(device[0].as_str()).expect("it should parse the identity");
+    let device_secret = Secret::try_from(device[1].as_str()).expect("it should parse the secret");
+    let certificate: webmessage::device::DeviceCertificate =
+        serde_json::from_str(&device[2]).expect("it should parse the certificate");
+    assert!(certificate.verify());
+    assert_eq!(certificate.body.master.to_string(), owner);
+
+    // the signature of the certificate is not a signature of a message with its body
+    let replayed = SignedMessage {
+        message: Message::new([0u8; 32], serde_json::to_vec(&certificate.body).unwrap()),
+        id: certificate.body.master.clone(),
+        seq: 0,
+        signature: certificate.signature.clone(),
+        version: 0,
+    };
+    assert!(!replayed.verify::<Sha256>());
+
+    let device_record = |kind: webmessage::MessageKind, data: Vec<u8>| {
+        let prev: SignedMessage<Identity, Signature> =
+            serde_json::from_str(messages("group1")[0].as_str()).expect("it should parse");
+        let mut message = Message::new(prev.hash::<Sha256>(), data);
+        message.kind = kind;
+        let signed_msg = SignedMessage::sign::<Secret, MessageSigner>(
+            device_id.clone(),
+            &device_secret,
+            message,
+            prev.seq + 1,
+        );
+        webmessage::addSignedMessage("group1", &serde_json::to_string(&signed_msg).unwrap())
+    };
+
+    // the device may post with the role of the master after presenting its certificate
+    device_record(webmessage::MessageKind::Data, b"device data".to_vec())
+        .expect_err("the certificate is not presented");
+    device_record(
+        webmessage::MessageKind::DeviceCertificate,
+        device[2].clone().into_bytes(),
+    )
+    .expect("it should add the certificate record");
+    device_record(webmessage::MessageKind::Data, b"device data".to_vec())
+        .expect("it should add the device message");
+
+    // the revoked device may not post
+    let revocations = webmessage::revokeDevice(&device[0]).expect("it should revoke the device");
+    assert_eq!(revocations.len(), 1);
+    assert!(webmessage::devices().is_empty());
+    device_record(webmessage::MessageKind::Data, b"device data".to_vec())
+        .expect_err("the device is revoked");
+    device_record(
+        webmessage::MessageKind::DeviceCertificate,
+        device[2].clone().into_bytes(),
+    )
+    .expect_err("the device is revoked");
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_cosigned_messages() {
+    let owner = initAccount().expect("it should create the account");
+    webmessage::createGroup("group1", "Group 1", "{}").expect("it should create the group");
+    let admin = webmessage::newAccount().expect("it should create the account");
+    webmessage::setCurrentAccount(&owner).expect("it should set the current account");
+    webmessage::addMember("group1", &admin).expect("it should add the member");
+    webmessage::setRole("group1", &admin, "admin").expect("it should set the role");
+
+    // the proposal below the threshold is rejected
+    let proposal = webmessage::proposeCoSigned(
+        "group1",
+        "announcement",
+        vec![owner.clone(), admin.clone()],
+        2,
+    )
+    .expect("it should create the proposal");
+    webmessage::submitCoSigned("group1", &proposal).expect_err("it is below the threshold");
+
+    // the proposal is accepted after the other admin co-signs
+    webmessage::setCurrentAccount(&admin).expect("it should set the current account");
+    let proposal = webmessage::coSign(&proposal).expect("it should co-sign the proposal");
+    let content: webmessage::multisig::CoSignedContent =
+        serde_json::from_str(&proposal).expect("it should parse the proposal");
+    assert!(content.verify());
+    assert_eq!(content.signature.partials().len(), 2);
+    let msg_str =
+        webmessage::submitCoSigned("group1", &proposal).expect("it should submit the proposal");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    assert_eq!(signed_msg.message.kind, webmessage::MessageKind::CoSigned);
+
+    // the proposal may not be replayed at another position
+    webmessage::submitCoSigned("group1", &proposal).expect_err("it is for another position");
+
+    // the signer set and the threshold are covered by the partial signatures
+    let mut rewritten = content.clone();
+    rewritten.signer_set = webmessage::multisig::SignerSet {
+        signers: vec![Identity::try_from(owner.as_str()).unwrap()],
+        threshold: 1,
+    };
+    assert!(!rewritten.verify());
+    let mut rewritten = content.clone();
+    rewritten.signer_set.threshold = 1;
+    assert!(!rewritten.verify());
+    let mut reordered = content.clone();
+    reordered.signer_set.signers.reverse();
+    assert!(reordered.verify());
+
+    // a partial signature is not a signature of a message with the proposed data
+    let partial = &content.signature.partials()[0];
+    let replayed = SignedMessage {
+        message: Message::new(content.previous_hash, content.data.clone()),
+        id: partial.signer.clone(),
+        seq: signed_msg.seq,
+        signature: partial.signature.clone(),
+        version: 0,
+    };
+    assert!(!replayed.verify::<Sha256>());
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_acknowledgments() {
+    initAccount().expect("it should create the account");
+    let msg_str = signMessage("group1", "some data").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    let hash = serde_json::to_string(&signed_msg.hash::<Sha256>()).unwrap();
+
+    // acknowledge the message by the current account
+    let ack = webmessage::acknowledge("group1", &hash, "received")
+        .expect("it should acknowledge the message");
+    assert!(webmessage::verifyAcknowledgment(&ack));
+    webmessage::acknowledge(
+        "group1",
+        &serde_json::to_string(&[0u8; 32]).unwrap(),
+        "received",
+    )
+    .expect_err("the message is not found");
+
+    // add the acknowledgment of another member
+    let other = webmessage::newAccount().expect("it should create the account");
+    let other_ack = webmessage::acknowledge("group1", &hash, "approved")
+        .expect("it should acknowledge the message");
+    let mut forged: webmessage::acknowledgment::Acknowledgment =
+        serde_json::from_str(&other_ack).expect("it should parse the acknowledgment");
+    assert_eq!(forged.body.signer.to_string(), other);
+    forged.body.kind = webmessage::acknowledgment::AckKind::Received;
+    let forged = serde_json::to_string(&forged).unwrap();
+    assert!(!webmessage::verifyAcknowledgment(&forged));
+    webmessage::addAcknowledgment(&forged).expect_err("the signature is invalid");
+    webmessage::addAcknowledgment(&other_ack).expect("it should add the acknowledgment");
+
+    // the signature of the acknowledgment is not a signature of a message with its body
+    let parsed: webmessage::acknowledgment::Acknowledgment =
+        serde_json::from_str(&ack).expect("it should parse the acknowledgment");
+    let replayed = SignedMessage {
+        message: Message::new([0u8; 32], serde_json::to_vec(&parsed.body).unwrap()),
+        id: parsed.body.signer.clone(),
+        seq: 0,
+        signature: parsed.signature.clone(),
+        version: 0,
+    };
+    assert!(!replayed.verify::<Sha256>());
+
+    let acks = webmessage::acknowledgments("group1", &hash).expect("it should list");
+    assert_eq!(acks, vec![ack, other_ack]);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_threaded_replies() {
+    initAccount().expect("it should create the account");
+    let root_str = signMessage("group1", "root").expect("it should sign the message");
+    let root: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&root_str).expect("it should parse the signed message");
+    let root_hash = serde_json::to_string(&root.hash::<Sha256>()).unwrap();
+
+    let reply_str =
+        webmessage::signReply("group1", "reply 1", &root_hash).expect("it should sign the reply");
+    let reply: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&reply_str).expect("it should parse the signed message");
+    assert_eq!(reply.message.reply_to, Some(root.hash::<Sha256>()));
+    let reply_hash = serde_json::to_string(&reply.hash::<Sha256>()).unwrap();
+    webmessage::signReply("group1", "reply 2", &root_hash).expect("it should sign the reply");
+    webmessage::signReply("group1", "nested reply", &reply_hash).expect("it should sign the reply");
+    signMessage("group1", "unrelated").expect("it should sign the message");
+    assert!(validateMessages("group1"));
+
+    // the direct replies are ordered from the oldest
+    let replies = webmessage::replies("group1", &root_hash).expect("it should list the replies");
+    let replies: Vec<String> = replies
+        .iter()
+        .map(|msg| {
+            let msg: SignedMessage<Identity, Signature> =
+                serde_json::from_str(msg).expect("it should parse the signed message");
+            String::from_utf8(msg.message.data).unwrap()
+        })
+        .collect();
+    assert_eq!(replies, vec!["reply 1", "reply 2"]);
+
+    // the thread tree contains the nested replies
+    let thread: webmessage::thread::ThreadNode =
+        serde_json::from_str(&webmessage::thread("group1", &root_hash).expect("it should build"))
+            .expect("it should parse the thread");
+    assert_eq!(thread.hash, root.hash::<Sha256>());
+    assert_eq!(thread.replies.len(), 2);
+    assert_eq!(thread.replies[0].replies.len(), 1);
+    assert_eq!(
+        thread.replies[0].replies[0].message.message.data,
+        b"nested reply"
+    );
+    assert!(thread.replies[1].replies.is_empty());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_reactions() {
+    initAccount().expect("it should create the account");
+    let msg_str = signMessage("group1", "some data").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    let hash = serde_json::to_string(&signed_msg.hash::<Sha256>()).unwrap();
+
+    let reaction = webmessage::react("group1", &hash, "👍").expect("it should react");
+    webmessage::react("group1", &hash, "👍").expect("it should react again");
+    let mut forged: webmessage::reaction::Reaction =
+        serde_json::from_str(&reaction).expect("it should parse the reaction");
+    forged.body.emoji = "👎".to_string();
+    webmessage::addReaction(&serde_json::to_string(&forged).unwrap())
+        .expect_err("the signature is invalid");
+
+    // the reactions are stored outside the chain
+    assert_eq!(messages("group1").len(), 2);
+    let reactions = webmessage::reactions("group1", &hash).expect("it should list");
+    assert_eq!(reactions.len(), 1);
+    let stored: webmessage::reaction::Reaction =
+        serde_json::from_str(&reactions[0]).expect("it should parse the reaction");
+    assert!(stored.verify());
+    assert_eq!(stored.body.emoji, "👍");
+
+    // the signature of the reaction is not a signature of a message appended after the reacted message
+    let replayed = SignedMessage {
+        message: Message::new(
+            signed_msg.hash::<Sha256>(),
+            ["group1".as_bytes(), b"\0", "👍".as_bytes()].concat(),
+        ),
+        id: stored.body.signer.clone(),
+        seq: signed_msg.seq + 1,
+        signature: stored.signature.clone(),
+        version: 0,
+    };
+    assert!(!replayed.verify::<Sha256>());
+    let replayed = SignedMessage {
+        message: Message::new(
+            signed_msg.hash::<Sha256>(),
+            serde_json::to_vec(&stored.body).unwrap(),
+        ),
+        ..replayed
+    };
+    assert!(!replayed.verify::<Sha256>());
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&replayed).unwrap())
+        .expect_err("the signature is not a signature of the message");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_edits() {
+    initAccount().expect("it should create the account");
+    let msg_str = signMessage("group1", "original").expect("it should sign the message");
+    let original: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    let hash = serde_json::to_string(&original.hash::<Sha256>()).unwrap();
+    signMessage("group1", "other").expect("it should sign the message");
+
+    let edit_str = webmessage::editMessage("group1", &hash, "edit 1").expect("it should edit");
+    let edit: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&edit_str).expect("it should parse the signed message");
+    let edit_hash = serde_json::to_string(&edit.hash::<Sha256>()).unwrap();
+    webmessage::editMessage("group1", &edit_hash, "edit 2").expect("it should edit the edit");
+    webmessage::editMessage(
+        "group1",
+        &serde_json::to_string(&[0u8; 32]).unwrap(),
+        "edit",
+    )
+    .expect_err("the message is not found");
+
+    // another signer may not edit the message
+    webmessage::newAccount().expect("it should create the account");
+    webmessage::editMessage("group1", &hash, "forged").expect_err("it is signed by another");
+    assert!(validateMessages("group1"));
+
+    // the original remains in the chain, and the collapsed view shows the latest edit
+    assert_eq!(messages("group1").len(), 5);
+    let collapsed: Vec<Vec<u8>> = webmessage::collapsedMessages("group1")
+        .iter()
+        .map(|msg| {
+            let msg: SignedMessage<Identity, Signature> =
+                serde_json::from_str(msg).expect("it should parse the signed message");
+            msg.message.data
+        })
+        .collect();
+    assert_eq!(collapsed.len(), 3);
+    assert_eq!(collapsed[0], b"other");
+    assert_eq!(collapsed[1], b"edit 2");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_redactions() {
+    let owner = initAccount().expect("it should create the account");
+    let hash_of = |msg_str: &str| {
+        let msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(msg_str).expect("it should parse the signed message");
+        serde_json::to_string(&msg.hash::<Sha256>()).unwrap()
+    };
+    let kept =
+        hash_of(&signMessage("group1", "kept in storage").expect("it should sign the message"));
+    let purged =
+        hash_of(&signMessage("group1", "purged from storage").expect("it should sign the message"));
+    signMessage("group1", "not redacted").expect("it should sign the message");
+
+    // another signer may not redact the message
+    webmessage::newAccount().expect("it should create the account");
+    webmessage::redactMessage("group1", &kept, false).expect_err("it is signed by another");
+    webmessage::setCurrentAccount(&owner).expect("it should set the current account");
+
+    webmessage::purgeMessage("group1", &kept).expect_err("the message is not redacted");
+    webmessage::redactMessage("group1", &kept, false).expect("it should redact");
+    webmessage::redactMessage("group1", &purged, true).expect("it should redact and purge");
+    webmessage::purgeMessage("group1", &kept).expect("it should purge");
+
+    // reads return tombstones while the chain remains valid
+    let data: Vec<(bool, Vec<u8>)> = messages("group1")
+        .iter()
+        .map(|msg| {
+            let msg: SignedMessage<Identity, Signature> =
+                serde_json::from_str(msg).expect("it should parse the signed message");
+            (msg.message.redacted, msg.message.data)
+        })
+        .collect();
+    assert_eq!(data.len(), 6);
+    assert_eq!(data[2], (false, b"not redacted".to_vec()));
+    assert_eq!(data[3], (true, vec![]));
+    assert_eq!(data[4], (true, vec![]));
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_retention_policy() {
+    initAccount().expect("it should create the account");
+    for data in ["data 1", "data 2", "data 3", "data 4"] {
+        let msg_str = signMessage("group1", data).expect("it should sign the message");
+        let signed_msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(&msg_str).expect("it should parse the signed message");
+        assert!(signed_msg.message.timestamp.is_some());
+    }
+    assert_eq!(
+        webmessage::compact("group1").expect("it should compact the group"),
+        0
+    );
+
+    webmessage::setRetentionPolicy("group1", r#"{"max_count":2}"#)
+        .expect("it should set the retention policy");
+    assert_eq!(
+        webmessage::retentionPolicy("group1"),
+        r#"{"max_age":null,"max_count":2}"#
+    );
+    // the messages are purged only if the group policy expires them as well
+    assert_eq!(
+        webmessage::compact("group1").expect("it should compact the group"),
+        0
+    );
+    webmessage::updatePolicy("group1", r#"{"retention":{"max_count":3}}"#)
+        .expect("it should update the policy");
+    assert_eq!(
+        webmessage::compact("group1").expect("it should compact the group"),
+        2
+    );
+    assert_eq!(
+        webmessage::compact("group1").expect("it should compact the group"),
+        0
+    );
+
+    // the expired data is purged while the chain remains valid
+    let data: Vec<Vec<u8>> = messages("group1")
+        .iter()
+        .map(|msg| {
+            let msg: SignedMessage<Identity, Signature> =
+                serde_json::from_str(msg).expect("it should parse the signed message");
+            msg.message.data
+        })
+        .collect();
+    assert_eq!(data.len(), 6);
+    assert_eq!(data[1], b"data 4");
+    assert_eq!(data[2], b"data 3");
+    assert!(data[3].is_empty());
+    assert!(data[4].is_empty());
+    assert!(validateMessages("group1"));
+
+    // the purged messages are checked against the group policy, not the local retention policy
+    webmessage::setRetentionPolicy("group1", "{}").expect("it should set the retention policy");
+    assert!(validateMessages("group1"));
+    webmessage::updatePolicy("group1", "{}").expect("it should update the policy");
+    assert!(!validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_checkpoints() {
+    let owner = initAccount().expect("it should create the account");
+    webmessage::checkpoint("group1").expect_err("the group is not found");
+    for data in ["data 1", "data 2", "data 3"] {
+        signMessage("group1", data).expect("it should sign the message");
+    }
+    assert_eq!(
+        webmessage::prune("group1").expect("it should prune the group"),
+        0
+    );
+
+    // only admins may sign checkpoints
+    webmessage::newAccount().expect("it should create the account");
+    webmessage::checkpoint("group1").expect_err("the signer is not an admin");
+    webmessage::setCurrentAccount(&owner).expect("it should set the current account");
+    let checkpoint_str = webmessage::checkpoint("group1").expect("it should sign the checkpoint");
+    let checkpoint_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&checkpoint_str).expect("it should parse the signed message");
+    let checkpoint: webmessage::state::Checkpoint =
+        serde_json::from_slice(&checkpoint_msg.message.data).expect("it should parse");
+    assert_eq!(checkpoint.seq, 3);
+    assert_eq!(checkpoint.head, checkpoint_msg.message.previous_hash);
+    signMessage("group1", "data 4").expect("it should sign the message");
+
+    // the chain is validated from the checkpoint after pruning
+    assert_eq!(
+        webmessage::prune("group1").expect("it should prune the group"),
+        4
+    );
+    assert_eq!(messages("group1").len(), 2);
+    assert!(validateMessages("group1"));
+    signMessage("group1", "data 5").expect("it should sign the message");
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_inclusion_proofs() {
+    initAccount().expect("it should create the account");
+    let mut hashes = vec![];
+    for data in ["data 1", "data 2", "data 3", "data 4", "data 5"] {
+        let msg_str = signMessage("group1", data).expect("it should sign the message");
+        let signed_msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(&msg_str).expect("it should parse the signed message");
+        hashes.push(serde_json::to_string(&signed_msg.hash::<Sha256>()).unwrap());
+    }
+
+    let root = webmessage::merkleRoot("group1");
+    for hash in hashes.iter() {
+        let proof = webmessage::proveInclusion("group1", hash).expect("it should prove");
+        assert!(webmessage::verifyInclusion(&proof, &root));
+    }
+    webmessage::proveInclusion("group1", &serde_json::to_string(&[0u8; 32]).unwrap())
+        .expect_err("the message is not found");
+
+    // the proof is not valid against another root
+    let proof = webmessage::proveInclusion("group1", &hashes[0]).expect("it should prove");
+    signMessage("group1", "data 6").expect("it should sign the message");
+    assert!(!webmessage::verifyInclusion(
+        &proof,
+        &webmessage::merkleRoot("group1")
+    ));
+
+    // the checkpoint contains the merkle root
+    let checkpoint_str = webmessage::checkpoint("group1").expect("it should sign the checkpoint");
+    let checkpoint_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&checkpoint_str).expect("it should parse the signed message");
+    let checkpoint: webmessage::state::Checkpoint =
+        serde_json::from_slice(&checkpoint_msg.message.data).expect("it should parse");
+    assert!(checkpoint.merkle_root.is_some());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_consistency_proofs() {
+    initAccount().expect("it should create the account");
+    let head_of = |msg_str: &str| {
+        let msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(msg_str).expect("it should parse the signed message");
+        serde_json::to_string(&msg.hash::<Sha256>()).unwrap()
+    };
+    let old_head = head_of(&signMessage("group1", "data 1").expect("it should sign the message"));
+    let old_root = webmessage::merkleRoot("group1");
+    for data in ["data 2", "data 3", "data 4"] {
+        signMessage("group1", data).expect("it should sign the message");
+    }
+    let new_head = head_of(&signMessage("group1", "data 5").expect("it should sign the message"));
+    let new_root = webmessage::merkleRoot("group1");
+
+    let proof = webmessage::consistencyProof("group1", &old_head, &new_head)
+        .expect("it should prove the consistency");
+    assert!(webmessage::verifyConsistency(&proof, &old_root, &new_root));
+    assert!(!webmessage::verifyConsistency(&proof, &new_root, &new_root));
+
+    // the chain of another group does not extend the old head
+    signMessage("group2", "data 1").expect("it should sign the message");
+    let other_root = webmessage::merkleRoot("group2");
+    assert!(!webmessage::verifyConsistency(
+        &proof,
+        &old_root,
+        &other_root
+    ));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_light_verification() {
+    initAccount().expect("it should create the account");
+    let data_1 = signMessage("group1", "data 1").expect("it should sign the message");
+    let genesis = messages("group1")
+        .pop()
+        .expect("it should have the genesis record");
+
+    // start from the genesis record
+    webmessage::lightStart("group1", &data_1, None).expect_err("it is not a genesis record");
+    webmessage::lightStart("group1", &genesis, None).expect("it should start");
+    webmessage::lightVerify("group1", &data_1).expect("it should verify");
+    webmessage::lightVerify("group1", &data_1).expect_err("the message is verified");
+    let data_2 = signMessage("group1", "data 2").expect("it should sign the message");
+    webmessage::lightVerify("group1", &data_2).expect("it should verify");
+    assert_eq!(
+        webmessage::lightRoot("group1"),
+        Some(webmessage::merkleRoot("group1"))
+    );
+
+    // start from a checkpoint with the proof of its head
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&data_2).expect("it should parse the signed message");
+    let head = serde_json::to_string(&signed_msg.hash::<Sha256>()).unwrap();
+    let proof = webmessage::proveInclusion("group1", &head).expect("it should prove");
+    let checkpoint = webmessage::checkpoint("group1").expect("it should sign the checkpoint");
+    webmessage::lightStart("group1", &checkpoint, None).expect_err("the proof is missing");
+    webmessage::lightStart("group1", &checkpoint, Some(proof)).expect("it should start");
+    let data_3 = signMessage("group1", "data 3").expect("it should sign the message");
+    webmessage::lightVerify("group1", &data_3).expect("it should verify");
+    assert_eq!(
+        webmessage::lightRoot("group1"),
+        Some(webmessage::merkleRoot("group1"))
+    );
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_anchors() {
+    initAccount().expect("it should create the account");
+    webmessage::anchor("group1", "open_timestamps", "http://localhost")
+        .expect_err("the group is not found");
+    let msg_str = signMessage("group1", "data 1").expect("it should sign the message");
+    webmessage::anchor("group1", "unknown", "http://localhost")
+        .expect_err("the service is unknown");
+
+    // the request of RFC 3161 contains the digest
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    let head = signed_msg.hash::<Sha256>();
+    let request = webmessage::anchor::timestamp_request(&head);
+    assert_eq!(request.len(), 59);
+    assert_eq!(&request[24..56], &head);
+
+    // add the proof obtained outside of the library
+    let proof = webmessage::anchor::AnchorProof {
+        service: webmessage::anchor::AnchorService::OpenTimestamps,
+        url: "https://a.pool.opentimestamps.org/digest".to_string(),
+        digest: head,
+        proof: vec![1, 2, 3],
+        submitted_at: 0,
+    };
+    webmessage::addAnchorProof("group1", &serde_json::to_string(&proof).unwrap())
+        .expect("it should add the proof");
+    webmessage::addAnchorProof("group2", &serde_json::to_string(&proof).unwrap())
+        .expect_err("the message is not found");
+    assert_eq!(webmessage::anchors("group1").len(), 1);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_evidence() {
+    initAccount().expect("it should create the account");
+    let mut hashes = vec![];
+    for data in ["data 1", "data 2", "data 3"] {
+        let msg_str = signMessage("group1", data).expect("it should sign the message");
+        let signed_msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(&msg_str).expect("it should parse the signed message");
+        hashes.push(serde_json::to_string(&signed_msg.hash::<Sha256>()).unwrap());
+    }
+
+    // the evidence contains the ancestors up to genesis
+    let evidence_str =
+        webmessage::exportEvidence("group1", &hashes[1]).expect("it should export evidence");
+    assert!(webmessage::verifyEvidence(&evidence_str));
+    let mut evidence: webmessage::evidence::Evidence =
+        serde_json::from_str(&evidence_str).expect("it should parse the evidence");
+    assert_eq!(evidence.messages.len(), 3);
+    assert!(evidence.inclusion.is_some() && evidence.consistency.is_some());
+
+    // tampered evidence is not valid
+    evidence.messages[1].message.data = b"other data".to_vec();
+    assert!(!webmessage::verifyEvidence(
+        &serde_json::to_string(&evidence).unwrap()
+    ));
+
+    // the evidence contains the ancestors up to the latest checkpoint
+    webmessage::checkpoint("group1").expect("it should sign the checkpoint");
+    let msg_str = signMessage("group1", "data 4").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    let hash = serde_json::to_string(&signed_msg.hash::<Sha256>()).unwrap();
+    let evidence_str = webmessage::exportEvidence("group1", &hash).expect("it should export");
+    assert!(webmessage::verifyEvidence(&evidence_str));
+    let evidence: webmessage::evidence::Evidence =
+        serde_json::from_str(&evidence_str).expect("it should parse the evidence");
+    assert_eq!(evidence.messages.len(), 2);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_notarization() {
+    let requester = initAccount().expect("it should create the account");
+    let notary = webmessage::newAccount().expect("it should create the account");
+    webmessage::setCurrentAccount(&requester).expect("it should set the current account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+
+    // the requester creates the request and the notary countersigns it
+    webmessage::requestNotarization("group2", &notary).expect_err("the group is not found");
+    let request =
+        webmessage::requestNotarization("group1", &notary).expect("it should create the request");
+    webmessage::notarize(&request).expect_err("the current account is not the notary");
+    webmessage::setCurrentAccount(&notary).expect("it should set the current account");
+    let attestation_str = webmessage::notarize(&request).expect("it should notarize");
+    assert!(webmessage::verifyAttestation(&attestation_str));
+
+    // the requester ingests the attestation
+    webmessage::setCurrentAccount(&requester).expect("it should set the current account");
+    webmessage::addAttestation(&attestation_str).expect("it should add the attestation");
+    assert_eq!(webmessage::attestations("group1").len(), 1);
+
+    // a tampered attestation is rejected
+    let mut attestation: webmessage::notary::Attestation =
+        serde_json::from_str(&attestation_str).expect("it should parse the attestation");
+    attestation.body.timestamp += 1;
+    let attestation_str = serde_json::to_string(&attestation).unwrap();
+    assert!(!webmessage::verifyAttestation(&attestation_str));
+    webmessage::addAttestation(&attestation_str).expect_err("the signature is invalid");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_contacts() {
+    initAccount().expect("it should create the account");
+    let other = webmessage::newAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    assert!(webmessage::contacts().is_empty());
+
+    webmessage::addContact(&other, "bob", "unknown", "").expect_err("the status is unknown");
+    webmessage::addContact(&other, "bob", "unverified", "").expect("it should add the contact");
+    webmessage::addContact(&other, "Bob", "verified", "met in person")
+        .expect("it should replace the contact");
+    let contacts = webmessage::contacts();
+    assert_eq!(contacts.len(), 1);
+    let contact: webmessage::contact::Contact =
+        serde_json::from_str(&contacts[0]).expect("it should parse the contact");
+    assert_eq!(contact.alias, "Bob");
+    assert_eq!(
+        contact.verification,
+        webmessage::contact::VerificationStatus::Verified
+    );
+
+    // the messages are resolved with the aliases of the signers
+    let msgs = webmessage::aliasedMessages("group1");
+    let aliased_msg: webmessage::contact::AliasedMessage =
+        serde_json::from_str(&msgs[0]).expect("it should parse the message");
+    assert_eq!(aliased_msg.alias.as_deref(), Some("Bob"));
+
+    webmessage::removeContact(&other).expect("it should remove the contact");
+    webmessage::removeContact("not an identity").expect_err("it is not an identity");
+    assert!(webmessage::contacts().is_empty());
+    let msgs = webmessage::aliasedMessages("group1");
+    let aliased_msg: webmessage::contact::AliasedMessage =
+        serde_json::from_str(&msgs[0]).expect("it should parse the message");
+    assert!(aliased_msg.alias.is_none());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_fingerprints() {
+    let account = initAccount().expect("it should create the account");
+    let other = webmessage::newAccount().expect("it should create the account");
+
+    let fingerprint = webmessage::fingerprint(&account).expect("it should parse the identity");
+    assert_eq!(fingerprint.len(), 35);
+    assert_eq!(webmessage::fingerprint(&account), Ok(fingerprint.clone()));
+    assert_ne!(webmessage::fingerprint(&other), Ok(fingerprint.clone()));
+    webmessage::fingerprint("not an identity").expect_err("it is not an identity");
+
+    // both parties compute the same safety number
+    let safety_number =
+        webmessage::safetyNumber(&account, &other).expect("it should parse the identities");
+    assert_eq!(
+        safety_number.chars().filter(char::is_ascii_digit).count(),
+        60
+    );
+    assert_eq!(
+        webmessage::safetyNumber(&other, &account),
+        Ok(safety_number.clone())
+    );
+    webmessage::safetyNumber(&account, "not an identity").expect_err("it is not an identity");
+    assert!(webmessage::compareFingerprint(
+        &safety_number,
+        &safety_number.replace(' ', "")
+    ));
+    assert!(!webmessage::compareFingerprint(
+        &safety_number,
+        &fingerprint
+    ));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_key_pinning() {
+    initAccount().expect("it should create the account");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(other_id.clone(), &other_secret, "group1");
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&genesis).unwrap())
+        .expect("it should add the genesis record");
+
+    let announce = |agreement_key: Vec<u8>, previous: &SignedMessage<Identity, Signature>| {
+        let announcement = webmessage::agreement::KeyAnnouncement { agreement_key };
+        let mut message = Message::new(
+            previous.hash::<Sha256>(),
+            serde_json::to_vec(&announcement).unwrap(),
+        );
+        message.kind = webmessage::MessageKind::KeyAnnouncement;
+        SignedMessage::sign::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            message,
+            previous.seq + 1,
+        )
+    };
+
+    // the first announced key is pinned
+    let other_id_str = other_id.to_string();
+    assert_eq!(webmessage::pinnedKey("group1", &other_id_str), Ok(None));
+    webmessage::pinnedKey("group1", "not an identity").expect_err("it is not an identity");
+    let msg = announce(vec![1, 2, 3], &genesis);
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&msg).unwrap())
+        .expect("it should add the announcement");
+    assert!(webmessage::pinnedKey("group1", &other_id_str)
+        .expect("it should parse the identity")
+        .is_some());
+
+    // another key is rejected until it is unpinned
+    let other_msg = announce(vec![4, 5, 6], &msg);
+    let err = webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
+        .expect_err("the key is changed");
+    assert!(err.starts_with("key changed"));
+    webmessage::unpinKey("group1", "not an identity").expect_err("it is not an identity");
+    webmessage::unpinKey("group1", &other_id_str).expect("it should unpin the key");
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
+        .expect("it should add the announcement");
+    assert_eq!(
+        webmessage::pinnedKey("group1", &other_id_str),
+        Ok(Some(serde_json::to_string(&vec![4, 5, 6]).unwrap()))
+    );
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_profiles() {
+    let account = initAccount().expect("it should create the account");
+    assert_eq!(webmessage::profileOf(&account), Ok(None));
+    webmessage::profileOf("not an identity").expect_err("it is not an identity");
+    webmessage::profileUpdates("not an identity").expect_err("it is not an identity");
+    webmessage::publishProfile("alice", None, vec![]).expect("it should publish the profile");
+    let update_str = webmessage::publishProfile(
+        "Alice",
+        Some(serde_json::to_string(&[1u8; 32]).unwrap()),
+        vec!["https://example.com".to_string()],
+    )
+    .expect("it should publish the profile");
+    let profile: webmessage::profile::Profile = serde_json::from_str(
+        &webmessage::profileOf(&account)
+            .unwrap()
+            .expect("it should have the profile"),
+    )
+    .expect("it should parse the profile");
+    assert_eq!(profile.display_name, "Alice");
+    assert_eq!(profile.avatar, Some([1u8; 32]));
+
+    // another identity verifies the profile updates in order
+    let updates = webmessage::profileUpdates(&account).expect("it should parse the identity");
+    assert_eq!(updates.len(), 2);
+    webmessage::clear().expect("it should clear the local storage");
+    webmessage::publishProfile("Alice", None, vec![]).expect_err("there is no current account");
+    let mut corrupted: serde_json::Value =
+        serde_json::from_str(&updates[0]).expect("it should parse the update");
+    corrupted["signature"]["signature"] = serde_json::Value::from("not a signature");
+    webmessage::addProfileUpdate(&corrupted.to_string()).expect_err("the signature is corrupted");
+    webmessage::addProfileUpdate(&update_str).expect_err("the first update is missing");
+    webmessage::addProfileUpdate(&updates[0]).expect("it should add the update");
+    webmessage::addProfileUpdate(&updates[0]).expect_err("the update is added");
+    webmessage::addProfileUpdate(&updates[1]).expect("it should add the update");
+    assert!(webmessage::profileOf(&account).unwrap().is_some());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_key_transparency_log() {
+    let account = initAccount().expect("it should create the account");
+    assert_eq!(webmessage::keyLog(&account).map(|log| log.len()), Ok(1));
+    initAccount().expect("it should create the account");
+    assert_eq!(webmessage::keyLog(&account).map(|log| log.len()), Ok(1));
+    webmessage::keyLog("not an identity").expect_err("it is not an identity");
+
+    // device addition and revocation are logged
+    let device = webmessage::addDevice("phone").expect("it should add the device");
+    webmessage::revokeDevice(&device[0]).expect("it should revoke the device");
+    let entries = webmessage::keyLog(&account).expect("it should parse the identity");
+    assert_eq!(entries.len(), 3);
+    assert!(webmessage::verifyKeyLog(entries.clone()));
+
+    // the log is not valid if an entry is removed
+    let mut removed = entries.clone();
+    removed.remove(1);
+    assert!(!webmessage::verifyKeyLog(removed));
+    assert!(!webmessage::verifyKeyLog(entries[1..].to_vec()));
+
+    // the log is not valid if a signature is corrupted
+    let mut corrupted = entries.clone();
+    let mut entry: serde_json::Value =
+        serde_json::from_str(&corrupted[0]).expect("it should parse the entry");
+    entry["signature"]["signature"] = serde_json::Value::from("not a signature");
+    corrupted[0] = entry.to_string();
+    assert!(!webmessage::verifyKeyLog(corrupted));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_account_metadata() {
+    let account = initAccount().expect("it should create the account");
+    let other = webmessage::newAccount().expect("it should create the account");
+    webmessage::setAccountMetadata(&account, "work", "#ff0000")
+        .expect("it should set the metadata");
+    webmessage::setAccountMetadata("not an identity", "work", "#ff0000")
+        .expect_err("it is not an identity");
+    webmessage::accountMetadata("not an identity").expect_err("it is not an identity");
+
+    let metadata: webmessage::account::AccountMetadata =
+        serde_json::from_str(&webmessage::accountMetadata(&account).unwrap())
+            .expect("it should parse the metadata");
+    assert_eq!(metadata.label, "work");
+    assert_eq!(metadata.color, "#ff0000");
+    assert!(metadata.created_at.is_some());
+
+    let infos: Vec<webmessage::account::AccountInfo> = webmessage::accountInfos()
+        .iter()
+        .map(|info| serde_json::from_str(info).expect("it should parse the account"))
+        .collect();
+    assert_eq!(infos.len(), 2);
+    assert_eq!(infos[0].metadata.label, "work");
+    assert!(!infos[0].current);
+    assert!(infos[1].current);
+    assert_eq!(infos[1].identity.to_string(), other);
+
+    // the metadata is removed with the account
+    webmessage::deleteAccount(&account, false).expect("it should delete the account");
+    let metadata: webmessage::account::AccountMetadata =
+        serde_json::from_str(&webmessage::accountMetadata(&account).unwrap())
+            .expect("it should parse the metadata");
+    assert!(metadata.label.is_empty());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_export_secret() {
+    let identity = initAccount().expect("it should create the account");
+    webmessage::exportSecret(&identity, "passphrase", false).expect_err("it is not confirmed");
+    let exported = webmessage::exportSecret(&identity, "passphrase", true)
+        .expect("it should export the secret");
+
+    let encrypted_secret: webmessage::account::EncryptedSecret =
+        serde_json::from_str(&exported).expect("it should parse the encrypted secret");
+    assert_eq!(encrypted_secret.identity.to_string(), identity);
+    assert!(encrypted_secret.decrypt("wrong passphrase").is_none());
+    assert!(encrypted_secret.decrypt("passphrase").is_some());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_import_account() {
+    let identity = initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    let exported = webmessage::exportSecret(&identity, "passphrase", true)
+        .expect("it should export the secret");
+    webmessage::clear().expect("it should clear the local storage");
+
+    // import the exported secret with the passphrase
+    webmessage::importAccount(&exported, None).expect_err("the passphrase is missing");
+    webmessage::importAccount(&exported, Some("wrong".to_string()))
+        .expect_err("the passphrase is wrong");
+    let imported = webmessage::importAccount(&exported, Some("passphrase".to_string()))
+        .expect("it should import the account");
+    assert_eq!(imported, identity);
+    assert_eq!(webmessage::allAccounts(), vec![identity.clone()]);
+    assert_eq!(
+        initAccount().expect("it should create the account"),
+        identity
+    );
+
+    // import the identity and the secret
+    let (secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let key_pair = serde_json::json!({ "identity": other_id, "secret": secret }).to_string();
+    let imported =
+        webmessage::importAccount(&key_pair, None).expect("it should import the account");
+    assert_eq!(imported, other_id.to_string());
+    assert_eq!(webmessage::allAccounts().len(), 2);
+
+    // the secret must match the identity
+    let (other_secret, _) = GenKeysAlgorithm::generate_keys();
+    let key_pair = serde_json::json!({ "identity": other_id, "secret": other_secret }).to_string();
+    webmessage::importAccount(&key_pair, None).expect_err("the secret does not match");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_storage_profiles() {
+    let identity = initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    assert_eq!(webmessage::listProfiles(), vec!["default".to_string()]);
+
+    // the new profile has its own accounts and groups
+    webmessage::switchProfile("").expect_err("the name is empty");
+    webmessage::switchProfile("work").expect("it should switch the profile");
+    assert_eq!(webmessage::currentProfile(), "work");
+    assert!(webmessage::allAccounts().is_empty());
+    assert!(groups().is_empty());
+    let work_identity = initAccount().expect("it should create the account");
+    signMessage("group2", "data 2").expect("it should sign the message");
+    assert_eq!(groups().len(), 1);
+
+    // the default profile is intact
+    webmessage::switchProfile("default").expect("it should switch the profile");
+    assert_eq!(webmessage::allAccounts(), vec![identity]);
+    assert_eq!(messages("group1").len(), 2);
+    assert!(messages("group2").is_empty());
+    assert_eq!(webmessage::listProfiles().len(), 2);
+
+    // delete the profile and its data
+    webmessage::deleteProfile("default").expect_err("the default profile cannot be deleted");
+    webmessage::deleteProfile("work").expect("it should delete the profile");
+    assert_eq!(webmessage::listProfiles().len(), 1);
+    webmessage::switchProfile("work").expect("it should switch the profile");
+    assert!(!webmessage::allAccounts().contains(&work_identity));
+    assert!(groups().is_empty());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_guarded_account_deletion() {
+    let identity = initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    signMessage("group2", "data 2").expect("it should sign the message");
+    let other = webmessage::newAccount().expect("it should create the account");
+    assert_eq!(
+        webmessage::authoredGroups(&identity).map(|groups| groups.len()),
+        Ok(2)
+    );
+    assert_eq!(webmessage::authoredGroups(&other), Ok(vec![]));
+    webmessage::authoredGroups("not an identity").expect_err("it is not an identity");
+    webmessage::deleteAccount("not an identity", true).expect_err("it is not an identity");
+
+    // the account with authored messages is deleted only with force or after exporting its secret
+    let err = webmessage::deleteAccount(&identity, false).expect_err("it authored messages");
+    assert!(err.contains("group1") && err.contains("group2"));
+    webmessage::exportSecret(&identity, "passphrase", true).expect("it should export");
+    webmessage::deleteAccount(&identity, false).expect("it should delete the account");
+    webmessage::deleteAccount(&other, false).expect("it should delete the account");
+    assert!(webmessage::allAccounts().is_empty());
+
+    let identity = initAccount().expect("it should create the account");
+    signMessage("group3", "data 3").expect("it should sign the message");
+    webmessage::deleteAccount(&identity, false).expect_err("it authored messages");
+    webmessage::deleteAccount(&identity, true).expect("it should delete the account");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_messages_by_author() {
+    let alice = initAccount().expect("it should create the account");
+    let first = signMessage("group1", "alice 1").expect("it should sign the message");
+    signMessage("group2", "alice 2").expect("it should sign the message");
+    let bob = webmessage::newAccount().expect("it should create the account");
+    signMessage("group1", "bob 1").expect("it should sign the message");
+    webmessage::setCurrentAccount(&alice).expect("it should set the current account");
+    signMessage("group1", "alice 3").expect("it should sign the message");
+
+    let data_of = |msgs: Result<Vec<String>, String>| -> Vec<Vec<u8>> {
+        msgs.expect("it should parse the identity")
+            .iter()
+            .map(|msg| {
+                let msg: SignedMessage<Identity, Signature> =
+                    serde_json::from_str(msg).expect("it should parse the signed message");
+                msg.message.data
+            })
+            .filter(|data| data.starts_with(b"alice") || data.starts_with(b"bob"))
+            .collect()
+    };
+    assert_eq!(
+        data_of(webmessage::messagesByAuthor(
+            &alice,
+            Some("group1".to_string())
+        )),
+        vec![b"alice 3".to_vec(), b"alice 1".to_vec()]
+    );
+    assert_eq!(data_of(webmessage::messagesByAuthor(&alice, None)).len(), 3);
+    assert_eq!(
+        data_of(webmessage::messagesByAuthor(&bob, None)),
+        vec![b"bob 1".to_vec()]
+    );
+    webmessage::messagesByAuthor("not an identity", None).expect_err("it is not an identity");
+
+    // redacted messages are returned as tombstones
+    let msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&first).expect("it should parse the signed message");
+    let hash = serde_json::to_string(&msg.hash::<Sha256>()).unwrap();
+    webmessage::redactMessage("group1", &hash, false).expect("it should redact");
+    let redacted = webmessage::messagesByAuthor(&alice, Some("group1".to_string()))
+        .expect("it should parse the identity")
+        .iter()
+        .map(|msg| serde_json::from_str::<SignedMessage<Identity, Signature>>(msg).unwrap())
+        .filter(|msg| msg.message.redacted)
+        .count();
+    assert_eq!(redacted, 1);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_time_range_and_tag_queries() {
+    initAccount().expect("it should create the account");
+    signMessage("group1", "untagged").expect("it should sign the message");
+    let tagged = webmessage::signTaggedMessage(
+        "group1",
+        "tagged",
+        vec!["news".to_string(), "urgent".to_string()],
+    )
+    .expect("it should sign the message");
+    webmessage::signTaggedMessage("group1", "news", vec!["news".to_string()])
+        .expect("it should sign the message");
+
+    // the tags are covered by the signature
+    let mut msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&tagged).expect("it should parse the signed message");
+    assert!(msg.verify::<Sha256>());
+    msg.message.tags.pop();
+    assert!(!msg.verify::<Sha256>());
+    let now = msg
+        .message
+        .timestamp
+        .expect("it should be signed with a timestamp");
+
+    let data_of = |msgs: Vec<String>| -> Vec<Vec<u8>> {
+        msgs.iter()
+            .map(|msg| {
+                let msg: SignedMessage<Identity, Signature> =
+                    serde_json::from_str(msg).expect("it should parse the signed message");
+                msg.message.data
+            })
+            .collect()
+    };
+    assert_eq!(
+        data_of(webmessage::messagesByTag("group1", "news")),
+        vec![b"news".to_vec(), b"tagged".to_vec()]
+    );
+    assert_eq!(
+        data_of(webmessage::messagesByTag("group1", "urgent")),
+        vec![b"tagged".to_vec()]
+    );
+    assert!(webmessage::messagesByTag("group1", "other").is_empty());
+
+    // the genesis record is signed without a timestamp
+    assert_eq!(
+        webmessage::messagesBetween("group1", now - 60, now + 60).len(),
+        3
+    );
+    assert!(webmessage::messagesBetween("group1", now + 60, now + 120).is_empty());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_full_text_search() {
+    initAccount().expect("it should create the account");
+    let hash_of = |msg_str: &str| {
+        let msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(msg_str).expect("it should parse the signed message");
+        msg.hash::<Sha256>()
+    };
+    let before =
+        hash_of(&signMessage("group1", "The quick brown fox").expect("it should sign the message"));
+    webmessage::search("group1", "fox").expect_err("the index is not built");
+
+    // the messages written before the index is built are indexed on rebuild
+    webmessage::rebuildSearchIndex("group1").expect("it should build the search index");
+    let after = hash_of(
+        &signMessage("group1", "A lazy dog, and a QUICK fox!").expect("it should sign the message"),
+    );
+    signMessage("group1", "nothing here").expect("it should sign the message");
+
+    let hashes_of = |query: &str| -> Vec<[u8; 32]> {
+        webmessage::search("group1", query)
+            .expect("it should search")
+            .iter()
+            .map(|result| {
+                let result: webmessage::search::SearchResult =
+                    serde_json::from_str(result).expect("it should parse the result");
+                result.hash
+            })
+            .collect()
+    };
+    assert_eq!(hashes_of("quick FOX"), vec![after, before]);
+    assert_eq!(hashes_of("lazy fox"), vec![after]);
+    assert!(hashes_of("cat").is_empty());
+
+    let result: webmessage::search::SearchResult =
+        serde_json::from_str(&webmessage::search("group1", "brown").expect("it should search")[0])
+            .expect("it should parse the result");
+    assert_eq!(result.snippet, "The quick brown fox");
+
+    // redacted messages are removed from the index
+    webmessage::redactMessage("group1", &serde_json::to_string(&before).unwrap(), false)
+        .expect("it should redact");
+    assert_eq!(hashes_of("quick fox"), vec![after]);
+
+    webmessage::removeSearchIndex("group1").expect("it should remove the search index");
+    webmessage::search("group1", "fox").expect_err("the index is removed");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_group_metadata() {
+    initAccount().expect("it should create the account");
+    signMessage("group1", "some data").expect("it should sign the message");
+    let group: Group = serde_json::from_str(&groups()[0]).expect("it should parse the group");
+    assert_eq!(group.metadata.display_name, "group1");
+    assert!(!group.metadata.archived);
+
+    let meta = r#"{"display_name":"Team","description":"Daily chat","archived":true}"#;
+    let updated: Group = serde_json::from_str(
+        &webmessage::updateGroup("group1", meta).expect("it should update the group"),
+    )
+    .expect("it should parse the group");
+    assert_eq!(updated.id, "group1");
+    assert_eq!(updated.timestamp, group.timestamp);
+
+    let group: Group = serde_json::from_str(&groups()[0]).expect("it should parse the group");
+    assert_eq!(group.metadata.display_name, "Team");
+    assert_eq!(group.metadata.description, "Daily chat");
+    assert!(group.metadata.icon.is_none());
+    assert!(group.metadata.archived);
+
+    webmessage::updateGroup("group2", meta).expect_err("the group does not exist");
+    webmessage::updateGroup("group1", "invalid").expect_err("the metadata is invalid");
+
+    // the chain is unchanged
+    assert_eq!(messages("group1").len(), 2);
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_group_info() {
+    let alice = initAccount().expect("it should create the account");
+    signMessage("group1", "alice 1").expect("it should sign the message");
+    webmessage::newAccount().expect("it should create the account");
+    let latest: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "bob 1").expect("it should sign the message"))
+            .expect("it should parse");
+
+    let info: webmessage::stats::GroupInfo =
+        serde_json::from_str(&webmessage::groupInfo("group1")).expect("it should parse the info");
+    assert_eq!(info.message_count, 3);
+    assert_eq!(info.head, Some(latest.hash::<Sha256>()));
+    assert_eq!(info.seq, Some(2));
+    assert_eq!(info.last_timestamp, latest.message.timestamp);
+    assert!(info.first_timestamp <= info.last_timestamp);
+    assert_eq!(info.authors.len(), 2);
+    assert_eq!(info.authors[0].to_string(), alice);
+    assert!(info.storage_bytes > 0);
+
+    let empty: webmessage::stats::GroupInfo =
+        serde_json::from_str(&webmessage::groupInfo("group2")).expect("it should parse the info");
+    assert_eq!(empty.message_count, 0);
+    assert!(empty.head.is_none());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_sort_groups() {
+    initAccount().expect("it should create the account");
+    signMessage("group1", "first").expect("it should sign the message");
+    signMessage("group2", "second").expect("it should sign the message");
+    signMessage("group3", "third").expect("it should sign the message");
+    webmessage::updateGroup("group2", r#"{"display_name":"A team"}"#)
+        .expect("it should update the group");
+    webmessage::updateGroup("group3", r#"{"display_name":"Old","archived":true}"#)
+        .expect("it should update the group");
+
+    let ids_of = |groups: Vec<String>| -> Vec<String> {
+        groups
+            .iter()
+            .map(|group| {
+                let group: Group = serde_json::from_str(group).expect("it should parse the group");
+                group.id
+            })
+            .collect()
+    };
+    let sorted = |by: &str, order: &str| {
+        ids_of(webmessage::groupsSorted(by, order).expect("it should sort the groups"))
+    };
+    assert_eq!(sorted("name", "asc"), vec!["group2", "group1"]);
+    assert_eq!(sorted("name", "desc"), vec!["group1", "group2"]);
+    assert_eq!(sorted("created", "asc"), vec!["group1", "group2"]);
+    assert_eq!(ids_of(webmessage::archivedGroups()), vec!["group3"]);
+
+    // writing a message records the activity of the group
+    let group: Group = serde_json::from_str(&groups()[0]).expect("it should parse the group");
+    assert!(group.last_activity.is_some_and(|t| t >= group.timestamp));
+    assert_eq!(sorted("activity", "desc").len(), 2);
+
+    webmessage::groupsSorted("size", "asc").expect_err("the sort key is unknown");
+    webmessage::groupsSorted("name", "up").expect_err("the order is unknown");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_direct_group_id() {
+    let (_, alice) = GenKeysAlgorithm::generate_keys();
+    let (_, bob) = GenKeysAlgorithm::generate_keys();
+    let (_, carol) = GenKeysAlgorithm::generate_keys();
+    let (alice, bob, carol) = (alice.to_string(), bob.to_string(), carol.to_string());
+
+    let group_id = webmessage::directGroupId(&alice, &bob).expect("it should derive the id");
+    assert_eq!(
+        webmessage::directGroupId(&bob, &alice).expect("it should derive the id"),
+        group_id
+    );
+    assert!(group_id.starts_with("dm_"));
+    assert_ne!(
+        webmessage::directGroupId(&alice, &carol).expect("it should derive the id"),
+        group_id
+    );
+    webmessage::directGroupId(&alice, "invalid").expect_err("the identity is invalid");
+}
+
+#[wasm_bindgen_test]
+fn test_seal_group() {
+    let owner = initAccount().expect("it should create the account");
+    signMessage("group1", "some data").expect("it should sign the message");
+    assert!(!webmessage::isGroupSealed("group1"));
+
+    // only the creator may seal the group
+    webmessage::newAccount().expect("it should create the account");
+    webmessage::sealGroup("group1").expect_err("the signer is not the creator");
+    webmessage::setCurrentAccount(&owner).expect("it should set the current account");
+    webmessage::sealGroup("group1").expect("it should seal the group");
+    assert!(webmessage::isGroupSealed("group1"));
+    assert!(validateMessages("group1"));
+
+    // no message may be appended after the seal
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let latest: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&messages("group1")[0]).expect("it should parse the signed message");
+    let other_msg = SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+        other_id,
+        &other_secret,
+        "after the seal".as_bytes().to_vec(),
+        latest.hash::<Sha256>(),
+        latest,
+    );
+    let err = webmessage::addSignedMessage("group1", &serde_json::to_string(&other_msg).unwrap())
+        .expect_err("the group is sealed");
+    assert_eq!(err, "group is sealed");
+    webmessage::sealGroup("group1").expect_err("the group is sealed");
+
+    // a chain with messages after the seal is invalid
+    signMessage("group1", "written without validation").expect("it should sign the message");
+    assert!(!validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_cross_group_references() {
+    initAccount().expect("it should create the account");
+    let quoted: SignedMessage<Identity, Signature> = serde_json::from_str(
+        &signMessage("group1", "the evidence").expect("it should sign the message"),
+    )
+    .expect("it should parse");
+    let quoted_hash = serde_json::to_string(&quoted.hash::<Sha256>()).unwrap();
+    assert!(webmessage::referencesTo("group1", &quoted_hash)
+        .expect("it should look up")
+        .is_empty());
+
+    let referring: SignedMessage<Identity, Signature> = serde_json::from_str(
+        &webmessage::signReference("group2", "see this", "group1", &quoted_hash)
+            .expect("it should sign the reference"),
+    )
+    .expect("it should parse");
+    let reference = referring
+        .message
+        .reference
+        .clone()
+        .expect("it should have the reference");
+    assert_eq!(reference.group_id, "group1");
+    assert_eq!(reference.hash, quoted.hash::<Sha256>());
+
+    // the reference is covered by the signature
+    let mut tampered = referring.clone();
+    tampered.message.reference = None;
+    assert!(referring.verify::<Sha256>());
+    assert!(!tampered.verify::<Sha256>());
+
+    let references: Vec<MessageRef> = webmessage::referencesTo("group1", &quoted_hash)
+        .expect("it should look up")
+        .iter()
+        .map(|reference| serde_json::from_str(reference).expect("it should parse"))
+        .collect();
+    assert_eq!(
+        references,
+        vec![MessageRef {
+            group_id: "group2".to_string(),
+            hash: referring.hash::<Sha256>(),
+        }]
+    );
+    webmessage::referencesTo("group1", "invalid").expect_err("the hash is invalid");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_drafts() {
+    initAccount().expect("it should create the account");
+    let draft_id = webmessage::saveDraft("group1", "hello", None).expect("it should save");
+    let other_id = webmessage::saveDraft("group1", "other", None).expect("it should save");
+    assert_ne!(draft_id, other_id);
+    assert_eq!(
+        webmessage::saveDraft("group1", "hello world", Some(draft_id.clone())),
+        Ok(draft_id.clone())
+    );
+    webmessage::saveDraft("group1", "lost", Some("unknown".to_string()))
+        .expect_err("the draft does not exist");
+
+    let drafts: Vec<webmessage::draft::Draft> = webmessage::listDrafts("group1")
+        .iter()
+        .map(|draft| serde_json::from_str(draft).expect("it should parse the draft"))
+        .collect();
+    assert_eq!(drafts.len(), 2);
+    assert_eq!(drafts[0].data, "hello world");
+    assert!(webmessage::listDrafts("group2").is_empty());
+
+    // drafts are not in the chain until they are signed
+    assert!(messages("group1").is_empty());
+    let signed_msg: SignedMessage<Identity, Signature> = serde_json::from_str(
+        &webmessage::signDraft("group1", &draft_id).expect("it should sign the draft"),
+    )
+    .expect("it should parse the signed message");
+    assert_eq!(signed_msg.message.data, b"hello world");
+    assert_eq!(messages("group1").len(), 2);
+    webmessage::signDraft("group1", &draft_id).expect_err("the draft is signed");
+
+    assert!(webmessage::deleteDraft("group1", &other_id).expect("it should delete the draft"));
+    assert!(!webmessage::deleteDraft("group1", &other_id).expect("it should delete the draft"));
+    assert!(webmessage::listDrafts("group1").is_empty());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_outbox() {
+    initAccount().expect("it should create the account");
+    let parse = |msg: &str| -> SignedMessage<Identity, Signature> {
+        serde_json::from_str(msg).expect("it should parse the signed message")
+    };
+
+    // the queued messages are chained but not written
+    let queued1 = parse(
+        &webmessage::queueMessage("group1", "queued 1").expect("it should queue the message"),
+    );
+    let queued2 = parse(
+        &webmessage::queueMessage("group1", "queued 2").expect("it should queue the message"),
+    );
+    assert_eq!(queued2.seq, queued1.seq + 1);
+    assert_eq!(queued2.message.previous_hash, queued1.hash::<Sha256>());
+    assert_eq!(webmessage::outbox().len(), 2);
+    assert!(messages("group1").is_empty());
+
+    // the head moves before the outbox is flushed, so the messages are re-signed onto the new head
+    signMessage("group1", "synced").expect("it should sign the message");
+    let written: Vec<SignedMessage<Identity, Signature>> = webmessage::flushOutbox()
+        .expect("it should flush the outbox")
+        .iter()
+        .map(|msg| parse(msg))
+        .collect();
+    assert_eq!(written.len(), 2);
+    assert_eq!(written[0].message.data, b"queued 1");
+    assert_eq!(written[1].message.data, b"queued 2");
+    assert_eq!(written[1].seq, 3);
+    assert!(webmessage::outbox().is_empty());
+    assert!(validateMessages("group1"));
+
+    // the message is written as signed if the head did not move
+    let queued =
+        webmessage::queueMessage("group1", "queued 3").expect("it should queue the message");
+    assert_eq!(
+        webmessage::flushOutbox().expect("it should flush the outbox"),
+        vec![queued]
+    );
+    assert_eq!(messages("group1").len(), 5);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_quarantine() {
+    initAccount().expect("it should create the account");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(other_id.clone(), &other_secret, "group1");
+    let next = |data: &str, previous: &SignedMessage<Identity, Signature>| {
+        SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            data.as_bytes().to_vec(),
+            previous.hash::<Sha256>(),
+            previous.clone(),
+        )
+    };
+    let msg1 = next("data 1", &genesis);
+    let msg2 = next("data 2", &msg1);
+    let add = |msg: &SignedMessage<Identity, Signature>| {
+        webmessage::addSignedMessage("group1", &serde_json::to_string(msg).unwrap())
+    };
+
+    // the messages delivered out of order are quarantined
+    add(&genesis).expect("it should add the genesis record");
+    add(&msg2).expect_err("the message is out of order");
+    let entries: Vec<webmessage::quarantine::QuarantineEntry> = webmessage::quarantined("group1")
+        .iter()
+        .map(|entry| serde_json::from_str(entry).expect("it should parse the entry"))
+        .collect();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].reason, "wrong message sequence");
+    assert!(webmessage::retryQuarantined("group1")
+        .expect("it should retry the quarantined messages")
+        .is_empty());
+
+    // the quarantined message is added once the missing message is synced
+    add(&msg1).expect("it should add the message");
+    assert_eq!(
+        webmessage::retryQuarantined("group1").expect("it should retry the quarantined messages"),
+        vec![serde_json::to_string(&msg2.hash::<Sha256>()).unwrap()]
+    );
+    assert!(webmessage::quarantined("group1").is_empty());
+    assert_eq!(messages("group1").len(), 3);
+
+    // a stale message may be discarded
+    let stale = next("fork", &genesis);
+    add(&stale).expect_err("the message is stale");
+    let hash = serde_json::to_string(&stale.hash::<Sha256>()).unwrap();
+    assert_eq!(webmessage::discardQuarantined("group1", &hash), Ok(true));
+    assert_eq!(webmessage::discardQuarantined("group1", &hash), Ok(false));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_reordering_buffer() {
+    use webmessage::writer::MAX_PENDING_MESSAGES;
+
+    initAccount().expect("it should create the account");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(other_id.clone(), &other_secret, "group1");
+    let next = |data: &str, previous: &SignedMessage<Identity, Signature>| {
+        SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            data.as_bytes().to_vec(),
+            previous.hash::<Sha256>(),
+            previous.clone(),
+        )
+    };
+    let msg1 = next("data 1", &genesis);
+    let msg2 = next("data 2", &msg1);
+    let msg3 = next("data 3", &msg2);
+    let ingest = |msg: &SignedMessage<Identity, Signature>| {
+        webmessage::ingestSignedMessage("group1", &serde_json::to_string(msg).unwrap())
+    };
+    let hash_of = |msg: &SignedMessage<Identity, Signature>| {
+        serde_json::to_string(&msg.hash::<Sha256>()).unwrap()
+    };
+
+    // the messages ahead of the chain are held
+    assert_eq!(ingest(&msg3), Ok(vec![]));
+    assert_eq!(ingest(&msg1), Ok(vec![]));
+    assert_eq!(webmessage::pendingMessages("group1").len(), 2);
+    assert!(messages("group1").is_empty());
+
+    // the held messages are written once the gaps are filled
+    assert_eq!(
+        ingest(&genesis),
+        Ok(vec![hash_of(&genesis), hash_of(&msg1)])
+    );
+    assert_eq!(ingest(&msg2), Ok(vec![hash_of(&msg2), hash_of(&msg3)]));
+    assert!(webmessage::pendingMessages("group1").is_empty());
+    assert_eq!(messages("group1").len(), 4);
+    assert!(validateMessages("group1"));
+
+    // a message with an invalid signature is not held
+    let mut forged = next("data 5", &next("data 4", &msg3));
+    forged.message.data = b"forged".to_vec();
+    ingest(&forged).expect_err("the signature is invalid");
+    assert!(webmessage::pendingMessages("group1").is_empty());
+
+    // the messages beyond the write limits or the capacity of the buffer are not held
+    let ahead = |data: &str, seq| {
+        SignedMessage::<Identity, Signature>::sign::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            Message::root(data.as_bytes().to_vec()),
+            seq,
+        )
+    };
+    webmessage::setWriteLimits(r#"{"max_message_size":8}"#).expect("it should set the limits");
+    assert_eq!(
+        ingest(&ahead("too large", 5)),
+        Err("message too large".to_string())
+    );
+    webmessage::setWriteLimits("{}").expect("it should set the limits");
+    let too_far = 4 + MAX_PENDING_MESSAGES as u64 + 1;
+    assert_eq!(
+        ingest(&ahead("too far", too_far)),
+        Err("too many pending messages".to_string())
+    );
+    for idx in 0..MAX_PENDING_MESSAGES {
+        assert_eq!(ingest(&ahead(&format!("held {idx}"), 5)), Ok(vec![]));
+    }
+    assert_eq!(
+        ingest(&ahead("full", 5)),
+        Err("too many pending messages".to_string())
+    );
+    assert_eq!(ingest(&ahead("held 0", 5)), Ok(vec![]));
+    assert_eq!(
+        webmessage::pendingMessages("group1").len(),
+        MAX_PENDING_MESSAGES
+    );
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_duplicate_detection() {
+    initAccount().expect("it should create the account");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(other_id, &other_secret, "group1");
+    let genesis_str = serde_json::to_string(&genesis).unwrap();
+
+    webmessage::addSignedMessage("group1", &genesis_str).expect("it should add the message");
+    assert_eq!(
+        webmessage::addSignedMessage("group1", &genesis_str),
+        Err("message already exists".to_string())
+    );
+    assert!(webmessage::quarantined("group1").is_empty());
+
+    // the submission is idempotent when the message is ingested
+    assert_eq!(
+        webmessage::ingestSignedMessage("group1", &genesis_str),
+        Ok(vec![])
+    );
+    assert_eq!(messages("group1").len(), 1);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_write_limits() {
+    initAccount().expect("it should create the account");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(other_id.clone(), &other_secret, "group1");
+    let next = |data: &str, previous: &SignedMessage<Identity, Signature>| {
+        SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            data.as_bytes().to_vec(),
+            previous.hash::<Sha256>(),
+            previous.clone(),
+        )
+    };
+    let add = |msg: &SignedMessage<Identity, Signature>| {
+        webmessage::addSignedMessage("group1", &serde_json::to_string(msg).unwrap())
+    };
+    add(&genesis).expect("it should add the genesis record");
+
+    webmessage::setWriteLimits("invalid").expect_err("the limits are invalid");
+    webmessage::setWriteLimits(r#"{"max_message_size":8,"max_messages_per_author":2,"window":60}"#)
+        .expect("it should set the limits");
+    let limits: webmessage::writer::WriteLimits =
+        serde_json::from_str(&webmessage::writeLimits()).expect("it should parse the limits");
+    assert_eq!(limits.max_message_size, Some(8));
+    assert_eq!(limits.max_messages_per_group, None);
+
+    let large = next("too large data", &genesis);
+    assert_eq!(add(&large), Err("message too large".to_string()));
+
+    // the signer may write two messages within the window
+    let msg1 = next("data 1", &genesis);
+    add(&msg1).expect("it should add the message");
+    let msg2 = next("data 2", &msg1);
+    add(&msg2).expect("it should add the message");
+    let msg3 = next("data 3", &msg2);
+    assert_eq!(add(&msg3), Err("too many messages from signer".to_string()));
+
+    webmessage::setWriteLimits(r#"{"max_messages_per_group":3}"#)
+        .expect("it should set the limits");
+    assert_eq!(add(&msg3), Err("too many messages in group".to_string()));
+
+    webmessage::setWriteLimits("{}").expect("it should set the limits");
+    add(&msg3).expect("it should add the message");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_strict_schema() {
+    initAccount().expect("it should create the account");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(other_id.clone(), &other_secret, "group1");
+    let valid = serde_json::to_value(&genesis).unwrap();
+    let add = |value: &serde_json::Value| {
+        webmessage::addSignedMessage("group1", &serde_json::to_string(value).unwrap())
+    };
+
+    let large = "x".repeat(webmessage::schema::MAX_PAYLOAD_LENGTH + 1);
+    assert_eq!(
+        webmessage::addSignedMessage("group1", &large),
+        Err("payload too large".to_string())
+    );
+    assert_eq!(
+        webmessage::addSignedMessage("group1", "{}"),
+        Err("malformed signed message".to_string())
+    );
+
+    let mut short_hash = valid.clone();
+    short_hash["message"]["previous_hash"] = serde_json::json!(vec![0u8; 31]);
+    assert_eq!(add(&short_hash), Err("invalid previous hash".to_string()));
+    let mut wide_hash = valid.clone();
+    wide_hash["message"]["previous_hash"][0] = serde_json::json!(256);
+    assert_eq!(add(&wide_hash), Err("invalid previous hash".to_string()));
+
+    let mut bad_id = valid.clone();
+    bad_id["id"]["public_key"] = serde_json::json!("not a point");
+    assert_eq!(add(&bad_id), Err("invalid identity".to_string()));
+
+    let mut bad_signature = valid.clone();
+    bad_signature["signature"]["signature"] = serde_json::json!("not a signature");
+    assert_eq!(add(&bad_signature), Err("malformed signature".to_string()));
+
+    // the storage flags, which are not signed, are set only by the local store
+    for (field, value) in [
+        ("redacted", serde_json::json!(true)),
+        ("compressed", serde_json::json!(true)),
+        (
+            "tombstone",
+            serde_json::json!({"hash": vec![0u8; 32], "digest": vec![0u8; 32]}),
+        ),
+    ] {
+        let mut flagged = valid.clone();
+        flagged["message"][field] = value;
+        assert_eq!(
+            add(&flagged),
+            Err("storage field in signed message".to_string())
+        );
+    }
+
+    add(&valid).expect("it should add the genesis record");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_hash_string() {
+    initAccount().expect("it should create the account");
+    let msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "data 1").expect("it should sign the message"))
+            .unwrap();
+    let hash = serde_json::to_string(&msg.hash::<Sha256>()).unwrap();
+
+    let hash_str = webmessage::hashToString(&hash).expect("it should encode the hash");
+    assert_eq!(hash_str.len(), 64);
+    assert!(hash_str
+        .chars()
+        .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)));
+    assert_eq!(webmessage::hashFromString(&hash_str), Ok(hash.clone()));
+    assert_eq!(
+        webmessage::hashFromString(&hash_str.to_uppercase()),
+        Ok(hash.clone())
+    );
+
+    webmessage::hashToString("[1, 2]").expect_err("the hash is too short");
+    webmessage::hashFromString(&hash_str[1..]).expect_err("the string is too short");
+    webmessage::hashFromString(&format!("{}g", &hash_str[1..])).expect_err("the string is not hex");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_schema_version() {
+    initAccount().expect("it should create the account");
+    assert_eq!(webmessage::schemaVersion(), 2);
+    signMessage("group1", "data 1").expect("it should sign the message");
+    assert_eq!(webmessage::schemaVersion(), 2);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+    assert_eq!(webmessage::schemaVersion(), 2);
+}
+
+#[wasm_bindgen_test]
+fn test_message_version() {
+    initAccount().expect("it should create the account");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(other_id.clone(), &other_secret, "group1");
+    assert_eq!(genesis.version, webmessage::MESSAGE_VERSION);
+    assert_eq!(genesis.message.version, webmessage::MESSAGE_VERSION);
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&genesis).unwrap())
+        .expect("it should add the genesis record");
+
+    // a message with a field added by a later version is verified with the field
+    let mut message = Message::new(genesis.hash::<Sha256>(), b"data".to_vec());
+    message
+        .extensions
+        .insert("future_field".to_string(), serde_json::json!("value"));
+    let signed_msg =
+        SignedMessage::sign::<Secret, MessageSigner>(other_id.clone(), &other_secret, message, 1);
+    let mut value = serde_json::to_value(&signed_msg).unwrap();
+    assert_eq!(value["message"]["future_field"], "value");
+
+    value["message"]["future_field"] = serde_json::json!("tampered");
+    assert_eq!(
+        webmessage::addSignedMessage("group1", &value.to_string()),
+        Err("fail to validate message".to_string())
+    );
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&signed_msg).unwrap())
+        .expect("it should add the message");
+
+    let stored: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&messages("group1")[0]).unwrap();
+    assert_eq!(
+        stored.message.extensions.get("future_field"),
+        Some(&serde_json::json!("value"))
+    );
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_custom_storage() {
+    use wasm_bindgen::JsCast;
+
+    let window = web_sys::window().unwrap();
+    let local_storage = window.local_storage().unwrap().unwrap();
+    let session_storage = window.session_storage().unwrap().unwrap();
+    let local_items = local_storage.length().unwrap();
+    let storage: wasm_bindgen::JsValue = session_storage.clone().into();
+    webmessage::setStorage(Some(storage.clone().unchecked_into()));
+
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    assert_eq!(messages("group1").len(), 2);
+    assert!(session_storage.length().unwrap() > 0);
+    assert_eq!(local_storage.length().unwrap(), local_items);
+
+    // the items are not in the local storage
+    webmessage::setStorage(None);
+    assert!(messages("group1").is_empty());
+    webmessage::setStorage(Some(storage.clone().unchecked_into()));
+    webmessage::clear().expect("it should clear the session storage");
+    assert!(messages("group1").is_empty());
+    webmessage::setStorage(None);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_validate_chunked() {
+    use webmessage::validation::ValidationCursor;
+
+    initAccount().expect("it should create the account");
+    let cursor: ValidationCursor =
+        serde_json::from_str(&webmessage::validateMessagesChunked("group1", 10)).unwrap();
+    assert_eq!(cursor.result, Some(true));
+    assert_eq!(cursor.checked, 0);
+
+    for i in 0..4 {
+        signMessage("group1", &format!("data {i}")).expect("it should sign the message");
+    }
+    // the genesis record and 4 messages are checked in both passes
+    let mut cursor: ValidationCursor =
+        serde_json::from_str(&webmessage::validateMessagesChunked("group1", 3)).unwrap();
+    let mut chunks = 1;
+    while cursor.result.is_none() {
+        assert_eq!(cursor.checked, chunks * 3);
+        let cursor_str = serde_json::to_string(&cursor).unwrap();
+        cursor =
+            serde_json::from_str(&webmessage::resumeValidation(&cursor_str, 3).unwrap()).unwrap();
+        chunks += 1;
+    }
+    assert_eq!(cursor.result, Some(true));
+    assert_eq!(cursor.checked, 10);
+    assert_eq!(cursor.total, 10);
+    assert_eq!(chunks, 4);
+    webmessage::resumeValidation("invalid", 3).expect_err("the cursor is invalid");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_validate_with_progress() {
+    use std::{cell::RefCell, rc::Rc};
+    use wasm_bindgen::{closure::Closure, JsCast};
+
+    initAccount().expect("it should create the account");
+    for i in 0..150 {
+        signMessage("group1", &format!("data {i}")).expect("it should sign the message");
+    }
+    let reports = Rc::new(RefCell::new(vec![]));
+    let on_progress = {
+        let reports = reports.clone();
+        Closure::<dyn FnMut(f64, f64)>::new(move |checked: f64, total: f64| {
+            reports.borrow_mut().push((checked, total))
+        })
+    };
+    assert!(webmessage::validateMessagesWithProgress(
+        "group1",
+        on_progress.as_ref().unchecked_ref()
+    ));
+    // the genesis record and 150 messages are checked in both passes, in chunks of 100 checks
+    assert_eq!(
+        *reports.borrow(),
+        vec![
+            (100.0, 302.0),
+            (200.0, 302.0),
+            (300.0, 302.0),
+            (302.0, 302.0)
+        ]
+    );
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_invalidate_cache() {
+    let local_storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
+
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    assert_eq!(webmessage::groups().len(), 1);
+
+    // the change bypassing the library is not seen until the cached value is invalidated
+    let groups = local_storage.get_item("groups").unwrap().unwrap();
+    local_storage.remove_item("groups").unwrap();
+    assert_eq!(webmessage::groups().len(), 1);
+    webmessage::invalidateCache(Some("groups".to_string()));
+    assert!(webmessage::groups().is_empty());
+
+    local_storage.set_item("groups", &groups).unwrap();
+    assert!(webmessage::groups().is_empty());
+    webmessage::invalidateCache(None);
+    assert_eq!(webmessage::groups().len(), 1);
+
+    // the writes through the library are seen
+    signMessage("group2", "data 1").expect("it should sign the message");
+    assert_eq!(webmessage::groups().len(), 2);
+    assert_eq!(messages("group1").len(), 2);
+
+    webmessage::clear().expect("it should clear the local storage");
+    assert!(webmessage::groups().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn test_compact_encoding() {
+    let identity = initAccount().expect("it should create the account");
+    let expanded = webmessage::expandIdentity(&identity).unwrap();
+    assert!(expanded.len() > identity.len());
+    assert_eq!(webmessage::compactIdentity(&expanded).unwrap(), identity);
+    assert_eq!(webmessage::compactIdentity(&identity).unwrap(), identity);
+    assert!(webmessage::compactIdentity("not a point").is_err());
+
+    // the identities and signatures of the messages are compact, and they are verified
+    signMessage("group1", "data 1").expect("it should sign the message");
+    let msgs = messages("group1");
+    let msg: serde_json::Value = serde_json::from_str(&msgs[1]).unwrap();
+    assert_eq!(msg["id"]["public_key"], serde_json::json!(identity));
+    assert!(!msg["signature"]["signature"]
+        .as_str()
+        .unwrap()
+        .starts_with(['{', '[']));
+    assert!(validateMessages("group1"));
+
+    // either encoding of the identity is accepted
+    assert_eq!(
+        webmessage::fingerprint(&identity).expect("it should parse the identity"),
+        webmessage::fingerprint(&expanded).expect("it should parse the identity")
+    );
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_metrics() {
+    use webmessage::metrics::Metrics;
+
+    // the metrics are not recorded until they are enabled
+    webmessage::resetMetrics();
+    initAccount().expect("it should create the account");
+    let metrics: Metrics = serde_json::from_str(&webmessage::metrics()).unwrap();
+    assert_eq!(metrics, Metrics::default());
+
+    webmessage::setMetricsEnabled(true);
+    signMessage("group1", "data 1").expect("it should sign the message");
+    assert!(validateMessages("group1"));
+    let metrics: Metrics = serde_json::from_str(&webmessage::metrics()).unwrap();
+    assert_eq!(metrics.sign.count, 2);
+    assert!(metrics.verify.count >= 2);
+    assert!(metrics.storage_read.count > 0);
+    assert!(metrics.storage_write.count > 0);
+    assert!(metrics.sign.total_ms >= 0.0);
+
+    webmessage::resetMetrics();
+    let metrics: Metrics = serde_json::from_str(&webmessage::metrics()).unwrap();
+    assert_eq!(metrics, Metrics::default());
+    webmessage::setMetricsEnabled(false);
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_diagnostics() {
+    use webmessage::diagnostics::{Category, Diagnostic, Level};
+
+    webmessage::clearDiagnostics();
+    webmessage::setLogLevel(Some("verbose".to_string())).expect_err("it is not a level");
+    webmessage::setLogLevel(Some("error".to_string())).expect("it should set the level");
+
+    initAccount().expect("it should create the account");
+    webmessage::setRetentionPolicy("group1", r#"{"max_count":1}"#)
+        .expect("it should set the retention policy");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    signMessage("group1", "data 2").expect("it should sign the message");
+    webmessage::updatePolicy("group1", r#"{"retention":{"max_count":1}}"#)
+        .expect("it should update the policy");
+    assert!(webmessage::compact("group1").expect("it should compact the group") > 0);
+    assert!(validateMessages("group1"));
+    assert!(webmessage::diagnostics().is_empty());
+
+    // the validation failure is kept, whatever the level of the console is
+    webmessage::updatePolicy("group1", "{}").expect("it should update the policy");
+    assert!(!validateMessages("group1"));
+    let diagnostics: Vec<Diagnostic> = webmessage::diagnostics()
+        .iter()
+        .map(|diagnostic| serde_json::from_str(diagnostic).unwrap())
+        .collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].level, Level::Warn);
+    assert_eq!(diagnostics[0].category, Category::Validation);
+    assert!(diagnostics[0].message.contains("group1"));
+
+    webmessage::clearDiagnostics();
+    assert!(webmessage::diagnostics().is_empty());
+    webmessage::setLogLevel(None).expect("it should disable the console");
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_seeded_rng() {
+    use webmessage::rng;
+
+    let sign_with_seed = |seed| {
+        rng::set_seed(Some(seed));
+        let (secret, identity) = GenKeysAlgorithm::generate_keys();
+        let signed_msg = SignedMessage::new_first_message::<Secret, MessageSigner>(
+            identity,
+            &secret,
+            b"data".to_vec(),
+        );
+        serde_json::to_string(&signed_msg).unwrap()
+    };
+
+    // the keys and the signatures are reproducible with the same seed
+    assert_eq!(sign_with_seed(1), sign_with_seed(1));
+    assert_ne!(sign_with_seed(1), sign_with_seed(2));
+
+    rng::set_seed(None);
+    let (_, first) = GenKeysAlgorithm::generate_keys();
+    let (_, second) = GenKeysAlgorithm::generate_keys();
+    assert_ne!(first.to_string(), second.to_string());
+}
+
+#[wasm_bindgen_test]
+fn test_generic_chain() {
+    use webmessage::{
+        chain::{MemoryBackend, StorageBackend, Writer},
+        writer::WriteError,
+    };
+
+    let (secret, identity) = GenKeysAlgorithm::generate_keys();
+    let mut writer: Writer<Identity, Signature, Sha256, _> = Writer::new(MemoryBackend::default());
+    for data in ["data 1", "data 2", "data 3"] {
+        writer
+            .sign::<Secret, MessageSigner>("group1", identity.clone(), &secret, data.into())
+            .expect("it should sign the message");
+    }
+    assert_eq!(writer.messages("group1").len(), 3);
+    assert_eq!(writer.messages("group1")[0].message.data, b"data 3");
+    assert!(writer.validate("group1"));
+
+    // the messages which do not follow the latest message are rejected
+    let first = writer.messages("group1").pop().unwrap();
+    assert!(matches!(
+        writer.write("group1", &first),
+        Err(WriteError::AlreadyExists)
+    ));
+    let other = SignedMessage::new_first_message::<Secret, MessageSigner>(
+        identity.clone(),
+        &secret,
+        b"other".to_vec(),
+    );
+    assert!(matches!(
+        writer.write("group1", &other),
+        Err(WriteError::WrongSequence)
+    ));
+
+    // the chains are kept in the storage of the library with the storage backend
+    let mut writer: Writer<Identity, Signature, Sha256, _> = Writer::new(StorageBackend);
+    writer
+        .sign::<Secret, MessageSigner>("group1", identity, &secret, b"data 1".to_vec())
+        .expect("it should sign the message");
+    assert!(writer.validate("group1"));
+    assert!(messages("group1").is_empty());
+    writer.clear("group1").expect("it should clear the chain");
+    assert!(writer.latest_hash("group1").is_none());
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_sign_message_as() {
+    let identity1 = initAccount().expect("it should create the account");
+    let identity2 = webmessage::newAccount().expect("it should create the account");
+
+    // the message is signed by the given account, and the current account is not changed
+    let signed_msg = webmessage::signMessageAs(&identity1, "group1", "some data")
+        .expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signed_msg).expect("it should parse the signed message");
+    assert_eq!(signed_msg.id.to_string(), identity1);
+    assert_eq!(
+        initAccount().expect("it should create the account"),
+        identity2
+    );
+
+    // the genesis record is signed by the given account too
+    let msgs = messages("group1");
+    assert_eq!(msgs.len(), 2);
+    let genesis_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msgs[1]).expect("it should parse the signed message");
+    assert!(genesis_msg.is_genesis_of("group1"));
+    assert_eq!(genesis_msg.id.to_string(), identity1);
+
+    assert!(!signMessage("group1", "other data")
+        .expect("it should sign the message")
+        .is_empty());
+    assert!(validateMessages("group1"));
+
+    // the account must exist
+    let (_, unknown) = GenKeysAlgorithm::generate_keys();
+    assert!(webmessage::signMessageAs(&unknown.to_string(), "group1", "some data").is_err());
+    assert!(webmessage::signMessageAs("invalid", "group1", "some data").is_err());
+    assert_eq!(messages("group1").len(), 3);
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_sign_data() {
+    let identity = initAccount().expect("it should create the account");
+
+    let signature = webmessage::signData("login challenge").expect("it should sign the data");
+    assert!(webmessage::verifyData(
+        &identity,
+        "login challenge",
+        &signature
+    ));
+
+    // the signature covers the data and the identity
+    assert!(!webmessage::verifyData(
+        &identity,
+        "other challenge",
+        &signature
+    ));
+    let other = webmessage::newAccount().expect("it should create the account");
+    assert!(!webmessage::verifyData(
+        &other,
+        "login challenge",
+        &signature
+    ));
+    assert!(!webmessage::verifyData(
+        &identity,
+        "login challenge",
+        "invalid"
+    ));
+    assert!(!webmessage::verifyData(
+        "invalid",
+        "login challenge",
+        &signature
+    ));
+
+    // the data is not signed as a message of a chain
+    let signed_msg: SignedMessage<Identity, Signature> = serde_json::from_str(
+        &signMessage("group1", "login challenge").expect("it should sign the message"),
+    )
+    .expect("it should parse the signed message");
+    let signature = serde_json::to_string(&signed_msg.signature).unwrap();
+    assert!(!webmessage::verifyData(
+        &other,
+        "login challenge",
+        &signature
+    ));
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_auth_challenge() {
+    let identity = initAccount().expect("it should create the account");
+
+    let challenge = webmessage::createAuthChallenge().expect("it should create the challenge");
+    let response = webmessage::signAuthChallenge(&challenge).expect("it should sign the challenge");
+
+    // the response is signed by the identity, and it is accepted once
+    let other = Identity::try_from(
+        webmessage::newAccount()
+            .expect("it should create the account")
+            .as_str(),
+    )
+    .unwrap();
+    assert!(
+        !webmessage::verifyAuthResponse(&other.to_string(), &challenge, &response)
+            .expect("it should verify the response")
+    );
+    assert!(
+        webmessage::verifyAuthResponse(&identity, &challenge, &response)
+            .expect("it should verify the response")
+    );
+    assert!(
+        !webmessage::verifyAuthResponse(&identity, &challenge, &response)
+            .expect("it should verify the response")
+    );
+
+    // the response answers the given challenge only
+    let challenge1 = webmessage::createAuthChallenge().expect("it should create the challenge");
+    let challenge2 = webmessage::createAuthChallenge().expect("it should create the challenge");
+    let response = webmessage::signAuthChallenge(&challenge1).unwrap();
+    assert!(
+        !webmessage::verifyAuthResponse(&other.to_string(), &challenge2, &response)
+            .expect("it should verify the response")
+    );
+
+    // the challenges which are not created by the verifier, or are expired, are rejected
+    let unknown = serde_json::to_string(&webmessage::auth::AuthChallenge::new(0)).unwrap();
+    assert!(webmessage::signAuthChallenge(&unknown).is_err());
+    let unknown =
+        serde_json::to_string(&webmessage::auth::AuthChallenge::new(u64::MAX / 2)).unwrap();
+    let response = webmessage::signAuthChallenge(&unknown).unwrap();
+    assert!(
+        !webmessage::verifyAuthResponse(&other.to_string(), &unknown, &response)
+            .expect("it should verify the response")
+    );
+    assert!(
+        !webmessage::verifyAuthResponse(&identity, &challenge2, "invalid")
+            .expect("it should verify the response")
+    );
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_share_token() {
+    let identity = initAccount().expect("it should create the account");
+
+    let token = webmessage::identityShareToken(&identity).expect("it should create the token");
+    assert!(token.starts_with(webmessage::share::TOKEN_PREFIX));
+    let parsed: webmessage::share::ShareToken =
+        serde_json::from_str(&webmessage::parseShareToken(&token).unwrap()).unwrap();
+    assert_eq!(parsed.identity.to_string(), identity);
+    assert!(parsed.profile.is_none());
+
+    // the token points to the latest profile update
+    let update: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&webmessage::publishProfile("alice", None, vec![]).unwrap()).unwrap();
+    let token_with_profile = webmessage::identityShareToken(&identity).unwrap();
+    assert!(token_with_profile.len() > token.len());
+    let parsed: webmessage::share::ShareToken =
+        serde_json::from_str(&webmessage::parseShareToken(&token_with_profile).unwrap()).unwrap();
+    assert_eq!(parsed.identity.to_string(), identity);
+    assert_eq!(parsed.profile, Some(update.hash::<Sha256>()));
+
+    // the tokens which are mistyped or truncated are rejected
+    let mut mistyped = token.clone().into_bytes();
+    let last = mistyped.len() - 10;
+    mistyped[last] = if mistyped[last] == b'A' { b'B' } else { b'A' };
+    assert!(webmessage::parseShareToken(&String::from_utf8(mistyped).unwrap()).is_err());
+    assert!(webmessage::parseShareToken(&token[..token.len() - 4]).is_err());
+    assert!(webmessage::parseShareToken("wm:").is_err());
+    assert!(webmessage::parseShareToken(&identity).is_err());
+    assert!(webmessage::identityShareToken("invalid").is_err());
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_group_settings() {
+    initAccount().expect("it should create the account");
+    let settings: webmessage::settings::GroupSettings =
+        serde_json::from_str(&webmessage::groupSettings("group1")).unwrap();
+    assert!(settings.encryption);
+    assert!(!settings.strict_timestamps);
+
+    // the messages are not encrypted when the encryption is turned off
+    signMessage("group1", "data 1").expect("it should sign the message");
+    webmessage::newGroupKey("group1").expect("it should create the group key");
+    webmessage::setGroupSettings("group1", r#"{"encryption":false}"#)
+        .expect("it should set the settings");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "data 2").expect("it should sign the message"))
+            .unwrap();
+    assert!(!signed_msg.message.encrypted);
+    webmessage::setGroupSettings("group1", r#"{"encryption":true}"#).unwrap();
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "data 3").expect("it should sign the message"))
+            .unwrap();
+    assert!(signed_msg.message.encrypted);
+    webmessage::removeGroupKey("group1").expect("it should remove the group key");
+
+    // the retention policy and the allowed signers are kept in the settings
+    webmessage::setRetentionPolicy("group1", r#"{"max_count":2}"#).unwrap();
+    webmessage::setAllowedSigners("group1", vec![signed_msg.id.to_string()]).unwrap();
+    let settings: webmessage::settings::GroupSettings =
+        serde_json::from_str(&webmessage::groupSettings("group1")).unwrap();
+    assert_eq!(settings.retention.max_count, Some(2));
+    assert_eq!(
+        settings.allowed_signers.map(|signers| signers.len()),
+        Some(1)
+    );
+    webmessage::clearAllowedSigners("group1").expect("it should clear the allowed signers");
+
+    // the messages with timestamps out of order are rejected when the timestamps are checked strictly
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let other_msg = |timestamp: Option<u64>| {
+        let prev: SignedMessage<Identity, Signature> =
+            serde_json::from_str(&messages("group1")[0]).unwrap();
+        let mut message = Message::new(prev.hash::<Sha256>(), b"other data".to_vec());
+        message.timestamp = timestamp;
+        let signed_msg = SignedMessage::sign::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            message,
+            prev.seq + 1,
+        );
+        serde_json::to_string(&signed_msg).unwrap()
+    };
+    webmessage::setGroupSettings("group1", r#"{"strict_timestamps":true}"#).unwrap();
+    for (timestamp, reason) in [
+        (None, "timestamp is missing"),
+        (Some(1), "timestamp is before the previous message"),
+        (Some(u64::MAX / 2), "timestamp is in the future"),
+    ] {
+        assert_eq!(
+            webmessage::addSignedMessage("group1", &other_msg(timestamp)),
+            Err(reason.to_string())
+        );
+    }
+    assert!(validateMessages("group1"));
+
+    // the timestamp may go back from the previous message within the backward skew
+    let prev_timestamp =
+        serde_json::from_str::<SignedMessage<Identity, Signature>>(&messages("group1")[0])
+            .unwrap()
+            .message
+            .timestamp
+            .unwrap();
+    webmessage::setGroupSettings(
+        "group1",
+        r#"{"strict_timestamps":true,"max_backward_skew":60}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        webmessage::addSignedMessage("group1", &other_msg(Some(prev_timestamp - 61))),
+        Err("timestamp is before the previous message".to_string())
+    );
+    webmessage::addSignedMessage("group1", &other_msg(Some(prev_timestamp - 60)))
+        .expect("it should add the signed message");
+    assert!(validateMessages("group1"));
+
+    // the message is accepted, but the chain is invalid once the timestamps are checked strictly
+    webmessage::setGroupSettings("group1", "{}").unwrap();
+    webmessage::addSignedMessage("group1", &other_msg(Some(1)))
+        .expect("it should add the signed message");
+    assert!(validateMessages("group1"));
+    webmessage::setGroupSettings("group1", r#"{"strict_timestamps":true}"#).unwrap();
+    assert!(!validateMessages("group1"));
+
+    assert!(webmessage::setGroupSettings("group1", "invalid").is_err());
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_pin_messages() {
+    initAccount().expect("it should create the account");
+    let hash_of = |msg_str: &str| {
+        let msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(msg_str).expect("it should parse the signed message");
+        serde_json::to_string(&msg.hash::<Sha256>()).unwrap()
+    };
+    let first = hash_of(&signMessage("group1", "first").expect("it should sign the message"));
+    let second = hash_of(&signMessage("group1", "second").expect("it should sign the message"));
+    assert!(webmessage::pinnedMessages("group1").is_empty());
+
+    let pin = webmessage::pinMessage("group1", &first).expect("it should pin");
+    webmessage::pinMessage("group1", &second).expect("it should pin");
+    webmessage::pinMessage("group1", &hash_of(&pin)).expect_err("only data may be pinned");
+    webmessage::pinMessage("group1", &serde_json::to_string(&[0u8; 32]).unwrap())
+        .expect_err("the message is not found");
+
+    let pinned_data = || -> Vec<Vec<u8>> {
+        webmessage::pinnedMessages("group1")
+            .iter()
+            .map(|msg| {
+                let msg: SignedMessage<Identity, Signature> =
+                    serde_json::from_str(msg).expect("it should parse the signed message");
+                msg.message.data
+            })
+            .collect()
+    };
+    assert_eq!(pinned_data(), vec![b"second".to_vec(), b"first".to_vec()]);
+
+    // pinning again moves the message to the latest pinned
+    webmessage::pinMessage("group1", &first).expect("it should pin");
+    assert_eq!(pinned_data(), vec![b"first".to_vec(), b"second".to_vec()]);
+
+    webmessage::unpinMessage("group1", &first).expect("it should unpin");
+    assert_eq!(pinned_data(), vec![b"second".to_vec()]);
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_unread_count() {
+    let reader = initAccount().expect("it should create the account");
+    let hash_of = |msg_str: &str| {
+        let msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(msg_str).expect("it should parse the signed message");
+        serde_json::to_string(&msg.hash::<Sha256>()).unwrap()
+    };
+    assert_eq!(webmessage::unreadCount("group1"), 0);
+    let first = hash_of(&signMessage("group1", "first").expect("it should sign the message"));
+    signMessage("group1", "second").expect("it should sign the message");
+    let third = hash_of(&signMessage("group1", "third").expect("it should sign the message"));
+    // the genesis record is counted as well
+    assert_eq!(webmessage::unreadCount("group1"), 4);
+
+    webmessage::markSeen("group1", &first).expect("it should mark the message as seen");
+    assert_eq!(webmessage::unreadCount("group1"), 2);
+    webmessage::markSeen("group1", &serde_json::to_string(&[0u8; 32]).unwrap())
+        .expect_err("the message is not found");
+
+    // each account has its own marker
+    webmessage::newAccount().expect("it should create the account");
+    assert_eq!(webmessage::unreadCount("group1"), 4);
+    webmessage::markSeen("group1", &third).expect("it should mark the message as seen");
+    assert_eq!(webmessage::unreadCount("group1"), 0);
+    signMessage("group1", "fourth").expect("it should sign the message");
+    assert_eq!(webmessage::unreadCount("group1"), 1);
+
+    webmessage::setCurrentAccount(&reader).expect("it should set the current account");
+    assert_eq!(webmessage::unreadCount("group1"), 3);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_export_transcript() {
+    let alice = initAccount().expect("it should create the account");
+    webmessage::addContact(&alice, "alice", "verified", "").expect("it should add the contact");
+    let msg_str = signMessage("group1", "hello, *world*").expect("it should sign the message");
+    let msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    let hash = webmessage::hash_to_string(&msg.hash::<Sha256>());
+    signMessage("group1", "say \"hi\"\n<bye>").expect("it should sign the message");
+
+    webmessage::exportTranscript("group1", "pdf").expect_err("the format is unknown");
+
+    let markdown = webmessage::exportTranscript("group1", "markdown").expect("it should export");
+    assert!(markdown.starts_with("# group1\n"));
+    assert!(markdown.contains("**alice** ("));
+    assert!(markdown.contains(": hello, \\*world\\*[^1]"));
+    assert!(markdown.contains(&format!("[^1]: `{hash}`")));
+    // the genesis record is not listed
+    assert!(!markdown.contains("[^3]"));
+
+    let html = webmessage::exportTranscript("group1", "html").expect("it should export");
+    assert!(html.contains("say &quot;hi&quot;<br>&lt;bye&gt;"));
+    assert!(html.contains(&format!("<li id=\"hash-1\"><code>{hash}</code></li>")));
+
+    let csv = webmessage::exportTranscript("group1", "csv").expect("it should export");
+    let lines: Vec<&str> = csv.split("\r\n").collect();
+    assert_eq!(lines[0], "timestamp,author,text,hash");
+    assert!(lines[1].ends_with(&format!(",alice,\"hello, *world*\",{hash}")));
+    assert!(lines[1].contains(" UTC,"));
+    assert!(csv.contains("\"say \"\"hi\"\"\n<bye>\""));
+
+    assert_eq!(
+        webmessage::transcript::format_timestamp(Some(951782400)),
+        "2000-02-29 00:00:00 UTC"
+    );
+    assert_eq!(
+        webmessage::transcript::format_timestamp(Some(1700000000)),
+        "2023-11-14 22:13:20 UTC"
+    );
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_import_history() {
+    initAccount().expect("it should create the account");
+    webmessage::importHistory("group1", "not json").expect_err("it should fail to parse");
+
+    let entries = r#"[
+        {"data":"legacy 1","timestamp":1500000000,"author":"carol"},
+        {"data":"legacy 2"}
+    ]"#;
+    let imported = webmessage::importHistory("group1", entries).expect("it should import");
+    assert_eq!(imported.len(), 2);
+    signMessage("group1", "after the import").expect("it should sign the message");
+
+    let tags: Vec<(Vec<u8>, Vec<String>)> = webmessage::messagesByTag("group1", "imported")
+        .iter()
+        .map(|msg| {
+            let msg: SignedMessage<Identity, Signature> =
+                serde_json::from_str(msg).expect("it should parse the signed message");
+            (msg.message.data, msg.message.tags)
+        })
+        .collect();
+    assert_eq!(
+        tags,
+        vec![
+            (b"legacy 2".to_vec(), vec!["imported".to_string()]),
+            (
+                b"legacy 1".to_vec(),
+                vec![
+                    "imported".to_string(),
+                    "original_timestamp:1500000000".to_string(),
+                    "original_author:carol".to_string()
+                ]
+            ),
+        ]
+    );
+    assert_eq!(messages("group1").len(), 4);
+    assert!(validateMessages("group1"));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_validate_all() {
+    initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    signMessage("group2", "data 2").expect("it should sign the message");
+    let reports = || -> Vec<(String, bool, bool)> {
+        webmessage::validateAll()
+            .expect("it should validate the groups")
+            .iter()
+            .map(|report| {
+                let report: serde_json::Value =
+                    serde_json::from_str(report).expect("it should parse the report");
+                (
+                    report["group_id"].as_str().unwrap().to_string(),
+                    report["valid"].as_bool().unwrap(),
+                    report["skipped"].as_bool().unwrap(),
+                )
+            })
+            .collect()
+    };
+    assert_eq!(
+        reports(),
+        vec![
+            ("group1".to_string(), true, false),
+            ("group2".to_string(), true, false)
+        ]
+    );
+
+    // the unchanged chains are not validated again
+    assert_eq!(
+        reports(),
+        vec![
+            ("group1".to_string(), true, true),
+            ("group2".to_string(), true, true)
+        ]
+    );
+
+    // only the new message is validated in both passes, from the watermark which is checked once
+    signMessage("group1", "data 3").expect("it should sign the message");
+    let report: serde_json::Value =
+        serde_json::from_str(&webmessage::validateAll().expect("it should validate the groups")[0])
+            .expect("it should parse the report");
+    assert_eq!(report["valid"], true);
+    assert_eq!(report["skipped"], false);
+    assert_eq!(report["checked"], 3);
+
+    // a chain with messages after the seal is invalid
+    webmessage::sealGroup("group2").expect("it should seal the group");
+    signMessage("group2", "written without validation").expect("it should sign the message");
+    signMessage("group1", "data 4").expect("it should sign the message");
+    assert_eq!(
+        reports(),
+        vec![
+            ("group1".to_string(), true, false),
+            ("group2".to_string(), false, false)
+        ]
+    );
+    assert_eq!(reports()[1], ("group2".to_string(), false, false));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_max_seq() {
+    use sha2::Digest;
+    use webmessage::{
+        chain::{MemoryBackend, Writer},
+        writer::WriteError,
+        MAX_SEQ,
+    };
+
+    let (secret, identity) = GenKeysAlgorithm::generate_keys();
+
+    // the sequence numbers which fit in 32 bits are hashed in 4 bytes
+    let signed_msg = SignedMessage::<Identity, Signature>::sign::<Secret, MessageSigner>(
+        identity.clone(),
+        &secret,
+        Message::root(b"data".to_vec()),
+        7,
+    );
+    let expected: [u8; 32] = Sha256::new()
+        .chain_update(b"data")
+        .chain_update(identity.as_ref())
+        .chain_update(7u32.to_le_bytes())
+        .chain_update(signed_msg.signature.as_ref())
+        .finalize()
+        .into();
+    assert_eq!(signed_msg.hash::<Sha256>(), expected);
+
+    // the chain at the maximum sequence number is full
+    let last = SignedMessage::<Identity, Signature>::sign::<Secret, MessageSigner>(
+        identity.clone(),
+        &secret,
+        Message::root(b"last".to_vec()),
+        MAX_SEQ,
+    );
+    assert_eq!(last.next_seq(), None);
+    let hash = last.hash::<Sha256>();
+    let mut backend = MemoryBackend::default();
+    backend.items.insert(
+        format!("chain_msg_group1_{}", webmessage::hash_to_string(&hash)),
+        serde_json::to_string(&last).unwrap(),
+    );
+    backend.items.insert(
+        "chain_latest_msghash_group1".to_string(),
+        serde_json::to_string(&hash).unwrap(),
+    );
+    let mut writer: Writer<Identity, Signature, Sha256, _> = Writer::new(backend);
+    let result =
+        writer.sign::<Secret, MessageSigner>("group1", identity, &secret, b"data".to_vec());
+    assert!(matches!(result, Err(WriteError::ChainFull)));
+    assert_eq!(
+        WriteError::ChainFull.to_string(),
+        "group reached its maximum length"
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_migrate_hash_algorithm() {
+    let admin = initAccount().expect("it should create the account");
+    signMessage("group1", "before migration").expect("it should sign the message");
+    assert_eq!(webmessage::hashAlgorithm("group1"), "sha256");
+    webmessage::migrateHashAlgorithm("group1", "md5").expect_err("the algorithm is not supported");
+    webmessage::migrateHashAlgorithm("group1", "sha256").expect_err("the algorithm is unchanged");
+
+    // only an admin may migrate the chain
+    webmessage::newAccount().expect("it should create the account");
+    webmessage::migrateHashAlgorithm("group1", "sha512_256")
+        .expect_err("the signer is not an admin");
+    webmessage::setCurrentAccount(&admin).expect("it should set the current account");
+
+    // the record is linked with the old algorithm, and the next message with the new one
+    let record_str = webmessage::migrateHashAlgorithm("group1", "sha512_256")
+        .expect("it should migrate the chain");
+    let record: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&record_str).expect("it should parse the signed message");
+    let before: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&messages("group1")[1]).expect("it should parse the signed message");
+    assert_eq!(record.message.previous_hash, before.hash::<Sha256>());
+    assert_eq!(webmessage::hashAlgorithm("group1"), "sha512_256");
+
+    let after: SignedMessage<Identity, Signature> = serde_json::from_str(
+        &signMessage("group1", "after migration").expect("it should sign the message"),
+    )
+    .expect("it should parse the signed message");
+    assert_eq!(
+        after.message.previous_hash,
+        record.hash::<sha2::Sha512_256>()
+    );
+    assert_eq!(messages("group1").len(), 4);
+    assert!(validateMessages("group1"));
+
+    // the next message may not be linked with the old algorithm
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let old_link = SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+        other_id,
+        &other_secret,
+        "linked with sha256".as_bytes().to_vec(),
+        after.hash::<Sha256>(),
+        after,
+    );
+    let err = webmessage::addSignedMessage("group1", &serde_json::to_string(&old_link).unwrap())
+        .expect_err("the previous hash is not of the new algorithm");
+    assert_eq!(err, "wrong previous hash");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_clock() {
+    use std::rc::Rc;
+    use webmessage::clock;
+
+    initAccount().expect("it should create the account");
+    let timestamp_of = |msg_str: &str| {
+        let signed_msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(msg_str).expect("it should parse the signed message");
+        signed_msg.message.timestamp
+    };
+
+    // the app provides the clock
+    clock::set_clock(Some(Rc::new(|| 1_000_000)));
+    assert_eq!(
+        timestamp_of(&signMessage("group1", "data 1").expect("it should sign the message")),
+        Some(1_000_000)
+    );
+    let group: Group = serde_json::from_str(&groups()[0]).expect("it should parse the group");
+    assert_eq!(group.timestamp, 1_000_000);
+
+    // the clock follows the tokens of the time authority
+    let authority = webmessage::newAccount().expect("it should create the account");
+    webmessage::setCurrentAccount(&authority).expect("it should set the current account");
+    let token = webmessage::signTimeToken().expect("it should sign the token");
+    webmessage::acceptTimeToken(&token).expect_err("no time authority is set");
+    webmessage::setTimeAuthority(Some(authority.clone())).expect("it should set the authority");
+    webmessage::acceptTimeToken(&token).expect("it should accept the token");
+    let mut forged: clock::TimeToken = serde_json::from_str(&token).unwrap();
+    forged.body.timestamp += 3600;
+    webmessage::acceptTimeToken(&serde_json::to_string(&forged).unwrap())
+        .expect_err("the signature is invalid");
+
+    // an older token does not roll the clock back
+    clock::set_clock(Some(Rc::new(|| 2_000_000)));
+    let newer = webmessage::signTimeToken().expect("it should sign the token");
+    webmessage::setTimeAuthority(Some(authority)).expect("it should set the authority");
+    webmessage::acceptTimeToken(&newer).expect("it should accept the token");
+    webmessage::acceptTimeToken(&token).expect("it should ignore the older token");
+    let timestamp =
+        timestamp_of(&signMessage("group1", "data 2").expect("it should sign the message"))
+            .unwrap();
+    assert!((2_000_000..2_000_010).contains(&timestamp));
+
+    // clear the local storage
+    webmessage::setTimeAuthority(None).expect("it should restore the system time");
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_causal_order() {
+    initAccount().expect("it should create the account");
+    let first = signMessage("group1", "first").expect("it should sign the message");
+    let second = signMessage("group1", "second").expect("it should sign the message");
+    let third = signMessage("group1", "third").expect("it should sign the message");
+
+    // another device signs over the first message concurrently with the second
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let parent: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&first).expect("it should parse the signed message");
+    let concurrent = serde_json::to_string(&SignedMessage::new_from_previous_message::<
+        Secret,
+        MessageSigner,
+    >(
+        other_id,
+        &other_secret,
+        "concurrent".as_bytes().to_vec(),
+        parent.hash::<Sha256>(),
+        parent,
+    ))
+    .unwrap();
+
+    let order = |a: &str, b: &str| webmessage::causalOrder("group1", a, b).unwrap();
+    assert_eq!(order(&first, &third), r#""before""#);
+    assert_eq!(order(&third, &first), r#""after""#);
+    assert_eq!(order(&second, &second), r#""equal""#);
+    assert_eq!(order(&first, &concurrent), r#""before""#);
+    assert_eq!(order(&third, &concurrent), r#""concurrent""#);
+    assert_eq!(order(&concurrent, &second), r#""concurrent""#);
+    assert!(webmessage::causalOrder("group1", "invalid", &first).is_err());
+
+    // the concurrent messages are sorted in the same way whatever the input order
+    let sorted = webmessage::sortCausally(vec![
+        third.clone(),
+        concurrent.clone(),
+        second.clone(),
+        first.clone(),
+    ])
+    .unwrap();
+    assert_eq!(sorted[0], first);
+    assert_eq!(sorted[3], third);
+    let reversed = webmessage::sortCausally(sorted.iter().rev().cloned().collect()).unwrap();
+    assert_eq!(sorted, reversed);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_key_interop() {
+    // a key generated with `openssl ecparam -name prime256v1 -genkey`
+    let pem = "-----BEGIN PUBLIC KEY-----
+MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEVoZTffMMHmTYxPzAnP33hZC2gvxG
+APTBFIFNgI93ZVjN+ObWv2uNx/zZQzOKKyTkkppvPKLyMKUgENculDbQiQ==
+-----END PUBLIC KEY-----
+";
+    let sec1 = "035686537df30c1e64d8c4fcc09cfdf78590b682fc4600f4c114814d808f776558";
+    let identity = webmessage::importIdentity(pem, "pem").expect("it should import the key");
+    assert_eq!(webmessage::importIdentity(sec1, "sec1").unwrap(), identity);
+    assert_eq!(webmessage::exportIdentity(&identity, "sec1").unwrap(), sec1);
+    assert_eq!(webmessage::exportIdentity(&identity, "pem").unwrap(), pem);
+
+    // the encodings are converted to each other
+    for format in ["sec1", "spki", "pem", "jwk"] {
+        let key = webmessage::exportIdentity(&identity, format).expect("it should export the key");
+        assert_eq!(webmessage::importIdentity(&key, format).unwrap(), identity);
+    }
+    let jwk: serde_json::Value =
+        serde_json::from_str(&webmessage::exportIdentity(&identity, "jwk").unwrap()).unwrap();
+    assert_eq!(jwk["kty"], "EC");
+    assert_eq!(jwk["crv"], "P-256");
+
+    // the identities of the library are exported
+    let account = initAccount().expect("it should create the account");
+    let key = webmessage::exportIdentity(&account, "jwk").expect("it should export the identity");
+    assert_eq!(webmessage::importIdentity(&key, "jwk").unwrap(), account);
+
+    assert!(webmessage::importIdentity(pem, "x509").is_err());
+    assert!(webmessage::importIdentity("00", "sec1").is_err());
+    assert!(webmessage::exportIdentity("invalid", "sec1").is_err());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_notifications() {
+    use std::{cell::RefCell, rc::Rc};
+    use webmessage::notification::{self, Notification, PREVIEW_LENGTH};
+
+    initAccount().expect("it should create the account");
+    let received: Rc<RefCell<Vec<Notification>>> = Rc::default();
+    let sink = received.clone();
+    notification::set_notifier(Some(Box::new(move |notification: &Notification| {
+        sink.borrow_mut().push(notification.clone())
+    })));
+
+    // the messages signed locally are not notified
+    signMessage("group1", "own message").expect("it should sign the message");
+    assert!(received.borrow().is_empty());
+
+    // the messages received from other parties are notified with their authors and previews
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let other = other_id.to_string();
+    webmessage::addContact(&other, "bob", "unverified", "").expect("it should add the contact");
+    let other_msg = |data: &str| {
+        let latest: SignedMessage<Identity, Signature> =
+            serde_json::from_str(&messages("group1")[0])
+                .expect("it should parse the signed message");
+        let signed_msg = SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            data.as_bytes().to_vec(),
+            latest.hash::<Sha256>(),
+            latest,
+        );
+        serde_json::to_string(&signed_msg).unwrap()
+    };
+    let hash = webmessage::addSignedMessage("group1", &other_msg("hello"))
+        .expect("it should add the signed message");
+    let long_text = "a".repeat(PREVIEW_LENGTH + 1);
+    webmessage::ingestSignedMessage("group1", &other_msg(&long_text))
+        .expect("it should add the signed message");
+
+    let received = received.borrow();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].group_id, "group1");
+    assert_eq!(serde_json::to_string(&received[0].hash).unwrap(), hash);
+    assert_eq!(received[0].author.to_string(), other);
+    assert_eq!(received[0].alias.as_deref(), Some("bob"));
+    assert_eq!(received[0].preview.as_deref(), Some("hello"));
+    assert_eq!(
+        received[1].preview.as_deref(),
+        Some(format!("{}…", &long_text[..PREVIEW_LENGTH]).as_str())
+    );
+
+    // clear the local storage
+    notification::set_notifier(None);
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_message_headers() {
+    use webmessage::header::{MessageHeader, PREVIEW_LENGTH};
+
+    let identity = initAccount().expect("it should create the account");
+    let headers = |limit: usize| -> Vec<MessageHeader> {
+        webmessage::messageHeaders("group1", limit)
+            .iter()
+            .map(|header| serde_json::from_str(header).expect("it should parse the header"))
+            .collect()
+    };
+    assert!(headers(10).is_empty());
+
+    let first: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "first").expect("it should sign the message"))
+            .unwrap();
+    let long_text = "b".repeat(PREVIEW_LENGTH + 10);
+    signMessage("group1", &long_text).expect("it should sign the message");
+
+    // the headers are ordered from the latest, and the records have no previews
+    let all = headers(10);
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[2].kind, webmessage::MessageKind::Genesis);
+    assert!(all[2].preview.is_none());
+    assert_eq!(all[1].seq, first.seq);
+    assert_eq!(all[1].hash, first.hash::<Sha256>());
+    assert_eq!(all[1].author.to_string(), identity);
+    assert_eq!(all[1].timestamp, first.message.timestamp);
+    assert_eq!(all[1].preview.as_deref(), Some("first"));
+    assert_eq!(
+        all[0].preview.as_deref(),
+        Some(format!("{}…", &long_text[..PREVIEW_LENGTH]).as_str())
+    );
+    assert_eq!(headers(1).len(), 1);
+    assert_eq!(headers(1)[0].hash, all[0].hash);
+
+    // the preview of a redacted message is removed
+    let hash = serde_json::to_string(&first.hash::<Sha256>()).unwrap();
+    webmessage::redactMessage("group1", &hash, true).expect("it should redact the message");
+    let all = headers(10);
+    assert_eq!(all.len(), 4);
+    assert_eq!(all[0].kind, webmessage::MessageKind::Redaction);
+    assert!(all[2].preview.is_none());
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_chain_stats() {
+    use webmessage::stats::ChainStats;
+
+    initAccount().expect("it should create the account");
+    let stats = || -> ChainStats {
+        serde_json::from_str(&webmessage::chainStats("group1")).expect("it should parse the stats")
+    };
+    let empty = stats();
+    assert_eq!(empty.message_count, 0);
+    assert_eq!(empty.average_message_bytes, 0);
+    assert!(empty.checkpoints.is_empty());
+
+    for data in ["data 1", "data 2"] {
+        signMessage("group1", data).expect("it should sign the message");
+    }
+    let checkpoint: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&webmessage::checkpoint("group1").expect("it should sign"))
+            .expect("it should parse the signed message");
+    let head: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "data 3").expect("it should sign the message"))
+            .unwrap();
+
+    let chain = stats();
+    assert_eq!(chain.group_id, "group1");
+    assert_eq!(chain.message_count, 5);
+    assert!(chain.average_message_bytes > 0);
+    assert_eq!(
+        chain
+            .signature_schemes
+            .get(webmessage::message::SIGNATURE_SCHEME),
+        Some(&5)
+    );
+    assert_eq!(chain.hash_algorithms.get("sha256"), Some(&5));
+    assert_eq!(chain.checkpoints.len(), 1);
+    assert_eq!(chain.checkpoints[0].seq, checkpoint.seq);
+    assert_eq!(chain.checkpoints[0].hash, checkpoint.hash::<Sha256>());
+    assert_eq!(chain.forks, 0);
+    assert!(chain.validated_head.is_none());
+
+    // the watermark is at the head after the validation
+    webmessage::validateAll().expect("it should validate the groups");
+    let validated = stats();
+    assert_eq!(validated.validated_head, Some(head.hash::<Sha256>()));
+    assert_eq!(validated.validated_seq, Some(head.seq));
+
+    // the quarantined messages over older messages of the chain are forks
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(other_id.clone(), &other_secret, "group2");
+    let next = |data: &str, previous: &SignedMessage<Identity, Signature>| {
+        SignedMessage::new_from_previous_message::<Secret, MessageSigner>(
+            other_id.clone(),
+            &other_secret,
+            data.as_bytes().to_vec(),
+            previous.hash::<Sha256>(),
+            previous.clone(),
+        )
+    };
+    let msg1 = next("data 1", &genesis);
+    for msg in [&genesis, &msg1] {
+        webmessage::addSignedMessage("group2", &serde_json::to_string(msg).unwrap())
+            .expect("it should add the message");
+    }
+    let fork = next("fork", &genesis);
+    webmessage::addSignedMessage("group2", &serde_json::to_string(&fork).unwrap())
+        .expect_err("the message is stale");
+    let forked: ChainStats =
+        serde_json::from_str(&webmessage::chainStats("group2")).expect("it should parse");
+    assert_eq!(forked.message_count, 2);
+    assert_eq!(forked.forks, 1);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_export_accounts() {
+    webmessage::exportAccounts("passphrase").expect_err("there are no accounts");
+    let first = initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+    let second = webmessage::newAccount().expect("it should create the account");
+    let exported = webmessage::exportAccounts("passphrase").expect("it should export");
+    let encrypted_accounts: webmessage::account::EncryptedAccounts =
+        serde_json::from_str(&exported).expect("it should parse the encrypted accounts");
+    assert_eq!(encrypted_accounts.identities.len(), 2);
+    assert!(encrypted_accounts.decrypt("wrong passphrase").is_none());
+    webmessage::clear().expect("it should clear the local storage");
+
+    // the accounts are imported without the messages, and the first becomes current
+    webmessage::importAccounts(&exported, "wrong").expect_err("the passphrase is wrong");
+    webmessage::importAccounts("{}", "passphrase").expect_err("it is not an export");
+    let imported = webmessage::importAccounts(&exported, "passphrase").expect("it should import");
+    assert_eq!(imported, vec![first.clone(), second.clone()]);
+    assert_eq!(
+        webmessage::allAccounts(),
+        vec![first.clone(), second.clone()]
+    );
+    assert_eq!(initAccount().expect("it should create the account"), first);
+    assert!(messages("group1").is_empty());
+    assert_eq!(webmessage::keyLog(&second).map(|log| log.len()), Ok(1));
+
+    // the existing accounts are kept with the current account
+    webmessage::setCurrentAccount(&second).expect("it should set the current account");
+    let third = webmessage::newAccount().expect("it should create the account");
+    webmessage::setCurrentAccount(&second).expect("it should set the current account");
+    let imported = webmessage::importAccounts(&exported, "passphrase").expect("it should import");
+    assert_eq!(imported.len(), 2);
+    assert_eq!(
+        webmessage::allAccounts(),
+        vec![first, second.clone(), third]
+    );
+    assert_eq!(initAccount().expect("it should create the account"), second);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_account_usage() {
+    use webmessage::account::AccountUsage;
+
+    let identity = initAccount().expect("it should create the account");
+    let usage = |identity: &str| -> AccountUsage {
+        serde_json::from_str(&webmessage::accountUsage(identity).expect("it should get the usage"))
+            .expect("it should parse the usage")
+    };
+    assert_eq!(usage(&identity).message_count, 0);
+    let first: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group1", "data 1").expect("it should sign the message"))
+            .unwrap();
+    signMessage("group1", "data 2").expect("it should sign the message");
+    let last: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&signMessage("group2", "data 3").expect("it should sign the message"))
+            .unwrap();
+
+    // the genesis records are counted with the messages
+    let current = usage(&identity);
+    assert_eq!(current.message_count, 5);
+    assert_eq!(current.groups.len(), 2);
+    assert_eq!(current.groups[0].group_id, "group1");
+    assert_eq!(current.groups[0].message_count, 3);
+    assert_eq!(current.groups[1].group_id, "group2");
+    assert_eq!(current.groups[1].message_count, 2);
+    assert!(current.first_signed <= first.message.timestamp);
+    assert_eq!(current.last_signed, last.message.timestamp);
+
+    // the messages of other accounts are not counted
+    let other = webmessage::newAccount().expect("it should create the account");
+    signMessage("group1", "data 4").expect("it should sign the message");
+    assert_eq!(usage(&other).message_count, 1);
+    assert_eq!(usage(&identity).message_count, 5);
+    webmessage::accountUsage("not an identity").expect_err("it is not an identity");
+    let (_, unknown) = GenKeysAlgorithm::generate_keys();
+    webmessage::accountUsage(&unknown.to_string()).expect_err("it is not an account");
+
+    // the usage of an imported account is rebuilt from the chains
+    let (secret, imported) = GenKeysAlgorithm::generate_keys();
+    let genesis = other_genesis(imported.clone(), &secret, "group3");
+    webmessage::addSignedMessage("group3", &serde_json::to_string(&genesis).unwrap())
+        .expect("it should add the genesis record");
+    let key_pair = serde_json::json!({ "identity": imported, "secret": secret }).to_string();
+    webmessage::importAccount(&key_pair, None).expect("it should import the account");
+    let rebuilt = usage(&imported.to_string());
+    assert_eq!(rebuilt.message_count, 1);
+    assert_eq!(rebuilt.groups[0].group_id, "group3");
+    signMessage("group3", "data 5").expect("it should sign the message");
+    assert_eq!(usage(&imported.to_string()).message_count, 2);
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+async fn test_group_lock() {
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+
+    initAccount().expect("it should create the account");
+
+    // the result of the callback resolves the promise
+    let callback = Closure::<dyn FnMut() -> JsValue>::new(|| {
+        JsValue::from_str(&signMessage("group1", "data 1").expect("it should sign the message"))
+    });
+    let promise = webmessage::withGroupLock(
+        "group1",
+        callback
+            .as_ref()
+            .unchecked_ref::<js_sys::Function>()
+            .clone(),
+    );
+    let result = JsFuture::from(promise)
+        .await
+        .expect("it should run the callback");
+    assert!(result.as_string().is_some());
+    // the genesis and the message
+    assert_eq!(messages("group1").len(), 2);
+
+    // the error of the callback rejects the promise
+    let failing = Closure::<dyn FnMut() -> Result<JsValue, JsValue>>::new(|| {
+        Err(JsValue::from_str("failed"))
+    });
+    let promise = webmessage::withGroupLock(
+        "group1",
+        failing.as_ref().unchecked_ref::<js_sys::Function>().clone(),
+    );
+    let err = JsFuture::from(promise)
+        .await
+        .expect_err("it should reject the promise");
+    assert_eq!(err.as_string().as_deref(), Some("failed"));
+
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_migrate_storage() {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let window = web_sys::window().unwrap();
+    let local_storage: JsValue = window.local_storage().unwrap().unwrap().into();
+    let session_storage = window.session_storage().unwrap().unwrap();
+    session_storage.clear().unwrap();
+
+    let identity = initAccount().expect("it should create the account");
+    signMessage("group1", "data 1").expect("it should sign the message");
+
+    // the items are copied and verified
+    let stages = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    let progress = {
+        let stages = stages.clone();
+        wasm_bindgen::closure::Closure::<dyn FnMut(String, u32, u32)>::new(
+            move |stage: String, done: u32, total: u32| {
+                stages.borrow_mut().push((stage, done, total))
+            },
+        )
+    };
+    let report = webmessage::migrateStorage(
+        local_storage.clone().unchecked_into(),
+        session_storage
+            .clone()
+            .unchecked_into::<JsValue>()
+            .unchecked_into(),
+        Some(
+            progress
+                .as_ref()
+                .unchecked_ref::<js_sys::Function>()
+                .clone(),
+        ),
+    )
+    .expect("it should migrate the storage");
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    let items = report["items"].as_u64().unwrap() as u32;
+    assert_eq!(session_storage.length().unwrap(), items);
+    assert_eq!(stages.borrow().len(), 2 * items as usize);
+    assert_eq!(
+        stages.borrow().last(),
+        Some(&("verify".to_string(), items, items))
+    );
+
+    // the library reads the same accounts and chains from the new storage
+    let signed_messages = messages("group1");
+    webmessage::setStorage(Some(
+        session_storage
+            .clone()
+            .unchecked_into::<JsValue>()
+            .unchecked_into(),
+    ));
+    assert_eq!(webmessage::allAccounts(), vec![identity]);
+    assert_eq!(messages("group1"), signed_messages);
+    webmessage::clear().expect("it should clear the session storage");
+    webmessage::setStorage(None);
+
+    // a failed write restores the target
+    let failing: JsValue = js_sys::Function::new_no_args(
+        "const items = new Map([['other', 'value']]);
+        return {
+            get length() { return items.size; },
+            key: (index) => [...items.keys()][index] ?? null,
+            getItem: (key) => items.has(key) ? items.get(key) : null,
+            setItem: (key, value) => {
+                if (items.size >= 3) throw new Error('quota exceeded');
+                items.set(key, value);
+            },
+            removeItem: (key) => items.delete(key),
+            clear: () => items.clear(),
+        };",
+    )
+    .call0(&JsValue::NULL)
+    .unwrap();
+    let err = webmessage::migrateStorage(
+        local_storage.clone().unchecked_into(),
+        failing.clone().unchecked_into(),
+        None,
+    )
+    .expect_err("it should fail to write the items");
+    assert!(err.contains("quota exceeded"));
+    let length = js_sys::Reflect::get(&failing, &JsValue::from_str("length")).unwrap();
+    assert_eq!(length.as_f64(), Some(1.0));
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+#[wasm_bindgen_test]
+fn test_group_key_authority() {
+    let (owner_secret, owner_id) = GenKeysAlgorithm::generate_keys();
+    let key_pair = serde_json::json!({ "identity": owner_id, "secret": owner_secret }).to_string();
+    webmessage::importAccount(&key_pair, None).expect("it should import the account");
+    let group_key = webmessage::newGroupKey("group1").expect("it should create the group key");
+    signMessage("group1", "secret data").expect("it should sign the message");
+    let (other_secret, other_id) = GenKeysAlgorithm::generate_keys();
+    webmessage::addMember("group1", &other_id.to_string()).expect("it should add the member");
+
+    let latest = || -> SignedMessage<Identity, Signature> {
+        serde_json::from_str(messages("group1")[0].as_str()).expect("it should parse")
+    };
+    let record = |kind: webmessage::MessageKind, epoch: u64, id: &Identity, secret: &Secret| {
+        let prev = latest();
+        let data = serde_json::to_vec(&webmessage::agreement::KeyDistribution {
+            grants: vec![],
+            epoch,
+        })
+        .unwrap();
+        let mut message = Message::new(prev.hash::<Sha256>(), data);
+        message.kind = kind;
+        let signed_msg =
+            SignedMessage::sign::<Secret, MessageSigner>(id.clone(), secret, message, prev.seq + 1);
+        serde_json::to_string(&signed_msg).unwrap()
+    };
+
+    // a member who is not an admin may not rotate the group key or start the ratchet
+    let err = webmessage::addSignedMessage(
+        "group1",
+        &record(webmessage::MessageKind::Rekey, 1, &other_id, &other_secret),
+    )
+    .expect_err("the signer is not an admin");
+    assert_eq!(err, "signer may not manage the group key");
+    webmessage::addSignedMessage(
+        "group1",
+        &record(
+            webmessage::MessageKind::RatchetStart,
+            0,
+            &other_id,
+            &other_secret,
+        ),
+    )
+    .expect_err("the signer does not hold the group key");
+
+    // the group key is kept if the rotation is rejected
+    let other_pair =
+        serde_json::json!({ "identity": other_id, "secret": other_secret }).to_string();
+    webmessage::importAccount(&other_pair, None).expect("it should import the account");
+    webmessage::setCurrentAccount(&other_id.to_string())
+        .expect("it should set the current account");
+    webmessage::rotateGroupKey("group1", vec![]).expect_err("the signer is not an admin");
+    webmessage::setCurrentAccount(&owner_id.to_string())
+        .expect("it should set the current account");
+    let msg_str = signMessage("group1", "more secret data").expect("it should sign the message");
+    let signed_msg: SignedMessage<Identity, Signature> =
+        serde_json::from_str(&msg_str).expect("it should parse the signed message");
+    let group_key = serde_json::from_str(&group_key).expect("it should parse the group key");
+    assert_eq!(
+        webmessage::encryption::decrypt(&group_key, &signed_msg.message.data),
+        Some(b"more secret data".to_vec())
+    );
+
+    // a message is received, and it is not read before the rotation
+    let mut message = Message::new(
+        signed_msg.hash::<Sha256>(),
+        webmessage::encryption::encrypt(&group_key, b"received data"),
+    );
+    message.encrypted = true;
+    let received = SignedMessage::sign::<Secret, MessageSigner>(
+        other_id.clone(),
+        &other_secret,
+        message,
+        signed_msg.seq + 1,
+    );
+    webmessage::addSignedMessage("group1", &serde_json::to_string(&received).unwrap())
+        .expect("it should add the message");
+
+    // the admin rotates the key to the next epoch, which does not go back
+    webmessage::rotateGroupKey("group1", vec![]).expect("it should rotate the group key");
+    let rekey = latest();
+    let distribution: webmessage::agreement::KeyDistribution =
+        serde_json::from_slice(&rekey.message.data).expect("it should parse");
+    assert_eq!(distribution.epoch, 1);
+    assert!(validateMessages("group1"));
+
+    // the message received before the rotation is read with the superseded key
+    assert!(messages("group1").iter().any(|msg_str| {
+        let msg: SignedMessage<Identity, Signature> =
+            serde_json::from_str(msg_str).expect("it should parse the signed message");
+        msg.message.data == b"received data"
+    }));
+
+    let err = webmessage::addSignedMessage(
+        "group1",
+        &record(
+            webmessage::MessageKind::KeyDistribution,
+            0,
+            &owner_id,
+            &owner_secret,
+        ),
+    )
+    .expect_err("the epoch does not move forward");
+    assert_eq!(err, "key distribution is for another epoch");
+
+    // clear the local storage
+    webmessage::clear().expect("it should clear the local storage");
+}
+
+fn other_genesis(
+    id: Identity,
+    secret: &Secret,
+    group_id: &str,
+) -> SignedMessage<Identity, Signature> {
+    let genesis = GroupGenesis::new(
+        group_id.to_string(),
+        group_id.to_string(),
+        id.clone(),
+        GroupPolicy::default(),
+    );
+    SignedMessage::new_genesis::<Secret, MessageSigner>(id, secret, &genesis)
+}